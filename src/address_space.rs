@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::io::{self, Cursor, Read, Seek};
+use std::marker::PhantomData;
+
+use byteorder::ReadBytesExt;
+
+use crate::constants::*;
+use crate::dyld_info::ChainedFixups;
+use crate::error::AppError;
+use crate::header::Header;
+use crate::load_commands::Segment;
+use crate::mach_o::MachO;
+
+/// A pointer-sized value read from the image after undoing the fixup encoding it is stored in.
+#[derive(Debug)]
+pub enum Pointer {
+    Address(u64),
+    /// The slot is bound to a symbol from another image.
+    Import(String),
+}
+
+struct MappedSegment {
+    vmaddr: u64,
+    vmsize: u64,
+    fileoff: u64,
+    filesize: u64,
+}
+
+/// Reads the image through its virtual addresses, the way the runtime sees it after dyld
+/// applied rebases and binds.
+pub struct AddressSpace<'a, R: Read + Seek, E: byteorder::ByteOrder> {
+    file: &'a mut R,
+    mach_o: &'a MachO,
+    segments: Vec<MappedSegment>,
+    base_address: u64,
+    pointer_size: u64,
+    binds: HashMap<u64, &'a str>,
+    _endian: PhantomData<E>,
+}
+
+impl<'a, R: Read + Seek, E: byteorder::ByteOrder> AddressSpace<'a, R, E> {
+    pub fn new(file: &'a mut R, mach_o: &'a MachO) -> AddressSpace<'a, R, E> {
+        let segments: Vec<MappedSegment> = mach_o.segments()
+            .map(|(segment, _)| MappedSegment {
+                vmaddr: segment.vmaddr(),
+                vmsize: segment.vmsize(),
+                fileoff: segment.fileoff(),
+                filesize: segment.filesize(),
+            })
+            .collect();
+        let base_address = segments.iter()
+            .find(|segment| segment.fileoff == 0 && segment.filesize != 0)
+            .map_or(0, |segment| segment.vmaddr);
+        let binds = mach_o.binds.iter()
+            .map(|bind| (bind.address, bind.symbol_name.as_str()))
+            .collect();
+
+        AddressSpace {
+            file,
+            mach_o,
+            segments,
+            base_address,
+            pointer_size: if mach_o.header.is_64() { 8 } else { 4 },
+            binds,
+            _endian: PhantomData,
+        }
+    }
+
+    pub fn pointer_size(&self) -> u64 {
+        self.pointer_size
+    }
+
+    fn segment_index(&self, vmaddr: u64) -> Option<usize> {
+        self.segments.iter().position(|segment| vmaddr >= segment.vmaddr && vmaddr - segment.vmaddr < segment.vmsize)
+    }
+
+    pub fn is_mapped(&self, vmaddr: u64) -> bool {
+        self.segment_index(vmaddr).is_some()
+    }
+
+    /// Returns the file offset backing `vmaddr` and how many bytes of the segment follow it in the file.
+    fn file_range(&self, vmaddr: u64) -> Result<(u64, u64), AppError> {
+        let segment = self.segment_index(vmaddr).map(|index| &self.segments[index]).filter(|segment| vmaddr - segment.vmaddr < segment.filesize).ok_or_else(|| {
+            AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("address 0x{:x} is not backed by file contents", vmaddr)))
+        })?;
+        let offset_in_segment = vmaddr - segment.vmaddr;
+        let fileoff = segment.fileoff.checked_add(offset_in_segment).ok_or(AppError::OutOfBounds { what: "segment", offset: segment.fileoff, size: segment.filesize })?;
+        Ok((fileoff, segment.filesize - offset_in_segment))
+    }
+
+    /// Fails unless `size` bytes starting at `vmaddr` are backed by file contents, e.g. before
+    /// reading a table whose entry count comes from the file. Addresses inside a checked range can
+    /// then be computed without overflowing.
+    pub fn check_range(&self, vmaddr: u64, size: u64) -> Result<(), AppError> {
+        let (_, available) = self.file_range(vmaddr)?;
+        if size > available || vmaddr.checked_add(size.saturating_sub(1)).is_none() {
+            return Err(AppError::from(io::Error::new(io::ErrorKind::UnexpectedEof, format!("0x{:x} bytes at 0x{:x} run past the end of the segment", size, vmaddr))));
+        }
+        Ok(())
+    }
+
+    pub fn read_bytes(&mut self, vmaddr: u64, size: u64) -> Result<Vec<u8>, AppError> {
+        self.check_range(vmaddr, size)?;
+        let (offset, _) = self.file_range(vmaddr)?;
+        self.mach_o.read_file_range(self.file, offset, size)
+    }
+
+    pub fn read_u16(&mut self, vmaddr: u64) -> Result<u16, AppError> {
+        Ok(Cursor::new(self.read_bytes(vmaddr, 2)?).read_u16::<E>()?)
+    }
+
+    pub fn read_u32(&mut self, vmaddr: u64) -> Result<u32, AppError> {
+        Ok(Cursor::new(self.read_bytes(vmaddr, 4)?).read_u32::<E>()?)
+    }
+
+    pub fn read_i32(&mut self, vmaddr: u64) -> Result<i32, AppError> {
+        Ok(Cursor::new(self.read_bytes(vmaddr, 4)?).read_i32::<E>()?)
+    }
+
+    pub fn read_u64(&mut self, vmaddr: u64) -> Result<u64, AppError> {
+        Ok(Cursor::new(self.read_bytes(vmaddr, 8)?).read_u64::<E>()?)
+    }
+
+    /// Reads a pointer-sized integer without interpreting it.
+    pub fn read_word(&mut self, vmaddr: u64) -> Result<u64, AppError> {
+        match self.pointer_size {
+            8 => self.read_u64(vmaddr),
+            _ => Ok(self.read_u32(vmaddr)? as u64),
+        }
+    }
+
+    pub fn read_c_string(&mut self, vmaddr: u64) -> Result<String, AppError> {
+        let (_, available) = self.file_range(vmaddr)?;
+        let mut bytes = Vec::new();
+        while (bytes.len() as u64) < available {
+            let chunk_size = (available - bytes.len() as u64).min(256);
+            let chunk = self.read_bytes(vmaddr.wrapping_add(bytes.len() as u64), chunk_size)?;
+            if let Some(end) = chunk.iter().position(|&byte| byte == 0) {
+                bytes.extend_from_slice(&chunk[..end]);
+                return Ok(String::from_utf8_lossy(&bytes).into_owned());
+            }
+            bytes.extend(chunk);
+        }
+        Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("unterminated string at 0x{:x}", vmaddr))))
+    }
+
+    /// Reads the pointer stored at `vmaddr`, resolving bind opcodes and decoding chained fixups.
+    pub fn read_pointer(&mut self, vmaddr: u64) -> Result<Pointer, AppError> {
+        if let Some(symbol_name) = self.binds.get(&vmaddr) {
+            return Ok(Pointer::Import(symbol_name.to_string()));
+        }
+        let raw = self.read_word(vmaddr)?;
+        if raw == 0 {
+            return Ok(Pointer::Address(0));
+        }
+        match (&self.mach_o.chained_fixups, self.segment_index(vmaddr)) {
+            (Some(chained_fixups), Some(index)) => match chained_fixups.pointer_formats.get(index).copied().flatten() {
+                Some(pointer_format) => self.decode_chained_pointer(chained_fixups, pointer_format, raw),
+                None => Ok(Pointer::Address(raw)),
+            },
+            _ => Ok(Pointer::Address(raw)),
+        }
+    }
+
+    /// Like `read_pointer`, but treats imported symbols as null since their address is unknown.
+    pub fn read_address(&mut self, vmaddr: u64) -> Result<u64, AppError> {
+        match self.read_pointer(vmaddr)? {
+            Pointer::Address(address) => Ok(address),
+            Pointer::Import(_) => Ok(0),
+        }
+    }
+
+    fn decode_chained_pointer(&self, chained_fixups: &ChainedFixups, pointer_format: u16, raw: u64) -> Result<Pointer, AppError> {
+        let import = |ordinal: u64| -> Result<Pointer, AppError> {
+            let import = chained_fixups.imports.get(ordinal as usize).ok_or_else(|| {
+                AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("chained bind references unknown import {}", ordinal)))
+            })?;
+            Ok(Pointer::Import(import.clone()))
+        };
+
+        match pointer_format {
+            DYLD_CHAINED_PTR_ARM64E | DYLD_CHAINED_PTR_ARM64E_KERNEL | DYLD_CHAINED_PTR_ARM64E_USERLAND | DYLD_CHAINED_PTR_ARM64E_FIRMWARE | DYLD_CHAINED_PTR_ARM64E_USERLAND24 => {
+                let is_auth = raw >> 63 != 0;
+                let is_bind = (raw >> 62) & 1 != 0;
+                let ordinal_mask = if pointer_format == DYLD_CHAINED_PTR_ARM64E_USERLAND24 { 0xff_ffff } else { 0xffff };
+                if is_bind {
+                    import(raw & ordinal_mask)
+                } else if is_auth {
+                    // Authenticated rebases always hold an offset from the image base.
+                    Ok(Pointer::Address(self.base_address.wrapping_add(raw & 0xffff_ffff)))
+                } else {
+                    let target = raw & 0x7ff_ffff_ffff;
+                    let high8 = (raw >> 43) & 0xff;
+                    let target = if pointer_format == DYLD_CHAINED_PTR_ARM64E { target } else { self.base_address.wrapping_add(target) };
+                    Ok(Pointer::Address((high8 << 56) | target))
+                }
+            }
+            DYLD_CHAINED_PTR_64 | DYLD_CHAINED_PTR_64_OFFSET => {
+                if raw >> 63 != 0 {
+                    import(raw & 0xff_ffff)
+                } else {
+                    let target = raw & 0xf_ffff_ffff;
+                    let high8 = (raw >> 36) & 0xff;
+                    let target = if pointer_format == DYLD_CHAINED_PTR_64 { target } else { self.base_address.wrapping_add(target) };
+                    Ok(Pointer::Address((high8 << 56) | target))
+                }
+            }
+            DYLD_CHAINED_PTR_32 => {
+                if raw >> 31 != 0 {
+                    import(raw & 0xf_ffff)
+                } else {
+                    Ok(Pointer::Address(raw & 0x3ff_ffff))
+                }
+            }
+            _ => Ok(Pointer::Address(raw)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn ranges_at_the_top_of_the_address_space() {
+        let base = u64::MAX - 0xfff;
+        let data = image(&[segment_64("__TEXT", base, 0x1000, 0, 0x1000, &[])], &[(0xff8, b"abcdefgh")]);
+        let mach_o = MachO::from_bytes(&data).unwrap();
+        let mut file = Cursor::new(&data);
+        let mut space = AddressSpace::<_, LittleEndian>::new(&mut file, &mach_o);
+
+        assert!(space.check_range(u64::MAX - 0xf, 0x10).is_ok());
+        assert!(space.check_range(u64::MAX - 0xf, 0x11).is_err());
+        assert!(space.check_range(u64::MAX - 7, u64::MAX).is_err());
+        // The string runs to the end of the segment without a terminator.
+        assert!(space.read_c_string(u64::MAX - 7).is_err());
+        assert_eq!(space.read_c_string(u64::MAX - 0xf).unwrap(), "");
+        assert!(space.read_u64(u64::MAX - 7).is_ok());
+        assert!(space.read_u64(u64::MAX - 3).is_err());
+    }
+}
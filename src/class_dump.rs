@@ -0,0 +1,267 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::objc::*;
+use crate::objc_types::*;
+
+/// Renders the Objective-C metadata of an image as class-dump style `@protocol` and
+/// `@interface` declarations.
+pub fn generate_headers(metadata: &ObjcMetadata) -> String {
+    let mut output = String::new();
+    output.push_str("//\n// Generated by Mach_O_Rust\n//\n\n");
+    output.push_str("#pragma mark Blocks\n\ntypedef void (^CDUnknownBlockType)(void); // return type and parameters are unknown\n\n");
+    output.push_str("#pragma mark Function Pointers\n\ntypedef void (*CDUnknownFunctionPointerType)(void); // return type and parameters are unknown\n\n");
+
+    for protocol in &metadata.protocols {
+        write_protocol(&mut output, protocol);
+    }
+    for class in &metadata.classes {
+        write_class(&mut output, class);
+    }
+    for category in &metadata.categories {
+        write_category(&mut output, category);
+    }
+    output
+}
+
+fn write_protocol(output: &mut String, protocol: &ObjcProtocol) {
+    writeln!(output, "@protocol {}{}", protocol.name, format_adopted_protocols(&protocol.protocols)).unwrap();
+    let accessors = write_properties(output, &protocol.properties);
+    write_methods(output, '+', &protocol.class_methods, &HashSet::new());
+    write_methods(output, '-', &protocol.instance_methods, &accessors);
+    if !protocol.optional_class_methods.is_empty() || !without_accessors(&protocol.optional_instance_methods, &accessors).is_empty() {
+        output.push_str("\n@optional\n");
+        write_methods(output, '+', &protocol.optional_class_methods, &HashSet::new());
+        write_methods(output, '-', &protocol.optional_instance_methods, &accessors);
+    }
+    output.push_str("@end\n\n");
+}
+
+fn write_class(output: &mut String, class: &ObjcClass) {
+    let superclass = class.superclass.as_ref().map_or(String::new(), |superclass| format!(" : {}", superclass));
+    writeln!(output, "@interface {}{}{}", class.name, superclass, format_adopted_protocols(&class.protocols)).unwrap();
+    if !class.ivars.is_empty() {
+        output.push_str("{\n");
+        for ivar in &class.ivars {
+            let declaration = match parse_type(&ivar.type_encoding) {
+                Some(ivar_type) => ivar_type.declaration(&ivar.name),
+                None => format!("void /* unknown type: {} */ {}", ivar.type_encoding, ivar.name),
+            };
+            match ivar.offset {
+                Some(offset) => writeln!(output, "    {};\t// 0x{:x}", declaration, offset).unwrap(),
+                None => writeln!(output, "    {};", declaration).unwrap(),
+            }
+        }
+        output.push_str("}\n");
+    }
+    let accessors = write_properties(output, &class.properties);
+    write_methods(output, '+', &class.class_methods, &HashSet::new());
+    write_methods(output, '-', &class.instance_methods, &accessors);
+    output.push_str("@end\n\n");
+}
+
+fn write_category(output: &mut String, category: &ObjcCategory) {
+    let class_name = category.class_name.as_deref().unwrap_or("?");
+    writeln!(output, "@interface {} ({}){}", class_name, category.name, format_adopted_protocols(&category.protocols)).unwrap();
+    let accessors = write_properties(output, &category.properties);
+    write_methods(output, '+', &category.class_methods, &HashSet::new());
+    write_methods(output, '-', &category.instance_methods, &accessors);
+    output.push_str("@end\n\n");
+}
+
+fn format_adopted_protocols(protocols: &[String]) -> String {
+    if protocols.is_empty() {
+        String::new()
+    } else {
+        format!(" <{}>", protocols.join(", "))
+    }
+}
+
+/// Writes the `@property` declarations and returns the selectors of their accessors, which are
+/// left out of the method list the way class-dump does.
+fn write_properties(output: &mut String, properties: &[ObjcProperty]) -> HashSet<String> {
+    let mut accessors = HashSet::new();
+    if properties.is_empty() {
+        return accessors;
+    }
+    output.push('\n');
+    for property in properties {
+        let mut attributes = Vec::new();
+        let mut property_type = None;
+        let mut getter = property.name.clone();
+        let mut setter = Some(format!("set{}:", capitalize(&property.name)));
+        let mut ivar = None;
+        let mut is_dynamic = false;
+
+        // Attributes are comma separated, each starting with a single letter code.
+        for attribute in property.attributes.split(',') {
+            let (code, value) = attribute.split_at(attribute.len().min(1));
+            match code {
+                "T" => property_type = Some(value),
+                "R" => {
+                    attributes.push(String::from("readonly"));
+                    setter = None;
+                }
+                "C" => attributes.push(String::from("copy")),
+                "&" => attributes.push(String::from("retain")),
+                "W" => attributes.push(String::from("weak")),
+                "N" => attributes.push(String::from("nonatomic")),
+                "G" => {
+                    attributes.push(format!("getter={}", value));
+                    getter = value.to_string();
+                }
+                "S" => {
+                    attributes.push(format!("setter={}", value));
+                    setter = Some(value.to_string());
+                }
+                "D" => is_dynamic = true,
+                "V" => ivar = Some(value),
+                _ => {}
+            }
+        }
+
+        let declaration = match property_type.and_then(parse_type) {
+            Some(property_type) => property_type.declaration(&property.name),
+            None => format!("void /* unknown type: {} */ {}", property_type.unwrap_or(""), property.name),
+        };
+        let attributes = if attributes.is_empty() { String::new() } else { format!("({})", attributes.join(", ")) };
+        let comment = match (ivar, is_dynamic) {
+            (Some(ivar), _) if ivar == property.name => format!(" // @synthesize {};", ivar),
+            (Some(ivar), _) => format!(" // @synthesize {}={};", property.name, ivar),
+            (None, true) => String::from(" // @dynamic"),
+            (None, false) => String::new(),
+        };
+        writeln!(output, "@property{} {};{}", attributes, declaration, comment).unwrap();
+
+        accessors.insert(getter);
+        accessors.extend(setter);
+    }
+    accessors
+}
+
+fn without_accessors<'a>(methods: &'a [ObjcMethod], accessors: &HashSet<String>) -> Vec<&'a ObjcMethod> {
+    methods.iter().filter(|method| !accessors.contains(&method.name)).collect()
+}
+
+fn write_methods(output: &mut String, kind: char, methods: &[ObjcMethod], accessors: &HashSet<String>) {
+    let methods = without_accessors(methods, accessors);
+    if methods.is_empty() {
+        return;
+    }
+    output.push('\n');
+    for method in methods {
+        writeln!(output, "{};", format_method(kind, method)).unwrap();
+    }
+}
+
+fn format_method(kind: char, method: &ObjcMethod) -> String {
+    let types = parse_method_types(&method.types).unwrap_or_default();
+    let type_name = |index: usize| types.get(index).map_or(String::from("id"), |objc_type| objc_type.declaration(""));
+
+    if !method.name.contains(':') {
+        return format!("{} ({}){}", kind, type_name(0), method.name);
+    }
+    // Argument types start after the return type and the implicit self and _cmd arguments.
+    let parts: Vec<String> = method.name.split_terminator(':').enumerate().map(|(index, part)| {
+        format!("{}:({})arg{}", part, type_name(index + 3), index + 1)
+    }).collect();
+    format!("{} ({}){}", kind, type_name(0), parts.join(" "))
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn method(name: &str, types: &str) -> ObjcMethod {
+        ObjcMethod { name: name.to_string(), types: types.to_string(), implementation: 0, entsize: 24 }
+    }
+
+    fn property(name: &str, attributes: &str) -> ObjcProperty {
+        ObjcProperty { name: name.to_string(), attributes: attributes.to_string() }
+    }
+
+    fn metadata() -> ObjcMetadata {
+        let widget = ObjcClass {
+            address: 0x1000,
+            name: String::from("Widget"),
+            superclass: Some(String::from("NSObject")),
+            flags: 0,
+            instance_start: 8,
+            instance_size: 40,
+            instance_methods: vec![method("title", "@16@0:8"), method("setTitle:", "v24@0:8@16"), method("moveBy:animated:", "v32@0:8{CGPoint=dd}16B32")],
+            class_methods: vec![method("sharedWidget", "@16@0:8")],
+            ivars: vec![
+                ObjcIvar { name: String::from("_title"), type_encoding: String::from("@\"NSString\""), offset: Some(8), size: 8, alignment: 8 },
+                ObjcIvar { name: String::from("_buffer"), type_encoding: String::from("[16c]"), offset: None, size: 16, alignment: 1 },
+            ],
+            properties: vec![property("title", "T@\"NSString\",C,N,V_title"), property("count", "Tq,R,N")],
+            protocols: vec![String::from("NSCopying")],
+        };
+        let category = ObjcCategory {
+            address: 0x2000,
+            name: String::from("Extras"),
+            class_name: Some(String::from("Widget")),
+            instance_methods: vec![method("extra", "v16@0:8")],
+            class_methods: Vec::new(),
+            properties: Vec::new(),
+            protocols: Vec::new(),
+        };
+        let protocol = ObjcProtocol {
+            address: 0x3000,
+            name: String::from("WidgetDelegate"),
+            protocols: vec![String::from("NSObject")],
+            instance_methods: vec![method("widgetDidChange:", "v24@0:8@\"Widget\"16")],
+            class_methods: Vec::new(),
+            optional_instance_methods: vec![method("widgetShouldChange:", "B24@0:8@\"Widget\"16")],
+            optional_class_methods: Vec::new(),
+            properties: Vec::new(),
+        };
+        ObjcMetadata {
+            image_info: None,
+            classes: vec![widget],
+            categories: vec![category],
+            protocols: vec![protocol],
+            selector_refs: Vec::new(),
+            class_refs: Vec::new(),
+            super_refs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn class_with_ivars_properties_and_methods() {
+        let headers = generate_headers(&metadata());
+        let class = &headers[headers.find("@interface Widget :").unwrap()..];
+        let class = &class[..class.find("@end").unwrap()];
+        assert_eq!(class, "@interface Widget : NSObject <NSCopying>\n\
+            {\n    NSString *_title;\t// 0x8\n    char _buffer[16];\n}\n\n\
+            @property(copy, nonatomic) NSString *title; // @synthesize title=_title;\n\
+            @property(readonly, nonatomic) long long count;\n\n\
+            + (id)sharedWidget;\n\n\
+            - (void)moveBy:(struct CGPoint)arg1 animated:(_Bool)arg2;\n");
+    }
+
+    #[test]
+    fn protocols_and_categories() {
+        let headers = generate_headers(&metadata());
+        assert!(headers.contains("@protocol WidgetDelegate <NSObject>\n\n- (void)widgetDidChange:(Widget *)arg1;\n\n@optional\n\n- (_Bool)widgetShouldChange:(Widget *)arg1;\n@end\n"));
+        assert!(headers.contains("@interface Widget (Extras)\n\n- (void)extra;\n@end\n"));
+    }
+
+    #[test]
+    fn unknown_encodings_are_kept_as_comments() {
+        let mut metadata = metadata();
+        metadata.classes[0].ivars[0].type_encoding = String::from("{broken");
+        metadata.classes[0].properties[0].attributes = String::from("T^^^,N");
+        let headers = generate_headers(&metadata);
+        assert!(headers.contains("    void /* unknown type: {broken */ _title;\t// 0x8\n"));
+        assert!(headers.contains("@property(nonatomic) void /* unknown type: ^^^ */ title;\n"));
+    }
+}
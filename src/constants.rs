@@ -3,6 +3,8 @@ pub const MH_MAGIC: u32 = 0xfeedface; // Big endian, 32 bit Mach-O
 pub const MH_CIGAM: u32 = 0xcefaedfe; // Little endian, 32 bit Mach-O
 pub const MH_MAGIC_64: u32 = 0xfeedfacf; // Big endian, 64 bit Mach-O
 pub const MH_CIGAM_64: u32 = 0xcffaedfe; // Little endian, 64 bit Mach-O
+pub const FAT_MAGIC: u32 = 0xcafebabe; // Universal binary, always big endian
+pub const FAT_MAGIC_64: u32 = 0xcafebabf; // Universal binary with 64 bit offsets
 
 // cputype
 // pub const CPU_ARCH_MASK: i32 = 0xff000000u32 as i32; // Mask for architecture bits
@@ -231,6 +233,22 @@ pub const LC_VERSION_MIN_TVOS: u32 = 0x2F; // build for AppleTV min OS version
 pub const LC_VERSION_MIN_WATCHOS: u32 = 0x30; // build for Watch min OS version
 pub const LC_NOTE: u32 = 0x31; // arbitrary data included within a Mach-O file
 pub const LC_BUILD_VERSION: u32 = 0x32; // build for platform min OS version
+pub const LC_DYLD_EXPORTS_TRIE: u32 = 0x33 | LC_REQ_DYLD; // used with linkedit_data_command, payload is trie
+pub const LC_DYLD_CHAINED_FIXUPS: u32 = 0x34 | LC_REQ_DYLD; // used with linkedit_data_command
+
+// known values for the platform field of build_version_command
+pub const PLATFORM_MACOS: u32 = 1;
+pub const PLATFORM_IOS: u32 = 2;
+pub const PLATFORM_TVOS: u32 = 3;
+pub const PLATFORM_WATCHOS: u32 = 4;
+pub const PLATFORM_BRIDGEOS: u32 = 5;
+pub const PLATFORM_MACCATALYST: u32 = 6;
+pub const PLATFORM_IOSSIMULATOR: u32 = 7;
+pub const PLATFORM_TVOSSIMULATOR: u32 = 8;
+pub const PLATFORM_WATCHOSSIMULATOR: u32 = 9;
+pub const PLATFORM_DRIVERKIT: u32 = 10;
+pub const PLATFORM_VISIONOS: u32 = 11;
+pub const PLATFORM_VISIONOSSIMULATOR: u32 = 12;
 
 // constants from vm_prot.h
 pub const VM_PROT_READ: i32 = 1;
@@ -242,3 +260,162 @@ pub const SG_HIGHVM: u32 = 0x1;
 pub const SG_FVMLIB	: u32 = 0x2;
 pub const SG_NORELOC: u32 = 0x4;
 pub const SG_PROTECTED_VERSION_1: u32 = 0x8;
+
+// LC_NOTE data owners
+pub const NOTE_OWNER_ADDRABLE_BITS: &str = "addrable bits";
+pub const NOTE_OWNER_MAIN_BIN_SPEC: &str = "main bin spec";
+pub const NOTE_OWNER_LOAD_BINARY: &str = "load binary";
+pub const NOTE_OWNER_ALL_IMAGE_INFOS: &str = "all image infos";
+pub const NOTE_OWNER_PROCESS_METADATA: &str = "process metadata";
+pub const NOTE_OWNER_KERN_VER_STR: &str = "kern ver str";
+
+// "main bin spec" binary types
+pub const MAIN_BIN_SPEC_TYPE_UNKNOWN: u32 = 0;
+pub const MAIN_BIN_SPEC_TYPE_KERNEL: u32 = 1;
+pub const MAIN_BIN_SPEC_TYPE_USER_PROCESS: u32 = 2;
+pub const MAIN_BIN_SPEC_TYPE_STANDALONE: u32 = 3;
+
+// thread state flavors (x86)
+pub const X86_THREAD_STATE32: u32 = 1;
+pub const X86_FLOAT_STATE32: u32 = 2;
+pub const X86_EXCEPTION_STATE32: u32 = 3;
+pub const X86_THREAD_STATE64: u32 = 4;
+pub const X86_FLOAT_STATE64: u32 = 5;
+pub const X86_EXCEPTION_STATE64: u32 = 6;
+pub const X86_THREAD_STATE: u32 = 7;
+pub const X86_FLOAT_STATE: u32 = 8;
+pub const X86_EXCEPTION_STATE: u32 = 9;
+
+// thread state flavors (arm)
+pub const ARM_THREAD_STATE: u32 = 1;
+pub const ARM_VFP_STATE: u32 = 2;
+pub const ARM_EXCEPTION_STATE: u32 = 3;
+pub const ARM_THREAD_STATE64: u32 = 6;
+pub const ARM_EXCEPTION_STATE64: u32 = 7;
+pub const ARM_NEON_STATE64: u32 = 17;
+
+// segment split info
+pub const DYLD_CACHE_ADJ_V2_FORMAT: u8 = 0x7F;
+
+pub const DYLD_CACHE_ADJ_V2_POINTER_32: u64 = 0x01;
+pub const DYLD_CACHE_ADJ_V2_POINTER_64: u64 = 0x02;
+pub const DYLD_CACHE_ADJ_V2_DELTA_32: u64 = 0x03;
+pub const DYLD_CACHE_ADJ_V2_DELTA_64: u64 = 0x04;
+pub const DYLD_CACHE_ADJ_V2_ARM64_ADRP: u64 = 0x05;
+pub const DYLD_CACHE_ADJ_V2_ARM64_OFF12: u64 = 0x06;
+pub const DYLD_CACHE_ADJ_V2_ARM64_BR26: u64 = 0x07;
+pub const DYLD_CACHE_ADJ_V2_ARM_MOVW_MOVT: u64 = 0x08;
+pub const DYLD_CACHE_ADJ_V2_ARM_BR24: u64 = 0x09;
+pub const DYLD_CACHE_ADJ_V2_THUMB_MOVW_MOVT: u64 = 0x0A;
+pub const DYLD_CACHE_ADJ_V2_THUMB_BR22: u64 = 0x0B;
+pub const DYLD_CACHE_ADJ_V2_IMAGE_OFF_32: u64 = 0x0C;
+pub const DYLD_CACHE_ADJ_V2_THREADED_POINTER_64: u64 = 0x0D;
+
+// n_type masks and values
+pub const N_STAB: u8 = 0xe0; // if any of these bits set, a symbolic debugging entry
+pub const N_TYPE: u8 = 0x0e; // mask for the type bits
+pub const N_UNDF: u8 = 0x0; // undefined, n_sect == NO_SECT
+pub const N_ABS: u8 = 0x2; // absolute, n_sect == NO_SECT
+pub const N_SECT: u8 = 0xe; // defined in section number n_sect
+pub const N_PBUD: u8 = 0xc; // prebound undefined (defined in a dylib)
+pub const N_INDR: u8 = 0xa; // indirect
+pub const N_PEXT: u8 = 0x10; // private external symbol bit
+pub const N_EXT: u8 = 0x01; // external symbol bit, set for external symbols
+
+// section types and indirect symbol table values
+pub const SECTION_TYPE: u32 = 0x000000ff; // mask for the section type bits of flags
+pub const S_ZEROFILL: u32 = 0x1; // zero fill on demand section
+pub const S_SYMBOL_STUBS: u32 = 0x8; // section with symbol stubs, byte size of stub in reserved2
+pub const S_GB_ZEROFILL: u32 = 0xc; // zero fill on demand section that can be larger than 4 gigabytes
+pub const S_THREAD_LOCAL_ZEROFILL: u32 = 0x12; // thread local zerofill section
+pub const INDIRECT_SYMBOL_LOCAL: u32 = 0x80000000; // indirect symbol for a symbol that was made local
+pub const INDIRECT_SYMBOL_ABS: u32 = 0x40000000; // indirect symbol for an absolute symbol
+
+// linker optimization hint kinds
+pub const LOH_ARM64_ADRP_ADRP: u64 = 1;
+pub const LOH_ARM64_ADRP_LDR: u64 = 2;
+pub const LOH_ARM64_ADRP_ADD_LDR: u64 = 3;
+pub const LOH_ARM64_ADRP_LDR_GOT_LDR: u64 = 4;
+pub const LOH_ARM64_ADRP_ADD_STR: u64 = 5;
+pub const LOH_ARM64_ADRP_LDR_GOT_STR: u64 = 6;
+pub const LOH_ARM64_ADRP_ADD: u64 = 7;
+pub const LOH_ARM64_ADRP_LDR_GOT: u64 = 8;
+
+// bind opcodes
+pub const BIND_OPCODE_MASK: u8 = 0xF0;
+pub const BIND_IMMEDIATE_MASK: u8 = 0x0F;
+pub const BIND_OPCODE_DONE: u8 = 0x00;
+pub const BIND_OPCODE_SET_DYLIB_ORDINAL_IMM: u8 = 0x10;
+pub const BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB: u8 = 0x20;
+pub const BIND_OPCODE_SET_DYLIB_SPECIAL_IMM: u8 = 0x30;
+pub const BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM: u8 = 0x40;
+pub const BIND_OPCODE_SET_TYPE_IMM: u8 = 0x50;
+pub const BIND_OPCODE_SET_ADDEND_SLEB: u8 = 0x60;
+pub const BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB: u8 = 0x70;
+pub const BIND_OPCODE_ADD_ADDR_ULEB: u8 = 0x80;
+pub const BIND_OPCODE_DO_BIND: u8 = 0x90;
+pub const BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB: u8 = 0xA0;
+pub const BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED: u8 = 0xB0;
+pub const BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB: u8 = 0xC0;
+pub const BIND_OPCODE_THREADED: u8 = 0xD0;
+
+pub const EXPORT_SYMBOL_FLAGS_KIND_MASK: u64 = 0x03;
+pub const EXPORT_SYMBOL_FLAGS_KIND_REGULAR: u64 = 0x00;
+pub const EXPORT_SYMBOL_FLAGS_KIND_THREAD_LOCAL: u64 = 0x01;
+pub const EXPORT_SYMBOL_FLAGS_KIND_ABSOLUTE: u64 = 0x02;
+pub const EXPORT_SYMBOL_FLAGS_WEAK_DEFINITION: u64 = 0x04;
+pub const EXPORT_SYMBOL_FLAGS_REEXPORT: u64 = 0x08;
+pub const EXPORT_SYMBOL_FLAGS_STUB_AND_RESOLVER: u64 = 0x10;
+
+// constants from fixup-chains.h
+pub const DYLD_CHAINED_PTR_ARM64E: u16 = 1;
+pub const DYLD_CHAINED_PTR_64: u16 = 2;
+pub const DYLD_CHAINED_PTR_32: u16 = 3;
+pub const DYLD_CHAINED_PTR_64_OFFSET: u16 = 6;
+pub const DYLD_CHAINED_PTR_ARM64E_KERNEL: u16 = 7;
+pub const DYLD_CHAINED_PTR_ARM64E_USERLAND: u16 = 9;
+pub const DYLD_CHAINED_PTR_ARM64E_FIRMWARE: u16 = 10;
+pub const DYLD_CHAINED_PTR_ARM64E_USERLAND24: u16 = 12;
+
+pub const DYLD_CHAINED_IMPORT: u32 = 1;
+pub const DYLD_CHAINED_IMPORT_ADDEND: u32 = 2;
+pub const DYLD_CHAINED_IMPORT_ADDEND64: u32 = 3;
+
+// constants from objc-runtime-new.h
+pub const OBJC_METHOD_LIST_IS_RELATIVE: u32 = 0x8000_0000;
+pub const OBJC_METHOD_LIST_USES_SELECTOR_OFFSETS: u32 = 0x4000_0000;
+pub const OBJC_METHOD_LIST_FLAGS_MASK: u32 = 0xffff_0003;
+pub const OBJC_CLASS_DATA_MASK_64: u64 = 0x0000_7fff_ffff_fff8;
+pub const OBJC_CLASS_DATA_MASK_32: u64 = 0xffff_fffc;
+pub const OBJC_CLASS_SYMBOL_PREFIX: &str = "_OBJC_CLASS_$_";
+
+// constants from swift/ABI/MetadataValues.h
+pub const SWIFT_CONTEXT_DESCRIPTOR_KIND_MASK: u32 = 0x1f;
+pub const SWIFT_CONTEXT_DESCRIPTOR_KIND_MODULE: u32 = 0;
+pub const SWIFT_CONTEXT_DESCRIPTOR_KIND_EXTENSION: u32 = 1;
+pub const SWIFT_CONTEXT_DESCRIPTOR_KIND_ANONYMOUS: u32 = 2;
+pub const SWIFT_CONTEXT_DESCRIPTOR_KIND_PROTOCOL: u32 = 3;
+pub const SWIFT_CONTEXT_DESCRIPTOR_KIND_CLASS: u32 = 16;
+pub const SWIFT_CONTEXT_DESCRIPTOR_KIND_STRUCT: u32 = 17;
+pub const SWIFT_CONTEXT_DESCRIPTOR_KIND_ENUM: u32 = 18;
+
+pub const SWIFT_TYPE_REFERENCE_DIRECT_TYPE_DESCRIPTOR: u32 = 0;
+pub const SWIFT_TYPE_REFERENCE_INDIRECT_TYPE_DESCRIPTOR: u32 = 1;
+pub const SWIFT_TYPE_REFERENCE_DIRECT_OBJC_CLASS_NAME: u32 = 2;
+pub const SWIFT_TYPE_REFERENCE_INDIRECT_OBJC_CLASS: u32 = 3;
+
+pub const SWIFT_FIELD_RECORD_IS_INDIRECT_CASE: u32 = 0x1;
+pub const SWIFT_FIELD_RECORD_IS_VAR: u32 = 0x2;
+
+pub const SWIFT_SYMBOLIC_REFERENCE_DIRECT_CONTEXT: u8 = 0x01;
+pub const SWIFT_SYMBOLIC_REFERENCE_INDIRECT_CONTEXT: u8 = 0x02;
+
+// constants from Go's debug/buildinfo and runtime/symtab.go
+pub const GO_BUILDINFO_MAGIC: &[u8] = b"\xff Go buildinf:";
+pub const GO_BUILDINFO_HEADER_SIZE: u64 = 32;
+pub const GO_BUILDINFO_FLAG_INLINE_STRINGS: u8 = 0x2;
+
+pub const GO_PCLNTAB_MAGIC_12: u32 = 0xfffffffb;
+pub const GO_PCLNTAB_MAGIC_116: u32 = 0xfffffffa;
+pub const GO_PCLNTAB_MAGIC_118: u32 = 0xfffffff0;
+pub const GO_PCLNTAB_MAGIC_120: u32 = 0xfffffff1;
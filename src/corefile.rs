@@ -0,0 +1,299 @@
+use std::io::{self, Cursor, Read, Seek};
+
+use byteorder::ReadBytesExt;
+
+use crate::constants::*;
+use crate::error::AppError;
+use crate::load_commands::*;
+use crate::memory_utils::*;
+
+#[derive(Debug)]
+pub struct CoreFile {
+    pub threads: Vec<CoreThread>,
+    pub memory_regions: Vec<MemoryRegion>,
+}
+
+#[derive(Debug)]
+pub struct CoreThread {
+    pub states: Vec<RegisterState>,
+}
+
+#[derive(Debug)]
+pub struct RegisterState {
+    pub flavor: u32,
+    pub flavor_name: &'static str,
+    pub registers: Option<Vec<Register>>,
+    pub raw: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct Register {
+    pub name: String,
+    pub value: u64,
+}
+
+#[derive(Debug)]
+pub struct MemoryRegion {
+    pub segname: [u8; 16],
+    pub vmaddr: u64,
+    pub vmsize: u64,
+    pub fileoff: u64,
+    pub filesize: u64,
+    pub initprot: i32,
+}
+
+impl MemoryRegion {
+    pub fn contains(&self, vmaddr: u64) -> bool {
+        vmaddr >= self.vmaddr && vmaddr - self.vmaddr < self.vmsize
+    }
+}
+
+impl CoreFile {
+    pub fn from_load_commands<E: byteorder::ByteOrder>(load_commands: &[LoadCommandEntry], cputype: i32) -> Result<CoreFile, AppError> {
+        let mut core_file = CoreFile {
+            threads: Vec::new(),
+            memory_regions: Vec::new(),
+        };
+
+        for load_command in load_commands {
+            match &load_command.command {
+                LoadCommand::ThreadCommand(command) => {
+                    let mut states = Vec::new();
+                    for thread_state in &command.thread_states {
+                        states.push(RegisterState::from_thread_state::<E>(thread_state.flavor, &thread_state.state, cputype)?);
+                    }
+                    core_file.threads.push(CoreThread { states });
+                }
+                LoadCommand::SegmentCommand(command) => core_file.push_region(command),
+                _ => {}
+            }
+        }
+
+        core_file.memory_regions.sort_by_key(|region| region.vmaddr);
+        Ok(core_file)
+    }
+
+    fn push_region<S: Segment + ?Sized>(&mut self, segment: &S) {
+        if segment.vmsize() == 0 {
+            return;
+        }
+        self.memory_regions.push(MemoryRegion {
+            segname: *segment.segname(),
+            vmaddr: segment.vmaddr(),
+            vmsize: segment.vmsize(),
+            fileoff: segment.fileoff(),
+            filesize: segment.filesize(),
+            initprot: segment.initprot(),
+        });
+    }
+
+    pub fn region_for_address(&self, vmaddr: u64) -> Option<&MemoryRegion> {
+        self.memory_regions.iter().find(|region| region.contains(vmaddr))
+    }
+
+    pub fn vm_to_file_offset(&self, vmaddr: u64) -> Option<u64> {
+        let region = self.region_for_address(vmaddr)?;
+        let offset_in_region = vmaddr - region.vmaddr;
+        if offset_in_region < region.filesize {
+            region.fileoff.checked_add(offset_in_region)
+        } else {
+            None
+        }
+    }
+
+    /// Reads `size` bytes of the dumped process memory starting at `vmaddr`. The range may span
+    /// several adjacent regions; parts of a region beyond its `filesize` read back as zeros.
+    pub fn read_memory<R: Read + Seek>(&self, file: &mut R, vmaddr: u64, size: u64) -> Result<Vec<u8>, AppError> {
+        // Map the whole range before allocating for it, so the buffer never outgrows the regions.
+        let chunks = self.chunks_for_range(vmaddr, size)?;
+        let mut memory = Vec::new();
+        usize::try_from(size).ok().and_then(|size| memory.try_reserve_exact(size).ok()).ok_or_else(|| {
+            AppError::from(io::Error::new(io::ErrorKind::OutOfMemory, format!("cannot allocate 0x{:x} bytes of memory", size)))
+        })?;
+
+        for (region, offset_in_region, chunk_size) in chunks {
+            let file_backed_size = chunk_size.min(region.filesize.saturating_sub(offset_in_region));
+            if file_backed_size > 0 {
+                let fileoff = region.fileoff.checked_add(offset_in_region)
+                    .ok_or(AppError::OutOfBounds { what: "core file segment", offset: region.fileoff, size: region.filesize })?;
+                memory.extend(read_bytes_at(file, fileoff, file_backed_size).map_err(AppError::out_of_bounds("core file segment", fileoff, file_backed_size))?);
+            }
+            memory.resize(memory.len() + (chunk_size - file_backed_size) as usize, 0);
+        }
+
+        Ok(memory)
+    }
+
+    /// Splits a range into (region, offset in the region, size) pieces, one per region it spans.
+    fn chunks_for_range(&self, vmaddr: u64, size: u64) -> Result<Vec<(&MemoryRegion, u64, u64)>, AppError> {
+        let mut chunks = Vec::new();
+        let mut address = vmaddr;
+        let mut remaining = size;
+
+        while remaining > 0 {
+            let region = self.region_for_address(address).ok_or_else(|| {
+                AppError::from(io::Error::new(io::ErrorKind::InvalidInput, format!("address 0x{:x} is not mapped in the core file", address)))
+            })?;
+            let offset_in_region = address - region.vmaddr;
+            let chunk_size = remaining.min(region.vmsize - offset_in_region);
+            chunks.push((region, offset_in_region, chunk_size));
+
+            remaining -= chunk_size;
+            if remaining > 0 {
+                address = address.checked_add(chunk_size).ok_or_else(|| {
+                    AppError::from(io::Error::new(io::ErrorKind::InvalidInput, format!("memory range at 0x{:x} wraps around the address space", vmaddr)))
+                })?;
+            }
+        }
+
+        Ok(chunks)
+    }
+}
+
+impl RegisterState {
+    pub fn from_thread_state<E: byteorder::ByteOrder>(flavor: u32, state: &[u8], cputype: i32) -> Result<RegisterState, AppError> {
+        let mut cursor = Cursor::new(state);
+        let (flavor_name, registers) = match (cputype, flavor) {
+            (CPU_TYPE_X86 | CPU_TYPE_X86_64, X86_THREAD_STATE | X86_EXCEPTION_STATE) => {
+                // The generic flavors wrap the actual state in an x86_state_hdr.
+                let inner_flavor = cursor.read_u32::<E>()?;
+                cursor.read_u32::<E>()?; // count
+                let inner = RegisterState::from_thread_state::<E>(inner_flavor, &state[8..], cputype)?;
+                (inner.flavor_name, inner.registers)
+            }
+            (CPU_TYPE_X86 | CPU_TYPE_X86_64, X86_THREAD_STATE32) => ("x86_THREAD_STATE32", Some(read_registers_32::<E>(&mut cursor, &X86_THREAD_STATE32_REGISTERS)?)),
+            (CPU_TYPE_X86 | CPU_TYPE_X86_64, X86_THREAD_STATE64) => ("x86_THREAD_STATE64", Some(read_registers_64::<E>(&mut cursor, &X86_THREAD_STATE64_REGISTERS)?)),
+            (CPU_TYPE_X86 | CPU_TYPE_X86_64, X86_EXCEPTION_STATE32) => ("x86_EXCEPTION_STATE32", Some(read_x86_exception_state::<E>(&mut cursor, false)?)),
+            (CPU_TYPE_X86 | CPU_TYPE_X86_64, X86_EXCEPTION_STATE64) => ("x86_EXCEPTION_STATE64", Some(read_x86_exception_state::<E>(&mut cursor, true)?)),
+            (CPU_TYPE_X86 | CPU_TYPE_X86_64, X86_FLOAT_STATE32) => ("x86_FLOAT_STATE32", None),
+            (CPU_TYPE_X86 | CPU_TYPE_X86_64, X86_FLOAT_STATE64) => ("x86_FLOAT_STATE64", None),
+            (CPU_TYPE_X86 | CPU_TYPE_X86_64, X86_FLOAT_STATE) => ("x86_FLOAT_STATE", None),
+            (CPU_TYPE_ARM, ARM_THREAD_STATE) => ("ARM_THREAD_STATE", Some(read_registers_32::<E>(&mut cursor, &ARM_THREAD_STATE_REGISTERS)?)),
+            (CPU_TYPE_ARM, ARM_EXCEPTION_STATE) => ("ARM_EXCEPTION_STATE", Some(read_registers_32::<E>(&mut cursor, &["exception", "fsr", "far"])?)),
+            (CPU_TYPE_ARM, ARM_VFP_STATE) => ("ARM_VFP_STATE", None),
+            (CPU_TYPE_ARM64, ARM_THREAD_STATE64) => ("ARM_THREAD_STATE64", Some(read_arm_thread_state64::<E>(&mut cursor)?)),
+            (CPU_TYPE_ARM64, ARM_EXCEPTION_STATE64) => ("ARM_EXCEPTION_STATE64", Some(read_arm_exception_state64::<E>(&mut cursor)?)),
+            (CPU_TYPE_ARM64, ARM_NEON_STATE64) => ("ARM_NEON_STATE64", None),
+            _ => ("Unrecognized flavor", None),
+        };
+
+        Ok(RegisterState {
+            flavor,
+            flavor_name,
+            registers,
+            raw: state.to_vec(),
+        })
+    }
+}
+
+const X86_THREAD_STATE32_REGISTERS: [&str; 16] = [
+    "eax", "ebx", "ecx", "edx", "edi", "esi", "ebp", "esp",
+    "ss", "eflags", "eip", "cs", "ds", "es", "fs", "gs",
+];
+
+const X86_THREAD_STATE64_REGISTERS: [&str; 21] = [
+    "rax", "rbx", "rcx", "rdx", "rdi", "rsi", "rbp", "rsp",
+    "r8", "r9", "r10", "r11", "r12", "r13", "r14", "r15",
+    "rip", "rflags", "cs", "fs", "gs",
+];
+
+const ARM_THREAD_STATE_REGISTERS: [&str; 17] = [
+    "r0", "r1", "r2", "r3", "r4", "r5", "r6", "r7", "r8",
+    "r9", "r10", "r11", "r12", "sp", "lr", "pc", "cpsr",
+];
+
+fn read_registers_32<E: byteorder::ByteOrder>(cursor: &mut Cursor<&[u8]>, names: &[&str]) -> Result<Vec<Register>, AppError> {
+    let mut registers = Vec::new();
+    for name in names {
+        registers.push(Register { name: name.to_string(), value: cursor.read_u32::<E>()? as u64 });
+    }
+    Ok(registers)
+}
+
+fn read_registers_64<E: byteorder::ByteOrder>(cursor: &mut Cursor<&[u8]>, names: &[&str]) -> Result<Vec<Register>, AppError> {
+    let mut registers = Vec::new();
+    for name in names {
+        registers.push(Register { name: name.to_string(), value: cursor.read_u64::<E>()? });
+    }
+    Ok(registers)
+}
+
+fn read_x86_exception_state<E: byteorder::ByteOrder>(cursor: &mut Cursor<&[u8]>, is_64: bool) -> Result<Vec<Register>, AppError> {
+    let trapno = cursor.read_u16::<E>()?;
+    let cpu = cursor.read_u16::<E>()?;
+    let err = cursor.read_u32::<E>()?;
+    let faultvaddr = if is_64 { cursor.read_u64::<E>()? } else { cursor.read_u32::<E>()? as u64 };
+    Ok(vec![
+        Register { name: String::from("trapno"), value: trapno as u64 },
+        Register { name: String::from("cpu"), value: cpu as u64 },
+        Register { name: String::from("err"), value: err as u64 },
+        Register { name: String::from("faultvaddr"), value: faultvaddr },
+    ])
+}
+
+fn read_arm_thread_state64<E: byteorder::ByteOrder>(cursor: &mut Cursor<&[u8]>) -> Result<Vec<Register>, AppError> {
+    let mut registers = Vec::new();
+    for index in 0..29 {
+        registers.push(Register { name: format!("x{}", index), value: cursor.read_u64::<E>()? });
+    }
+    registers.extend(read_registers_64::<E>(cursor, &["fp", "lr", "sp", "pc"])?);
+    registers.push(Register { name: String::from("cpsr"), value: cursor.read_u32::<E>()? as u64 });
+    Ok(registers)
+}
+
+fn read_arm_exception_state64<E: byteorder::ByteOrder>(cursor: &mut Cursor<&[u8]>) -> Result<Vec<Register>, AppError> {
+    let far = cursor.read_u64::<E>()?;
+    let esr = cursor.read_u32::<E>()?;
+    let exception = cursor.read_u32::<E>()?;
+    Ok(vec![
+        Register { name: String::from("far"), value: far },
+        Register { name: String::from("esr"), value: esr as u64 },
+        Register { name: String::from("exception"), value: exception as u64 },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(vmaddr: u64, vmsize: u64, fileoff: u64, filesize: u64) -> MemoryRegion {
+        MemoryRegion { segname: [0; 16], vmaddr, vmsize, fileoff, filesize, initprot: 0 }
+    }
+
+    fn core_file(memory_regions: Vec<MemoryRegion>) -> CoreFile {
+        CoreFile { threads: Vec::new(), memory_regions }
+    }
+
+    #[test]
+    fn read_memory_spans_regions_and_zero_fills() {
+        let core = core_file(vec![region(0x1000, 0x4, 0, 0x2), region(0x1004, 0x4, 2, 0x4)]);
+        let memory = core.read_memory(&mut Cursor::new(vec![1, 2, 3, 4, 5, 6]), 0x1001, 0x6).unwrap();
+        assert_eq!(memory, [2, 0, 0, 3, 4, 5]);
+    }
+
+    #[test]
+    fn read_memory_rejects_a_file_offset_that_overflows() {
+        let core = core_file(vec![region(0x1000, 0x100, u64::MAX - 0x10, 0x100)]);
+        assert!(matches!(core.read_memory(&mut Cursor::new(vec![0; 0x10]), 0x1080, 0x10), Err(AppError::OutOfBounds { .. })));
+        assert_eq!(core.vm_to_file_offset(0x1080), None);
+    }
+
+    #[test]
+    fn read_memory_rejects_a_range_that_wraps_around() {
+        let core = core_file(vec![region(u64::MAX - 0xf, 0x10, 0, 0)]);
+        assert!(core.read_memory(&mut Cursor::new(Vec::new()), u64::MAX - 0xf, 0x20).is_err());
+        assert_eq!(core.read_memory(&mut Cursor::new(Vec::new()), u64::MAX - 0xf, 0x10).unwrap(), [0; 0x10]);
+    }
+
+    #[test]
+    fn read_memory_does_not_allocate_for_unmapped_ranges() {
+        let core = core_file(vec![region(0x1000, 0x10, 0, 0)]);
+        assert!(core.read_memory(&mut Cursor::new(Vec::new()), 0x1000, u64::MAX).is_err());
+    }
+
+    #[test]
+    fn read_memory_fails_instead_of_aborting_on_huge_zero_fill() {
+        let core = core_file(vec![region(0, u64::MAX, 0, 0)]);
+        assert!(core.read_memory(&mut Cursor::new(Vec::new()), 0, u64::MAX - 1).is_err());
+    }
+}
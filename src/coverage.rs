@@ -0,0 +1,193 @@
+//! Maps which byte ranges of the file the image's structures account for, to spot data that no
+//! load command points at: payloads hidden in the header padding, between `__LINKEDIT` tables or
+//! appended after the code signature.
+//!
+//! ```
+//! use std::fs::File;
+//! use mach_o_rust::coverage::FileCoverage;
+//!
+//! let mut file = File::open("test_files/mach_o_ex1")?;
+//! let mach_o = mach_o_rust::parse(&mut file)?;
+//! let coverage = FileCoverage::from_file(&mut file, &mach_o)?;
+//! if let Some(trailing) = &coverage.trailing {
+//!     println!("0x{:x} bytes appended after {}", trailing.size, trailing.after);
+//! }
+//! # Ok::<(), mach_o_rust::AppError>(())
+//! ```
+
+use std::io::{Read, Seek};
+
+use crate::error::AppError;
+use crate::header::Header;
+use crate::load_commands::*;
+use crate::mach_o::MachO;
+use crate::memory_utils::*;
+
+/// Bytes read at a time when scanning a gap.
+const SCAN_CHUNK_SIZE: u64 = 1 << 16;
+
+#[derive(Debug)]
+pub struct CoveredRange {
+    /// What the bytes hold, e.g. `"load commands"` or `"section __TEXT,__text"`.
+    pub name: String,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Bytes that no structure accounts for.
+#[derive(Debug)]
+pub struct Gap {
+    pub offset: u64,
+    pub size: u64,
+    /// Name of the structure the gap follows.
+    pub after: String,
+    /// Padding is all zeros, so anything else in a gap deserves a closer look.
+    pub nonzero_bytes: u64,
+}
+
+#[derive(Debug)]
+pub struct FileCoverage {
+    pub file_size: u64,
+    /// Structures sorted by offset. Ranges may overlap or extend past the end of a malformed file.
+    pub structures: Vec<CoveredRange>,
+    /// Unaccounted bytes between structures, including the header padding.
+    pub gaps: Vec<Gap>,
+    /// Bytes after the end of the last structure.
+    pub trailing: Option<Gap>,
+}
+
+impl FileCoverage {
+    pub fn from_file<R: Read + Seek>(file: &mut R, mach_o: &MachO) -> Result<FileCoverage, AppError> {
+        let file_size = get_file_size(file)?;
+        let mut structures = structures(mach_o);
+        structures.retain(|range| range.size != 0);
+        structures.sort_by_key(|range| (range.offset, range.size));
+
+        let mut gaps = Vec::new();
+        let mut end = 0;
+        let mut after = "";
+        for range in &structures {
+            if range.offset > end && end < file_size {
+                gaps.push(scan_gap(file, end, range.offset.min(file_size), after)?);
+            }
+            let range_end = range.offset.saturating_add(range.size);
+            if range_end > end {
+                end = range_end;
+                after = &range.name;
+            }
+        }
+        let trailing = if end < file_size { Some(scan_gap(file, end, file_size, after)?) } else { None };
+
+        Ok(FileCoverage { file_size, structures, gaps, trailing })
+    }
+
+    /// Number of bytes inside the file that some structure accounts for.
+    pub fn covered_bytes(&self) -> u64 {
+        let gap_bytes: u64 = self.gaps.iter().chain(&self.trailing).map(|gap| gap.size).sum();
+        self.file_size - gap_bytes.min(self.file_size)
+    }
+}
+
+fn structures(mach_o: &MachO) -> Vec<CoveredRange> {
+    let header_size = if mach_o.header.is_64() { 32 } else { 28 };
+    let mut structures = vec![
+        CoveredRange { name: String::from("mach header"), offset: 0, size: header_size },
+        CoveredRange { name: String::from("load commands"), offset: header_size, size: mach_o.header.sizeofcmds() as u64 },
+    ];
+
+    for (segment, sections) in mach_o.segments() {
+        let segname = fixed_bytes_to_string(segment.segname());
+        // Segments without sections, such as the memory regions of a core file, hold their data
+        // directly. __LINKEDIT is broken down into its tables below.
+        if sections.is_empty() && segname != "__LINKEDIT" {
+            structures.push(CoveredRange { name: format!("segment {}", segname), offset: segment.fileoff(), size: segment.filesize() });
+        }
+        for section in sections {
+            let name = format!("{},{}", fixed_bytes_to_string(section.segname()), fixed_bytes_to_string(section.sectname()));
+            if !section.is_zerofill() {
+                structures.push(CoveredRange { name: format!("section {}", name), offset: section.offset() as u64, size: section.size() });
+            }
+            // Relocation entries are 8 bytes each.
+            structures.push(CoveredRange { name: format!("relocations of {}", name), offset: section.reloff() as u64, size: section.nreloc() as u64 * 8 });
+        }
+    }
+
+    for blob in mach_o.linkedit_blobs() {
+        structures.push(CoveredRange { name: blob.name.to_string(), offset: blob.offset, size: blob.size });
+    }
+    for entry in &mach_o.load_commands {
+        if let LoadCommand::NoteCommand(command) = &entry.command {
+            structures.push(CoveredRange { name: format!("note {}", fixed_bytes_to_string(&command.data_owner)), offset: command.offset, size: command.size });
+        }
+    }
+    structures
+}
+
+fn scan_gap<R: Read + Seek>(file: &mut R, start: u64, end: u64, after: &str) -> Result<Gap, AppError> {
+    let mut nonzero_bytes = 0;
+    let mut offset = start;
+    while offset < end {
+        let chunk = read_bytes_up_to(file, offset, (end - offset).min(SCAN_CHUNK_SIZE))?;
+        if chunk.is_empty() {
+            break;
+        }
+        nonzero_bytes += chunk.iter().filter(|&&byte| byte != 0).count() as u64;
+        offset += chunk.len() as u64;
+    }
+    Ok(Gap { offset: start, size: end - start, after: after.to_string(), nonzero_bytes })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::constants::*;
+    use crate::test_utils::*;
+
+    fn coverage(payloads: &[(u64, &[u8])]) -> FileCoverage {
+        let commands = [
+            segment_64("__TEXT", 0, 0x4000, 0, 0x4000, &[("__text", 0x1000, 0x100, 0x1000)]),
+            segment_64("__LINKEDIT", 0x4000, 0x1000, 0x4000, 0x1000, &[]),
+            command(LC_SYMTAB, &[0x4000, 1, 0x4010, 8]),
+            command(LC_CODE_SIGNATURE, &[0x4020, 0x10]),
+        ];
+        let symbol = nlist_64(1, N_SECT | N_EXT, 1, 0x1000);
+        let mut payloads = payloads.to_vec();
+        payloads.extend([(0x1000, &[0xff; 0x100][..]), (0x4000, &symbol), (0x4010, b"\0_main\0\0"), (0x4020, &[0xfa; 0x10])]);
+        let data = image(&commands, &payloads);
+        let mach_o = MachO::from_bytes(&data).unwrap();
+        FileCoverage::from_file(&mut Cursor::new(&data), &mach_o).unwrap()
+    }
+
+    fn gaps(coverage: &FileCoverage) -> Vec<(u64, u64, &str, u64)> {
+        coverage.gaps.iter().map(|gap| (gap.offset, gap.size, gap.after.as_str(), gap.nonzero_bytes)).collect()
+    }
+
+    #[test]
+    fn gaps_between_structures() {
+        let coverage = coverage(&[(0x800, b"hidden"), (0x4fff, &[0])]);
+        assert_eq!(gaps(&coverage), [
+            (0x128, 0xed8, "load commands", 6),
+            (0x1100, 0x2f00, "section __TEXT,__text", 0),
+            (0x4018, 0x8, "string table", 0),
+        ]);
+        let trailing = coverage.trailing.as_ref().unwrap();
+        assert_eq!((trailing.offset, trailing.size, trailing.after.as_str(), trailing.nonzero_bytes), (0x4030, 0xfd0, "code signature", 0));
+        assert_eq!(coverage.covered_bytes(), 0x5000 - 0xed8 - 0x2f00 - 0x8 - 0xfd0);
+    }
+
+    #[test]
+    fn data_appended_after_the_code_signature() {
+        let coverage = coverage(&[(0x4030, b"\xde\xad\0\xbe\xef")]);
+        let trailing = coverage.trailing.as_ref().unwrap();
+        assert_eq!((trailing.offset, trailing.size, trailing.after.as_str(), trailing.nonzero_bytes), (0x4030, 5, "code signature", 4));
+    }
+
+    #[test]
+    fn fully_covered_file_has_no_trailing_data() {
+        let coverage = coverage(&[]);
+        assert!(coverage.trailing.is_none());
+        assert_eq!(coverage.file_size, 0x4030);
+    }
+}
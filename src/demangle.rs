@@ -0,0 +1,29 @@
+use crate::itanium_demangle;
+use crate::rust_demangle;
+use crate::swift_demangle;
+
+/// How much of a demangled symbol to print.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DemangleStyle {
+    /// Qualified names with parameter and return types
+    Full,
+    /// Qualified names only
+    NameOnly,
+}
+
+/// How demangled symbols are printed, as chosen on the command line.
+#[derive(Clone, Copy, Debug)]
+pub struct DemangleOptions {
+    pub style: DemangleStyle,
+    /// Leave out the hashes rustc adds to keep symbols unique
+    pub hide_hash: bool,
+}
+
+/// Demangles a symbol name in any of the manglings we understand. Returns `None` for names
+/// that are not mangled or could not be decoded, which callers print as they are.
+pub fn demangle_symbol(name: &str, options: DemangleOptions) -> Option<String> {
+    // Legacy Rust symbols are valid C++ names too, so Rust gets the first look.
+    swift_demangle::demangle_symbol(name, options.style)
+        .or_else(|| rust_demangle::demangle_symbol(name, options.hide_hash))
+        .or_else(|| itanium_demangle::demangle_symbol(name, options.style))
+}
@@ -0,0 +1,77 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The structure was truncated, but what could be read is kept. From [`crate::lint`], the
+    /// image loads but breaks a convention tools rely on.
+    Warning,
+    /// The structure was skipped. From [`crate::lint`], dyld or the kernel would refuse the image.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem the lenient parser recovered from, or a finding of [`crate::lint`].
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// File offset of the broken structure, when the error names one.
+    pub offset: Option<u64>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    pub fn from_error(severity: Severity, error: &AppError) -> Diagnostic {
+        let mut message = error.to_string();
+        let mut source = error.source();
+        while let Some(cause) = source {
+            message.push_str(": ");
+            message.push_str(&cause.to_string());
+            source = cause.source();
+        }
+        Diagnostic { severity, offset: error.offset(), message }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.severity, self.message)?;
+        match self.offset {
+            Some(offset) if !names_offset(&self.message, offset) => write!(f, " at 0x{:x}", offset),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Whether `message` already spells out `offset`, as the messages of errors that carry one do.
+fn names_offset(message: &str, offset: u64) -> bool {
+    let offset = format!("0x{:x}", offset);
+    message.match_indices(&offset).any(|(index, _)| {
+        !message[index + offset.len()..].starts_with(|character: char| character.is_ascii_hexdigit())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_locates_the_problem() {
+        let diagnostic = Diagnostic::from_error(Severity::Warning, &AppError::OutOfBounds { what: "string table", offset: 0x100, size: 0x20 });
+        assert_eq!(diagnostic.to_string(), "warning: string table at 0x100-0x120 extends past the end of the file");
+        let diagnostic = Diagnostic { severity: Severity::Error, offset: Some(0x100), message: String::from("cmdsize 0x1000 is not a multiple of 8") };
+        assert_eq!(diagnostic.to_string(), "error: cmdsize 0x1000 is not a multiple of 8 at 0x100");
+        let diagnostic = Diagnostic { severity: Severity::Error, offset: None, message: String::from("bad") };
+        assert_eq!(diagnostic.to_string(), "error: bad");
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::HashSet;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+use byteorder::ReadBytesExt;
+
+use crate::constants::*;
+use crate::error::AppError;
+use crate::load_commands::*;
+use crate::memory_utils::*;
+use crate::parser::ParseLimits;
+
+/// Deepest export trie path followed; each level is a recursive call.
+const MAX_EXPORT_TRIE_DEPTH: usize = 1024;
+
+#[derive(Clone, Copy, PartialEq)]
+enum BindKind {
+    Regular,
+    Weak,
+    Lazy,
+}
+
+#[derive(Debug)]
+pub struct BindRecord {
+    pub address: u64,
+    pub symbol_name: String,
+}
+
+pub fn parse_binds<R: Read + Seek>(file: &mut R, load_commands: &[LoadCommandEntry], pointer_size: u64, limits: &ParseLimits) -> Result<Vec<BindRecord>, AppError> {
+    let segment_addresses: Vec<u64> = load_commands.iter().filter_map(|load_command| match &load_command.command {
+        LoadCommand::SegmentCommand(segment) => Some(segment.vmaddr()),
+        _ => None,
+    }).collect();
+
+    let mut binds = Vec::new();
+    let mut name_bytes = 0u64;
+    for load_command in load_commands {
+        if let LoadCommand::DyldInfoCommand(command) = &load_command.command {
+            let streams = [
+                (BindKind::Regular, command.bind_off, command.bind_size),
+                (BindKind::Weak, command.weak_bind_off, command.weak_bind_size),
+                (BindKind::Lazy, command.lazy_bind_off, command.lazy_bind_size),
+            ];
+            for (kind, offset, size) in streams {
+                if size == 0 {
+                    continue;
+                }
+                AppError::check_limit("bind opcode byte", size as u64, limits.max_table_size)?;
+                let opcodes = read_bytes_at(file, offset as u64, size as u64).map_err(AppError::out_of_bounds("bind opcodes", offset as u64, size as u64))?;
+                parse_bind_opcodes(&opcodes, kind, &segment_addresses, pointer_size, limits, &mut name_bytes, &mut binds)?;
+            }
+        }
+    }
+    Ok(binds)
+}
+
+// `name_bytes` counts the symbol name bytes copied into `binds`, since a single opcode can repeat
+// a long name many times.
+fn parse_bind_opcodes(opcodes: &[u8], kind: BindKind, segment_addresses: &[u64], pointer_size: u64, limits: &ParseLimits, name_bytes: &mut u64, binds: &mut Vec<BindRecord>) -> Result<(), AppError> {
+    let mut cursor = Cursor::new(opcodes);
+    let mut segment_index = 0u8;
+    let mut segment_offset = 0u64;
+    let mut symbol_name = String::new();
+
+    while (cursor.position() as usize) < opcodes.len() {
+        let byte = cursor.read_u8()?;
+        let immediate = byte & BIND_IMMEDIATE_MASK;
+        let mut bind_count = 0u64;
+        let mut skip = 0u64;
+
+        match byte & BIND_OPCODE_MASK {
+            BIND_OPCODE_DONE => {
+                // The lazy bind stream separates its entries with DONE opcodes.
+                if kind != BindKind::Lazy {
+                    break;
+                }
+            }
+            BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | BIND_OPCODE_SET_DYLIB_SPECIAL_IMM | BIND_OPCODE_SET_TYPE_IMM => {}
+            BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB => {
+                read_uleb128(&mut cursor)?;
+            }
+            BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM => symbol_name = read_c_string(&mut cursor)?,
+            BIND_OPCODE_SET_ADDEND_SLEB => {
+                read_sleb128(&mut cursor)?;
+            }
+            BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB => {
+                segment_index = immediate;
+                segment_offset = read_uleb128(&mut cursor)?;
+            }
+            BIND_OPCODE_ADD_ADDR_ULEB => segment_offset = segment_offset.wrapping_add(read_uleb128(&mut cursor)?),
+            BIND_OPCODE_DO_BIND => bind_count = 1,
+            BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB => {
+                bind_count = 1;
+                skip = read_uleb128(&mut cursor)?;
+            }
+            BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED => {
+                bind_count = 1;
+                skip = immediate as u64 * pointer_size;
+            }
+            BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB => {
+                bind_count = read_uleb128(&mut cursor)?;
+                skip = read_uleb128(&mut cursor)?;
+            }
+            // Threaded binds are applied by walking chained pointers, which carry their own targets.
+            BIND_OPCODE_THREADED => break,
+            _ => return Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("unknown bind opcode 0x{:x}", byte)))),
+        }
+
+        AppError::check_limit("bind record", (binds.len() as u64).saturating_add(bind_count), limits.max_records)?;
+        *name_bytes = name_bytes.saturating_add(bind_count.saturating_mul(symbol_name.len() as u64));
+        AppError::check_limit("bind symbol name byte", *name_bytes, limits.max_table_size)?;
+        for _ in 0..bind_count {
+            let segment_address = segment_addresses.get(segment_index as usize).ok_or_else(|| {
+                AppError::from(io::Error::new(io::ErrorKind::InvalidData, "bind opcode references an unknown segment"))
+            })?;
+            binds.push(BindRecord {
+                address: segment_address.wrapping_add(segment_offset),
+                symbol_name: symbol_name.clone(),
+            });
+            segment_offset = segment_offset.wrapping_add(pointer_size).wrapping_add(skip);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct ChainedFixups {
+    /// Pointer format of the chains in each segment, indexed like the segment load commands.
+    pub pointer_formats: Vec<Option<u16>>,
+    /// Symbol names of the imports, indexed by bind ordinal.
+    pub imports: Vec<String>,
+}
+
+impl ChainedFixups {
+    pub fn from_file<R: Read + Seek, E: byteorder::ByteOrder>(file: &mut R, command: &LinkeditDataCommand, limits: &ParseLimits) -> Result<ChainedFixups, AppError> {
+        AppError::check_limit("chained fixups byte", command.datasize as u64, limits.max_table_size)?;
+        let data = command.read_data(file)?;
+        let mut cursor = Cursor::new(data.as_slice());
+
+        // dyld_chained_fixups_header
+        cursor.read_u32::<E>()?; // fixups_version
+        let starts_offset = cursor.read_u32::<E>()? as u64;
+        let imports_offset = cursor.read_u32::<E>()? as u64;
+        let symbols_offset = cursor.read_u32::<E>()? as u64;
+        let imports_count = cursor.read_u32::<E>()?;
+        AppError::check_limit("chained fixup import", imports_count as u64, limits.max_symbols)?;
+        let imports_format = cursor.read_u32::<E>()?;
+        let symbols_format = cursor.read_u32::<E>()?;
+        if symbols_format != 0 {
+            return Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, "compressed chained fixup symbols are not supported")));
+        }
+
+        // dyld_chained_starts_in_image, followed by a dyld_chained_starts_in_segment per segment
+        cursor.seek(SeekFrom::Start(starts_offset))?;
+        let seg_count = cursor.read_u32::<E>()?;
+        let mut seg_info_offsets = Vec::new();
+        for _ in 0..seg_count {
+            seg_info_offsets.push(cursor.read_u32::<E>()? as u64);
+        }
+        let mut pointer_formats = Vec::new();
+        for seg_info_offset in seg_info_offsets {
+            if seg_info_offset == 0 {
+                pointer_formats.push(None);
+                continue;
+            }
+            cursor.seek(SeekFrom::Start(starts_offset + seg_info_offset))?;
+            cursor.read_u32::<E>()?; // size
+            cursor.read_u16::<E>()?; // page_size
+            pointer_formats.push(Some(cursor.read_u16::<E>()?));
+        }
+
+        cursor.seek(SeekFrom::Start(imports_offset))?;
+        let mut imports = Vec::new();
+        let mut name_bytes = 0u64;
+        for _ in 0..imports_count {
+            // Only the name offset matters to us; ordinals, weak flags and addends are skipped.
+            let name_offset = match imports_format {
+                DYLD_CHAINED_IMPORT => (cursor.read_u32::<E>()? >> 9) as u64,
+                DYLD_CHAINED_IMPORT_ADDEND => {
+                    let raw = cursor.read_u32::<E>()?;
+                    cursor.read_i32::<E>()?; // addend
+                    (raw >> 9) as u64
+                }
+                DYLD_CHAINED_IMPORT_ADDEND64 => {
+                    let raw = cursor.read_u64::<E>()?;
+                    cursor.read_u64::<E>()?; // addend
+                    raw >> 32
+                }
+                _ => return Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("unknown chained imports format {}", imports_format)))),
+            };
+            let position = cursor.position();
+            cursor.seek(SeekFrom::Start(symbols_offset + name_offset))?;
+            let symbol_name = read_c_string(&mut cursor)?;
+            cursor.seek(SeekFrom::Start(position))?;
+            // Imports may share a name, so the names can add up to more than the data itself.
+            name_bytes += symbol_name.len() as u64;
+            AppError::check_limit("chained fixup import name byte", name_bytes, limits.max_table_size)?;
+            imports.push(symbol_name);
+        }
+
+        Ok(ChainedFixups { pointer_formats, imports })
+    }
+}
+
+#[derive(Debug)]
+pub struct ExportRecord {
+    pub name: String,
+    pub flags: u64,
+    /// Offset of the symbol from the start of the image, or its value for absolute symbols.
+    pub address: u64,
+    /// Resolver function offset of stub-and-resolver exports.
+    pub resolver: Option<u64>,
+    /// Library ordinal and name in that library of re-exports.
+    pub reexport: Option<(u64, String)>,
+}
+
+pub fn parse_exports<R: Read + Seek>(file: &mut R, load_commands: &[LoadCommandEntry], limits: &ParseLimits) -> Result<Vec<ExportRecord>, AppError> {
+    let trie_range = load_commands.iter().find_map(|load_command| match &load_command.command {
+        LoadCommand::DyldInfoCommand(command) if command.export_size != 0 => Some((command.export_off, command.export_size)),
+        LoadCommand::LinkeditDataCommand(command) if command.cmd == LC_DYLD_EXPORTS_TRIE => Some((command.dataoff, command.datasize)),
+        _ => None,
+    });
+    let mut exports = Vec::new();
+    if let Some((offset, size)) = trie_range {
+        AppError::check_limit("export trie byte", size as u64, limits.max_table_size)?;
+        let trie = read_bytes_at(file, offset as u64, size as u64).map_err(AppError::out_of_bounds("export trie", offset as u64, size as u64))?;
+        let mut walk = ExportTrieWalk { trie: &trie, limits, visited: HashSet::new(), name_bytes: 0, exports: &mut exports };
+        walk.parse_node(0, 0, &mut String::new())?;
+    }
+    Ok(exports)
+}
+
+struct ExportTrieWalk<'a> {
+    trie: &'a [u8],
+    limits: &'a ParseLimits,
+    /// Every node reached so far. Well-formed tries are trees, so a second edge to a node means
+    /// a cycle or shared subtrees whose exports would multiply.
+    visited: HashSet<u64>,
+    name_bytes: u64,
+    exports: &'a mut Vec<ExportRecord>,
+}
+
+impl ExportTrieWalk<'_> {
+    // Each trie node holds the export info of the prefix spelled so far, followed by the edges to
+    // the nodes of longer prefixes.
+    fn parse_node(&mut self, offset: u64, depth: usize, prefix: &mut String) -> Result<(), AppError> {
+        let trie = self.trie;
+        if !self.visited.insert(offset) || offset >= trie.len() as u64 || depth > MAX_EXPORT_TRIE_DEPTH {
+            return Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("export trie node at 0x{:x} is invalid", offset))));
+        }
+
+        let mut cursor = Cursor::new(trie);
+        cursor.set_position(offset);
+        let terminal_size = read_uleb128(&mut cursor)?;
+        let children_offset = cursor.position().saturating_add(terminal_size);
+        if terminal_size != 0 {
+            AppError::check_limit("export record", self.exports.len() as u64 + 1, self.limits.max_records)?;
+            self.name_bytes += prefix.len() as u64;
+            AppError::check_limit("export name byte", self.name_bytes, self.limits.max_table_size)?;
+            let flags = read_uleb128(&mut cursor)?;
+            let mut record = ExportRecord { name: prefix.clone(), flags, address: 0, resolver: None, reexport: None };
+            if flags & EXPORT_SYMBOL_FLAGS_REEXPORT != 0 {
+                let ordinal = read_uleb128(&mut cursor)?;
+                let imported_name = read_c_string(&mut cursor)?;
+                record.reexport = Some((ordinal, imported_name));
+            } else {
+                record.address = read_uleb128(&mut cursor)?;
+                if flags & EXPORT_SYMBOL_FLAGS_STUB_AND_RESOLVER != 0 {
+                    record.resolver = Some(read_uleb128(&mut cursor)?);
+                }
+            }
+            self.exports.push(record);
+        }
+
+        cursor.set_position(children_offset);
+        let child_count = cursor.read_u8()?;
+        for _ in 0..child_count {
+            let edge = read_c_string(&mut cursor)?;
+            let child_offset = read_uleb128(&mut cursor)?;
+            let prefix_length = prefix.len();
+            prefix.push_str(&edge);
+            self.parse_node(child_offset, depth + 1, prefix)?;
+            prefix.truncate(prefix_length);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mach_o::MachO;
+    use crate::test_utils::*;
+
+    fn image_with_binds(opcodes: &[u8]) -> Vec<u8> {
+        let data = segment_64("__DATA", 0x4000, 0x1000, 0, 0, &[]);
+        let dyld_info = command(LC_DYLD_INFO_ONLY, &[0, 0, 0x200, opcodes.len() as u32, 0, 0, 0, 0, 0, 0]);
+        image(&[data, dyld_info], &[(0x200, opcodes)])
+    }
+
+    #[test]
+    fn decodes_binds() {
+        let opcodes = [&[BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM][..], b"_malloc\0", &[BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB, 0x10, BIND_OPCODE_DO_BIND, BIND_OPCODE_DONE]].concat();
+        let mach_o = MachO::from_bytes(&image_with_binds(&opcodes)).unwrap();
+        assert_eq!(mach_o.binds.len(), 1);
+        assert_eq!((mach_o.binds[0].address, mach_o.binds[0].symbol_name.as_str()), (0x4010, "_malloc"));
+    }
+
+    #[test]
+    fn bad_bind_opcode_does_not_fail_a_strict_parse() {
+        let mach_o = MachO::from_bytes(&image_with_binds(&[0xf0])).unwrap();
+        assert!(mach_o.binds.is_empty());
+        assert_eq!(mach_o.diagnostics.len(), 1);
+        assert!(mach_o.diagnostics[0].message.contains("unknown bind opcode 0xf0"));
+    }
+
+    #[test]
+    fn bad_chained_fixups_do_not_fail_a_strict_parse() {
+        // dyld_chained_fixups_header with compressed symbols
+        let header: Vec<u8> = [0u32, 0x1c, 0x20, 0x24, 0, DYLD_CHAINED_IMPORT, 1].iter().flat_map(|word| word.to_le_bytes()).collect();
+        let command = command(LC_DYLD_CHAINED_FIXUPS, &[0x200, header.len() as u32]);
+        let mach_o = MachO::from_bytes(&image(&[command], &[(0x200, &header)])).unwrap();
+        assert!(mach_o.chained_fixups.is_none());
+        assert!(mach_o.diagnostics[0].message.contains("compressed chained fixup symbols"));
+    }
+
+    #[test]
+    fn bind_count_over_the_limit_is_left_out() {
+        let opcodes = [&[BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM][..], b"_malloc\0", &[BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB], &uleb128(1 << 40), &[0, BIND_OPCODE_DONE]].concat();
+        let mach_o = MachO::from_bytes(&image_with_binds(&opcodes)).unwrap();
+        assert!(mach_o.binds.is_empty());
+        assert!(mach_o.diagnostics[0].message.contains("bind record"));
+    }
+
+    #[test]
+    fn export_trie_cycle_is_rejected() {
+        // A root node without export info whose only edge leads back to itself.
+        let trie = [0, 1, b'a', 0, 0];
+        let command = command(LC_DYLD_EXPORTS_TRIE, &[0x200, trie.len() as u32]);
+        let mach_o = MachO::from_bytes(&image(&[command], &[(0x200, &trie)])).unwrap();
+        assert!(mach_o.exports.is_empty());
+        assert!(mach_o.diagnostics[0].message.contains("export trie node at 0x0 is invalid"));
+    }
+}
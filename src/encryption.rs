@@ -0,0 +1,70 @@
+use crate::load_commands::*;
+use crate::memory_utils::*;
+
+#[derive(Debug)]
+pub struct EncryptedRange {
+    pub cryptoff: u64,
+    pub cryptsize: u64,
+    pub cryptid: u32,
+    /// (segname, sectname) of every section whose file contents overlap the range
+    pub sections: Vec<(String, String)>,
+}
+
+impl EncryptedRange {
+    pub fn from_load_commands(load_commands: &[LoadCommandEntry]) -> Vec<EncryptedRange> {
+        let mut ranges = Vec::new();
+        for load_command in load_commands {
+            let LoadCommand::EncryptionInfoCommand(command) = &load_command.command else { continue };
+            let mut range = EncryptedRange {
+                cryptoff: command.cryptoff() as u64,
+                cryptsize: command.cryptsize() as u64,
+                cryptid: command.cryptid(),
+                sections: Vec::new(),
+            };
+            for section in load_commands.iter().flat_map(|load_command| &load_command.sections) {
+                // Zero-fill sections have no file contents to overlap.
+                if section.offset() != 0 && range.overlaps(section.offset() as u64, section.size()) {
+                    range.sections.push((fixed_bytes_to_string(section.segname()), fixed_bytes_to_string(section.sectname())));
+                }
+            }
+            ranges.push(range);
+        }
+        ranges
+    }
+
+    /// A cryptid of 0 means the range has been decrypted (or was never encrypted).
+    pub fn is_encrypted(&self) -> bool {
+        self.cryptid != 0
+    }
+
+    pub fn overlaps(&self, offset: u64, size: u64) -> bool {
+        size != 0 && offset < self.cryptoff.saturating_add(self.cryptsize) && self.cryptoff < offset.saturating_add(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::*;
+    use crate::error::AppError;
+    use crate::mach_o::MachO;
+    use crate::test_utils::*;
+
+    #[test]
+    fn huge_section_size_does_not_overflow() {
+        let text = segment_64("__TEXT", 0x1000, 0x1000, 0, 0x1000, &[("__text", 0x1000, u64::MAX, 0x800)]);
+        let encryption = command(LC_ENCRYPTION_INFO_64, &[0x400, 0x800, 1, 0]);
+        let data = image(&[text, encryption], &[(0xfff, &[0])]);
+
+        let mach_o = MachO::from_bytes(&data).unwrap();
+        let range = &mach_o.encrypted_ranges[0];
+        assert_eq!(range.sections, [(String::from("__TEXT"), String::from("__text"))]);
+        assert!(range.overlaps(0xbff, u64::MAX));
+        assert!(!range.overlaps(0xc00, u64::MAX));
+    }
+
+    #[test]
+    fn encrypted_error_saturates_the_end_of_the_range() {
+        let error = AppError::Encrypted { offset: u64::MAX - 1, size: u64::MAX, cryptid: 1 };
+        assert_eq!(error.to_string(), "file range 0xfffffffffffffffe-0xffffffffffffffff is encrypted (cryptid 1)");
+    }
+}
@@ -2,9 +2,74 @@
 use thiserror::Error;
 use std::io;
 
-// TODO: Make sure all is covered. backtrace required as well... consider moving to anyhow crate
+use crate::load_commands::load_command_name;
+
+// TODO: backtrace required as well... consider moving to anyhow crate
 #[derive(Error, Debug)]
 pub enum AppError {
-    #[error("IO Error")]
+    #[error(transparent)]
     Io(#[from] io::Error),
+    #[error("not a thin Mach-O image (magic 0x{magic:08x})")]
+    BadMagic { magic: u32 },
+    #[error("file is truncated: 0x{needed:x} bytes needed at offset 0x{offset:x}")]
+    Truncated { offset: u64, needed: u64 },
+    #[error("load command #{index} ({}) at offset 0x{offset:x} is invalid", describe_cmd(*cmd))]
+    InvalidLoadCommand { index: u32, cmd: u32, offset: u64, #[source] source: Box<AppError> },
+    #[error("{what} at 0x{offset:x}-0x{end:x} extends past the end of the file", end = offset.saturating_add(*size))]
+    OutOfBounds { what: &'static str, offset: u64, size: u64 },
+    #[error("invalid UTF-8 in string at offset 0x{offset:x}")]
+    Utf8 { offset: u64 },
+    #[error("{what} count {count} exceeds the limit of {limit}")]
+    LimitExceeded { what: &'static str, count: u64, limit: u64 },
+    #[error("file range 0x{offset:x}-0x{end:x} is encrypted (cryptid {cryptid})", end = offset.saturating_add(*size))]
+    Encrypted { offset: u64, size: u64, cryptid: u32 },
+}
+
+impl AppError {
+    /// Maps a short read of `needed` bytes at `offset` to [`AppError::Truncated`].
+    pub(crate) fn truncated<E: Into<AppError>>(offset: u64, needed: u64) -> impl FnOnce(E) -> AppError {
+        move |error| match error.into() {
+            error if error.is_eof() => AppError::Truncated { offset, needed },
+            error => error,
+        }
+    }
+
+    /// Maps a short read of `what` to [`AppError::OutOfBounds`].
+    pub(crate) fn out_of_bounds<E: Into<AppError>>(what: &'static str, offset: u64, size: u64) -> impl FnOnce(E) -> AppError {
+        move |error| match error.into() {
+            error if error.is_eof() => AppError::OutOfBounds { what, offset, size },
+            error => error,
+        }
+    }
+
+    /// Fails with [`AppError::LimitExceeded`] if `count` is over `limit`.
+    pub(crate) fn check_limit(what: &'static str, count: u64, limit: u64) -> Result<(), AppError> {
+        if count > limit {
+            return Err(AppError::LimitExceeded { what, count, limit });
+        }
+        Ok(())
+    }
+
+    /// File offset of the structure the error is about, if it names one.
+    pub fn offset(&self) -> Option<u64> {
+        match self {
+            AppError::Truncated { offset, .. } |
+            AppError::InvalidLoadCommand { offset, .. } |
+            AppError::OutOfBounds { offset, .. } |
+            AppError::Utf8 { offset } |
+            AppError::Encrypted { offset, .. } => Some(*offset),
+            AppError::Io(_) | AppError::BadMagic { .. } | AppError::LimitExceeded { .. } => None,
+        }
+    }
+
+    fn is_eof(&self) -> bool {
+        matches!(self, AppError::Io(error) if error.kind() == io::ErrorKind::UnexpectedEof)
+    }
+}
+
+pub(crate) fn describe_cmd(cmd: u32) -> String {
+    match load_command_name(cmd) {
+        Some(name) => name.to_string(),
+        None => format!("cmd 0x{:x}", cmd),
+    }
 }
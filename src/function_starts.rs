@@ -0,0 +1,52 @@
+use std::io::{self, Cursor, Read, Seek};
+
+use crate::error::AppError;
+use crate::load_commands::LinkeditDataCommand;
+use crate::memory_utils::*;
+
+// LC_FUNCTION_STARTS :== <uleb128 delta>* 0, the first delta relative to the __TEXT segment
+pub fn from_file<R: Read + Seek>(file: &mut R, command: &LinkeditDataCommand, text_vmaddr: u64) -> Result<Vec<u64>, AppError> {
+    let data = command.read_data(file)?;
+    let mut cursor = Cursor::new(data.as_slice());
+    let mut function_starts = Vec::new();
+    let mut address = text_vmaddr;
+
+    while cursor.position() < data.len() as u64 {
+        let delta = read_uleb128(&mut cursor)?;
+        if delta == 0 {
+            break;
+        }
+        address = address.checked_add(delta).ok_or_else(|| {
+            AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("function start delta 0x{:x} overflows the address", delta)))
+        })?;
+        function_starts.push(address);
+    }
+    Ok(function_starts)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::*;
+    use crate::mach_o::MachO;
+    use crate::test_utils::*;
+
+    fn image_with_function_starts(deltas: &[u64]) -> Vec<u8> {
+        let starts: Vec<u8> = deltas.iter().flat_map(|&delta| uleb128(delta)).chain([0]).collect();
+        let text = segment_64("__TEXT", 0x1_0000_0000, 0x1000, 0, 0x1000, &[]);
+        let command = command(LC_FUNCTION_STARTS, &[0x800, starts.len() as u32]);
+        image(&[text, command], &[(0x800, &starts), (0xfff, &[0])])
+    }
+
+    #[test]
+    fn decodes_deltas_from_the_text_segment() {
+        let mach_o = MachO::from_bytes(&image_with_function_starts(&[0x400, 0x20])).unwrap();
+        assert_eq!(mach_o.function_starts, Some(vec![0x1_0000_0400, 0x1_0000_0420]));
+    }
+
+    #[test]
+    fn overflowing_delta_does_not_fail_a_strict_parse() {
+        let mach_o = MachO::from_bytes(&image_with_function_starts(&[0x400, u64::MAX])).unwrap();
+        assert_eq!(mach_o.function_starts, None);
+        assert!(mach_o.diagnostics[0].message.contains("overflows"));
+    }
+}
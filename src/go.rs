@@ -0,0 +1,584 @@
+use std::io::{self, Read, Seek};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::address_space::AddressSpace;
+use crate::constants::*;
+use crate::error::AppError;
+use crate::header::Header;
+use crate::load_commands::SectionLike;
+use crate::mach_o::MachO;
+use crate::memory_utils::{fixed_bytes_to_string, read_uleb128};
+
+#[derive(Debug)]
+pub struct GoMetadata {
+    pub build_info: Option<GoBuildInfo>,
+    pub line_table: Option<GoLineTable>,
+}
+
+#[derive(Debug)]
+pub struct GoBuildInfo {
+    /// Toolchain the binary was built with, e.g. `go1.21.5`.
+    pub go_version: String,
+    /// Import path of the main package.
+    pub path: Option<String>,
+    pub main_module: Option<GoModule>,
+    pub dependencies: Vec<GoModule>,
+    /// `build` lines such as `GOARCH=arm64` or `vcs.revision=...`, in file order.
+    pub settings: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub struct GoModule {
+    pub path: String,
+    pub version: String,
+    /// go.sum checksum; empty for the main module and for local replacements.
+    pub sum: String,
+    /// Module substituted by a `replace` directive.
+    pub replacement: Option<Box<GoModule>>,
+}
+
+#[derive(Debug)]
+pub struct GoLineTable {
+    /// Go release that introduced the pclntab layout, e.g. `1.20`.
+    pub version: &'static str,
+    pub functions: Vec<GoFunction>,
+}
+
+#[derive(Debug)]
+pub struct GoFunction {
+    pub entry: u64,
+    pub name: String,
+    /// Source position at every pc where the file or line changes.
+    pub lines: Vec<GoSourceLine>,
+}
+
+#[derive(Debug)]
+pub struct GoSourceLine {
+    pub address: u64,
+    pub file: String,
+    pub line: i32,
+}
+
+impl GoMetadata {
+    pub fn from_file<R: Read + Seek>(file: &mut R, mach_o: &MachO) -> Result<GoMetadata, AppError> {
+        match mach_o.header.magic() {
+            MH_MAGIC | MH_MAGIC_64 => GoReader::<R, BigEndian>::new(file, mach_o).parse(),
+            _ => GoReader::<R, LittleEndian>::new(file, mach_o).parse(),
+        }
+    }
+}
+
+struct GoReader<'a, R: Read + Seek, E: ByteOrder> {
+    space: AddressSpace<'a, R, E>,
+    // (sectname, addr, size) of every section in the image
+    sections: Vec<(String, u64, u64)>,
+}
+
+impl<'a, R: Read + Seek, E: ByteOrder> GoReader<'a, R, E> {
+    fn new(file: &'a mut R, mach_o: &'a MachO) -> GoReader<'a, R, E> {
+        let sections = mach_o.sections()
+            .map(|section| (fixed_bytes_to_string(section.sectname()), section.addr(), section.size()))
+            .collect();
+        GoReader {
+            space: AddressSpace::new(file, mach_o),
+            sections,
+        }
+    }
+
+    fn parse(&mut self) -> Result<GoMetadata, AppError> {
+        let build_info = match self.section_bounds("__go_buildinfo") {
+            Some((addr, size)) => Some(self.read_build_info(addr, size)?),
+            None => None,
+        };
+        let line_table = match self.section_bounds("__gopclntab") {
+            Some((addr, size)) => Some(self.read_line_table(addr, size)?),
+            None => None,
+        };
+        if build_info.is_none() && line_table.is_none() {
+            return Err(AppError::from(io::Error::new(io::ErrorKind::InvalidInput, "no __go_buildinfo or __gopclntab section")));
+        }
+        Ok(GoMetadata { build_info, line_table })
+    }
+
+    fn section_bounds(&self, sectname: &str) -> Option<(u64, u64)> {
+        self.sections.iter()
+            .find(|(name, _, _)| name == sectname)
+            .map(|&(_, addr, size)| (addr, size))
+    }
+
+    fn read_build_info(&mut self, addr: u64, size: u64) -> Result<GoBuildInfo, AppError> {
+        if size < GO_BUILDINFO_HEADER_SIZE {
+            return Err(invalid_data("__go_buildinfo is truncated"));
+        }
+        let header = self.space.read_bytes(addr, GO_BUILDINFO_HEADER_SIZE)?;
+        if !header.starts_with(GO_BUILDINFO_MAGIC) {
+            return Err(invalid_data("__go_buildinfo has a bad magic"));
+        }
+
+        // Go 1.18 and later store both strings inline after the header, each prefixed with its
+        // length as a varint. Older toolchains store pointers to Go string headers instead.
+        let (go_version, modinfo) = if header[15] & GO_BUILDINFO_FLAG_INLINE_STRINGS != 0 {
+            let data = self.space.read_bytes(addr.wrapping_add(GO_BUILDINFO_HEADER_SIZE), size - GO_BUILDINFO_HEADER_SIZE)?;
+            let mut cursor = data.as_slice();
+            let go_version = read_varint_bytes(&mut cursor)?.to_vec();
+            let modinfo = read_varint_bytes(&mut cursor)?.to_vec();
+            (go_version, modinfo)
+        } else {
+            let pointer_size = header[14] as u64;
+            let go_version = self.read_go_string(addr.wrapping_add(16))?;
+            let modinfo = self.read_go_string(addr.wrapping_add(16 + pointer_size))?;
+            (go_version, modinfo)
+        };
+
+        Ok(parse_modinfo(String::from_utf8_lossy(&go_version).into_owned(), &modinfo))
+    }
+
+    // A pointer to a string header, which is itself a data pointer followed by a length.
+    fn read_go_string(&mut self, vmaddr: u64) -> Result<Vec<u8>, AppError> {
+        let string_header = self.space.read_address(vmaddr)?;
+        if string_header == 0 {
+            return Ok(Vec::new());
+        }
+        let data = self.space.read_address(string_header)?;
+        let length = self.space.read_word(string_header.wrapping_add(self.space.pointer_size()))?;
+        if data == 0 || length == 0 {
+            return Ok(Vec::new());
+        }
+        self.space.read_bytes(data, length)
+    }
+
+    fn read_line_table(&mut self, addr: u64, size: u64) -> Result<GoLineTable, AppError> {
+        let data = self.space.read_bytes(addr, size)?;
+        let table = Pclntab::<E>::new(&data)?;
+        // Since Go 1.18 function entries are offsets from runtime.text, whose address the linker
+        // writes into the header as a pointer that may need rebasing.
+        let text_start = match table.version {
+            "1.18" | "1.20" => self.space.read_address(addr.wrapping_add(8 + 2 * table.pointer_size as u64))?,
+            _ => 0,
+        };
+        let functions = table.functions(text_start)?;
+        Ok(GoLineTable { version: table.version, functions })
+    }
+}
+
+/// The sections of a pclntab, as offsets into its data.
+struct Pclntab<'d, E: ByteOrder> {
+    data: &'d [u8],
+    version: &'static str,
+    quantum: u64,
+    pointer_size: usize,
+    nfunc: usize,
+    funcname: usize,
+    cutab: usize,
+    filetab: usize,
+    pctab: usize,
+    functab: usize,
+    _endian: std::marker::PhantomData<E>,
+}
+
+impl<'d, E: ByteOrder> Pclntab<'d, E> {
+    fn new(data: &'d [u8]) -> Result<Pclntab<'d, E>, AppError> {
+        if data.len() < 16 || data[4] != 0 || data[5] != 0 {
+            return Err(invalid_data("__gopclntab has a bad header"));
+        }
+        let version = match E::read_u32(data) {
+            GO_PCLNTAB_MAGIC_12 => "1.2",
+            GO_PCLNTAB_MAGIC_116 => "1.16",
+            GO_PCLNTAB_MAGIC_118 => "1.18",
+            GO_PCLNTAB_MAGIC_120 => "1.20",
+            magic => return Err(invalid_data(&format!("__gopclntab has an unknown magic 0x{:x}", magic))),
+        };
+        let pointer_size = data[7] as usize;
+        if pointer_size != 4 && pointer_size != 8 {
+            return Err(invalid_data("__gopclntab has a bad pointer size"));
+        }
+        let header = Pclntab {
+            data,
+            version,
+            quantum: data[6] as u64,
+            pointer_size,
+            nfunc: 0,
+            funcname: 0,
+            cutab: 0,
+            filetab: 0,
+            pctab: 0,
+            functab: 0,
+            _endian: std::marker::PhantomData,
+        };
+        let word = |index: usize| header.read_word(8 + index * pointer_size);
+        let (nfunc, funcname, cutab, filetab, pctab, functab) = match version {
+            // Go 1.2 keeps everything in one blob, with the file table located after the function table.
+            "1.2" => {
+                let nfunc = word(0)?;
+                let functab = 8 + pointer_size;
+                let filetab = header.read_u32(nfunc.saturating_mul(2).saturating_add(1).saturating_mul(pointer_size).saturating_add(functab))? as usize;
+                (nfunc, 0, 0, filetab, 0, functab)
+            }
+            "1.16" => (word(0)?, word(2)?, word(3)?, word(4)?, word(5)?, word(6)?),
+            _ => (word(0)?, word(3)?, word(4)?, word(5)?, word(6)?, word(7)?),
+        };
+        let table = Pclntab { nfunc, funcname, cutab, filetab, pctab, functab, ..header };
+        if table.nfunc > data.len() / table.functab_entry_size() {
+            return Err(invalid_data("__gopclntab function count exceeds the section"));
+        }
+        Ok(table)
+    }
+
+    fn functab_entry_size(&self) -> usize {
+        match self.version {
+            "1.2" | "1.16" => 2 * self.pointer_size,
+            _ => 8,
+        }
+    }
+
+    fn functions(&self, text_start: u64) -> Result<Vec<GoFunction>, AppError> {
+        let entry_size = self.functab_entry_size();
+        let field_size = entry_size / 2;
+        // Function data is addressed from the table start in 1.2 and from the function table afterwards.
+        let func_base = if self.version == "1.2" { 0 } else { self.functab };
+        let read_field = |offset: usize| -> Result<u64, AppError> {
+            if field_size == 8 { self.read_u64(offset) } else { Ok(self.read_u32(offset)? as u64) }
+        };
+
+        let mut functions = Vec::with_capacity(self.nfunc);
+        for index in 0..self.nfunc {
+            let slot = self.functab.saturating_add(index * entry_size);
+            let entry = text_start.wrapping_add(read_field(slot)?);
+            // Each entry's end is the next entry's start; the table ends with a sentinel pc.
+            let end = text_start.wrapping_add(read_field(slot.saturating_add(entry_size))?);
+            let func = func_base.saturating_add(read_field(slot.saturating_add(field_size))? as usize);
+
+            // _func starts with the entry, followed by 32-bit fields whose layout is stable across versions.
+            let fields = func.saturating_add(field_size);
+            let name_offset = self.read_u32(fields)? as usize;
+            let pcfile = self.read_u32(fields.saturating_add(16))? as usize;
+            let pcln = self.read_u32(fields.saturating_add(20))? as usize;
+            let cu_offset = if self.version == "1.2" { 0 } else { self.read_u32(fields.saturating_add(28))? as usize };
+
+            let name = self.read_c_string(self.funcname.saturating_add(name_offset))?;
+            let files = self.decode_pcvalue(pcfile, entry, end);
+            let mut lines: Vec<GoSourceLine> = Vec::new();
+            for (address, line) in self.decode_pcvalue(pcln, entry, end) {
+                let file_index = files.iter().rev().find(|&&(start, _)| start <= address).map_or(-1, |&(_, file)| file);
+                let file = self.file_name(cu_offset, file_index).unwrap_or_else(|| "?".to_string());
+                if lines.last().is_some_and(|last| last.file == file && last.line == line) {
+                    continue;
+                }
+                lines.push(GoSourceLine { address, file, line });
+            }
+            functions.push(GoFunction { entry, name, lines });
+        }
+        Ok(functions)
+    }
+
+    /// Decodes a pc-value table into the pc at which each value starts. Values are
+    /// zig-zag encoded deltas, each followed by the number of instructions it covers.
+    fn decode_pcvalue(&self, offset: usize, entry: u64, end: u64) -> Vec<(u64, i32)> {
+        let mut ranges = Vec::new();
+        if offset == 0 {
+            return ranges;
+        }
+        let Some(mut cursor) = self.data.get(self.pctab.saturating_add(offset)..) else {
+            return ranges;
+        };
+        let mut pc = entry;
+        let mut value: i32 = -1;
+        while pc < end {
+            let Ok(value_delta) = read_uleb128(&mut cursor) else { break };
+            if value_delta == 0 && !ranges.is_empty() {
+                break;
+            }
+            let value_delta = if value_delta & 1 != 0 { !(value_delta >> 1) } else { value_delta >> 1 };
+            value = value.wrapping_add(value_delta as i32);
+            ranges.push((pc, value));
+            let Ok(pc_delta) = read_uleb128(&mut cursor) else { break };
+            pc = pc.saturating_add(pc_delta.saturating_mul(self.quantum));
+        }
+        ranges
+    }
+
+    fn file_name(&self, cu_offset: usize, file_index: i32) -> Option<String> {
+        if file_index < 0 {
+            return None;
+        }
+        let file_index = file_index as usize;
+        let offset = if self.version == "1.2" {
+            // Entry 0 of the 1.2 file table holds the number of files.
+            if file_index == 0 {
+                return None;
+            }
+            self.read_u32(self.filetab.saturating_add(file_index.saturating_mul(4))).ok()? as usize
+        } else {
+            match self.read_u32(self.cutab.saturating_add(cu_offset.saturating_add(file_index).saturating_mul(4))).ok()? {
+                u32::MAX => return None,
+                offset => self.filetab.saturating_add(offset as usize),
+            }
+        };
+        self.read_c_string(offset).ok()
+    }
+
+    fn read_u32(&self, offset: usize) -> Result<u32, AppError> {
+        self.data.get(offset..offset.saturating_add(4)).map(E::read_u32).ok_or_else(truncated)
+    }
+
+    fn read_u64(&self, offset: usize) -> Result<u64, AppError> {
+        self.data.get(offset..offset.saturating_add(8)).map(E::read_u64).ok_or_else(truncated)
+    }
+
+    fn read_word(&self, offset: usize) -> Result<usize, AppError> {
+        if self.pointer_size == 8 { Ok(self.read_u64(offset)? as usize) } else { Ok(self.read_u32(offset)? as usize) }
+    }
+
+    fn read_c_string(&self, offset: usize) -> Result<String, AppError> {
+        let bytes = self.data.get(offset..).ok_or_else(truncated)?;
+        let end = bytes.iter().position(|&byte| byte == 0).ok_or_else(truncated)?;
+        Ok(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    }
+}
+
+/// Splits the module info recorded by the go command into the main module, its dependencies
+/// and build settings. The text is framed by 16-byte binary sentinels on both ends, which have
+/// to be stripped before the rest is decoded as UTF-8.
+fn parse_modinfo(go_version: String, modinfo: &[u8]) -> GoBuildInfo {
+    let modinfo = if modinfo.len() >= 33 && modinfo[modinfo.len() - 17] == b'\n' {
+        &modinfo[16..modinfo.len() - 16]
+    } else {
+        modinfo
+    };
+    let modinfo = String::from_utf8_lossy(modinfo);
+
+    let mut build_info = GoBuildInfo {
+        go_version,
+        path: None,
+        main_module: None,
+        dependencies: Vec::new(),
+        settings: Vec::new(),
+    };
+    for line in modinfo.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let module = || GoModule {
+            path: fields.get(1).unwrap_or(&"").to_string(),
+            version: fields.get(2).unwrap_or(&"").to_string(),
+            sum: fields.get(3).unwrap_or(&"").to_string(),
+            replacement: None,
+        };
+        match fields[0] {
+            "path" => build_info.path = fields.get(1).map(|path| path.to_string()),
+            "mod" => build_info.main_module = Some(module()),
+            "dep" => build_info.dependencies.push(module()),
+            // A replacement applies to the module on the line before it.
+            "=>" => {
+                let replaced = match build_info.dependencies.last_mut() {
+                    Some(dependency) => Some(dependency),
+                    None => build_info.main_module.as_mut(),
+                };
+                if let Some(replaced) = replaced {
+                    replaced.replacement = Some(Box::new(module()));
+                }
+            }
+            "build" => {
+                let setting = fields[1..].join("\t");
+                let (key, value) = setting.split_once('=').unwrap_or((&setting, ""));
+                build_info.settings.push((key.to_string(), value.to_string()));
+            }
+            _ => {}
+        }
+    }
+    build_info
+}
+
+fn read_varint_bytes<'d>(cursor: &mut &'d [u8]) -> Result<&'d [u8], AppError> {
+    let length = read_uleb128(cursor)? as usize;
+    let bytes = cursor.get(..length).ok_or_else(truncated)?;
+    *cursor = &cursor[length..];
+    Ok(bytes)
+}
+
+fn invalid_data(message: &str) -> AppError {
+    AppError::from(io::Error::new(io::ErrorKind::InvalidData, message.to_string()))
+}
+
+fn truncated() -> AppError {
+    invalid_data("Go metadata is truncated")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    const ENTRY: u64 = 0x1_0000_1000;
+
+    fn put(table: &mut Vec<u8>, offset: usize, bytes: &[u8]) {
+        if table.len() < offset + bytes.len() {
+            table.resize(offset + bytes.len(), 0);
+        }
+        table[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    /// A pclntab with a single function `main.main` covering 16 bytes from `ENTRY`, at line 10
+    /// of main.go for its first two instructions and line 12 for the rest.
+    fn pclntab(magic: u32) -> Vec<u8> {
+        let legacy = magic == GO_PCLNTAB_MAGIC_12;
+        let relative_entries = magic == GO_PCLNTAB_MAGIC_118 || magic == GO_PCLNTAB_MAGIC_120;
+        // nfunc, nfiles, textStart, funcname, cutab, filetab, pctab, functab from 1.18 on
+        let header_words = match magic {
+            GO_PCLNTAB_MAGIC_12 => 1,
+            GO_PCLNTAB_MAGIC_116 => 7,
+            _ => 8,
+        };
+        let word_offset = |index: usize| 8 + 8 * index;
+        let (text_start, funcname, cutab, filetab, pctab, functab) = if magic == GO_PCLNTAB_MAGIC_116 {
+            (None, Some(2), Some(3), Some(4), Some(5), Some(6))
+        } else if legacy {
+            (None, None, None, None, None, None)
+        } else {
+            (Some(2), Some(3), Some(4), Some(5), Some(6), Some(7))
+        };
+
+        let mut table = vec![0; word_offset(header_words)];
+        put(&mut table, 0, &magic.to_le_bytes());
+        put(&mut table, 6, &[4, 8]);
+        put(&mut table, word_offset(0), &1u64.to_le_bytes());
+        if let Some(index) = text_start {
+            put(&mut table, word_offset(index), &ENTRY.to_le_bytes());
+        }
+
+        // The function table comes first so 1.2 finds it right after nfunc; _func follows it.
+        let functab_offset = table.len();
+        let func_offset = functab_offset + 32;
+        let func_base = if legacy { 0 } else { functab_offset };
+        if relative_entries {
+            put(&mut table, functab_offset, &words(&[0, (func_offset - func_base) as u32, 0x10]));
+        } else {
+            for (index, value) in [ENTRY, (func_offset - func_base) as u64, ENTRY + 0x10].into_iter().enumerate() {
+                put(&mut table, functab_offset + 8 * index, &value.to_le_bytes());
+            }
+        }
+        let field_size = if relative_entries { 4 } else { 8 };
+        let fields = func_offset + field_size;
+        table.resize(fields + 32, 0);
+
+        // Offsets are from the start of the table in 1.2 and from each sub-table afterwards.
+        let base = |offset: usize| if legacy { offset } else { 0 };
+        let funcname_offset = table.len();
+        put(&mut table, funcname_offset, b"main.main\0");
+        put(&mut table, fields, &(base(funcname_offset) as u32).to_le_bytes());
+
+        // The compilation unit table maps file 0 to offset 0 of the file table.
+        let cutab_offset = table.len();
+        let filetab_offset = cutab_offset + 4;
+        put(&mut table, cutab_offset, &[0; 4]);
+        if legacy {
+            // Entry 0 holds the number of files, entry 1 the offset of main.go.
+            put(&mut table, filetab_offset, &words(&[2, filetab_offset as u32 + 8]));
+            put(&mut table, functab_offset + 24, &(filetab_offset as u32).to_le_bytes());
+        }
+        let file_name_offset = table.len();
+        put(&mut table, file_name_offset, b"main.go\0");
+
+        // pcfile and pcln tables; offset 0 of pctab is never used since it means no table.
+        let pctab_offset = table.len();
+        let file_value = if legacy { 4 } else { 2 };
+        put(&mut table, pctab_offset, &[0, file_value, 4, 0, 22, 2, 4, 2, 0]);
+        put(&mut table, fields + 16, &words(&[base(pctab_offset) as u32 + 1, base(pctab_offset) as u32 + 4]));
+
+        for (index, offset) in [(funcname, funcname_offset), (cutab, cutab_offset), (filetab, filetab_offset), (pctab, pctab_offset), (functab, functab_offset)] {
+            if let Some(index) = index {
+                put(&mut table, word_offset(index), &(offset as u64).to_le_bytes());
+            }
+        }
+        table
+    }
+
+    fn line_table(table: &[u8], text_start: u64) -> Result<Vec<GoFunction>, AppError> {
+        Pclntab::<LittleEndian>::new(table)?.functions(text_start)
+    }
+
+    #[test]
+    fn decodes_every_pclntab_version() {
+        for (magic, version) in [(GO_PCLNTAB_MAGIC_12, "1.2"), (GO_PCLNTAB_MAGIC_116, "1.16"), (GO_PCLNTAB_MAGIC_118, "1.18"), (GO_PCLNTAB_MAGIC_120, "1.20")] {
+            let table = pclntab(magic);
+            assert_eq!(Pclntab::<LittleEndian>::new(&table).unwrap().version, version);
+            let text_start = if magic == GO_PCLNTAB_MAGIC_118 || magic == GO_PCLNTAB_MAGIC_120 { ENTRY } else { 0 };
+            let functions = line_table(&table, text_start).unwrap();
+            assert_eq!((functions[0].entry, functions[0].name.as_str()), (ENTRY, "main.main"), "{}", version);
+            let lines: Vec<(u64, &str, i32)> = functions[0].lines.iter().map(|line| (line.address, line.file.as_str(), line.line)).collect();
+            assert_eq!(lines, [(ENTRY, "main.go", 10), (ENTRY + 8, "main.go", 12)], "{}", version);
+        }
+    }
+
+    #[test]
+    fn bad_pclntabs_are_rejected() {
+        let mut table = pclntab(GO_PCLNTAB_MAGIC_120);
+        // Ends right after the function table, before the _func it points to.
+        assert!(line_table(&table[..8 + 8 * 8 + 12], ENTRY).is_err());
+        table[7] = 3;
+        assert!(line_table(&table, ENTRY).is_err());
+        table[7] = 8;
+        put(&mut table, 8, &u64::MAX.to_le_bytes());
+        assert!(line_table(&table, ENTRY).is_err());
+        put(&mut table, 0, &0x1234u32.to_le_bytes());
+        assert!(line_table(&table, ENTRY).is_err());
+    }
+
+    fn build_info(modinfo: &str) -> Vec<u8> {
+        let mut data = GO_BUILDINFO_MAGIC.to_vec();
+        data.extend([8, GO_BUILDINFO_FLAG_INLINE_STRINGS]);
+        data.resize(GO_BUILDINFO_HEADER_SIZE as usize, 0);
+        data.extend(uleb128(8));
+        data.extend(b"go1.21.5");
+        // The module info is framed by 16-byte sentinels.
+        data.extend(uleb128(modinfo.len() as u64 + 32));
+        data.extend(b"\x30\x77\xaf\x0c\x92\x74\x08\x02\x41\xe1\xc1\x07\xe6\xd6\x18\xe6");
+        data.extend(modinfo.as_bytes());
+        data.extend(b"\xf9\x32\x43\x31\x86\x18\x20\x72\x00\x82\x42\x10\x41\x16\xd8\xf2");
+        data
+    }
+
+    #[test]
+    fn decodes_go_binaries() {
+        let modinfo = "path\texample.com/app\n\
+            mod\texample.com/app\t(devel)\t\n\
+            dep\tgolang.org/x/sys\tv0.1.0\th1:abc=\n\
+            =>\t../sys\t(devel)\t\n\
+            build\tGOARCH=arm64\n\
+            build\t-ldflags=-s -w\n";
+        let mut data = SegmentData::new();
+        let table = pclntab(GO_PCLNTAB_MAGIC_120);
+        let build_info = build_info(modinfo);
+        let sections = [
+            ("__gopclntab", data.push(&table), table.len() as u64),
+            ("__go_buildinfo", data.push(&build_info), build_info.len() as u64),
+        ];
+        let image = data_image(&data, &sections);
+        let mach_o = MachO::from_bytes(&image).unwrap();
+        let metadata = GoMetadata::from_file(&mut Cursor::new(&image), &mach_o).unwrap();
+
+        let line_table = metadata.line_table.unwrap();
+        assert_eq!((line_table.version, line_table.functions[0].entry), ("1.20", ENTRY));
+        let build_info = metadata.build_info.unwrap();
+        assert_eq!((build_info.go_version.as_str(), build_info.path.as_deref()), ("go1.21.5", Some("example.com/app")));
+        let main_module = build_info.main_module.unwrap();
+        assert_eq!((main_module.path.as_str(), main_module.version.as_str()), ("example.com/app", "(devel)"));
+        let dependency = &build_info.dependencies[0];
+        assert_eq!((dependency.path.as_str(), dependency.version.as_str(), dependency.sum.as_str()), ("golang.org/x/sys", "v0.1.0", "h1:abc="));
+        assert_eq!(dependency.replacement.as_ref().unwrap().path, "../sys");
+        let settings: Vec<(&str, &str)> = build_info.settings.iter().map(|(key, value)| (key.as_str(), value.as_str())).collect();
+        assert_eq!(settings, [("GOARCH", "arm64"), ("-ldflags", "-s -w")]);
+    }
+
+    #[test]
+    fn truncated_build_info_is_rejected() {
+        for build_info in [build_info("")[..20].to_vec(), build_info("path\tx\n")[..40].to_vec()] {
+            let mut data = SegmentData::new();
+            let sections = [("__go_buildinfo", data.push(&build_info), build_info.len() as u64)];
+            let image = data_image(&data, &sections);
+            let mach_o = MachO::from_bytes(&image).unwrap();
+            assert!(GoMetadata::from_file(&mut Cursor::new(&image), &mach_o).is_err());
+        }
+    }
+}
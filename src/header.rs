@@ -1,6 +1,7 @@
 use std::io::Read;
 
 use byteorder::ReadBytesExt;
+use crate::constants::{MH_CIGAM_64, MH_MAGIC_64};
 use crate::error::AppError;
 
 #[derive(Debug)]
@@ -9,15 +10,50 @@ pub enum MachHeader {
     MH64(MachHeader64),
 }
 
+/// Fields of `mach_header` and `mach_header_64`.
+pub trait Header {
+    fn magic(&self) -> u32;
+    fn cputype(&self) -> i32;
+    fn cpusubtype(&self) -> i32;
+    fn filetype(&self) -> u32;
+    fn ncmds(&self) -> u32;
+    fn sizeofcmds(&self) -> u32;
+    fn flags(&self) -> u32;
+    /// Only present in `mach_header_64`.
+    fn reserved(&self) -> Option<u32>;
+
+    fn is_64(&self) -> bool {
+        self.reserved().is_some()
+    }
+}
+
 impl MachHeader {
-    pub fn ncmds(&self) -> u32 {
+    pub fn from_file<R: Read, E: byteorder::ByteOrder>(file: &mut R, magic: u32) -> Result<MachHeader, AppError> {
+        match magic {
+            MH_MAGIC_64 | MH_CIGAM_64 => MachHeader64::from_file::<R, E>(file, magic),
+            _ => MachHeader32::from_file::<R, E>(file, magic),
+        }
+    }
+
+    fn as_dyn(&self) -> &dyn Header {
         match self {
-            MachHeader::MH32(header) => header.ncmds,
-            MachHeader::MH64(header) => header.ncmds,
+            MachHeader::MH32(header) => header,
+            MachHeader::MH64(header) => header,
         }
     }
 }
 
+impl Header for MachHeader {
+    fn magic(&self) -> u32 { self.as_dyn().magic() }
+    fn cputype(&self) -> i32 { self.as_dyn().cputype() }
+    fn cpusubtype(&self) -> i32 { self.as_dyn().cpusubtype() }
+    fn filetype(&self) -> u32 { self.as_dyn().filetype() }
+    fn ncmds(&self) -> u32 { self.as_dyn().ncmds() }
+    fn sizeofcmds(&self) -> u32 { self.as_dyn().sizeofcmds() }
+    fn flags(&self) -> u32 { self.as_dyn().flags() }
+    fn reserved(&self) -> Option<u32> { self.as_dyn().reserved() }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct MachHeader32 {
@@ -45,6 +81,17 @@ impl MachHeader32 {
     }
 }
 
+impl Header for MachHeader32 {
+    fn magic(&self) -> u32 { self.magic }
+    fn cputype(&self) -> i32 { self.cputype }
+    fn cpusubtype(&self) -> i32 { self.cpusubtype }
+    fn filetype(&self) -> u32 { self.filetype }
+    fn ncmds(&self) -> u32 { self.ncmds }
+    fn sizeofcmds(&self) -> u32 { self.sizeofcmds }
+    fn flags(&self) -> u32 { self.flags }
+    fn reserved(&self) -> Option<u32> { None }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct MachHeader64 {
@@ -73,3 +120,14 @@ impl MachHeader64 {
         Ok(MachHeader::MH64(header))
     }
 }
+
+impl Header for MachHeader64 {
+    fn magic(&self) -> u32 { self.magic }
+    fn cputype(&self) -> i32 { self.cputype }
+    fn cpusubtype(&self) -> i32 { self.cpusubtype }
+    fn filetype(&self) -> u32 { self.filetype }
+    fn ncmds(&self) -> u32 { self.ncmds }
+    fn sizeofcmds(&self) -> u32 { self.sizeofcmds }
+    fn flags(&self) -> u32 { self.flags }
+    fn reserved(&self) -> Option<u32> { Some(self.reserved) }
+}
@@ -0,0 +1,2022 @@
+use std::rc::Rc;
+
+use crate::demangle::DemangleStyle;
+
+// C++ symbols follow the Itanium C++ ABI mangling. The parser builds a tree of names, types and
+// expressions, which is printed the way llvm-cxxfilt does, declarators and all.
+
+// Guards against malformed or hostile names blowing up the recursion or the output.
+const MAX_SYMBOL_LENGTH: usize = 16 * 1024;
+const MAX_NESTING: usize = 256;
+const MAX_OUTPUT_LENGTH: usize = 64 * 1024;
+
+const QUAL_CONST: u8 = 0x1;
+const QUAL_VOLATILE: u8 = 0x2;
+const QUAL_RESTRICT: u8 = 0x4;
+
+/// Demangles an Itanium C++ symbol such as `__ZN3foo3barEv`, including blocks invoked from C++
+/// functions (`___Z3foov_block_invoke`). Mach-O symbol names carry an extra leading underscore,
+/// names without it are accepted as well. Returns `None` for names that are not C++ mangled or
+/// could not be decoded.
+pub fn demangle_symbol(name: &str, style: DemangleStyle) -> Option<String> {
+    let name = match name.strip_prefix('_') {
+        Some(stripped) if stripped.starts_with("_Z") || stripped.starts_with("__Z") => stripped,
+        _ => name,
+    };
+    if name.len() > MAX_SYMBOL_LENGTH {
+        return None;
+    }
+    if let Some(mangled) = name.strip_prefix('_').filter(|mangled| mangled.starts_with("_Z")) {
+        // Blocks are named after the function they are declared in.
+        let (mangled, suffix) = mangled.split_once("_block_invoke")?;
+        if !suffix.is_empty() && !suffix.strip_prefix('_').is_some_and(|number| !number.is_empty() && number.bytes().all(|byte| byte.is_ascii_digit())) {
+            return None;
+        }
+        let encoding = Parser::new(mangled).parse_mangled_name()?;
+        let node = Node::Special { prefix: "invocation function for block in ", child: encoding }.rc();
+        return Printer::print_tree(&node, style);
+    }
+    if !name.starts_with("_Z") {
+        return None;
+    }
+    let node = Parser::new(name).parse_mangled_name()?;
+    Printer::print_tree(&node, style)
+}
+
+type NodeRef = Rc<Node>;
+
+#[derive(Debug)]
+enum Node {
+    Name(String),
+    /// `std::string` and friends; constructors use the shorter base name.
+    SpecialSubstitution { name: &'static str, base_name: &'static str },
+    Nested { qualifier: NodeRef, name: NodeRef },
+    Local { encoding: NodeRef, entity: NodeRef },
+    Template { name: NodeRef, args: Vec<NodeRef> },
+    ArgumentPack(Vec<NodeRef>),
+    AbiTagged { name: NodeRef, tag: String },
+    CtorDtor { base_name: NodeRef, is_dtor: bool },
+    ConversionOperator(NodeRef),
+    Closure { template_params: Vec<NodeRef>, params: Vec<NodeRef>, count: String },
+    Unnamed(String),
+    StructuredBinding(Vec<NodeRef>),
+    Qualified { child: NodeRef, quals: u8 },
+    VendorQualified { child: NodeRef, qualifier: String },
+    Elaborated { kind: &'static str, child: NodeRef },
+    Pointer(NodeRef),
+    Reference { child: NodeRef, is_rvalue: bool },
+    PointerToMember { class: NodeRef, member: NodeRef },
+    Function { ret: NodeRef, params: Vec<NodeRef>, quals: u8, ref_qualifier: Option<&'static str>, exception: Option<NodeRef> },
+    Encoding { ret: Option<NodeRef>, name: NodeRef, params: Vec<NodeRef>, quals: u8, ref_qualifier: Option<&'static str> },
+    Array { element: NodeRef, dimension: Option<NodeRef> },
+    Vector { element: NodeRef, dimension: Option<NodeRef> },
+    PackExpansion(NodeRef),
+    Special { prefix: &'static str, child: NodeRef },
+    ConstructionVtable { base: NodeRef, derived: NodeRef },
+    DotSuffix { child: NodeRef, suffix: String },
+    // Expressions
+    Literal { ty: Option<NodeRef>, value: String, suffix: &'static str },
+    Binary { left: NodeRef, op: &'static str, right: NodeRef },
+    Prefix { op: &'static str, operand: NodeRef },
+    Postfix { operand: NodeRef, op: &'static str },
+    Conditional { condition: NodeRef, then: NodeRef, otherwise: NodeRef },
+    Call { callee: NodeRef, args: Vec<NodeRef> },
+    Cast { kind: &'static str, ty: NodeRef, expr: NodeRef },
+    ConversionExpr { ty: NodeRef, args: Vec<NodeRef> },
+    MemberAccess { object: NodeRef, op: &'static str, member: NodeRef },
+    Subscript { array: NodeRef, index: NodeRef },
+    Enclosing { prefix: &'static str, child: NodeRef, postfix: &'static str },
+    Throw(Option<NodeRef>),
+    New { op: &'static str, placement: Vec<NodeRef>, ty: NodeRef, initializer: Option<Vec<NodeRef>> },
+}
+
+impl Node {
+    fn rc(self) -> NodeRef {
+        Rc::new(self)
+    }
+}
+
+fn name(text: impl Into<String>) -> NodeRef {
+    Node::Name(text.into()).rc()
+}
+
+// The name constructors and destructors are spelled with.
+fn base_name(node: &NodeRef) -> NodeRef {
+    match &**node {
+        Node::Nested { name, .. } | Node::Template { name, .. } | Node::AbiTagged { name, .. } => base_name(name),
+        Node::Local { entity, .. } => base_name(entity),
+        Node::SpecialSubstitution { base_name, .. } => name(*base_name),
+        _ => node.clone(),
+    }
+}
+
+fn expand_special_substitution(node: &NodeRef) -> Option<NodeRef> {
+    let Node::SpecialSubstitution { name: substitution, base_name } = &**node else {
+        return None;
+    };
+    let args = match *substitution {
+        "std::string" => vec![name("char"), name("std::char_traits<char>"), name("std::allocator<char>")],
+        "std::istream" | "std::ostream" | "std::iostream" => vec![name("char"), name("std::char_traits<char>")],
+        _ => return None,
+    };
+    Some(Node::Nested { qualifier: name("std"), name: Node::Template { name: name(*base_name), args }.rc() }.rc())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum OperatorKind {
+    Prefix,
+    Postfix,
+    Binary,
+    Call,
+    Subscript,
+    Member,
+    Conditional,
+    NamedCast,
+    OfType,
+    OfExpr,
+    New,
+    Delete,
+}
+
+// (code, spelling, kind); the spelling of operator functions is `operator` followed by it.
+const OPERATORS: &[(&str, &str, OperatorKind)] = &[
+    ("aN", "&=", OperatorKind::Binary),
+    ("aS", "=", OperatorKind::Binary),
+    ("aa", "&&", OperatorKind::Binary),
+    ("ad", "&", OperatorKind::Prefix),
+    ("an", "&", OperatorKind::Binary),
+    ("at", "alignof (", OperatorKind::OfType),
+    ("aw", "co_await", OperatorKind::Prefix),
+    ("az", "alignof (", OperatorKind::OfExpr),
+    ("cc", "const_cast", OperatorKind::NamedCast),
+    ("cl", "()", OperatorKind::Call),
+    ("cm", ",", OperatorKind::Binary),
+    ("co", "~", OperatorKind::Prefix),
+    ("dV", "/=", OperatorKind::Binary),
+    ("da", "delete[]", OperatorKind::Delete),
+    ("dc", "dynamic_cast", OperatorKind::NamedCast),
+    ("de", "*", OperatorKind::Prefix),
+    ("dl", "delete", OperatorKind::Delete),
+    ("ds", ".*", OperatorKind::Member),
+    ("dt", ".", OperatorKind::Member),
+    ("dv", "/", OperatorKind::Binary),
+    ("eO", "^=", OperatorKind::Binary),
+    ("eo", "^", OperatorKind::Binary),
+    ("eq", "==", OperatorKind::Binary),
+    ("ge", ">=", OperatorKind::Binary),
+    ("gt", ">", OperatorKind::Binary),
+    ("ix", "[]", OperatorKind::Subscript),
+    ("lS", "<<=", OperatorKind::Binary),
+    ("le", "<=", OperatorKind::Binary),
+    ("ls", "<<", OperatorKind::Binary),
+    ("lt", "<", OperatorKind::Binary),
+    ("mI", "-=", OperatorKind::Binary),
+    ("mL", "*=", OperatorKind::Binary),
+    ("mi", "-", OperatorKind::Binary),
+    ("ml", "*", OperatorKind::Binary),
+    ("mm", "--", OperatorKind::Postfix),
+    ("na", "new[]", OperatorKind::New),
+    ("ne", "!=", OperatorKind::Binary),
+    ("ng", "-", OperatorKind::Prefix),
+    ("nt", "!", OperatorKind::Prefix),
+    ("nw", "new", OperatorKind::New),
+    ("oR", "|=", OperatorKind::Binary),
+    ("oo", "||", OperatorKind::Binary),
+    ("or", "|", OperatorKind::Binary),
+    ("pL", "+=", OperatorKind::Binary),
+    ("pl", "+", OperatorKind::Binary),
+    ("pm", "->*", OperatorKind::Member),
+    ("pp", "++", OperatorKind::Postfix),
+    ("ps", "+", OperatorKind::Prefix),
+    ("pt", "->", OperatorKind::Member),
+    ("qu", "?", OperatorKind::Conditional),
+    ("rM", "%=", OperatorKind::Binary),
+    ("rS", ">>=", OperatorKind::Binary),
+    ("rc", "reinterpret_cast", OperatorKind::NamedCast),
+    ("rm", "%", OperatorKind::Binary),
+    ("rs", ">>", OperatorKind::Binary),
+    ("sc", "static_cast", OperatorKind::NamedCast),
+    ("ss", "<=>", OperatorKind::Binary),
+    ("st", "sizeof (", OperatorKind::OfType),
+    ("sz", "sizeof (", OperatorKind::OfExpr),
+    ("te", "typeid (", OperatorKind::OfExpr),
+    ("ti", "typeid (", OperatorKind::OfType),
+];
+
+fn find_operator(code: &[u8]) -> Option<(&'static str, OperatorKind)> {
+    OPERATORS.iter().find(|(operator_code, _, _)| operator_code.as_bytes() == code).map(|&(_, spelling, kind)| (spelling, kind))
+}
+
+fn builtin_type(code: u8) -> Option<&'static str> {
+    let name = match code {
+        b'v' => "void",
+        b'w' => "wchar_t",
+        b'b' => "bool",
+        b'c' => "char",
+        b'a' => "signed char",
+        b'h' => "unsigned char",
+        b's' => "short",
+        b't' => "unsigned short",
+        b'i' => "int",
+        b'j' => "unsigned int",
+        b'l' => "long",
+        b'm' => "unsigned long",
+        b'x' => "long long",
+        b'y' => "unsigned long long",
+        b'n' => "__int128",
+        b'o' => "unsigned __int128",
+        b'f' => "float",
+        b'd' => "double",
+        b'e' => "long double",
+        b'g' => "__float128",
+        b'z' => "...",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// Builtin types spelled with a `D` prefix.
+fn extended_builtin_type(code: u8) -> Option<&'static str> {
+    let name = match code {
+        b'd' => "decimal64",
+        b'e' => "decimal128",
+        b'f' => "decimal32",
+        b'h' => "half",
+        b'i' => "char32_t",
+        b's' => "char16_t",
+        b'u' => "char8_t",
+        b'a' => "auto",
+        b'c' => "decltype(auto)",
+        b'n' => "std::nullptr_t",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// Information about the name of an encoding needed to parse the rest of it.
+#[derive(Default)]
+struct NameState {
+    ends_with_template_args: bool,
+    is_ctor_dtor_conversion: bool,
+    quals: u8,
+    ref_qualifier: Option<&'static str>,
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+    depth: usize,
+    substitutions: Vec<NodeRef>,
+    template_params: Vec<NodeRef>,
+    name_state: NameState,
+    // Generic lambdas refer to their invented `auto` parameters as template parameters.
+    parsing_lambda_params: bool,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Parser<'a> {
+        Parser {
+            text: text.as_bytes(),
+            position: 0,
+            depth: 0,
+            substitutions: Vec::new(),
+            template_params: Vec::new(),
+            name_state: NameState::default(),
+            parsing_lambda_params: false,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.text.get(self.position + offset).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn next_if(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next_if_str(&mut self, prefix: &str) -> bool {
+        if self.text[self.position..].starts_with(prefix.as_bytes()) {
+            self.position += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        if self.next_if(byte) {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.position >= self.text.len()
+    }
+
+    // Every recursive production goes through this guard.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        if self.depth >= MAX_NESTING {
+            return None;
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    // mangled-name ::= _Z <encoding> [. <vendor-specific suffix>]
+    fn parse_mangled_name(&mut self) -> Option<NodeRef> {
+        if !self.next_if_str("_Z") {
+            return None;
+        }
+        let mut node = self.parse_encoding()?;
+        if self.peek() == Some(b'.') {
+            let suffix = String::from_utf8_lossy(&self.text[self.position..]).into_owned();
+            self.position = self.text.len();
+            node = Node::DotSuffix { child: node, suffix }.rc();
+        }
+        if !self.is_at_end() {
+            return None;
+        }
+        Some(node)
+    }
+
+    fn parse_number(&mut self) -> Option<String> {
+        let start = self.position;
+        self.next_if(b'n');
+        let digits = self.position;
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.position += 1;
+        }
+        if digits == self.position {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&self.text[start..self.position]).replace('n', "-"))
+    }
+
+    fn parse_positive_number(&mut self) -> Option<usize> {
+        let start = self.position;
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.text[start..self.position]).ok()?.parse().ok()
+    }
+
+    // seq-id ::= [0-9A-Z]+, base 36
+    fn parse_seq_id(&mut self) -> Option<usize> {
+        let mut value: usize = 0;
+        let start = self.position;
+        while let Some(digit) = self.peek().and_then(|byte| (byte as char).to_digit(36).filter(|_| !byte.is_ascii_lowercase())) {
+            value = value.checked_mul(36)?.checked_add(digit as usize)?;
+            self.position += 1;
+        }
+        if start == self.position {
+            return None;
+        }
+        Some(value)
+    }
+
+    fn parse_source_name(&mut self) -> Option<String> {
+        let length = self.parse_positive_number()?;
+        if length == 0 {
+            return None;
+        }
+        let bytes = self.text.get(self.position..self.position.checked_add(length)?)?;
+        self.position += length;
+        // GCC names anonymous namespaces after their file.
+        if bytes.starts_with(b"_GLOBAL__N") {
+            return Some(String::from("(anonymous namespace)"));
+        }
+        Some(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn parse_cv_qualifiers(&mut self) -> u8 {
+        let mut quals = 0;
+        if self.next_if(b'r') {
+            quals |= QUAL_RESTRICT;
+        }
+        if self.next_if(b'V') {
+            quals |= QUAL_VOLATILE;
+        }
+        if self.next_if(b'K') {
+            quals |= QUAL_CONST;
+        }
+        quals
+    }
+
+    // encoding ::= <name> <bare-function-type> | <name> | <special-name>
+    fn parse_encoding(&mut self) -> Option<NodeRef> {
+        self.nested(|parser| {
+            if matches!(parser.peek(), Some(b'G' | b'T')) {
+                return parser.parse_special_name();
+            }
+            // Local names contain encodings of their own.
+            let outer_state = std::mem::take(&mut parser.name_state);
+            let result = parser.parse_function_encoding();
+            parser.name_state = outer_state;
+            result
+        })
+    }
+
+    fn parse_function_encoding(&mut self) -> Option<NodeRef> {
+        let entity_name = self.parse_name(true)?;
+        if matches!(self.peek(), None | Some(b'E' | b'.')) {
+            return Some(entity_name);
+        }
+        // Template functions mangle their return type, except for constructors, destructors and
+        // conversion operators, which have none.
+        let ret = if self.name_state.ends_with_template_args && !self.name_state.is_ctor_dtor_conversion {
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+        let mut params = Vec::new();
+        if !self.next_if(b'v') {
+            while !matches!(self.peek(), None | Some(b'E' | b'.')) {
+                params.push(self.parse_type()?);
+            }
+            if params.is_empty() {
+                return None;
+            }
+        }
+        Some(Node::Encoding { ret, name: entity_name, params, quals: self.name_state.quals, ref_qualifier: self.name_state.ref_qualifier }.rc())
+    }
+
+    fn parse_call_offset(&mut self) -> Option<()> {
+        match self.next()? {
+            b'h' => {
+                self.parse_number()?;
+            }
+            b'v' => {
+                self.parse_number()?;
+                self.expect(b'_')?;
+                self.parse_number()?;
+            }
+            _ => return None,
+        }
+        self.expect(b'_')
+    }
+
+    fn parse_special_name(&mut self) -> Option<NodeRef> {
+        let special = |prefix: &'static str, child: NodeRef| Some(Node::Special { prefix, child }.rc());
+        match (self.next()?, self.next()?) {
+            (b'T', b'V') => special("vtable for ", self.parse_type()?),
+            (b'T', b'T') => special("VTT for ", self.parse_type()?),
+            (b'T', b'I') => special("typeinfo for ", self.parse_type()?),
+            (b'T', b'S') => special("typeinfo name for ", self.parse_type()?),
+            (b'T', b'h') => {
+                self.position -= 1;
+                self.parse_call_offset()?;
+                special("non-virtual thunk to ", self.parse_encoding()?)
+            }
+            (b'T', b'v') => {
+                self.position -= 1;
+                self.parse_call_offset()?;
+                special("virtual thunk to ", self.parse_encoding()?)
+            }
+            (b'T', b'c') => {
+                self.parse_call_offset()?;
+                self.parse_call_offset()?;
+                special("covariant return thunk to ", self.parse_encoding()?)
+            }
+            (b'T', b'C') => {
+                let derived = self.parse_type()?;
+                self.parse_number()?;
+                self.expect(b'_')?;
+                let base = self.parse_type()?;
+                Some(Node::ConstructionVtable { base, derived }.rc())
+            }
+            (b'T', b'W') => special("thread-local wrapper routine for ", self.parse_name(false)?),
+            (b'T', b'H') => special("thread-local initialization routine for ", self.parse_name(false)?),
+            (b'T', b'A') => special("template parameter object for ", self.parse_template_arg()?),
+            (b'G', b'V') => special("guard variable for ", self.parse_name(false)?),
+            (b'G', b'R') => {
+                let entity = self.parse_name(false)?;
+                if !self.next_if(b'_') && self.parse_seq_id().is_some() {
+                    self.expect(b'_')?;
+                }
+                special("reference temporary for ", entity)
+            }
+            (b'G', b'T') => {
+                let prefix = match self.next()? {
+                    b'n' => "non-transaction clone for ",
+                    b't' => "transaction clone for ",
+                    _ => return None,
+                };
+                special(prefix, self.parse_encoding()?)
+            }
+            _ => None,
+        }
+    }
+
+    // name ::= <nested-name> | <local-name> | <unscoped-template-name> <template-args> | <unscoped-name>
+    fn parse_name(&mut self, tag: bool) -> Option<NodeRef> {
+        self.nested(|parser| {
+            match parser.peek()? {
+                b'N' => return parser.parse_nested_name(tag),
+                b'Z' => return parser.parse_local_name(tag),
+                _ => {}
+            }
+            let is_std = parser.next_if_str("St");
+            parser.next_if(b'L');
+            let (mut result, is_substitution) = if !is_std && parser.peek() == Some(b'S') {
+                (parser.parse_substitution()?, true)
+            } else {
+                let unqualified = parser.parse_unqualified_name(tag, None)?;
+                let result = if is_std { Node::Nested { qualifier: name("std"), name: unqualified }.rc() } else { unqualified };
+                (result, false)
+            };
+            if parser.peek() == Some(b'I') {
+                if !is_substitution {
+                    parser.substitutions.push(result.clone());
+                }
+                let args = parser.parse_template_args(tag)?;
+                if tag {
+                    parser.name_state.ends_with_template_args = true;
+                }
+                result = Node::Template { name: result, args }.rc();
+            } else if is_substitution {
+                // An unscoped template name has to be followed by its arguments.
+                return None;
+            }
+            Some(result)
+        })
+    }
+
+    // nested-name ::= N [<CV-qualifiers>] [<ref-qualifier>] <prefix> <unqualified-name> E
+    fn parse_nested_name(&mut self, tag: bool) -> Option<NodeRef> {
+        self.expect(b'N')?;
+        let quals = self.parse_cv_qualifiers();
+        let ref_qualifier = if self.next_if(b'O') {
+            Some("&&")
+        } else if self.next_if(b'R') {
+            Some("&")
+        } else {
+            None
+        };
+        if tag {
+            self.name_state.quals = quals;
+            self.name_state.ref_qualifier = ref_qualifier;
+        }
+
+        let mut so_far: Option<NodeRef> = None;
+        let push_component = |so_far: &mut Option<NodeRef>, component: NodeRef| {
+            *so_far = Some(match so_far.take() {
+                Some(qualifier) => Node::Nested { qualifier, name: component }.rc(),
+                None => component,
+            });
+        };
+        while !self.next_if(b'E') {
+            self.next_if(b'L');
+            if tag {
+                self.name_state.ends_with_template_args = false;
+            }
+            match (self.peek()?, self.peek_at(1)) {
+                (b'T', _) => {
+                    let param = self.parse_template_param()?;
+                    push_component(&mut so_far, param);
+                }
+                (b'I', _) => {
+                    let args = self.parse_template_args(tag)?;
+                    if tag {
+                        self.name_state.ends_with_template_args = true;
+                    }
+                    so_far = Some(Node::Template { name: so_far?, args }.rc());
+                }
+                (b'D', Some(b't' | b'T')) => {
+                    let decltype = self.parse_decltype()?;
+                    push_component(&mut so_far, decltype);
+                }
+                (b'S', Some(b't')) => {
+                    self.position += 2;
+                    push_component(&mut so_far, name("std"));
+                    continue;
+                }
+                (b'S', _) => {
+                    let substitution = self.parse_substitution()?;
+                    let is_first = so_far.is_none();
+                    push_component(&mut so_far, substitution);
+                    if is_first {
+                        continue;
+                    }
+                }
+                _ => {
+                    // Constructors and destructors of `std::string` and the streams spell out the
+                    // template they are members of.
+                    if matches!((self.peek()?, self.peek_at(1)), (b'C', _) | (b'D', Some(b'0'..=b'5'))) {
+                        so_far = so_far.map(|scope| expand_special_substitution(&scope).unwrap_or(scope));
+                    }
+                    let unqualified = self.parse_unqualified_name(tag, so_far.as_ref())?;
+                    push_component(&mut so_far, unqualified);
+                }
+            }
+            self.substitutions.push(so_far.clone()?);
+            // A data member prefix of a closure in a variable initializer.
+            self.next_if(b'M');
+        }
+        // The complete name is only substitutable as a type.
+        self.substitutions.pop()?;
+        so_far
+    }
+
+    // local-name ::= Z <encoding> E <entity name> [<discriminator>]
+    //            ::= Z <encoding> E s [<discriminator>]
+    //            ::= Z <encoding> Ed [<number>] _ <entity name>
+    fn parse_local_name(&mut self, tag: bool) -> Option<NodeRef> {
+        self.expect(b'Z')?;
+        let encoding = self.parse_encoding()?;
+        self.expect(b'E')?;
+        if self.next_if(b's') {
+            self.parse_discriminator();
+            return Some(Node::Local { encoding, entity: name("string literal") }.rc());
+        }
+        if self.next_if(b'd') {
+            let index = if self.next_if(b'_') {
+                1
+            } else {
+                let number = self.parse_positive_number()?;
+                self.expect(b'_')?;
+                number + 2
+            };
+            let entity = self.parse_name(tag)?;
+            let qualifier = name(format!("{{default arg#{}}}", index));
+            return Some(Node::Local { encoding, entity: Node::Nested { qualifier, name: entity }.rc() }.rc());
+        }
+        let entity = self.parse_name(tag)?;
+        self.parse_discriminator();
+        Some(Node::Local { encoding, entity }.rc())
+    }
+
+    // discriminator ::= _ <digit> | __ <number> _
+    fn parse_discriminator(&mut self) {
+        let start = self.position;
+        if self.next_if(b'_') {
+            if self.next_if(b'_') {
+                if self.parse_positive_number().is_some() && self.next_if(b'_') {
+                    return;
+                }
+            } else if self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                self.position += 1;
+                return;
+            }
+        }
+        self.position = start;
+    }
+
+    fn parse_unqualified_name(&mut self, tag: bool, scope: Option<&NodeRef>) -> Option<NodeRef> {
+        let mut result = match (self.peek()?, self.peek_at(1)) {
+            (b'U', _) => self.parse_unnamed_type_name()?,
+            (b'0'..=b'9', _) => name(self.parse_source_name()?),
+            (b'D', Some(b'C')) => {
+                self.position += 2;
+                let mut bindings = Vec::new();
+                while !self.next_if(b'E') {
+                    bindings.push(name(self.parse_source_name()?));
+                }
+                Node::StructuredBinding(bindings).rc()
+            }
+            (b'C', _) | (b'D', Some(b'0'..=b'5')) => self.parse_ctor_dtor_name(tag, scope?)?,
+            _ => self.parse_operator_name(tag)?,
+        };
+        while self.next_if(b'B') {
+            let tag = self.parse_source_name()?;
+            result = Node::AbiTagged { name: result, tag }.rc();
+        }
+        Some(result)
+    }
+
+    // ctor-dtor-name ::= C [I] (1|2|3|4|5) [<base class type>] | D (0|1|2|4|5)
+    fn parse_ctor_dtor_name(&mut self, tag: bool, scope: &NodeRef) -> Option<NodeRef> {
+        let is_dtor = match self.next()? {
+            b'C' => {
+                let is_inheriting = self.next_if(b'I');
+                if !matches!(self.next()?, b'1'..=b'5') {
+                    return None;
+                }
+                if is_inheriting {
+                    self.parse_name(false)?;
+                }
+                false
+            }
+            b'D' => {
+                if !matches!(self.next()?, b'0' | b'1' | b'2' | b'4' | b'5') {
+                    return None;
+                }
+                true
+            }
+            _ => return None,
+        };
+        if tag {
+            self.name_state.is_ctor_dtor_conversion = true;
+        }
+        Some(Node::CtorDtor { base_name: base_name(scope), is_dtor }.rc())
+    }
+
+    // unnamed-type-name ::= Ut [<number>] _ | Ul <lambda-sig> E [<number>] _
+    fn parse_unnamed_type_name(&mut self) -> Option<NodeRef> {
+        self.expect(b'U')?;
+        if self.next_if(b't') {
+            let count = self.parse_optional_count()?;
+            return Some(Node::Unnamed(count).rc());
+        }
+        self.expect(b'l')?;
+
+        let outer_params = std::mem::take(&mut self.template_params);
+        let outer_parsing_lambda_params = std::mem::replace(&mut self.parsing_lambda_params, true);
+        let closure = self.parse_lambda_signature();
+        self.template_params = outer_params;
+        self.parsing_lambda_params = outer_parsing_lambda_params;
+        closure
+    }
+
+    fn parse_lambda_signature(&mut self) -> Option<NodeRef> {
+        let mut template_params = Vec::new();
+        while self.peek() == Some(b'T') && matches!(self.peek_at(1), Some(b'y' | b'n')) {
+            self.position += 1;
+            let param_name = if self.template_params.is_empty() { String::from("$T") } else { format!("$T{}", self.template_params.len() - 1) };
+            let declaration = if self.next_if(b'y') {
+                format!("typename {}", param_name)
+            } else {
+                self.position += 1;
+                let ty = self.parse_type()?;
+                let mut printer = Printer::new(None);
+                printer.print(&ty).ok()?;
+                format!("{} {}", printer.output, param_name)
+            };
+            template_params.push(name(declaration));
+            self.template_params.push(name(param_name));
+        }
+        let mut params = Vec::new();
+        if !self.next_if(b'v') {
+            while self.peek() != Some(b'E') {
+                params.push(self.parse_type()?);
+            }
+        }
+        self.expect(b'E')?;
+        let count = self.parse_optional_count()?;
+        Some(Node::Closure { template_params, params, count }.rc())
+    }
+
+    // [<number>] _
+    fn parse_optional_count(&mut self) -> Option<String> {
+        let start = self.position;
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.position += 1;
+        }
+        let count = String::from_utf8_lossy(&self.text[start..self.position]).into_owned();
+        self.expect(b'_')?;
+        Some(count)
+    }
+
+    fn parse_operator_name(&mut self, tag: bool) -> Option<NodeRef> {
+        let code = [self.next()?, self.next()?];
+        match &code {
+            b"cv" => {
+                let ty = self.parse_type()?;
+                if tag {
+                    self.name_state.is_ctor_dtor_conversion = true;
+                }
+                return Some(Node::ConversionOperator(ty).rc());
+            }
+            b"li" => return Some(name(format!("operator\"\" {}", self.parse_source_name()?))),
+            [b'v', b'0'..=b'9'] => return Some(name(format!("operator {}", self.parse_source_name()?))),
+            _ => {}
+        }
+        let (spelling, kind) = find_operator(&code)?;
+        if matches!(kind, OperatorKind::NamedCast | OperatorKind::OfType | OperatorKind::OfExpr) || code == *b"dt" || code == *b"ds" {
+            return None;
+        }
+        let separator = if spelling.starts_with(|character: char| character.is_ascii_alphabetic()) { " " } else { "" };
+        Some(name(format!("operator{}{}", separator, spelling)))
+    }
+
+    // substitution ::= S_ | S <seq-id> _ | Sa | Sb | Ss | Si | So | Sd
+    fn parse_substitution(&mut self) -> Option<NodeRef> {
+        self.expect(b'S')?;
+        let special = |name: &'static str, base_name: &'static str| Some(Node::SpecialSubstitution { name, base_name }.rc());
+        match self.peek()? {
+            b'a' | b'b' | b's' | b'i' | b'o' | b'd' => {
+                let code = self.next()?;
+                let substitution = match code {
+                    b'a' => special("std::allocator", "allocator"),
+                    b'b' => special("std::basic_string", "basic_string"),
+                    b's' => special("std::string", "basic_string"),
+                    b'i' => special("std::istream", "basic_istream"),
+                    b'o' => special("std::ostream", "basic_ostream"),
+                    _ => special("std::iostream", "basic_iostream"),
+                };
+                // Special substitutions may carry ABI tags, e.g. `SsB5cxx11`.
+                let mut substitution = substitution?;
+                while self.next_if(b'B') {
+                    let tag = self.parse_source_name()?;
+                    substitution = Node::AbiTagged { name: substitution, tag }.rc();
+                }
+                Some(substitution)
+            }
+            b'_' => {
+                self.position += 1;
+                self.substitutions.first().cloned()
+            }
+            _ => {
+                let index = self.parse_seq_id()?.checked_add(1)?;
+                self.expect(b'_')?;
+                self.substitutions.get(index).cloned()
+            }
+        }
+    }
+
+    // template-param ::= T_ | T <number> _ | TL <level> __ | TL <level> _ <number> _
+    fn parse_template_param(&mut self) -> Option<NodeRef> {
+        self.expect(b'T')?;
+        let mut level = 0;
+        if self.next_if(b'L') {
+            level = self.parse_positive_number()?.checked_add(1)?;
+            self.expect(b'_')?;
+        }
+        let index = if self.next_if(b'_') {
+            0
+        } else {
+            let index = self.parse_positive_number()?.checked_add(1)?;
+            self.expect(b'_')?;
+            index
+        };
+        if level > 1 {
+            return None;
+        }
+        match self.template_params.get(index) {
+            Some(param) => Some(param.clone()),
+            // The invented template parameters of generic lambdas are spelled as `auto`.
+            None if self.parsing_lambda_params => Some(name(format!("auto:{}", index + 1))),
+            None => None,
+        }
+    }
+
+    // template-args ::= I <template-arg>+ E
+    fn parse_template_args(&mut self, tag: bool) -> Option<Vec<NodeRef>> {
+        self.expect(b'I')?;
+        if tag {
+            self.template_params.clear();
+        }
+        let mut args = Vec::new();
+        while !self.next_if(b'E') {
+            if tag {
+                // References inside an argument are to the enclosing template's parameters.
+                let params = std::mem::take(&mut self.template_params);
+                let arg = self.parse_template_arg();
+                self.template_params = params;
+                let arg = arg?;
+                self.template_params.push(arg.clone());
+                args.push(arg);
+            } else {
+                args.push(self.parse_template_arg()?);
+            }
+        }
+        Some(args)
+    }
+
+    fn parse_template_arg(&mut self) -> Option<NodeRef> {
+        self.nested(|parser| match parser.peek()? {
+            b'X' => {
+                parser.position += 1;
+                let expr = parser.parse_expr()?;
+                parser.expect(b'E')?;
+                Some(expr)
+            }
+            b'J' => {
+                parser.position += 1;
+                let mut args = Vec::new();
+                while !parser.next_if(b'E') {
+                    args.push(parser.parse_template_arg()?);
+                }
+                Some(Node::ArgumentPack(args).rc())
+            }
+            b'L' if parser.peek_at(1) == Some(b'Z') => {
+                parser.position += 2;
+                let encoding = parser.parse_encoding()?;
+                parser.expect(b'E')?;
+                Some(encoding)
+            }
+            b'L' => parser.parse_expr_primary(),
+            _ => parser.parse_type(),
+        })
+    }
+
+    fn parse_type(&mut self) -> Option<NodeRef> {
+        self.nested(|parser| parser.parse_type_inner())
+    }
+
+    fn parse_type_inner(&mut self) -> Option<NodeRef> {
+        let result = match (self.peek()?, self.peek_at(1)) {
+            (b'r' | b'V' | b'K', _) => {
+                let quals = self.parse_cv_qualifiers();
+                let child = self.parse_type()?;
+                Node::Qualified { child, quals }.rc()
+            }
+            (b'U', _) => {
+                self.position += 1;
+                let mut qualifier = self.parse_source_name()?;
+                if self.peek() == Some(b'I') {
+                    let args = self.parse_template_args(false)?;
+                    let mut printer = Printer::new(None);
+                    printer.print_list(&args, ", ").ok()?;
+                    qualifier = format!("{}<{}>", qualifier, printer.output);
+                }
+                let child = self.parse_type()?;
+                Node::VendorQualified { child, qualifier }.rc()
+            }
+            (b'u', _) => {
+                self.position += 1;
+                name(self.parse_source_name()?)
+            }
+            (b'D', Some(b't' | b'T')) => self.parse_decltype()?,
+            (b'D', Some(b'v')) => {
+                self.position += 2;
+                let dimension = if self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                    Some(name(self.parse_positive_number()?.to_string()))
+                } else {
+                    self.expect(b'_')?;
+                    Some(self.parse_expr()?)
+                };
+                self.expect(b'_')?;
+                let element = self.parse_type()?;
+                Node::Vector { element, dimension }.rc()
+            }
+            (b'D', Some(b'p')) => {
+                self.position += 2;
+                Node::PackExpansion(self.parse_type()?).rc()
+            }
+            (b'D', Some(b'F')) => {
+                self.position += 2;
+                let bits = self.parse_positive_number()?;
+                self.expect(b'_')?;
+                return Some(name(format!("_Float{}", bits)));
+            }
+            (b'D', Some(b'o' | b'O' | b'w' | b'x')) => self.parse_function_type()?,
+            (b'D', Some(code)) => {
+                let builtin = extended_builtin_type(code)?;
+                self.position += 2;
+                return Some(name(builtin));
+            }
+            (b'F', _) => self.parse_function_type()?,
+            (b'A', _) => self.parse_array_type()?,
+            (b'M', _) => {
+                self.position += 1;
+                let class = self.parse_type()?;
+                let member = self.parse_type()?;
+                Node::PointerToMember { class, member }.rc()
+            }
+            (b'T', Some(b's' | b'u' | b'e')) => {
+                self.position += 1;
+                let kind = match self.next()? {
+                    b's' => "struct",
+                    b'u' => "union",
+                    _ => "enum",
+                };
+                let child = self.parse_name(false)?;
+                Node::Elaborated { kind, child }.rc()
+            }
+            (b'T', _) => {
+                let mut param = self.parse_template_param()?;
+                // A template template parameter with its arguments.
+                if self.peek() == Some(b'I') {
+                    self.substitutions.push(param.clone());
+                    let args = self.parse_template_args(false)?;
+                    param = Node::Template { name: param, args }.rc();
+                }
+                param
+            }
+            (b'P', _) => {
+                self.position += 1;
+                Node::Pointer(self.parse_type()?).rc()
+            }
+            (b'R' | b'O', _) => {
+                let is_rvalue = self.next()? == b'O';
+                Node::Reference { child: self.parse_type()?, is_rvalue }.rc()
+            }
+            (b'C' | b'G', _) => {
+                let suffix = if self.next()? == b'C' { "complex" } else { "imaginary" };
+                let child = self.parse_type()?;
+                Node::VendorQualified { child, qualifier: String::from(suffix) }.rc()
+            }
+            (b'S', Some(byte)) if byte != b't' => {
+                let substitution = self.parse_substitution()?;
+                if self.peek() != Some(b'I') {
+                    return Some(substitution);
+                }
+                let args = self.parse_template_args(false)?;
+                Node::Template { name: substitution, args }.rc()
+            }
+            (code, _) if builtin_type(code).is_some() => {
+                self.position += 1;
+                return Some(name(builtin_type(code)?));
+            }
+            _ => self.parse_name(false)?,
+        };
+        self.substitutions.push(result.clone());
+        Some(result)
+    }
+
+    fn parse_decltype(&mut self) -> Option<NodeRef> {
+        self.expect(b'D')?;
+        if !matches!(self.next()?, b't' | b'T') {
+            return None;
+        }
+        let expr = self.parse_expr()?;
+        self.expect(b'E')?;
+        Some(Node::Enclosing { prefix: "decltype(", child: expr, postfix: ")" }.rc())
+    }
+
+    // function-type ::= [<CV-qualifiers>] [<exception-spec>] [Dx] F [Y] <return type> <parameter types> [<ref-qualifier>] E
+    fn parse_function_type(&mut self) -> Option<NodeRef> {
+        let mut exception = None;
+        if self.next_if_str("Do") {
+            exception = Some(name("noexcept"));
+        } else if self.next_if_str("DO") {
+            let expr = self.parse_expr()?;
+            self.expect(b'E')?;
+            exception = Some(Node::Enclosing { prefix: "noexcept(", child: expr, postfix: ")" }.rc());
+        } else if self.next_if_str("Dw") {
+            let mut types = Vec::new();
+            while !self.next_if(b'E') {
+                types.push(self.parse_type()?);
+            }
+            let mut printer = Printer::new(None);
+            printer.print_list(&types, ", ").ok()?;
+            exception = Some(name(format!("throw({})", printer.output)));
+        }
+        self.next_if_str("Dx");
+        self.expect(b'F')?;
+        self.next_if(b'Y');
+        let ret = self.parse_type()?;
+        let mut params = Vec::new();
+        let mut ref_qualifier = None;
+        loop {
+            if self.next_if(b'E') {
+                break;
+            }
+            if self.next_if(b'v') {
+                continue;
+            }
+            if self.next_if_str("RE") {
+                ref_qualifier = Some("&");
+                break;
+            }
+            if self.next_if_str("OE") {
+                ref_qualifier = Some("&&");
+                break;
+            }
+            params.push(self.parse_type()?);
+        }
+        Some(Node::Function { ret, params, quals: 0, ref_qualifier, exception }.rc())
+    }
+
+    // array-type ::= A <number> _ <element type> | A [<expression>] _ <element type>
+    fn parse_array_type(&mut self) -> Option<NodeRef> {
+        self.expect(b'A')?;
+        let dimension = if self.next_if(b'_') {
+            None
+        } else {
+            let dimension = if self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                name(self.parse_positive_number()?.to_string())
+            } else {
+                self.parse_expr()?
+            };
+            self.expect(b'_')?;
+            Some(dimension)
+        };
+        let element = self.parse_type()?;
+        Some(Node::Array { element, dimension }.rc())
+    }
+
+    // expr-primary ::= L <type> <value number> E | L <type> <value float> E | L <mangled-name> E | LDnE
+    fn parse_expr_primary(&mut self) -> Option<NodeRef> {
+        self.expect(b'L')?;
+        if self.peek() == Some(b'_') && self.peek_at(1) == Some(b'Z') {
+            let encoding = self.parse_mangled_name_in_literal()?;
+            return Some(encoding);
+        }
+        if self.next_if_str("DnE") {
+            return Some(name("nullptr"));
+        }
+        if self.next_if_str("Dn0E") {
+            return Some(name("nullptr"));
+        }
+        let literal = |ty: Option<NodeRef>, value: String, suffix: &'static str| Some(Node::Literal { ty, value, suffix }.rc());
+        let code = self.peek()?;
+        let result = match code {
+            b'b' if matches!(self.peek_at(1), Some(b'0' | b'1')) && self.peek_at(2) == Some(b'E') => {
+                self.position += 2;
+                name(if self.text[self.position - 1] == b'1' { "true" } else { "false" })
+            }
+            b'i' | b'j' | b'l' | b'm' | b'x' | b'y' => {
+                self.position += 1;
+                let suffix = match code {
+                    b'j' => "u",
+                    b'l' => "l",
+                    b'm' => "ul",
+                    b'x' => "ll",
+                    b'y' => "ull",
+                    _ => "",
+                };
+                literal(None, self.parse_number()?, suffix)?
+            }
+            b'f' | b'd' | b'e' => {
+                // Floating point values are mangled as the hex digits of their representation.
+                self.position += 1;
+                let start = self.position;
+                while self.peek().is_some_and(|byte| byte.is_ascii_hexdigit()) {
+                    self.position += 1;
+                }
+                let value = String::from_utf8_lossy(&self.text[start..self.position]).into_owned();
+                literal(Some(name(builtin_type(code)?)), format!("[{}]", value), "")?
+            }
+            _ => {
+                let ty = self.parse_type()?;
+                // String literals only mangle their type.
+                if self.peek() == Some(b'E') {
+                    self.position += 1;
+                    return literal(Some(ty), String::new(), "");
+                }
+                literal(Some(ty), self.parse_number()?, "")?
+            }
+        };
+        self.expect(b'E')?;
+        Some(result)
+    }
+
+    fn parse_mangled_name_in_literal(&mut self) -> Option<NodeRef> {
+        self.expect(b'_')?;
+        self.expect(b'Z')?;
+        let encoding = self.parse_encoding()?;
+        self.expect(b'E')?;
+        Some(encoding)
+    }
+
+    fn parse_expr(&mut self) -> Option<NodeRef> {
+        self.nested(|parser| parser.parse_expr_inner())
+    }
+
+    fn parse_expr_inner(&mut self) -> Option<NodeRef> {
+        match (self.peek()?, self.peek_at(1)) {
+            (b'L', _) => return self.parse_expr_primary(),
+            (b'T', _) => return self.parse_template_param(),
+            (b'f', Some(b'p' | b'L')) => {
+                // fp <CV-qualifiers> [<number>] _ | fL <level> p <CV-qualifiers> [<number>] _
+                self.position += 1;
+                if self.next_if(b'L') {
+                    self.parse_positive_number()?;
+                }
+                self.expect(b'p')?;
+                self.parse_cv_qualifiers();
+                let start = self.position;
+                while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                    self.position += 1;
+                }
+                let index = String::from_utf8_lossy(&self.text[start..self.position]).into_owned();
+                self.expect(b'_')?;
+                return Some(name(format!("fp{}", index)));
+            }
+            (b'0'..=b'9', _) => return self.parse_unresolved_name(),
+            _ => {}
+        }
+
+        let code = [self.next()?, self.next()?];
+        match &code {
+            b"sp" => return Some(Node::PackExpansion(self.parse_expr()?).rc()),
+            b"sZ" => {
+                let pack = if self.peek() == Some(b'T') { self.parse_template_param()? } else { self.parse_expr()? };
+                return Some(Node::Enclosing { prefix: "sizeof...(", child: pack, postfix: ")" }.rc());
+            }
+            b"sr" | b"on" | b"dn" => {
+                self.position -= 2;
+                return self.parse_unresolved_name();
+            }
+            b"gs" => {
+                let expr = self.parse_expr()?;
+                return Some(Node::Prefix { op: "::", operand: expr }.rc());
+            }
+            b"tw" => return Some(Node::Throw(Some(self.parse_expr()?)).rc()),
+            b"tr" => return Some(Node::Throw(None).rc()),
+            b"nx" => return Some(Node::Enclosing { prefix: "noexcept (", child: self.parse_expr()?, postfix: ")" }.rc()),
+            b"cv" => {
+                let ty = self.parse_type()?;
+                let mut args = Vec::new();
+                if self.next_if(b'_') {
+                    while !self.next_if(b'E') {
+                        args.push(self.parse_expr()?);
+                    }
+                } else {
+                    args.push(self.parse_expr()?);
+                }
+                return Some(Node::ConversionExpr { ty, args }.rc());
+            }
+            _ => {}
+        }
+
+        let (spelling, kind) = find_operator(&code)?;
+        let node = match kind {
+            OperatorKind::Prefix => Node::Prefix { op: spelling, operand: self.parse_expr()? },
+            OperatorKind::Postfix => {
+                // `pp_` and `mm_` are the prefix forms.
+                if self.next_if(b'_') {
+                    Node::Prefix { op: spelling, operand: self.parse_expr()? }
+                } else {
+                    Node::Postfix { operand: self.parse_expr()?, op: spelling }
+                }
+            }
+            OperatorKind::Binary => {
+                let left = self.parse_expr()?;
+                let right = self.parse_expr()?;
+                Node::Binary { left, op: spelling, right }
+            }
+            OperatorKind::Call => {
+                let callee = self.parse_expr()?;
+                let mut args = Vec::new();
+                while !self.next_if(b'E') {
+                    args.push(self.parse_expr()?);
+                }
+                Node::Call { callee, args }
+            }
+            OperatorKind::Subscript => {
+                let array = self.parse_expr()?;
+                let index = self.parse_expr()?;
+                Node::Subscript { array, index }
+            }
+            OperatorKind::Member => {
+                let object = self.parse_expr()?;
+                let member = if matches!(&code, b"dt" | b"pt") { self.parse_unresolved_name()? } else { self.parse_expr()? };
+                Node::MemberAccess { object, op: spelling, member }
+            }
+            OperatorKind::Conditional => {
+                let condition = self.parse_expr()?;
+                let then = self.parse_expr()?;
+                let otherwise = self.parse_expr()?;
+                Node::Conditional { condition, then, otherwise }
+            }
+            OperatorKind::NamedCast => {
+                let ty = self.parse_type()?;
+                let expr = self.parse_expr()?;
+                Node::Cast { kind: spelling, ty, expr }
+            }
+            OperatorKind::OfType => Node::Enclosing { prefix: spelling, child: self.parse_type()?, postfix: ")" },
+            OperatorKind::OfExpr => Node::Enclosing { prefix: spelling, child: self.parse_expr()?, postfix: ")" },
+            OperatorKind::Delete => Node::Prefix { op: if code == *b"da" { "delete[] " } else { "delete " }, operand: self.parse_expr()? },
+            OperatorKind::New => {
+                // nw <placement>* _ <type> [pi <initializer>*] E
+                let mut placement = Vec::new();
+                while !self.next_if(b'_') {
+                    placement.push(self.parse_expr()?);
+                }
+                let ty = self.parse_type()?;
+                let mut initializer = None;
+                if self.next_if_str("pi") {
+                    let mut args = Vec::new();
+                    while self.peek() != Some(b'E') {
+                        args.push(self.parse_expr()?);
+                    }
+                    initializer = Some(args);
+                }
+                self.expect(b'E')?;
+                Node::New { op: spelling, placement, ty, initializer }
+            }
+        };
+        Some(node.rc())
+    }
+
+    // unresolved-name ::= [gs] <base-unresolved-name>
+    //                 ::= sr <unresolved-type> <base-unresolved-name>
+    //                 ::= srN <unresolved-type> <unresolved-qualifier-level>+ E <base-unresolved-name>
+    //                 ::= [gs] sr <unresolved-qualifier-level>+ E <base-unresolved-name>
+    fn parse_unresolved_name(&mut self) -> Option<NodeRef> {
+        let is_global = self.next_if_str("gs");
+        let mut qualifier = None;
+        if self.next_if_str("srN") {
+            qualifier = Some(self.parse_unresolved_type()?);
+            while !self.next_if(b'E') {
+                let level = self.parse_simple_id()?;
+                qualifier = Some(Node::Nested { qualifier: qualifier?, name: level }.rc());
+            }
+        } else if self.next_if_str("sr") {
+            if self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+                while !self.next_if(b'E') {
+                    let level = self.parse_simple_id()?;
+                    qualifier = Some(match qualifier {
+                        Some(qualifier) => Node::Nested { qualifier, name: level }.rc(),
+                        None => level,
+                    });
+                }
+            } else {
+                qualifier = Some(self.parse_unresolved_type()?);
+            }
+        }
+        let base = self.parse_base_unresolved_name()?;
+        let mut result = match qualifier {
+            Some(qualifier) => Node::Nested { qualifier, name: base }.rc(),
+            None => base,
+        };
+        if is_global {
+            result = Node::Prefix { op: "::", operand: result }.rc();
+        }
+        Some(result)
+    }
+
+    fn parse_unresolved_type(&mut self) -> Option<NodeRef> {
+        match self.peek()? {
+            b'T' => {
+                let mut param = self.parse_template_param()?;
+                self.substitutions.push(param.clone());
+                if self.peek() == Some(b'I') {
+                    let args = self.parse_template_args(false)?;
+                    param = Node::Template { name: param, args }.rc();
+                }
+                Some(param)
+            }
+            b'D' => {
+                let decltype = self.parse_decltype()?;
+                self.substitutions.push(decltype.clone());
+                Some(decltype)
+            }
+            b'S' if self.peek_at(1) == Some(b't') => self.parse_type(),
+            b'S' => self.parse_substitution(),
+            _ => self.parse_simple_id(),
+        }
+    }
+
+    // simple-id ::= <source-name> [<template-args>]
+    fn parse_simple_id(&mut self) -> Option<NodeRef> {
+        let id = name(self.parse_source_name()?);
+        if self.peek() == Some(b'I') {
+            let args = self.parse_template_args(false)?;
+            return Some(Node::Template { name: id, args }.rc());
+        }
+        Some(id)
+    }
+
+    // base-unresolved-name ::= <simple-id> | on <operator-name> [<template-args>] | dn <destructor-name>
+    fn parse_base_unresolved_name(&mut self) -> Option<NodeRef> {
+        if self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            return self.parse_simple_id();
+        }
+        if self.next_if_str("dn") {
+            let destroyed = if self.peek().is_some_and(|byte| byte.is_ascii_digit()) { self.parse_simple_id()? } else { self.parse_unresolved_type()? };
+            return Some(Node::Prefix { op: "~", operand: destroyed }.rc());
+        }
+        self.next_if_str("on");
+        let operator = self.parse_operator_name(false)?;
+        if self.peek() == Some(b'I') {
+            let args = self.parse_template_args(false)?;
+            return Some(Node::Template { name: operator, args }.rc());
+        }
+        Some(operator)
+    }
+}
+
+struct Printer {
+    output: String,
+    depth: usize,
+    // Index of the pack element being printed while expanding a pack.
+    pack_index: Option<usize>,
+    style: Option<DemangleStyle>,
+}
+
+type PrintResult = Result<(), ()>;
+
+impl Printer {
+    fn new(style: Option<DemangleStyle>) -> Printer {
+        Printer { output: String::new(), depth: 0, pack_index: None, style }
+    }
+
+    fn print_tree(node: &NodeRef, style: DemangleStyle) -> Option<String> {
+        let mut printer = Printer::new(Some(style));
+        printer.print(node).ok()?;
+        Some(printer.output)
+    }
+
+    fn push(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    fn print(&mut self, node: &NodeRef) -> PrintResult {
+        self.print_left(node)?;
+        self.print_right(node)
+    }
+
+    fn print_list(&mut self, nodes: &[NodeRef], separator: &str) -> PrintResult {
+        let mut first = true;
+        for node in nodes {
+            let start = self.output.len();
+            if !first {
+                self.push(separator);
+            }
+            let element_start = self.output.len();
+            self.print(node)?;
+            // Empty pack expansions leave no trace, not even a separator.
+            if self.output.len() == element_start {
+                self.output.truncate(start);
+            } else {
+                first = false;
+            }
+        }
+        Ok(())
+    }
+
+    fn enter(&mut self) -> PrintResult {
+        if self.depth > MAX_NESTING || self.output.len() > MAX_OUTPUT_LENGTH {
+            return Err(());
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // The pack element printed in place of a pack, if one is being expanded.
+    fn resolve<'n>(&self, node: &'n NodeRef) -> &'n NodeRef {
+        match (&**node, self.pack_index) {
+            (Node::ArgumentPack(args), Some(index)) if index < args.len() => &args[index],
+            _ => node,
+        }
+    }
+
+    fn has_rhs_component(&self, node: &NodeRef) -> bool {
+        match &**self.resolve(node) {
+            Node::Array { .. } | Node::Function { .. } => true,
+            Node::Pointer(child) | Node::Reference { child, .. } | Node::Qualified { child, .. } => self.has_rhs_component(child),
+            Node::PointerToMember { member, .. } => self.has_rhs_component(member),
+            _ => false,
+        }
+    }
+
+    fn has_array(&self, node: &NodeRef) -> bool {
+        match &**self.resolve(node) {
+            Node::Array { .. } => true,
+            Node::Qualified { child, .. } => self.has_array(child),
+            _ => false,
+        }
+    }
+
+    fn has_function(&self, node: &NodeRef) -> bool {
+        match &**self.resolve(node) {
+            Node::Function { .. } => true,
+            Node::Qualified { child, .. } => self.has_function(child),
+            _ => false,
+        }
+    }
+
+    fn print_left(&mut self, node: &NodeRef) -> PrintResult {
+        self.enter()?;
+        let result = self.print_left_inner(node);
+        self.depth -= 1;
+        result
+    }
+
+    fn print_right(&mut self, node: &NodeRef) -> PrintResult {
+        self.enter()?;
+        let result = self.print_right_inner(node);
+        self.depth -= 1;
+        result
+    }
+
+    fn print_left_inner(&mut self, node: &NodeRef) -> PrintResult {
+        match &**node {
+            Node::Name(text) => self.push(text),
+            Node::SpecialSubstitution { name, .. } => self.push(name),
+            Node::Nested { qualifier, name } => {
+                self.print(qualifier)?;
+                self.push("::");
+                self.print(name)?;
+            }
+            Node::Local { encoding, entity } => {
+                self.print(encoding)?;
+                self.push("::");
+                self.print(entity)?;
+            }
+            Node::Template { name, args } => {
+                self.print(name)?;
+                self.push("<");
+                self.print_list(args, ", ")?;
+                self.push(">");
+            }
+            Node::ArgumentPack(args) => match self.pack_index {
+                Some(index) if index < args.len() => self.print_left(&args[index])?,
+                _ => self.print_list(args, ", ")?,
+            },
+            Node::AbiTagged { name, tag } => {
+                self.print(name)?;
+                self.push("[abi:");
+                self.push(tag);
+                self.push("]");
+            }
+            Node::CtorDtor { base_name, is_dtor } => {
+                if *is_dtor {
+                    self.push("~");
+                }
+                self.print(base_name)?;
+            }
+            Node::ConversionOperator(ty) => {
+                self.push("operator ");
+                self.print(ty)?;
+            }
+            Node::Closure { template_params, params, count } => {
+                self.push("'lambda");
+                self.push(count);
+                self.push("'");
+                if !template_params.is_empty() {
+                    self.push("<");
+                    self.print_list(template_params, ", ")?;
+                    self.push(">");
+                }
+                self.push("(");
+                self.print_list(params, ", ")?;
+                self.push(")");
+            }
+            Node::Unnamed(count) => {
+                self.push("'unnamed");
+                self.push(count);
+                self.push("'");
+            }
+            Node::StructuredBinding(bindings) => {
+                self.push("[");
+                self.print_list(bindings, ", ")?;
+                self.push("]");
+            }
+            Node::Qualified { child, quals } => {
+                self.print_left(child)?;
+                if !self.has_function(child) {
+                    self.print_quals(*quals);
+                }
+            }
+            Node::VendorQualified { child, qualifier } => {
+                self.print(child)?;
+                self.push(" ");
+                self.push(qualifier);
+            }
+            Node::Elaborated { kind, child } => {
+                self.push(kind);
+                self.push(" ");
+                self.print(child)?;
+            }
+            Node::Pointer(pointee) => {
+                self.print_left(pointee)?;
+                if self.has_array(pointee) {
+                    self.push(" ");
+                }
+                if self.has_array(pointee) || self.has_function(pointee) {
+                    self.push("(");
+                }
+                self.push("*");
+            }
+            Node::Reference { .. } => {
+                let (referee, is_rvalue) = self.collapse_reference(node);
+                self.print_left(&referee)?;
+                if self.has_array(&referee) {
+                    self.push(" ");
+                }
+                if self.has_array(&referee) || self.has_function(&referee) {
+                    self.push("(");
+                }
+                self.push(if is_rvalue { "&&" } else { "&" });
+            }
+            Node::PointerToMember { class, member } => {
+                self.print_left(member)?;
+                if self.has_array(member) || self.has_function(member) {
+                    self.push("(");
+                } else {
+                    self.push(" ");
+                }
+                self.print(class)?;
+                self.push("::*");
+            }
+            Node::Function { ret, .. } => {
+                self.print_left(ret)?;
+                self.push(" ");
+            }
+            Node::Encoding { ret, name, .. } => {
+                let name_only = self.take_name_only();
+                if let Some(ret) = ret.as_ref().filter(|_| !name_only) {
+                    self.print_left(ret)?;
+                    if !self.has_rhs_component(ret) {
+                        self.push(" ");
+                    }
+                }
+                self.print(name)?;
+                if !name_only {
+                    self.print_encoding_right(node)?;
+                }
+            }
+            Node::Array { element, .. } => self.print_left(element)?,
+            Node::Vector { element, dimension } => {
+                self.print(element)?;
+                self.push(" vector[");
+                if let Some(dimension) = dimension {
+                    self.print(dimension)?;
+                }
+                self.push("]");
+            }
+            Node::PackExpansion(pattern) => self.print_pack_expansion(pattern)?,
+            Node::Special { prefix, child } => {
+                self.push(prefix);
+                self.print(child)?;
+            }
+            Node::ConstructionVtable { base, derived } => {
+                self.push("construction vtable for ");
+                self.print(base)?;
+                self.push("-in-");
+                self.print(derived)?;
+            }
+            Node::DotSuffix { child, suffix } => {
+                self.print(child)?;
+                self.push(" (");
+                self.push(suffix);
+                self.push(")");
+            }
+            Node::Literal { ty, value, suffix } => {
+                if let Some(ty) = ty {
+                    self.push("(");
+                    self.print(ty)?;
+                    self.push(")");
+                }
+                self.push(value);
+                self.push(suffix);
+            }
+            Node::Binary { left, op, right } => {
+                // Keep `>` from closing an enclosing template argument list.
+                let needs_parens = op.contains('>');
+                if needs_parens {
+                    self.push("(");
+                }
+                self.print_operand(left)?;
+                if *op == "," {
+                    self.push(", ");
+                } else {
+                    self.push(" ");
+                    self.push(op);
+                    self.push(" ");
+                }
+                self.print_operand(right)?;
+                if needs_parens {
+                    self.push(")");
+                }
+            }
+            Node::Prefix { op, operand } => {
+                self.push(op);
+                self.print_operand(operand)?;
+            }
+            Node::Postfix { operand, op } => {
+                self.print_operand(operand)?;
+                self.push(op);
+            }
+            Node::Conditional { condition, then, otherwise } => {
+                self.print_operand(condition)?;
+                self.push(" ? ");
+                self.print_operand(then)?;
+                self.push(" : ");
+                self.print_operand(otherwise)?;
+            }
+            Node::Call { callee, args } => {
+                self.print_operand(callee)?;
+                self.push("(");
+                self.print_list(args, ", ")?;
+                self.push(")");
+            }
+            Node::Cast { kind, ty, expr } => {
+                self.push(kind);
+                self.push("<");
+                self.print(ty)?;
+                self.push(">(");
+                self.print(expr)?;
+                self.push(")");
+            }
+            Node::ConversionExpr { ty, args } => {
+                self.push("(");
+                self.print(ty)?;
+                self.push(")(");
+                self.print_list(args, ", ")?;
+                self.push(")");
+            }
+            Node::MemberAccess { object, op, member } => {
+                self.print_operand(object)?;
+                self.push(op);
+                self.print(member)?;
+            }
+            Node::Subscript { array, index } => {
+                self.print_operand(array)?;
+                self.push("[");
+                self.print(index)?;
+                self.push("]");
+            }
+            Node::Enclosing { prefix, child, postfix } => {
+                self.push(prefix);
+                self.print(child)?;
+                self.push(postfix);
+            }
+            Node::New { op, placement, ty, initializer } => {
+                self.push(op);
+                if !placement.is_empty() {
+                    self.push(" (");
+                    self.print_list(placement, ", ")?;
+                    self.push(")");
+                }
+                self.push(" ");
+                self.print(ty)?;
+                if let Some(initializer) = initializer {
+                    self.push("(");
+                    self.print_list(initializer, ", ")?;
+                    self.push(")");
+                }
+            }
+            Node::Throw(expr) => {
+                self.push("throw");
+                if let Some(expr) = expr {
+                    self.push(" ");
+                    self.print(expr)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn print_right_inner(&mut self, node: &NodeRef) -> PrintResult {
+        match &**node {
+            Node::ArgumentPack(args) => {
+                if let Some(element) = self.pack_index.and_then(|index| args.get(index)) {
+                    self.print_right(element)?;
+                }
+            }
+            Node::Qualified { child, quals } => {
+                self.print_right(child)?;
+                if self.has_function(child) {
+                    self.print_quals(*quals);
+                }
+            }
+            Node::Pointer(pointee) => {
+                if self.has_array(pointee) || self.has_function(pointee) {
+                    self.push(")");
+                }
+                self.print_right(pointee)?;
+            }
+            Node::Reference { .. } => {
+                let (referee, _) = self.collapse_reference(node);
+                if self.has_array(&referee) || self.has_function(&referee) {
+                    self.push(")");
+                }
+                self.print_right(&referee)?;
+            }
+            Node::PointerToMember { member, .. } => {
+                if self.has_array(member) || self.has_function(member) {
+                    self.push(")");
+                }
+                self.print_right(member)?;
+            }
+            Node::Function { ret, params, quals, ref_qualifier, exception } => {
+                self.push("(");
+                self.print_list(params, ", ")?;
+                self.push(")");
+                self.print_right(ret)?;
+                self.print_quals(*quals);
+                if let Some(ref_qualifier) = ref_qualifier {
+                    self.push(" ");
+                    self.push(ref_qualifier);
+                }
+                if let Some(exception) = exception {
+                    self.push(" ");
+                    self.print(exception)?;
+                }
+            }
+            Node::Array { element, dimension } => {
+                if !self.output.ends_with(']') {
+                    self.push(" ");
+                }
+                self.push("[");
+                if let Some(dimension) = dimension {
+                    self.print(dimension)?;
+                }
+                self.push("]");
+                self.print_right(element)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // Parameters, return type declarators and qualifiers of a function encoding.
+    fn print_encoding_right(&mut self, node: &NodeRef) -> PrintResult {
+        if let Node::Encoding { ret, params, quals, ref_qualifier, .. } = &**node {
+            self.push("(");
+            self.print_list(params, ", ")?;
+            self.push(")");
+            if let Some(ret) = ret {
+                self.print_right(ret)?;
+            }
+            self.print_quals(*quals);
+            if let Some(ref_qualifier) = ref_qualifier {
+                self.push(" ");
+                self.push(ref_qualifier);
+            }
+        }
+        Ok(())
+    }
+
+    // The name-only style applies to the outermost function encoding; functions appearing in
+    // its name, e.g. as the scope of a local entity, keep their signature.
+    fn take_name_only(&mut self) -> bool {
+        let name_only = self.style == Some(DemangleStyle::NameOnly);
+        if self.style.is_some() {
+            self.style = Some(DemangleStyle::Full);
+        }
+        name_only
+    }
+
+    fn print_quals(&mut self, quals: u8) {
+        if quals & QUAL_CONST != 0 {
+            self.push(" const");
+        }
+        if quals & QUAL_VOLATILE != 0 {
+            self.push(" volatile");
+        }
+        if quals & QUAL_RESTRICT != 0 {
+            self.push(" restrict");
+        }
+    }
+
+    // References to references collapse into an lvalue reference unless both are rvalue ones.
+    fn collapse_reference(&self, node: &NodeRef) -> (NodeRef, bool) {
+        let mut node = node.clone();
+        let mut is_rvalue = true;
+        for _ in 0..MAX_NESTING {
+            match &**self.resolve(&node) {
+                Node::Reference { child, is_rvalue: child_is_rvalue } => {
+                    is_rvalue &= *child_is_rvalue;
+                    node = child.clone();
+                }
+                _ => break,
+            }
+        }
+        (node, is_rvalue)
+    }
+
+    fn print_operand(&mut self, operand: &NodeRef) -> PrintResult {
+        let needs_parens = matches!(&**operand, Node::Binary { .. } | Node::Conditional { .. } | Node::Cast { .. } | Node::ConversionExpr { .. });
+        if needs_parens {
+            self.push("(");
+        }
+        self.print(operand)?;
+        if needs_parens {
+            self.push(")");
+        }
+        Ok(())
+    }
+
+    // A pack expansion prints its pattern once per element of the pack it contains.
+    fn print_pack_expansion(&mut self, pattern: &NodeRef) -> PrintResult {
+        if self.pack_index.is_some() {
+            return self.print(pattern);
+        }
+        let Some(pack_size) = find_pack_size(pattern, 0) else {
+            self.print(pattern)?;
+            self.push("...");
+            return Ok(());
+        };
+        let start = self.output.len();
+        for index in 0..pack_size {
+            if self.output.len() != start {
+                self.push(", ");
+            }
+            self.pack_index = Some(index);
+            let result = self.print(pattern);
+            self.pack_index = None;
+            result?;
+        }
+        Ok(())
+    }
+}
+
+fn find_pack_size(node: &NodeRef, depth: usize) -> Option<usize> {
+    if depth > MAX_NESTING {
+        return None;
+    }
+    let children: Vec<&NodeRef> = match &**node {
+        Node::ArgumentPack(args) => return Some(args.len()),
+        Node::Qualified { child, .. } | Node::VendorQualified { child, .. } | Node::Pointer(child) | Node::Reference { child, .. }
+        | Node::Elaborated { child, .. } => vec![child],
+        Node::PointerToMember { class, member } => vec![class, member],
+        Node::Template { name, args } => std::iter::once(name).chain(args).collect(),
+        Node::Nested { qualifier, name } => vec![qualifier, name],
+        Node::Function { ret, params, .. } => std::iter::once(ret).chain(params).collect(),
+        Node::Array { element, dimension } => std::iter::once(element).chain(dimension).collect(),
+        Node::Binary { left, right, .. } => vec![left, right],
+        Node::Prefix { operand, .. } | Node::Postfix { operand, .. } => vec![operand],
+        Node::Call { callee, args } => std::iter::once(callee).chain(args).collect(),
+        Node::Cast { ty, expr, .. } => vec![ty, expr],
+        Node::MemberAccess { object, member, .. } => vec![object, member],
+        Node::Enclosing { child, .. } => vec![child],
+        _ => Vec::new(),
+    };
+    children.into_iter().find_map(|child| find_pack_size(child, depth + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demangle(name: &str) -> Option<String> {
+        demangle_symbol(name, DemangleStyle::Full)
+    }
+
+    // Expected output as printed by llvm-cxxfilt.
+    #[test]
+    fn functions_and_names() {
+        let cases = [
+            ("_Z3foov", "foo()"),
+            ("__Z3foov", "foo()"),
+            ("_Z1fi", "f(int)"),
+            ("_ZN3foo3barEv", "foo::bar()"),
+            ("_ZNK3Foo3getEv", "Foo::get() const"),
+            ("_ZN3FooC1Ev", "Foo::Foo()"),
+            ("_ZN3FooD2Ev", "Foo::~Foo()"),
+            ("_ZN12_GLOBAL__N_13fooEv", "(anonymous namespace)::foo()"),
+            ("_ZN3foo3barE", "foo::bar"),
+            ("_ZZ3foovE1x", "foo()::x"),
+            ("_ZN5Outer5InnerIiE3getEv", "Outer::Inner<int>::get()"),
+            ("_Z3maxIiET_S0_S0_", "int max<int>(int, int)"),
+            ("_Z1fIJidEEvDpT_", "void f<int, double>(int, double)"),
+            ("_Z5helloIcLi3EEvv", "void hello<char, 3>()"),
+            ("_Z1fILb1EEvv", "void f<true>()"),
+            ("_Z1fIiEDTcl1gfp_EET_", "decltype(g(fp)) f<int>(int)"),
+            ("_ZZ4mainENKUlvE_clEv", "main::'lambda'()::operator()() const"),
+            ("_ZNSt3__112basic_stringIcNS_11char_traitsIcEENS_9allocatorIcEEEC1Ev", "std::__1::basic_string<char, std::__1::char_traits<char>, std::__1::allocator<char>>::basic_string()"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle(mangled).as_deref(), Some(expected), "{}", mangled);
+        }
+    }
+
+    #[test]
+    fn types() {
+        let cases = [
+            ("_Z1fPFviE", "f(void (*)(int))"),
+            ("_Z1fA10_i", "f(int [10])"),
+            ("_Z1fM1AFivE", "f(int (A::*)())"),
+            ("_Z1fRKPc", "f(char* const&)"),
+            ("_Z1fPKc", "f(char const*)"),
+            ("_Z1fDn", "f(std::nullptr_t)"),
+            ("_Z1fSs", "f(std::string)"),
+            ("_ZNSt3__16vectorIiNS_9allocatorIiEEE9push_backERKi", "std::__1::vector<int, std::__1::allocator<int>>::push_back(int const&)"),
+            ("_ZNKSt3__16vectorIiNS_9allocatorIiEEE4sizeEv", "std::__1::vector<int, std::__1::allocator<int>>::size() const"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle(mangled).as_deref(), Some(expected), "{}", mangled);
+        }
+    }
+
+    #[test]
+    fn operators_and_special_names() {
+        let cases = [
+            ("_ZplRK1AS1_", "operator+(A const&, A const&)"),
+            ("_ZdlPv", "operator delete(void*)"),
+            ("_Znwm", "operator new(unsigned long)"),
+            ("_ZN1AcviEv", "A::operator int()"),
+            ("_ZTV3Foo", "vtable for Foo"),
+            ("_ZTI3Foo", "typeinfo for Foo"),
+            ("_ZTS3Foo", "typeinfo name for Foo"),
+            ("_ZGVZ3foovE1x", "guard variable for foo()::x"),
+            ("_ZThn8_N3Foo3barEv", "non-virtual thunk to Foo::bar()"),
+            ("___Z3foov_block_invoke", "invocation function for block in foo()"),
+            ("___Z3foov_block_invoke_2", "invocation function for block in foo()"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle(mangled).as_deref(), Some(expected), "{}", mangled);
+        }
+    }
+
+    #[test]
+    fn name_only() {
+        assert_eq!(demangle_symbol("_ZN3foo3barEi", DemangleStyle::NameOnly).as_deref(), Some("foo::bar"));
+        assert_eq!(demangle_symbol("_ZNK3Foo3getEv", DemangleStyle::NameOnly).as_deref(), Some("Foo::get"));
+    }
+
+    #[test]
+    fn rejects_truncated_and_garbage_input() {
+        let long = format!("_Z1f{}", "P".repeat(MAX_SYMBOL_LENGTH));
+        let deep = format!("_Z1f{}i", "P".repeat(MAX_NESTING * 2));
+        let cases = ["", "main", "_Z", "_Z3fo", "_ZN3foo", "_ZN3foo3bar", "_Z1fS_", "_Z1fT_", "_ZZ", "_Z1fPFvi", "___Z3foov_block_invoke_x", &long, &deep];
+        for mangled in cases {
+            assert_eq!(demangle(mangled), None, "{}", mangled);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! Parsing and pretty-printing of Mach-O files.
+//!
+//! [`parse`] reads a thin Mach-O image into a [`MachO`], which holds the header, load commands,
+//! symbol table and the decoded `__LINKEDIT` data. Heavier analyses such as
+//! [`objc::ObjcMetadata`], [`swift::SwiftMetadata`] and [`go::GoMetadata`] are built on demand
+//! from the parsed image and the file it came from. The [`printer`] module renders all of these
+//! as the tables printed by the `mach_o_rust` command-line tool. [`MachOView`] parses an image
+//! that is already in memory without copying it, for scanning many files quickly, and [`lint`]
+//! checks an image's layout against the rules dyld and the kernel enforce.
+//!
+//! 32-bit and 64-bit structures are read into enums over both layouts. The [`Header`],
+//! [`Segment`], [`SectionLike`] and [`Symbol`] traits give width-independent access to them,
+//! widening addresses and sizes to `u64`.
+//!
+//! ```
+//! use std::fs::File;
+//! use mach_o_rust::Header;
+//!
+//! let mut file = File::open("test_files/mach_o_ex1")?;
+//! let mach_o = mach_o_rust::parse(&mut file)?;
+//!
+//! println!("{} load commands, 64-bit: {}", mach_o.header.ncmds(), mach_o.header.is_64());
+//! for dylib in mach_o.dylibs() {
+//!     println!("links {}", dylib.install_name);
+//! }
+//!
+//! if let Some(section) = mach_o.section_by_name("__TEXT", "__cstring") {
+//!     let strings = mach_o.read_section_data(&mut file, section)?;
+//!     println!("{} bytes of C strings", strings.len());
+//! }
+//! # Ok::<(), mach_o_rust::AppError>(())
+//! ```
+
+mod address_space;
+pub mod class_dump;
+pub mod constants;
+pub mod corefile;
+pub mod coverage;
+pub mod demangle;
+pub mod diagnostics;
+pub mod dyld_info;
+pub mod encryption;
+pub mod error;
+mod function_starts;
+pub mod go;
+pub mod header;
+mod itanium_demangle;
+pub mod linkedit;
+pub mod lint;
+pub mod load_commands;
+pub mod loh;
+pub mod mach_o;
+mod memory_utils;
+pub mod notes;
+pub mod objc;
+mod objc_types;
+pub mod objc_usage;
+pub mod parser;
+pub mod printer;
+mod rust_demangle;
+pub mod split_info;
+pub mod swift;
+mod swift_demangle;
+pub mod symbols;
+#[cfg(test)]
+mod test_utils;
+pub mod view;
+
+pub use crate::error::AppError;
+pub use crate::header::Header;
+pub use crate::load_commands::{SectionLike, Segment};
+pub use crate::mach_o::MachO;
+pub use crate::parser::parse;
+pub use crate::symbols::Symbol;
+pub use crate::view::MachOView;
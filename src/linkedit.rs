@@ -0,0 +1,128 @@
+//! Breaks the `__LINKEDIT` segment down into the tables the load commands locate in it, in file
+//! order, with the padding the linker left between them.
+//!
+//! ```
+//! use mach_o_rust::linkedit::LinkeditLayout;
+//!
+//! let data = std::fs::read("test_files/mach_o_ex1")?;
+//! let mach_o = mach_o_rust::MachO::from_bytes(&data)?;
+//! if let Some(layout) = LinkeditLayout::from_mach_o(&mach_o) {
+//!     for entry in &layout.entries {
+//!         println!("{}: {:.1}%", entry.blob.name, layout.percentage(entry.blob.size));
+//!     }
+//! }
+//! # Ok::<(), mach_o_rust::AppError>(())
+//! ```
+
+use crate::load_commands::Segment;
+use crate::mach_o::{LinkeditBlob, MachO};
+
+/// The linker aligns tables to at most 16 bytes, so larger alignments of an offset are incidental.
+const MAX_ALIGNMENT: u64 = 16;
+
+#[derive(Debug)]
+pub struct LinkeditEntry {
+    pub blob: LinkeditBlob,
+    /// Largest power of two up to 16 that the offset is a multiple of.
+    pub alignment: u64,
+    /// Unused bytes between the end of the previous table, or the start of the segment, and this one.
+    pub padding_before: u64,
+}
+
+#[derive(Debug)]
+pub struct LinkeditLayout {
+    pub fileoff: u64,
+    pub filesize: u64,
+    /// Tables inside the segment, sorted by offset.
+    pub entries: Vec<LinkeditEntry>,
+    /// Unused bytes between the end of the last table and the end of the segment.
+    pub trailing_padding: u64,
+}
+
+impl LinkeditLayout {
+    /// Returns `None` for images without a `__LINKEDIT` segment, such as object files.
+    pub fn from_mach_o(mach_o: &MachO) -> Option<LinkeditLayout> {
+        let (segment, _) = mach_o.segment_by_name("__LINKEDIT")?;
+        let (fileoff, filesize) = (segment.fileoff(), segment.filesize());
+        let end = fileoff.saturating_add(filesize);
+
+        let mut blobs: Vec<LinkeditBlob> = mach_o.linkedit_blobs().into_iter()
+            .filter(|blob| blob.offset >= fileoff && blob.offset < end)
+            .collect();
+        blobs.sort_by_key(|blob| blob.offset);
+
+        let mut entries = Vec::new();
+        let mut previous_end = fileoff;
+        for blob in blobs {
+            let alignment = 1u64.checked_shl(blob.offset.trailing_zeros()).unwrap_or(MAX_ALIGNMENT).min(MAX_ALIGNMENT);
+            let padding_before = blob.offset.saturating_sub(previous_end);
+            previous_end = previous_end.max(blob.offset.saturating_add(blob.size));
+            entries.push(LinkeditEntry { blob, alignment, padding_before });
+        }
+        Some(LinkeditLayout { fileoff, filesize, entries, trailing_padding: end.saturating_sub(previous_end) })
+    }
+
+    /// Share of the segment taken up by `size` bytes, in percent.
+    pub fn percentage(&self, size: u64) -> f64 {
+        if self.filesize == 0 {
+            return 0.0;
+        }
+        size as f64 * 100.0 / self.filesize as f64
+    }
+
+    /// All unused bytes in the segment.
+    pub fn padding(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.padding_before).sum::<u64>() + self.trailing_padding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::test_utils::*;
+
+    fn layout(linkedit_filesize: u64) -> LinkeditLayout {
+        let commands = [
+            segment_64("__TEXT", 0, 0x4000, 0, 0x4000, &[]),
+            segment_64("__LINKEDIT", 0x4000, 0x1000, 0x4000, linkedit_filesize, &[]),
+            command(LC_FUNCTION_STARTS, &[0x4000, 8]),
+            command(LC_SYMTAB, &[0x4008, 1, 0x4020, 0xc]),
+            command(LC_CODE_SIGNATURE, &[0x4034, 0x10]),
+        ];
+        let data = image(&commands, &[(0x4008, &nlist_64(1, N_SECT | N_EXT, 1, 0x1000)), (0x4020, b"\0_main\0"), (0x4fff, &[0])]);
+        let mach_o = MachO::from_bytes(&data).unwrap();
+        LinkeditLayout::from_mach_o(&mach_o).unwrap()
+    }
+
+    fn entries(layout: &LinkeditLayout) -> Vec<(&str, u64, u64)> {
+        layout.entries.iter().map(|entry| (entry.blob.name, entry.alignment, entry.padding_before)).collect()
+    }
+
+    #[test]
+    fn tables_in_file_order_with_their_padding() {
+        let layout = layout(0x1000);
+        assert_eq!(entries(&layout), [
+            ("function starts", 16, 0),
+            ("symbol table", 8, 0),
+            ("string table", 16, 8),
+            ("code signature", 4, 8),
+        ]);
+        assert_eq!(layout.trailing_padding, 0x1000 - 0x44);
+        assert_eq!(layout.padding(), 16 + 0x1000 - 0x44);
+    }
+
+    #[test]
+    fn tables_outside_the_segment_are_left_out() {
+        let layout = layout(0x30);
+        assert_eq!(entries(&layout), [("function starts", 16, 0), ("symbol table", 8, 0), ("string table", 16, 8)]);
+        assert_eq!(layout.trailing_padding, 4);
+    }
+
+    #[test]
+    fn object_files_have_no_linkedit() {
+        let data = image(&[segment_64("", 0, 0x100, 0x100, 0x100, &[])], &[(0x1ff, &[0])]);
+        let mach_o = MachO::from_bytes(&data).unwrap();
+        assert!(LinkeditLayout::from_mach_o(&mach_o).is_none());
+    }
+}
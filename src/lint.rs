@@ -0,0 +1,252 @@
+//! Checks the layout of a parsed image against the rules dyld and the kernel enforce when
+//! loading it. Findings are reported as [`Diagnostic`]s with the file offset of the offending
+//! structure.
+//!
+//! ```
+//! let data = std::fs::read("test_files/mach_o_ex1")?;
+//! let mach_o = mach_o_rust::MachO::from_bytes(&data)?;
+//! for finding in mach_o_rust::lint::lint(&mach_o) {
+//!     println!("{}", finding);
+//! }
+//! # Ok::<(), mach_o_rust::AppError>(())
+//! ```
+
+use crate::constants::*;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::error::describe_cmd;
+use crate::header::Header;
+use crate::load_commands::*;
+use crate::mach_o::MachO;
+use crate::memory_utils::fixed_bytes_to_string;
+
+/// Offset of `sizeofcmds` in the header.
+const SIZEOFCMDS_OFFSET: u64 = 20;
+
+pub fn lint(mach_o: &MachO) -> Vec<Diagnostic> {
+    let mut findings = Vec::new();
+    check_load_command_sizes(mach_o, &mut findings);
+    check_segment_overlaps(mach_o, &mut findings);
+    check_sections_in_segments(mach_o, &mut findings);
+    check_linkedit_blobs(mach_o, &mut findings);
+    check_code_signature_is_last(mach_o, &mut findings);
+    check_header_padding(mach_o, &mut findings);
+    findings
+}
+
+fn finding(severity: Severity, offset: u64, message: String) -> Diagnostic {
+    Diagnostic { severity, offset: Some(offset), message }
+}
+
+fn header_size(mach_o: &MachO) -> u64 {
+    if mach_o.header.is_64() { 32 } else { 28 }
+}
+
+// The kernel and dyld walk the commands by cmdsize and refuse images whose commands do not
+// add up to sizeofcmds or would leave the next command misaligned.
+fn check_load_command_sizes(mach_o: &MachO, findings: &mut Vec<Diagnostic>) {
+    // Commands skipped by a lenient parse have already been reported.
+    let sizeofcmds = mach_o.header.sizeofcmds() as u64;
+    let commands_size: u64 = mach_o.load_commands.iter().map(|entry| entry.cmdsize as u64).sum();
+    if mach_o.load_commands.len() == mach_o.header.ncmds() as usize && commands_size != sizeofcmds {
+        findings.push(finding(Severity::Error, SIZEOFCMDS_OFFSET, format!("sizeofcmds 0x{:x} does not match the 0x{:x} bytes of load commands", sizeofcmds, commands_size)));
+    }
+
+    let alignment = if mach_o.header.is_64() { 8 } else { 4 };
+    for entry in &mach_o.load_commands {
+        if entry.cmdsize % alignment != 0 {
+            findings.push(finding(Severity::Error, entry.offset, format!("{} cmdsize 0x{:x} is not a multiple of {}", describe_cmd(entry.cmd), entry.cmdsize, alignment)));
+        }
+    }
+}
+
+fn segments(mach_o: &MachO) -> Vec<(&LoadCommandEntry, &SegmentCommand)> {
+    mach_o.load_commands.iter().filter_map(|entry| match &entry.command {
+        LoadCommand::SegmentCommand(segment) => Some((entry, segment)),
+        _ => None,
+    }).collect()
+}
+
+fn overlaps(start: u64, size: u64, other_start: u64, other_size: u64) -> bool {
+    size != 0 && other_size != 0 && start < other_start.saturating_add(other_size) && other_start < start.saturating_add(size)
+}
+
+fn check_segment_overlaps(mach_o: &MachO, findings: &mut Vec<Diagnostic>) {
+    let segments = segments(mach_o);
+    for (index, (entry, segment)) in segments.iter().enumerate() {
+        let name = fixed_bytes_to_string(segment.segname());
+        for (_, other) in &segments[..index] {
+            let other_name = fixed_bytes_to_string(other.segname());
+            if overlaps(segment.vmaddr(), segment.vmsize(), other.vmaddr(), other.vmsize()) {
+                findings.push(finding(Severity::Error, entry.offset, format!(
+                    "segment {} at vm 0x{:x}-0x{:x} overlaps {} at vm 0x{:x}-0x{:x}",
+                    name, segment.vmaddr(), segment.vmaddr().saturating_add(segment.vmsize()),
+                    other_name, other.vmaddr(), other.vmaddr().saturating_add(other.vmsize()))));
+            }
+            if overlaps(segment.fileoff(), segment.filesize(), other.fileoff(), other.filesize()) {
+                findings.push(finding(Severity::Error, entry.offset, format!(
+                    "segment {} at file 0x{:x}-0x{:x} overlaps {} at file 0x{:x}-0x{:x}",
+                    name, segment.fileoff(), segment.fileoff().saturating_add(segment.filesize()),
+                    other_name, other.fileoff(), other.fileoff().saturating_add(other.filesize()))));
+            }
+        }
+    }
+}
+
+fn contains(start: u64, size: u64, inner_start: u64, inner_size: u64) -> bool {
+    inner_start >= start && inner_start.saturating_add(inner_size) <= start.saturating_add(size)
+}
+
+fn check_sections_in_segments(mach_o: &MachO, findings: &mut Vec<Diagnostic>) {
+    for (entry, segment) in segments(mach_o) {
+        // Sections follow the segment_command(_64) in the load command.
+        let (header_size, section_size) = if segment.cmd() == LC_SEGMENT_64 { (72, 80) } else { (56, 68) };
+        let segname = fixed_bytes_to_string(segment.segname());
+        for (index, section) in entry.sections.iter().enumerate() {
+            let offset = entry.offset + header_size + index as u64 * section_size;
+            let name = format!("{},{}", fixed_bytes_to_string(section.segname()), fixed_bytes_to_string(section.sectname()));
+            if !contains(segment.vmaddr(), segment.vmsize(), section.addr(), section.size()) {
+                findings.push(finding(Severity::Error, offset, format!(
+                    "section {} at vm 0x{:x}-0x{:x} is outside segment {} at vm 0x{:x}-0x{:x}",
+                    name, section.addr(), section.addr().saturating_add(section.size()),
+                    segname, segment.vmaddr(), segment.vmaddr().saturating_add(segment.vmsize()))));
+            }
+            let fileoff = section.offset() as u64;
+            if !section.is_zerofill() && section.size() != 0 && !contains(segment.fileoff(), segment.filesize(), fileoff, section.size()) {
+                findings.push(finding(Severity::Error, offset, format!(
+                    "section {} at file 0x{:x}-0x{:x} is outside segment {} at file 0x{:x}-0x{:x}",
+                    name, fileoff, fileoff.saturating_add(section.size()),
+                    segname, segment.fileoff(), segment.fileoff().saturating_add(segment.filesize()))));
+            }
+        }
+    }
+}
+
+// Object files have no __LINKEDIT and keep their tables after the section contents.
+fn check_linkedit_blobs(mach_o: &MachO, findings: &mut Vec<Diagnostic>) {
+    let Some((linkedit, _)) = mach_o.segment_by_name("__LINKEDIT") else { return };
+    for blob in mach_o.linkedit_blobs() {
+        if !contains(linkedit.fileoff(), linkedit.filesize(), blob.offset, blob.size) {
+            findings.push(finding(Severity::Error, blob.offset, format!(
+                "{} of {} at 0x{:x}-0x{:x} is outside __LINKEDIT at 0x{:x}-0x{:x}",
+                blob.name, describe_cmd(blob.cmd), blob.offset, blob.offset.saturating_add(blob.size),
+                linkedit.fileoff(), linkedit.fileoff().saturating_add(linkedit.filesize()))));
+        }
+    }
+}
+
+// codesign appends the signature to the end of the file and expects its command to be the last
+// one, so that signing only ever grows the load commands at the end.
+fn check_code_signature_is_last(mach_o: &MachO, findings: &mut Vec<Diagnostic>) {
+    let last = mach_o.load_commands.len().saturating_sub(1);
+    for (index, entry) in mach_o.load_commands.iter().enumerate() {
+        if entry.cmd == LC_CODE_SIGNATURE && index != last {
+            findings.push(finding(Severity::Warning, entry.offset, format!("LC_CODE_SIGNATURE is load command #{} of {}, not the last", index, mach_o.load_commands.len())));
+        }
+    }
+}
+
+// The header and load commands are mapped with the start of __TEXT, so they have to end before
+// the contents of the first section.
+fn check_header_padding(mach_o: &MachO, findings: &mut Vec<Diagnostic>) {
+    let commands_end = header_size(mach_o) + mach_o.header.sizeofcmds() as u64;
+    let first_section = mach_o.sections()
+        .filter(|section| !section.is_zerofill() && section.size() != 0 && section.offset() != 0)
+        .min_by_key(|section| section.offset());
+    if let Some(section) = first_section {
+        if commands_end > section.offset() as u64 {
+            findings.push(finding(Severity::Error, header_size(mach_o), format!(
+                "load commands end at 0x{:x}, past the start of section {},{} at 0x{:x}",
+                commands_end, fixed_bytes_to_string(section.segname()), fixed_bytes_to_string(section.sectname()), section.offset())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    // __TEXT with __text, __LINKEDIT holding the symbol and string tables and a code signature
+    fn commands() -> Vec<Vec<u8>> {
+        vec![
+            segment_64("__TEXT", 0, 0x4000, 0, 0x4000, &[("__text", 0x1000, 0x100, 0x1000)]),
+            segment_64("__LINKEDIT", 0x4000, 0x1000, 0x4000, 0x1000, &[]),
+            command(LC_SYMTAB, &[0x4000, 1, 0x4010, 8]),
+            command(LC_CODE_SIGNATURE, &[0x4020, 0x10]),
+        ]
+    }
+
+    fn findings_for(data: &[u8]) -> Vec<(Severity, u64, String)> {
+        let mach_o = MachO::from_bytes(data).unwrap();
+        lint(&mach_o).into_iter().map(|finding| (finding.severity, finding.offset.unwrap(), finding.message)).collect()
+    }
+
+    fn findings(commands: &[Vec<u8>]) -> Vec<(Severity, u64, String)> {
+        let symbol = nlist_64(1, N_SECT | N_EXT, 1, 0x1000);
+        findings_for(&image(commands, &[(0x4000, &symbol), (0x4010, b"\0_main\0\0"), (0x4fff, &[0])]))
+    }
+
+    fn error(offset: u64, message: &str) -> (Severity, u64, String) {
+        (Severity::Error, offset, message.to_string())
+    }
+
+    #[test]
+    fn well_formed_image_has_no_findings() {
+        assert!(findings(&commands()).is_empty());
+    }
+
+    #[test]
+    fn sizeofcmds_must_match_the_commands() {
+        let mut data = image(&commands(), &[(0x4fff, &[0])]);
+        data[20..24].copy_from_slice(&0x110u32.to_le_bytes());
+        assert_eq!(findings_for(&data), [error(20, "sizeofcmds 0x110 does not match the 0x108 bytes of load commands")]);
+    }
+
+    #[test]
+    fn cmdsize_must_keep_commands_aligned() {
+        let mut commands = commands();
+        commands[3] = command(LC_CODE_SIGNATURE, &[0x4020, 0x10, 0]);
+        assert_eq!(findings(&commands), [error(0x118, "LC_CODE_SIGNATURE cmdsize 0x14 is not a multiple of 8")]);
+    }
+
+    #[test]
+    fn segments_must_not_overlap() {
+        let mut commands = commands();
+        commands[1] = segment_64("__LINKEDIT", 0x3000, 0x2000, 0x3000, 0x2000, &[]);
+        assert_eq!(findings(&commands), [
+            error(0xb8, "segment __LINKEDIT at vm 0x3000-0x5000 overlaps __TEXT at vm 0x0-0x4000"),
+            error(0xb8, "segment __LINKEDIT at file 0x3000-0x5000 overlaps __TEXT at file 0x0-0x4000"),
+        ]);
+    }
+
+    #[test]
+    fn sections_must_be_inside_their_segment() {
+        let mut commands = commands();
+        commands[0] = segment_64("__TEXT", 0, 0x4000, 0, 0x4000, &[("__text", 0x3f80, 0x100, 0x3f80)]);
+        assert_eq!(findings(&commands), [
+            error(0x68, "section __TEXT,__text at vm 0x3f80-0x4080 is outside segment __TEXT at vm 0x0-0x4000"),
+            error(0x68, "section __TEXT,__text at file 0x3f80-0x4080 is outside segment __TEXT at file 0x0-0x4000"),
+        ]);
+    }
+
+    #[test]
+    fn linkedit_tables_must_be_inside_linkedit() {
+        let mut commands = commands();
+        commands[1] = segment_64("__LINKEDIT", 0x4000, 0x1000, 0x4000, 0x18, &[]);
+        assert_eq!(findings(&commands), [error(0x4020, "code signature of LC_CODE_SIGNATURE at 0x4020-0x4030 is outside __LINKEDIT at 0x4000-0x4018")]);
+    }
+
+    #[test]
+    fn code_signature_must_be_the_last_command() {
+        let mut commands = commands();
+        commands.swap(2, 3);
+        assert_eq!(findings(&commands), [(Severity::Warning, 0x100, String::from("LC_CODE_SIGNATURE is load command #2 of 4, not the last"))]);
+    }
+
+    #[test]
+    fn load_commands_must_end_before_the_first_section() {
+        let mut commands = commands();
+        commands[0] = segment_64("__TEXT", 0, 0x4000, 0, 0x4000, &[("__text", 0x100, 0x100, 0x100)]);
+        assert_eq!(findings(&commands), [error(32, "load commands end at 0x128, past the start of section __TEXT,__text at 0x100")]);
+    }
+}
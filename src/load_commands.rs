@@ -1,7 +1,9 @@
-use std::io::Read;
+use std::io::{self, Read, Seek};
 
 use byteorder::ReadBytesExt;
+use crate::constants::*;
 use crate::error::AppError;
+use crate::memory_utils::read_bytes_at;
 
 pub enum LoadCommand {
     SegmentCommand(SegmentCommand),
@@ -31,6 +33,9 @@ pub enum LoadCommand {
     EntryPointCommand(EntryPointCommand),
     SourceVersionCommand(SourceVersionCommand),
     NoteCommand(NoteCommand),
+    FvmlibCommand(FvmlibCommand),
+    FvmfileCommand(FvmfileCommand),
+    PrepageCommand(PrepageCommand),
 }
 
 #[repr(C)]
@@ -49,8 +54,93 @@ impl LoadCommandPrefix {
     }
 }
 
+/// The `LC_*` name of a load command type, if it is one this crate knows.
+pub fn load_command_name(cmd: u32) -> Option<&'static str> {
+    match cmd {
+        LC_SEGMENT => Some("LC_SEGMENT"),
+        LC_SYMTAB => Some("LC_SYMTAB"),
+        LC_SYMSEG => Some("LC_SYMSEG"),
+        LC_THREAD => Some("LC_THREAD"),
+        LC_UNIXTHREAD => Some("LC_UNIXTHREAD"),
+        LC_LOADFVMLIB => Some("LC_LOADFVMLIB"),
+        LC_IDFVMLIB => Some("LC_IDFVMLIB"),
+        LC_IDENT => Some("LC_IDENT"),
+        LC_FVMFILE => Some("LC_FVMFILE"),
+        LC_PREPAGE => Some("LC_PREPAGE"),
+        LC_DYSYMTAB => Some("LC_DYSYMTAB"),
+        LC_LOAD_DYLIB => Some("LC_LOAD_DYLIB"),
+        LC_ID_DYLIB => Some("LC_ID_DYLIB"),
+        LC_LOAD_DYLINKER => Some("LC_LOAD_DYLINKER"),
+        LC_ID_DYLINKER => Some("LC_ID_DYLINKER"),
+        LC_PREBOUND_DYLIB => Some("LC_PREBOUND_DYLIB"),
+        LC_ROUTINES => Some("LC_ROUTINES"),
+        LC_SUB_FRAMEWORK => Some("LC_SUB_FRAMEWORK"),
+        LC_SUB_UMBRELLA => Some("LC_SUB_UMBRELLA"),
+        LC_SUB_CLIENT => Some("LC_SUB_CLIENT"),
+        LC_SUB_LIBRARY => Some("LC_SUB_LIBRARY"),
+        LC_TWOLEVEL_HINTS => Some("LC_TWOLEVEL_HINTS"),
+        LC_PREBIND_CKSUM => Some("LC_PREBIND_CKSUM"),
+        LC_LOAD_WEAK_DYLIB => Some("LC_LOAD_WEAK_DYLIB"),
+        LC_SEGMENT_64 => Some("LC_SEGMENT_64"),
+        LC_ROUTINES_64 => Some("LC_ROUTINES_64"),
+        LC_UUID => Some("LC_UUID"),
+        LC_RPATH => Some("LC_RPATH"),
+        LC_CODE_SIGNATURE => Some("LC_CODE_SIGNATURE"),
+        LC_SEGMENT_SPLIT_INFO => Some("LC_SEGMENT_SPLIT_INFO"),
+        LC_REEXPORT_DYLIB => Some("LC_REEXPORT_DYLIB"),
+        LC_LAZY_LOAD_DYLIB => Some("LC_LAZY_LOAD_DYLIB"),
+        LC_ENCRYPTION_INFO => Some("LC_ENCRYPTION_INFO"),
+        LC_DYLD_INFO => Some("LC_DYLD_INFO"),
+        LC_DYLD_INFO_ONLY => Some("LC_DYLD_INFO_ONLY"),
+        LC_LOAD_UPWARD_DYLIB => Some("LC_LOAD_UPWARD_DYLIB"),
+        LC_VERSION_MIN_MACOSX => Some("LC_VERSION_MIN_MACOSX"),
+        LC_VERSION_MIN_IPHONEOS => Some("LC_VERSION_MIN_IPHONEOS"),
+        LC_FUNCTION_STARTS => Some("LC_FUNCTION_STARTS"),
+        LC_DYLD_ENVIRONMENT => Some("LC_DYLD_ENVIRONMENT"),
+        LC_MAIN => Some("LC_MAIN"),
+        LC_DATA_IN_CODE => Some("LC_DATA_IN_CODE"),
+        LC_SOURCE_VERSION => Some("LC_SOURCE_VERSION"),
+        LC_DYLIB_CODE_SIGN_DRS => Some("LC_DYLIB_CODE_SIGN_DRS"),
+        LC_ENCRYPTION_INFO_64 => Some("LC_ENCRYPTION_INFO_64"),
+        LC_LINKER_OPTION => Some("LC_LINKER_OPTION"),
+        LC_LINKER_OPTIMIZATION_HINT => Some("LC_LINKER_OPTIMIZATION_HINT"),
+        LC_VERSION_MIN_TVOS => Some("LC_VERSION_MIN_TVOS"),
+        LC_VERSION_MIN_WATCHOS => Some("LC_VERSION_MIN_WATCHOS"),
+        LC_NOTE => Some("LC_NOTE"),
+        LC_BUILD_VERSION => Some("LC_BUILD_VERSION"),
+        LC_DYLD_EXPORTS_TRIE => Some("LC_DYLD_EXPORTS_TRIE"),
+        LC_DYLD_CHAINED_FIXUPS => Some("LC_DYLD_CHAINED_FIXUPS"),
+        _ => None,
+    }
+}
+
 pub type LcStr = Vec<u8>;
 
+/// A load command together with the sections and strings stored after it in the file.
+pub struct LoadCommandEntry {
+    /// File offset of the command.
+    pub offset: u64,
+    pub cmd: u32,
+    /// Size of the command, including its sections and `lc_str`.
+    pub cmdsize: u32,
+    pub command: LoadCommand,
+    /// Sections of a segment command; empty for every other command.
+    pub sections: Vec<Section>,
+    /// Bytes following commands that embed an `lc_str`, such as a dylib's install name.
+    pub lc_str: LcStr,
+}
+
+impl LoadCommandEntry {
+    /// Decodes the first NUL-terminated string of `lc_str`, if the command has one.
+    pub fn string(&self) -> Option<String> {
+        if self.lc_str.is_empty() {
+            return None;
+        }
+        let end = self.lc_str.iter().position(|&byte| byte == 0).unwrap_or(self.lc_str.len());
+        Some(String::from_utf8_lossy(&self.lc_str[..end]).into_owned())
+    }
+}
+
 #[repr(C)]
 pub union LcStrUnion {
     pub offset: u32,
@@ -63,6 +153,44 @@ pub enum SegmentCommand {
     SEG64(SegmentCommand64),
 }
 
+/// Fields of `segment_command` and `segment_command_64`, with addresses and sizes widened to 64 bits.
+pub trait Segment {
+    fn cmd(&self) -> u32;
+    fn cmdsize(&self) -> u32;
+    fn segname(&self) -> &[u8; 16];
+    fn vmaddr(&self) -> u64;
+    fn vmsize(&self) -> u64;
+    fn fileoff(&self) -> u64;
+    fn filesize(&self) -> u64;
+    fn maxprot(&self) -> i32;
+    fn initprot(&self) -> i32;
+    fn nsects(&self) -> u32;
+    fn flags(&self) -> u32;
+}
+
+impl SegmentCommand {
+    fn as_dyn(&self) -> &dyn Segment {
+        match self {
+            SegmentCommand::SEG32(command) => command,
+            SegmentCommand::SEG64(command) => command,
+        }
+    }
+}
+
+impl Segment for SegmentCommand {
+    fn cmd(&self) -> u32 { self.as_dyn().cmd() }
+    fn cmdsize(&self) -> u32 { self.as_dyn().cmdsize() }
+    fn segname(&self) -> &[u8; 16] { self.as_dyn().segname() }
+    fn vmaddr(&self) -> u64 { self.as_dyn().vmaddr() }
+    fn vmsize(&self) -> u64 { self.as_dyn().vmsize() }
+    fn fileoff(&self) -> u64 { self.as_dyn().fileoff() }
+    fn filesize(&self) -> u64 { self.as_dyn().filesize() }
+    fn maxprot(&self) -> i32 { self.as_dyn().maxprot() }
+    fn initprot(&self) -> i32 { self.as_dyn().initprot() }
+    fn nsects(&self) -> u32 { self.as_dyn().nsects() }
+    fn flags(&self) -> u32 { self.as_dyn().flags() }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct SegmentCommand32 {
@@ -104,6 +232,20 @@ impl SegmentCommand32 {
     }
 }
 
+impl Segment for SegmentCommand32 {
+    fn cmd(&self) -> u32 { self.cmd }
+    fn cmdsize(&self) -> u32 { self.cmdsize }
+    fn segname(&self) -> &[u8; 16] { &self.segname }
+    fn vmaddr(&self) -> u64 { self.vmaddr as u64 }
+    fn vmsize(&self) -> u64 { self.vmsize as u64 }
+    fn fileoff(&self) -> u64 { self.fileoff as u64 }
+    fn filesize(&self) -> u64 { self.filesize as u64 }
+    fn maxprot(&self) -> i32 { self.maxprot }
+    fn initprot(&self) -> i32 { self.initprot }
+    fn nsects(&self) -> u32 { self.nsects }
+    fn flags(&self) -> u32 { self.flags }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct SegmentCommand64 {
@@ -145,12 +287,80 @@ impl SegmentCommand64 {
     }
 }
 
+impl Segment for SegmentCommand64 {
+    fn cmd(&self) -> u32 { self.cmd }
+    fn cmdsize(&self) -> u32 { self.cmdsize }
+    fn segname(&self) -> &[u8; 16] { &self.segname }
+    fn vmaddr(&self) -> u64 { self.vmaddr }
+    fn vmsize(&self) -> u64 { self.vmsize }
+    fn fileoff(&self) -> u64 { self.fileoff }
+    fn filesize(&self) -> u64 { self.filesize }
+    fn maxprot(&self) -> i32 { self.maxprot }
+    fn initprot(&self) -> i32 { self.initprot }
+    fn nsects(&self) -> u32 { self.nsects }
+    fn flags(&self) -> u32 { self.flags }
+}
+
 #[derive(Debug)]
 pub enum Section {
     SEC32(Section32),
     SEC64(Section64),
 }
 
+/// Fields of `section` and `section_64`, with the address and size widened to 64 bits.
+pub trait SectionLike {
+    fn sectname(&self) -> &[u8; 16];
+    fn segname(&self) -> &[u8; 16];
+    fn addr(&self) -> u64;
+    fn size(&self) -> u64;
+    fn offset(&self) -> u32;
+    fn align(&self) -> u32;
+    fn reloff(&self) -> u32;
+    fn nreloc(&self) -> u32;
+    fn flags(&self) -> u32;
+    fn reserved1(&self) -> u32;
+    fn reserved2(&self) -> u32;
+    /// Only present in `section_64`.
+    fn reserved3(&self) -> Option<u32>;
+
+    /// Zero fill sections take up address space but have no contents in the file.
+    fn is_zerofill(&self) -> bool {
+        matches!(self.flags() & SECTION_TYPE, S_ZEROFILL | S_GB_ZEROFILL | S_THREAD_LOCAL_ZEROFILL)
+    }
+}
+
+impl Section {
+    /// Reads a `section` or `section_64` depending on the segment command that contains it.
+    pub fn from_file<R: Read, E: byteorder::ByteOrder>(file: &mut R, segment_cmd: u32) -> Result<Section, AppError> {
+        match segment_cmd {
+            LC_SEGMENT_64 => Section64::from_file::<R, E>(file),
+            _ => Section32::from_file::<R, E>(file),
+        }
+    }
+
+    fn as_dyn(&self) -> &dyn SectionLike {
+        match self {
+            Section::SEC32(section) => section,
+            Section::SEC64(section) => section,
+        }
+    }
+}
+
+impl SectionLike for Section {
+    fn sectname(&self) -> &[u8; 16] { self.as_dyn().sectname() }
+    fn segname(&self) -> &[u8; 16] { self.as_dyn().segname() }
+    fn addr(&self) -> u64 { self.as_dyn().addr() }
+    fn size(&self) -> u64 { self.as_dyn().size() }
+    fn offset(&self) -> u32 { self.as_dyn().offset() }
+    fn align(&self) -> u32 { self.as_dyn().align() }
+    fn reloff(&self) -> u32 { self.as_dyn().reloff() }
+    fn nreloc(&self) -> u32 { self.as_dyn().nreloc() }
+    fn flags(&self) -> u32 { self.as_dyn().flags() }
+    fn reserved1(&self) -> u32 { self.as_dyn().reserved1() }
+    fn reserved2(&self) -> u32 { self.as_dyn().reserved2() }
+    fn reserved3(&self) -> Option<u32> { self.as_dyn().reserved3() }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct Section32 {
@@ -192,6 +402,21 @@ impl Section32 {
     }
 }
 
+impl SectionLike for Section32 {
+    fn sectname(&self) -> &[u8; 16] { &self.sectname }
+    fn segname(&self) -> &[u8; 16] { &self.segname }
+    fn addr(&self) -> u64 { self.addr as u64 }
+    fn size(&self) -> u64 { self.size as u64 }
+    fn offset(&self) -> u32 { self.offset }
+    fn align(&self) -> u32 { self.align }
+    fn reloff(&self) -> u32 { self.reloff }
+    fn nreloc(&self) -> u32 { self.nreloc }
+    fn flags(&self) -> u32 { self.flags }
+    fn reserved1(&self) -> u32 { self.reserved1 }
+    fn reserved2(&self) -> u32 { self.reserved2 }
+    fn reserved3(&self) -> Option<u32> { None }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct Section64 {
@@ -235,6 +460,21 @@ impl Section64 {
     }
 }
 
+impl SectionLike for Section64 {
+    fn sectname(&self) -> &[u8; 16] { &self.sectname }
+    fn segname(&self) -> &[u8; 16] { &self.segname }
+    fn addr(&self) -> u64 { self.addr }
+    fn size(&self) -> u64 { self.size }
+    fn offset(&self) -> u32 { self.offset }
+    fn align(&self) -> u32 { self.align }
+    fn reloff(&self) -> u32 { self.reloff }
+    fn nreloc(&self) -> u32 { self.nreloc }
+    fn flags(&self) -> u32 { self.flags }
+    fn reserved1(&self) -> u32 { self.reserved1 }
+    fn reserved2(&self) -> u32 { self.reserved2 }
+    fn reserved3(&self) -> Option<u32> { Some(self.reserved3) }
+}
+
 #[repr(C)]
 pub struct Dylib {
     pub name: LcStrUnion,
@@ -255,6 +495,62 @@ impl Dylib {
     }
 }
 
+#[repr(C)]
+pub struct Fvmlib {
+    pub name: LcStrUnion,
+    pub minor_version: u32,
+    pub header_addr: u32,
+}
+
+impl Fvmlib {
+    pub fn from_file<R: Read, E: byteorder::ByteOrder>(file: &mut R) -> Result<Fvmlib, AppError> {
+        let fvmlib = Fvmlib {
+            name: LcStrUnion { offset: file.read_u32::<E>()? },
+            minor_version: file.read_u32::<E>()?,
+            header_addr: file.read_u32::<E>()?,
+        };
+        Ok(fvmlib)
+    }
+}
+
+#[repr(C)]
+pub struct FvmlibCommand {
+    pub cmd: u32,
+    pub cmdsize: u32,
+    pub fvmlib: Fvmlib,
+}
+
+impl FvmlibCommand {
+    pub fn from_file<R: Read, E: byteorder::ByteOrder>(file: &mut R, load_command: &LoadCommandPrefix) -> Result<LoadCommand, AppError> {
+        let fvmlib_command = FvmlibCommand {
+            cmd: load_command.cmd,
+            cmdsize: load_command.cmdsize,
+            fvmlib: Fvmlib::from_file::<R, E>(file)?,
+        };
+        Ok(LoadCommand::FvmlibCommand(fvmlib_command))
+    }
+}
+
+#[repr(C)]
+pub struct FvmfileCommand {
+    pub cmd: u32,
+    pub cmdsize: u32,
+    pub name: LcStrUnion,
+    pub header_addr: u32,
+}
+
+impl FvmfileCommand {
+    pub fn from_file<R: Read, E: byteorder::ByteOrder>(file: &mut R, load_command: &LoadCommandPrefix) -> Result<LoadCommand, AppError> {
+        let fvmfile_command = FvmfileCommand {
+            cmd: load_command.cmd,
+            cmdsize: load_command.cmdsize,
+            name: LcStrUnion { offset: file.read_u32::<E>()? },
+            header_addr: file.read_u32::<E>()?,
+        };
+        Ok(LoadCommand::FvmfileCommand(fvmfile_command))
+    }
+}
+
 #[repr(C)]
 pub struct DylibCommand {
     pub cmd: u32,
@@ -414,12 +710,15 @@ impl ThreadCommand {
     }
 
     fn parse_thread_states<R: Read, E: byteorder::ByteOrder>(file: &mut R, thread_command: &mut ThreadCommand) -> Result<(), AppError> {
-        let mut bytes_read = 8; // cmd and cmdsize already read
+        let mut bytes_read = 8u64; // cmd and cmdsize already read
 
-        while bytes_read < thread_command.cmdsize {
+        while bytes_read < thread_command.cmdsize as u64 {
             let flavor = file.read_u32::<E>()?;
             let count = file.read_u32::<E>()?;
-            let state_size = count * 4;
+            let state_size = count as u64 * 4;
+            if bytes_read + 8 + state_size > thread_command.cmdsize as u64 {
+                return Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("thread state of {} words does not fit in cmdsize 0x{:x}", count, thread_command.cmdsize))));
+            }
             let mut state = vec![0u8; state_size as usize];
             file.read_exact(&mut state)?;
 
@@ -442,6 +741,32 @@ pub enum RoutinesCommand {
     RTN64(RoutinesCommand64),
 }
 
+/// Fields of `routines_command` and `routines_command_64`, widened to 64 bits.
+pub trait Routines {
+    fn cmd(&self) -> u32;
+    fn cmdsize(&self) -> u32;
+    fn init_address(&self) -> u64;
+    fn init_module(&self) -> u64;
+    fn reserved(&self) -> [u64; 6];
+}
+
+impl RoutinesCommand {
+    fn as_dyn(&self) -> &dyn Routines {
+        match self {
+            RoutinesCommand::RTN32(command) => command,
+            RoutinesCommand::RTN64(command) => command,
+        }
+    }
+}
+
+impl Routines for RoutinesCommand {
+    fn cmd(&self) -> u32 { self.as_dyn().cmd() }
+    fn cmdsize(&self) -> u32 { self.as_dyn().cmdsize() }
+    fn init_address(&self) -> u64 { self.as_dyn().init_address() }
+    fn init_module(&self) -> u64 { self.as_dyn().init_module() }
+    fn reserved(&self) -> [u64; 6] { self.as_dyn().reserved() }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct RoutinesCommand32 {
@@ -475,6 +800,16 @@ impl RoutinesCommand32 {
     }
 }
 
+impl Routines for RoutinesCommand32 {
+    fn cmd(&self) -> u32 { self.cmd }
+    fn cmdsize(&self) -> u32 { self.cmdsize }
+    fn init_address(&self) -> u64 { self.init_address as u64 }
+    fn init_module(&self) -> u64 { self.init_module as u64 }
+    fn reserved(&self) -> [u64; 6] {
+        [self.reserved1 as u64, self.reserved2 as u64, self.reserved3 as u64, self.reserved4 as u64, self.reserved5 as u64, self.reserved6 as u64]
+    }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct RoutinesCommand64 {
@@ -508,6 +843,16 @@ impl RoutinesCommand64 {
     }
 }
 
+impl Routines for RoutinesCommand64 {
+    fn cmd(&self) -> u32 { self.cmd }
+    fn cmdsize(&self) -> u32 { self.cmdsize }
+    fn init_address(&self) -> u64 { self.init_address }
+    fn init_module(&self) -> u64 { self.init_module }
+    fn reserved(&self) -> [u64; 6] {
+        [self.reserved1, self.reserved2, self.reserved3, self.reserved4, self.reserved5, self.reserved6]
+    }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct SymtabCommand {
@@ -609,6 +954,48 @@ pub enum DylibModule {
     DMD64(DylibModule64),
 }
 
+/// Fields of `dylib_module` and `dylib_module_64`, with the Objective-C module info address widened to 64 bits.
+pub trait DylibModuleLike {
+    fn module_name(&self) -> u32;
+    fn iextdefsym(&self) -> u32;
+    fn nextdefsym(&self) -> u32;
+    fn irefsym(&self) -> u32;
+    fn nrefsym(&self) -> u32;
+    fn ilocalsym(&self) -> u32;
+    fn nlocalsym(&self) -> u32;
+    fn iextrel(&self) -> u32;
+    fn nextrel(&self) -> u32;
+    fn iinit_iterm(&self) -> u32;
+    fn ninit_nterm(&self) -> u32;
+    fn objc_module_info_addr(&self) -> u64;
+    fn objc_module_info_size(&self) -> u32;
+}
+
+impl DylibModule {
+    fn as_dyn(&self) -> &dyn DylibModuleLike {
+        match self {
+            DylibModule::DMD32(module) => module,
+            DylibModule::DMD64(module) => module,
+        }
+    }
+}
+
+impl DylibModuleLike for DylibModule {
+    fn module_name(&self) -> u32 { self.as_dyn().module_name() }
+    fn iextdefsym(&self) -> u32 { self.as_dyn().iextdefsym() }
+    fn nextdefsym(&self) -> u32 { self.as_dyn().nextdefsym() }
+    fn irefsym(&self) -> u32 { self.as_dyn().irefsym() }
+    fn nrefsym(&self) -> u32 { self.as_dyn().nrefsym() }
+    fn ilocalsym(&self) -> u32 { self.as_dyn().ilocalsym() }
+    fn nlocalsym(&self) -> u32 { self.as_dyn().nlocalsym() }
+    fn iextrel(&self) -> u32 { self.as_dyn().iextrel() }
+    fn nextrel(&self) -> u32 { self.as_dyn().nextrel() }
+    fn iinit_iterm(&self) -> u32 { self.as_dyn().iinit_iterm() }
+    fn ninit_nterm(&self) -> u32 { self.as_dyn().ninit_nterm() }
+    fn objc_module_info_addr(&self) -> u64 { self.as_dyn().objc_module_info_addr() }
+    fn objc_module_info_size(&self) -> u32 { self.as_dyn().objc_module_info_size() }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct DylibModule32 {
@@ -648,6 +1035,22 @@ impl DylibModule32 {
     }
 }
 
+impl DylibModuleLike for DylibModule32 {
+    fn module_name(&self) -> u32 { self.module_name }
+    fn iextdefsym(&self) -> u32 { self.iextdefsym }
+    fn nextdefsym(&self) -> u32 { self.nextdefsym }
+    fn irefsym(&self) -> u32 { self.irefsym }
+    fn nrefsym(&self) -> u32 { self.nrefsym }
+    fn ilocalsym(&self) -> u32 { self.ilocalsym }
+    fn nlocalsym(&self) -> u32 { self.nlocalsym }
+    fn iextrel(&self) -> u32 { self.iextrel }
+    fn nextrel(&self) -> u32 { self.nextrel }
+    fn iinit_iterm(&self) -> u32 { self.iinit_iterm }
+    fn ninit_nterm(&self) -> u32 { self.ninit_nterm }
+    fn objc_module_info_addr(&self) -> u64 { self.objc_module_info_addr as u64 }
+    fn objc_module_info_size(&self) -> u32 { self.objc_module_info_size }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct DylibModule64 {
@@ -687,6 +1090,22 @@ impl DylibModule64 {
     }
 }
 
+impl DylibModuleLike for DylibModule64 {
+    fn module_name(&self) -> u32 { self.module_name }
+    fn iextdefsym(&self) -> u32 { self.iextdefsym }
+    fn nextdefsym(&self) -> u32 { self.nextdefsym }
+    fn irefsym(&self) -> u32 { self.irefsym }
+    fn nrefsym(&self) -> u32 { self.nrefsym }
+    fn ilocalsym(&self) -> u32 { self.ilocalsym }
+    fn nlocalsym(&self) -> u32 { self.nlocalsym }
+    fn iextrel(&self) -> u32 { self.iextrel }
+    fn nextrel(&self) -> u32 { self.nextrel }
+    fn iinit_iterm(&self) -> u32 { self.iinit_iterm }
+    fn ninit_nterm(&self) -> u32 { self.ninit_nterm }
+    fn objc_module_info_addr(&self) -> u64 { self.objc_module_info_addr }
+    fn objc_module_info_size(&self) -> u32 { self.objc_module_info_size }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct DylibReference {
@@ -823,6 +1242,12 @@ impl LinkeditDataCommand {
         };
         Ok(LoadCommand::LinkeditDataCommand(linkedit_data_command))
     }
+
+    /// Reads the `__LINKEDIT` blob the command points at.
+    pub fn read_data<R: Read + Seek>(&self, file: &mut R) -> Result<Vec<u8>, AppError> {
+        let (offset, size) = (self.dataoff as u64, self.datasize as u64);
+        read_bytes_at(file, offset, size).map_err(AppError::out_of_bounds(load_command_name(self.cmd).unwrap_or("linkedit data"), offset, size))
+    }
 }
 
 #[derive(Debug)]
@@ -831,6 +1256,35 @@ pub enum EncryptionInfoCommand {
     ENI64(EncryptionInfoCommand64),
 }
 
+/// Fields of `encryption_info_command` and `encryption_info_command_64`.
+pub trait EncryptionInfo {
+    fn cmd(&self) -> u32;
+    fn cmdsize(&self) -> u32;
+    fn cryptoff(&self) -> u32;
+    fn cryptsize(&self) -> u32;
+    fn cryptid(&self) -> u32;
+    /// Only present in `encryption_info_command_64`.
+    fn pad(&self) -> Option<u32>;
+}
+
+impl EncryptionInfoCommand {
+    fn as_dyn(&self) -> &dyn EncryptionInfo {
+        match self {
+            EncryptionInfoCommand::ENI32(command) => command,
+            EncryptionInfoCommand::ENI64(command) => command,
+        }
+    }
+}
+
+impl EncryptionInfo for EncryptionInfoCommand {
+    fn cmd(&self) -> u32 { self.as_dyn().cmd() }
+    fn cmdsize(&self) -> u32 { self.as_dyn().cmdsize() }
+    fn cryptoff(&self) -> u32 { self.as_dyn().cryptoff() }
+    fn cryptsize(&self) -> u32 { self.as_dyn().cryptsize() }
+    fn cryptid(&self) -> u32 { self.as_dyn().cryptid() }
+    fn pad(&self) -> Option<u32> { self.as_dyn().pad() }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct EncryptionInfoCommand32 {
@@ -856,6 +1310,15 @@ impl EncryptionInfoCommand32 {
     }
 }
 
+impl EncryptionInfo for EncryptionInfoCommand32 {
+    fn cmd(&self) -> u32 { self.cmd }
+    fn cmdsize(&self) -> u32 { self.cmdsize }
+    fn cryptoff(&self) -> u32 { self.cryptoff }
+    fn cryptsize(&self) -> u32 { self.cryptsize }
+    fn cryptid(&self) -> u32 { self.cryptid }
+    fn pad(&self) -> Option<u32> { None }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct EncryptionInfoCommand64 {
@@ -883,6 +1346,15 @@ impl EncryptionInfoCommand64 {
     }
 }
 
+impl EncryptionInfo for EncryptionInfoCommand64 {
+    fn cmd(&self) -> u32 { self.cmd }
+    fn cmdsize(&self) -> u32 { self.cmdsize }
+    fn cryptoff(&self) -> u32 { self.cryptoff }
+    fn cryptsize(&self) -> u32 { self.cryptsize }
+    fn cryptid(&self) -> u32 { self.cryptid }
+    fn pad(&self) -> Option<u32> { Some(self.pad) }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct VersionMinCommand {
@@ -1040,6 +1512,23 @@ impl IdentCommand {
     }
 }
 
+#[derive(Debug)]
+#[repr(C)]
+pub struct PrepageCommand {
+    pub cmd: u32,
+    pub cmdsize: u32,
+}
+
+impl PrepageCommand {
+    pub fn from_file(load_command: &LoadCommandPrefix) -> Result<LoadCommand, AppError> {
+        let prepage_command = PrepageCommand {
+            cmd: load_command.cmd,
+            cmdsize: load_command.cmdsize,
+        };
+        Ok(LoadCommand::PrepageCommand(prepage_command))
+    }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct EntryPointCommand {
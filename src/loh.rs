@@ -0,0 +1,135 @@
+use std::io::{Cursor, Read, Seek};
+
+use crate::constants::*;
+use crate::error::AppError;
+use crate::load_commands::*;
+use crate::mach_o::MachO;
+use crate::memory_utils::*;
+use crate::symbols::*;
+
+#[derive(Debug)]
+pub struct LinkerOptimizationHint {
+    pub kind: u64,
+    pub addresses: Vec<LohAddress>,
+}
+
+#[derive(Debug)]
+pub struct LohAddress {
+    pub address: u64,
+    /// (segname, sectname) of the section containing the instruction
+    pub section: Option<(String, String)>,
+    /// Nearest preceding symbol in the same section and the offset from it
+    pub symbol: Option<(String, u64)>,
+}
+
+impl LinkerOptimizationHint {
+    /// Decodes the LC_LINKER_OPTIMIZATION_HINT payload, or returns `None` if the image has none.
+    // LOH stream :== (<uleb128 kind> <uleb128 count> <uleb128 address>{count})*
+    pub fn from_file<R: Read + Seek>(file: &mut R, mach_o: &MachO) -> Result<Option<Vec<LinkerOptimizationHint>>, AppError> {
+        let command = mach_o.load_commands.iter().find_map(|entry| match &entry.command {
+            LoadCommand::LinkeditDataCommand(command) if command.cmd == LC_LINKER_OPTIMIZATION_HINT => Some(command),
+            _ => None,
+        });
+        let Some(command) = command else { return Ok(None) };
+        let data = command.read_data(file)?;
+        let sections: Vec<&Section> = mach_o.sections().collect();
+        let mut cursor = Cursor::new(data.as_slice());
+        let mut hints = Vec::new();
+
+        while cursor.position() < data.len() as u64 {
+            let kind = read_uleb128(&mut cursor)?;
+            // The stream is padded with zeros up to pointer alignment.
+            if kind == 0 {
+                break;
+            }
+            let count = read_uleb128(&mut cursor)?;
+            let mut addresses = Vec::new();
+            for _ in 0..count {
+                let address = read_uleb128(&mut cursor)?;
+                addresses.push(LohAddress::new(address, &sections, &mach_o.symtab, &mach_o.strtab));
+            }
+            hints.push(LinkerOptimizationHint { kind, addresses });
+        }
+
+        Ok(Some(hints))
+    }
+
+    pub fn expected_address_count(&self) -> Option<usize> {
+        match self.kind {
+            LOH_ARM64_ADRP_ADRP | LOH_ARM64_ADRP_LDR | LOH_ARM64_ADRP_ADD | LOH_ARM64_ADRP_LDR_GOT => Some(2),
+            LOH_ARM64_ADRP_ADD_LDR | LOH_ARM64_ADRP_LDR_GOT_LDR | LOH_ARM64_ADRP_ADD_STR | LOH_ARM64_ADRP_LDR_GOT_STR => Some(3),
+            _ => None,
+        }
+    }
+}
+
+impl LohAddress {
+    fn new(address: u64, sections: &[&Section], symtab: &Symtab, strtab: &Strtab) -> LohAddress {
+        let section_index = sections.iter().position(|section| address >= section.addr() && address - section.addr() < section.size());
+        let section = section_index.map(|index| (fixed_bytes_to_string(sections[index].segname()), fixed_bytes_to_string(sections[index].sectname())));
+        // n_sect is a single byte, so symbols cannot point into sections past the 255th.
+        let n_sect = section_index.and_then(|index| u8::try_from(index + 1).ok());
+        let symbol = n_sect.and_then(|n_sect| nearest_symbol(address, n_sect, symtab, strtab));
+        LohAddress { address, section, symbol }
+    }
+}
+
+fn nearest_symbol(address: u64, n_sect: u8, symtab: &Symtab, strtab: &Strtab) -> Option<(String, u64)> {
+    symtab.iter()
+        .filter(|nlist| nlist.n_type() & N_STAB == 0 && nlist.n_type() & N_TYPE == N_SECT)
+        .filter(|nlist| nlist.n_sect() == n_sect && nlist.n_value() <= address)
+        .max_by_key(|nlist| nlist.n_value())
+        .map(|nlist| (symbol_name(strtab, nlist.n_strx()), address - nlist.n_value()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    // 257 sections of 0x10 bytes from 0x1000, a symbol at the start of the first one and the hints at 0x6000.
+    fn image_with_hints(hints: &[u8]) -> Vec<u8> {
+        let names: Vec<String> = (0..257).map(|index| format!("__s{}", index)).collect();
+        let sections: Vec<SectionSpec> = names.iter().enumerate().map(|(index, name)| (name.as_str(), 0x1000 + index as u64 * 0x10, 0x10, 0)).collect();
+        let text = segment_64("__TEXT", 0x1000, 0x2000, 0, 0, &sections);
+        let symtab = command(LC_SYMTAB, &[0x7000, 1, 0x7100, 8]);
+        let loh = command(LC_LINKER_OPTIMIZATION_HINT, &[0x6000, hints.len() as u32]);
+        image(&[text, symtab, loh], &[(0x6000, hints), (0x7000, &nlist_64(1, N_SECT, 1, 0x1000)), (0x7100, b"\0_start\0")])
+    }
+
+    fn hint(addresses: &[u64]) -> Vec<u8> {
+        let mut data = uleb128(LOH_ARM64_ADRP_ADD);
+        data.extend(uleb128(addresses.len() as u64));
+        for &address in addresses {
+            data.extend(uleb128(address));
+        }
+        data
+    }
+
+    fn decode(data: &[u8]) -> Vec<LinkerOptimizationHint> {
+        let mach_o = MachO::from_bytes(data).unwrap();
+        LinkerOptimizationHint::from_file(&mut Cursor::new(data), &mach_o).unwrap().unwrap()
+    }
+
+    #[test]
+    fn resolves_the_nearest_symbol() {
+        let hints = decode(&image_with_hints(&hint(&[0x1004, 0x1008])));
+        assert_eq!(hints[0].addresses[0].symbol, Some((String::from("_start"), 4)));
+        assert_eq!(hints[0].addresses[1].section, Some((String::from("__TEXT"), String::from("__s0"))));
+    }
+
+    #[test]
+    fn skips_symbols_for_sections_past_255() {
+        // Section #257 would truncate to n_sect 1 and pick up the symbol of the first section.
+        let hints = decode(&image_with_hints(&hint(&[0x2000])));
+        assert_eq!(hints[0].addresses[0].section, Some((String::from("__TEXT"), String::from("__s256"))));
+        assert_eq!(hints[0].addresses[0].symbol, None);
+    }
+
+    #[test]
+    fn broken_hints_do_not_fail_the_parse() {
+        let data = image_with_hints(&[0x80]);
+        let mach_o = MachO::from_bytes(&data).unwrap();
+        assert!(LinkerOptimizationHint::from_file(&mut Cursor::new(&data), &mach_o).is_err());
+    }
+}
@@ -1,19 +1,315 @@
-use crate::header::MachHeader;
-use crate::load_commands::{LcStr, LoadCommand, Section};
-use crate::symbols::Symtab;
+use std::io::{Cursor, Read, Seek};
+
+use byteorder::{BigEndian, LittleEndian};
+
+use crate::constants::*;
+use crate::corefile::{CoreFile, RegisterState};
+use crate::diagnostics::Diagnostic;
+use crate::dyld_info::{BindRecord, ChainedFixups, ExportRecord};
+use crate::encryption::EncryptedRange;
+use crate::error::AppError;
+use crate::header::{Header, MachHeader};
+use crate::load_commands::{LoadCommand, LoadCommandEntry, Section, SectionLike, Segment, SegmentCommand};
+use crate::memory_utils::{fixed_bytes_to_string, read_bytes_at};
+use crate::notes::Note;
+use crate::parser;
+use crate::symbols::{Strtab, Symtab};
 
 pub struct MachO {
-    pub header: Option<MachHeader>,
-    pub load_commands: Option<(Vec<LoadCommand>, Vec<Vec<Section>>, Vec<LcStr>)>,
-    pub symtab: Option<Symtab>
+    pub header: MachHeader,
+    pub load_commands: Vec<LoadCommandEntry>,
+    pub symtab: Symtab,
+    pub strtab: Strtab,
+    pub indirect_symbols: Vec<u32>,
+    pub notes: Vec<Note>,
+    /// Threads and memory regions, only present for MH_CORE files.
+    pub core: Option<CoreFile>,
+    pub encrypted_ranges: Vec<EncryptedRange>,
+    pub binds: Vec<BindRecord>,
+    pub exports: Vec<ExportRecord>,
+    pub chained_fixups: Option<ChainedFixups>,
+    pub function_starts: Option<Vec<u64>>,
+    /// Problems skipped over by a lenient parse. A strict parse only records the notes and the
+    /// bind, export, chained fixup and function start tables it could not decode here, and fails on
+    /// anything else.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// A library the image links against, decoded from an LC_*_DYLIB command.
+#[derive(Debug)]
+pub struct LinkedDylib {
+    /// LC_LOAD_DYLIB, LC_LOAD_WEAK_DYLIB, LC_REEXPORT_DYLIB and so on.
+    pub cmd: u32,
+    pub install_name: String,
+    pub current_version: u32,
+    pub compatibility_version: u32,
+}
+
+/// A table in `__LINKEDIT` that a load command locates by file offset and size.
+#[derive(Debug, Clone)]
+pub struct LinkeditBlob {
+    /// What the table holds, e.g. `"string table"` or `"function starts"`.
+    pub name: &'static str,
+    /// The command that locates the table.
+    pub cmd: u32,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Deployment target from LC_BUILD_VERSION or one of the older LC_VERSION_MIN_* commands.
+/// Versions are encoded as xxxx.yy.zz in nibbles, as in the load commands.
+#[derive(Debug, Clone, Copy)]
+pub struct Platform {
+    pub platform: u32,
+    pub minos: u32,
+    pub sdk: u32,
 }
 
 impl MachO {
-    pub fn new() -> Self {
-        MachO {
-            header: None,
-            load_commands: None,
-            symtab: None,
+    /// Parses a thin image held in memory, e.g. extracted from an archive. File offsets in the
+    /// image are relative to the start of `data`, so pass the sub-slice that holds just the image.
+    ///
+    /// The on-demand analyses and [`MachO::read_file_range`] read from the same bytes through a cursor:
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use mach_o_rust::MachO;
+    ///
+    /// // An image embedded at a non-zero offset of a larger buffer, like a static archive member.
+    /// let member = std::fs::read("test_files/mach_o_ex1")?;
+    /// let mut archive = vec![0u8; 0x1000];
+    /// archive.extend(&member);
+    /// archive.extend(b"next member");
+    ///
+    /// let image = &archive[0x1000..0x1000 + member.len()];
+    /// let mach_o = MachO::from_bytes(image)?;
+    /// let objc = mach_o_rust::objc::ObjcMetadata::from_file(&mut Cursor::new(image), &mach_o)?;
+    /// println!("{} classes", objc.classes.len());
+    /// # Ok::<(), mach_o_rust::AppError>(())
+    /// ```
+    pub fn from_bytes(data: &[u8]) -> Result<MachO, AppError> {
+        parser::parse(&mut Cursor::new(data))
+    }
+
+    /// Reads raw file contents, refusing ranges that overlap data still encrypted by an
+    /// LC_ENCRYPTION_INFO command since decoding it would only produce garbage.
+    pub fn read_file_range<R: Read + Seek>(&self, file: &mut R, offset: u64, size: u64) -> Result<Vec<u8>, AppError> {
+        for range in &self.encrypted_ranges {
+            if range.is_encrypted() && range.overlaps(offset, size) {
+                return Err(AppError::Encrypted { offset, size, cryptid: range.cryptid });
+            }
+        }
+        read_bytes_at(file, offset, size).map_err(AppError::out_of_bounds("file range", offset, size))
+    }
+
+    pub fn read_section_data<R: Read + Seek>(&self, file: &mut R, section: &Section) -> Result<Vec<u8>, AppError> {
+        self.read_file_range(file, section.offset() as u64, section.size())
+    }
+
+    /// Segment commands in load command order, each with its sections.
+    pub fn segments(&self) -> impl Iterator<Item = (&SegmentCommand, &[Section])> {
+        self.load_commands.iter().filter_map(|entry| match &entry.command {
+            LoadCommand::SegmentCommand(segment) => Some((segment, entry.sections.as_slice())),
+            _ => None,
+        })
+    }
+
+    /// Every section of every segment, in file order.
+    pub fn sections(&self) -> impl Iterator<Item = &Section> {
+        self.load_commands.iter().flat_map(|entry| &entry.sections)
+    }
+
+    /// Looks up a segment by name, e.g. `"__LINKEDIT"`.
+    pub fn segment_by_name(&self, segname: &str) -> Option<(&SegmentCommand, &[Section])> {
+        self.segments().find(|(segment, _)| fixed_bytes_to_string(segment.segname()) == segname)
+    }
+
+    /// Looks up a section by segment and section name, e.g. `("__TEXT", "__text")`.
+    pub fn section_by_name(&self, segname: &str, sectname: &str) -> Option<&Section> {
+        self.sections().find(|section| {
+            fixed_bytes_to_string(section.segname()) == segname && fixed_bytes_to_string(section.sectname()) == sectname
+        })
+    }
+
+    /// Libraries loaded by the image; the image's own LC_ID_DYLIB is not included.
+    pub fn dylibs(&self) -> Vec<LinkedDylib> {
+        self.load_commands.iter().filter_map(|entry| match &entry.command {
+            LoadCommand::DylibCommand(command) if command.cmd != LC_ID_DYLIB => Some(LinkedDylib {
+                cmd: command.cmd,
+                install_name: entry.string().unwrap_or_default(),
+                current_version: command.dylib.current_version,
+                compatibility_version: command.dylib.compatibility_version,
+            }),
+            _ => None,
+        }).collect()
+    }
+
+    /// The install name from LC_ID_DYLIB, for dynamic libraries.
+    pub fn install_name(&self) -> Option<String> {
+        self.load_commands.iter().find_map(|entry| match &entry.command {
+            LoadCommand::DylibCommand(command) if command.cmd == LC_ID_DYLIB => entry.string(),
+            _ => None,
+        })
+    }
+
+    pub fn rpaths(&self) -> Vec<String> {
+        self.load_commands.iter().filter_map(|entry| match &entry.command {
+            LoadCommand::RpathCommand(_) => entry.string(),
+            _ => None,
+        }).collect()
+    }
+
+    pub fn uuid(&self) -> Option<[u8; 16]> {
+        self.load_commands.iter().find_map(|entry| match &entry.command {
+            LoadCommand::UuidCommand(command) => Some(command.uuid),
+            _ => None,
+        })
+    }
+
+    /// Virtual address of the entry point from LC_MAIN, or the initial pc of LC_UNIXTHREAD.
+    pub fn entry_point(&self) -> Option<u64> {
+        for entry in &self.load_commands {
+            match &entry.command {
+                // LC_MAIN holds a file offset, which the segment containing it maps to an address.
+                LoadCommand::EntryPointCommand(command) => {
+                    return self.segments().map(|(segment, _)| segment)
+                        .find(|segment| command.entryoff >= segment.fileoff() && command.entryoff - segment.fileoff() < segment.filesize())
+                        .map(|segment| segment.vmaddr().wrapping_add(command.entryoff - segment.fileoff()));
+                }
+                LoadCommand::ThreadCommand(command) if command.cmd == LC_UNIXTHREAD => {
+                    let thread_state = command.thread_states.first()?;
+                    let cputype = self.header.cputype();
+                    let registers = match self.header.magic() {
+                        MH_MAGIC | MH_MAGIC_64 => RegisterState::from_thread_state::<BigEndian>(thread_state.flavor, &thread_state.state, cputype),
+                        _ => RegisterState::from_thread_state::<LittleEndian>(thread_state.flavor, &thread_state.state, cputype),
+                    }.ok()?.registers?;
+                    return registers.iter()
+                        .find(|register| matches!(register.name.as_str(), "pc" | "rip" | "eip"))
+                        .map(|register| register.value);
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// The `__LINKEDIT` tables located by the load commands, in load command order. Empty tables
+    /// are left out.
+    pub fn linkedit_blobs(&self) -> Vec<LinkeditBlob> {
+        let symbol_size = if self.header.is_64() { 16 } else { 12 };
+        let module_size = if self.header.is_64() { 56 } else { 52 };
+        let mut blobs = Vec::new();
+        for entry in &self.load_commands {
+            let tables: Vec<(&'static str, u32, u64)> = match &entry.command {
+                LoadCommand::SymtabCommand(command) => vec![
+                    ("symbol table", command.symoff, command.nsyms as u64 * symbol_size),
+                    ("string table", command.stroff, command.strsize as u64),
+                ],
+                LoadCommand::DynSymtabCommand(command) => vec![
+                    ("table of contents", command.tocoff, command.ntoc as u64 * 8),
+                    ("module table", command.modtaboff, command.nmodtab as u64 * module_size),
+                    ("external references", command.extrefsymoff, command.nextrefsyms as u64 * 4),
+                    ("indirect symbol table", command.indirectsymoff, command.nindirectsyms as u64 * 4),
+                    ("external relocations", command.extreloff, command.nextrel as u64 * 8),
+                    ("local relocations", command.locreloff, command.nlocrel as u64 * 8),
+                ],
+                LoadCommand::DyldInfoCommand(command) => vec![
+                    ("rebase opcodes", command.rebase_off, command.rebase_size as u64),
+                    ("bind opcodes", command.bind_off, command.bind_size as u64),
+                    ("weak bind opcodes", command.weak_bind_off, command.weak_bind_size as u64),
+                    ("lazy bind opcodes", command.lazy_bind_off, command.lazy_bind_size as u64),
+                    ("export trie", command.export_off, command.export_size as u64),
+                ],
+                LoadCommand::LinkeditDataCommand(command) => {
+                    let name = match command.cmd {
+                        LC_CODE_SIGNATURE => "code signature",
+                        LC_SEGMENT_SPLIT_INFO => "segment split info",
+                        LC_FUNCTION_STARTS => "function starts",
+                        LC_DATA_IN_CODE => "data in code",
+                        LC_DYLIB_CODE_SIGN_DRS => "code signing DRs",
+                        LC_LINKER_OPTIMIZATION_HINT => "linker optimization hints",
+                        LC_DYLD_EXPORTS_TRIE => "export trie",
+                        _ => "chained fixups",
+                    };
+                    vec![(name, command.dataoff, command.datasize as u64)]
+                }
+                _ => Vec::new(),
+            };
+            blobs.extend(tables.into_iter()
+                .filter(|&(_, _, size)| size != 0)
+                .map(|(name, offset, size)| LinkeditBlob { name, cmd: entry.cmd, offset: offset as u64, size }));
+        }
+        blobs
+    }
+
+    pub fn platform(&self) -> Option<Platform> {
+        self.load_commands.iter().find_map(|entry| match &entry.command {
+            LoadCommand::BuildVersionCommand(command) => Some(Platform { platform: command.platform, minos: command.minos, sdk: command.sdk }),
+            LoadCommand::VersionMinCommand(command) => {
+                let platform = match command.cmd {
+                    LC_VERSION_MIN_MACOSX => PLATFORM_MACOS,
+                    LC_VERSION_MIN_IPHONEOS => PLATFORM_IOS,
+                    LC_VERSION_MIN_TVOS => PLATFORM_TVOS,
+                    _ => PLATFORM_WATCHOS,
+                };
+                Some(Platform { platform, minos: command.version, sdk: command.sdk })
+            }
+            _ => None,
+        })
+    }
+}
+
+impl Platform {
+    pub fn name(&self) -> &'static str {
+        match self.platform {
+            PLATFORM_MACOS => "macOS",
+            PLATFORM_IOS => "iOS",
+            PLATFORM_TVOS => "tvOS",
+            PLATFORM_WATCHOS => "watchOS",
+            PLATFORM_BRIDGEOS => "bridgeOS",
+            PLATFORM_MACCATALYST => "Mac Catalyst",
+            PLATFORM_IOSSIMULATOR => "iOS Simulator",
+            PLATFORM_TVOSSIMULATOR => "tvOS Simulator",
+            PLATFORM_WATCHOSSIMULATOR => "watchOS Simulator",
+            PLATFORM_DRIVERKIT => "DriverKit",
+            PLATFORM_VISIONOS => "visionOS",
+            PLATFORM_VISIONOSSIMULATOR => "visionOS Simulator",
+            _ => "unknown",
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn embedded_image_offsets_are_relative_to_the_slice() {
+        let symtab = command(LC_SYMTAB, &[0x100, 1, 0x110, 8]);
+        let member = image(&[symtab], &[(0x100, &nlist_64(1, N_SECT | N_EXT, 1, 0x1000)), (0x110, b"\0_main\0\0")]);
+        let mut archive = vec![0xffu8; 0x1000];
+        archive.extend(&member);
+        archive.extend([0xff; 0x200]);
+
+        let image = &archive[0x1000..0x1000 + member.len()];
+        let mach_o = MachO::from_bytes(image).unwrap();
+        assert_eq!(symbol_name(&mach_o.strtab, mach_o.symtab[0].n_strx()), "_main");
+        assert_eq!(mach_o.read_file_range(&mut Cursor::new(image), 0x110, 6).unwrap(), b"\0_main");
+        assert!(mach_o.read_file_range(&mut Cursor::new(image), member.len() as u64, 1).is_err());
+    }
+
+    #[test]
+    fn entry_point_wraps_instead_of_overflowing() {
+        let text = segment_64("__TEXT", u64::MAX - 0xfff, 0x1000, 0, 0x1000, &[]);
+        let main = command(LC_MAIN, &[0x800, 0, 0, 0]);
+        let mach_o = MachO::from_bytes(&image(&[text, main], &[(0xfff, &[0])])).unwrap();
+        assert_eq!(mach_o.entry_point(), Some(u64::MAX - 0x7ff));
+
+        let text = segment_64("__TEXT", u64::MAX, 0x1000, 0, 0x1000, &[]);
+        let main = command(LC_MAIN, &[0x800, 0, 0, 0]);
+        let mach_o = MachO::from_bytes(&image(&[text, main], &[(0xfff, &[0])])).unwrap();
+        assert_eq!(mach_o.entry_point(), Some(0x7ff));
+    }
+}
@@ -1,28 +1,35 @@
+use std::error::Error;
 use std::fs::File;
-use std::path::PathBuf;
+use std::io::{self, Cursor, Read, Seek};
+use std::path::{Path, PathBuf};
 
 use clap::Parser;
-use crate::error::AppError;
-use crate::mach_o::MachO;
-
-mod constants;
-mod header;
-mod load_commands;
-mod mach_o;
-mod memory_utils;
-mod parser;
-mod printer;
-mod error;
-mod symbols;
+use mach_o_rust::constants::{FAT_MAGIC, FAT_MAGIC_64};
+use mach_o_rust::{class_dump, coverage, demangle, go, linkedit, lint, loh, objc, objc_usage, parser, printer, split_info, swift, AppError, SectionLike};
 
 /// A command-line tool written in Rust to view and explore mach-o files.
 #[derive(Parser)]
 #[command(name = "Mach_O_Rust")]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Input mach-o file
+    /// Input mach-o file, or - to read it from stdin
     #[arg(short, long, value_name = "PATH", required = true)]
     file: PathBuf,
+    /// Skip broken structures instead of stopping at the first error, and report them on stderr
+    #[arg(long, required = false)]
+    lenient: bool,
+    /// Refuse files with more load commands than this
+    #[arg(long, value_name = "COUNT", required = false)]
+    max_load_commands: Option<u32>,
+    /// Refuse symbol tables with more entries than this
+    #[arg(long, value_name = "COUNT", required = false)]
+    max_symbols: Option<u64>,
+    /// Refuse more bind or export records than this
+    #[arg(long, value_name = "COUNT", required = false)]
+    max_records: Option<u64>,
+    /// Refuse tables and opcode streams larger than this many bytes
+    #[arg(long, value_name = "BYTES", required = false)]
+    max_table_size: Option<u64>,
     /// Interactive mode
     #[arg(short, long, required = false)]
     interactive: bool,
@@ -32,13 +39,82 @@ struct Args {
     /// Print mach-o load commands
     #[arg(short, long, required = false)]
     load_commands: bool,
+    /// Print the tables in the __LINKEDIT segment with their size, alignment and padding
+    #[arg(long, required = false)]
+    linkedit: bool,
+    /// Print the structure accounting for each byte range of the file, with gaps and appended data
+    #[arg(long, required = false)]
+    coverage: bool,
+    /// Check the layout against the rules dyld and the kernel enforce; implies --lenient
+    #[arg(long, required = false)]
+    lint: bool,
+    /// Print decoded LC_NOTE payloads
+    #[arg(long, required = false)]
+    notes: bool,
+    /// Print threads and memory regions of an MH_CORE file
+    #[arg(long, required = false)]
+    core: bool,
+    /// Dump core file memory at a virtual address
+    #[arg(long, value_name = "VMADDR:SIZE", required = false)]
+    read_memory: Option<String>,
+    /// Print decoded LC_SEGMENT_SPLIT_INFO references
+    #[arg(long, required = false)]
+    split_info: bool,
+    /// Print decoded LC_LINKER_OPTIMIZATION_HINT records
+    #[arg(long, required = false)]
+    loh: bool,
+    /// Print Objective-C classes, categories, protocols and references
+    #[arg(long, required = false)]
+    objc: bool,
+    /// Print Objective-C methods and classes that are never referenced
+    #[arg(long, required = false)]
+    objc_unused: bool,
+    /// Print class-dump style Objective-C headers
+    #[arg(long, required = false)]
+    class_dump: bool,
+    /// Print Swift types, protocols and conformances from the reflection metadata
+    #[arg(long, required = false)]
+    swift: bool,
+    /// Print Go build info, module dependencies and functions with their source lines
+    #[arg(long, required = false)]
+    go: bool,
+    /// Print the symbol table, exports and imported symbols
+    #[arg(long, required = false)]
+    symbols: bool,
+    /// Demangle Swift, C++ and Rust symbol names wherever they are printed
+    #[arg(long, value_enum, value_name = "STYLE", num_args = 0..=1, default_missing_value = "full", required = false)]
+    demangle: Option<DemangleStyle>,
+    /// Leave the hash out of demangled Rust symbol names
+    #[arg(long, requires = "demangle", required = false)]
+    hide_hash: bool,
+    /// Hex dump the contents of a section
+    #[arg(long, value_name = "SEGNAME,SECTNAME", required = false)]
+    dump_section: Option<String>,
+}
+
+/// Values of --demangle, mirroring [`demangle::DemangleStyle`] so the library does not depend on clap.
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum DemangleStyle {
+    /// Qualified names with parameter and return types
+    Full,
+    /// Qualified names only
+    NameOnly,
+}
+
+impl From<DemangleStyle> for demangle::DemangleStyle {
+    fn from(style: DemangleStyle) -> demangle::DemangleStyle {
+        match style {
+            DemangleStyle::Full => demangle::DemangleStyle::Full,
+            DemangleStyle::NameOnly => demangle::DemangleStyle::NameOnly,
+        }
+    }
 }
 
 fn main() -> Result<(), AppError> {
     let args = Args::parse();
 
     if let Err(e) = run(&args) {
-        eprintln!("Error: {}", e);
+        report_error(&e);
         std::process::exit(1);
     }
 
@@ -46,25 +122,176 @@ fn main() -> Result<(), AppError> {
 }
 
 fn run(args: &Args) -> Result<(), AppError> {
-    let mach_o = parse_from_file(&args.file)?;
+    let input = Input::open(&args.file)?;
+    let defaults = parser::ParseLimits::default();
+    let limits = parser::ParseLimits {
+        max_load_commands: args.max_load_commands.unwrap_or(defaults.max_load_commands),
+        max_symbols: args.max_symbols.unwrap_or(defaults.max_symbols),
+        max_records: args.max_records.unwrap_or(defaults.max_records),
+        max_table_size: args.max_table_size.unwrap_or(defaults.max_table_size),
+    };
+    let options = parser::ParseOptions { lenient: args.lenient || args.lint, limits };
+    let mach_o = parser::parse_with_options(&mut input.reader()?, options)?;
+    for diagnostic in &mach_o.diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    let demangle = args.demangle.map(|style| demangle::DemangleOptions { style: style.into(), hide_hash: args.hide_hash });
 
     if args.interactive {
         println!("Not yet implemented!");
     }
 
     if args.header {
-        printer::print_header(&mach_o.header.unwrap());
+        printer::print_header(&mach_o.header);
+        printer::print_encryption_summary(&mach_o.encrypted_ranges);
     }
 
     if args.load_commands {
-        printer::print_load_commands(&mach_o.load_commands.unwrap());
+        printer::print_load_commands(&mach_o.load_commands);
+    }
+
+    if args.linkedit {
+        let layout = linkedit::LinkeditLayout::from_mach_o(&mach_o).ok_or_else(|| AppError::from(io::Error::new(io::ErrorKind::InvalidInput, "no __LINKEDIT segment")))?;
+        printer::print_linkedit_layout(&layout);
+    }
+
+    if args.coverage {
+        let mut file = input.reader()?;
+        printer::print_coverage(&coverage::FileCoverage::from_file(&mut file, &mach_o)?);
+    }
+
+    if args.lint {
+        printer::print_lint(&lint::lint(&mach_o));
+    }
+
+    if args.notes {
+        printer::print_notes(&mach_o.notes);
+    }
+
+    if args.core {
+        printer::print_core_file(mach_o.core.as_ref().ok_or_else(not_a_core_file)?);
+    }
+
+    if let Some(range) = &args.read_memory {
+        let (vmaddr, size) = parse_memory_range(range)?;
+        let mut file = input.reader()?;
+        let memory = mach_o.core.as_ref().ok_or_else(not_a_core_file)?.read_memory(&mut file, vmaddr, size)?;
+        printer::print_memory(vmaddr, &memory);
+    }
+
+    if args.split_info {
+        let mut file = input.reader()?;
+        let split_info = split_info::SplitSegInfo::from_file(&mut file, &mach_o)?.ok_or_else(|| AppError::from(io::Error::new(io::ErrorKind::InvalidInput, "no LC_SEGMENT_SPLIT_INFO load command")))?;
+        printer::print_split_info(&split_info);
+    }
+
+    if args.loh {
+        let mut file = input.reader()?;
+        let hints = loh::LinkerOptimizationHint::from_file(&mut file, &mach_o)?.ok_or_else(|| AppError::from(io::Error::new(io::ErrorKind::InvalidInput, "no LC_LINKER_OPTIMIZATION_HINT load command")))?;
+        printer::print_linker_optimization_hints(&hints, demangle);
+    }
+
+    if args.objc {
+        let mut file = input.reader()?;
+        let metadata = objc::ObjcMetadata::from_file(&mut file, &mach_o)?;
+        printer::print_objc(&metadata);
+    }
+
+    if args.objc_unused {
+        let mut file = input.reader()?;
+        let metadata = objc::ObjcMetadata::from_file(&mut file, &mach_o)?;
+        printer::print_objc_usage(&objc_usage::ObjcUsageReport::from_metadata(&metadata, &mach_o));
+    }
+
+    if args.class_dump {
+        let mut file = input.reader()?;
+        let metadata = objc::ObjcMetadata::from_file(&mut file, &mach_o)?;
+        print!("{}", class_dump::generate_headers(&metadata));
+    }
+
+    if args.swift {
+        let mut file = input.reader()?;
+        let metadata = swift::SwiftMetadata::from_file(&mut file, &mach_o)?;
+        printer::print_swift(&metadata, demangle);
+    }
+
+    if args.go {
+        let mut file = input.reader()?;
+        let metadata = go::GoMetadata::from_file(&mut file, &mach_o)?;
+        printer::print_go(&metadata);
+    }
+
+    if args.symbols {
+        printer::print_symbols(&mach_o, demangle);
+    }
+
+    if let Some(name) = &args.dump_section {
+        let (segname, sectname) = name.split_once(',').ok_or_else(|| AppError::from(io::Error::new(io::ErrorKind::InvalidInput, "expected section as SEGNAME,SECTNAME")))?;
+        let section = mach_o.section_by_name(segname, sectname).ok_or_else(|| AppError::from(io::Error::new(io::ErrorKind::InvalidInput, format!("no section {},{}", segname, sectname))))?;
+        let mut file = input.reader()?;
+        let data = mach_o.read_section_data(&mut file, section)?;
+        printer::print_memory(section.addr(), &data);
     }
 
     Ok(())
 }
 
-fn parse_from_file(path: &PathBuf) -> Result<MachO, AppError> {
-    let mut file = File::open(path)?;
-    let mach_o = parser::parse(&mut file)?;
-    Ok(mach_o)
+/// The file named by `--file`. Stdin can only be read once, so it is kept in memory and every
+/// analysis reads from the same buffer.
+enum Input {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
+}
+
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+impl Input {
+    fn open(path: &Path) -> Result<Input, AppError> {
+        if path.as_os_str() == "-" {
+            let mut bytes = Vec::new();
+            io::stdin().lock().read_to_end(&mut bytes)?;
+            return Ok(Input::Bytes(bytes));
+        }
+        Ok(Input::Path(path.to_path_buf()))
+    }
+
+    fn reader(&self) -> Result<Box<dyn ReadSeek + '_>, AppError> {
+        match self {
+            Input::Path(path) => Ok(Box::new(File::open(path)?)),
+            Input::Bytes(bytes) => Ok(Box::new(Cursor::new(bytes.as_slice()))),
+        }
+    }
+}
+
+fn report_error(error: &AppError) {
+    eprintln!("Error: {}", error);
+    let mut source = error.source();
+    while let Some(cause) = source {
+        eprintln!("  caused by: {}", cause);
+        source = cause.source();
+    }
+    if let AppError::BadMagic { magic: FAT_MAGIC | FAT_MAGIC_64 } = error {
+        eprintln!("  hint: this is a universal binary; extract one architecture with `lipo -thin <arch>` first");
+    }
+}
+
+fn not_a_core_file() -> AppError {
+    AppError::from(io::Error::new(io::ErrorKind::InvalidInput, "not an MH_CORE file"))
+}
+
+fn parse_memory_range(range: &str) -> Result<(u64, u64), AppError> {
+    let invalid_range = || AppError::from(io::Error::new(io::ErrorKind::InvalidInput, "expected memory range as VMADDR:SIZE"));
+    let (vmaddr, size) = range.split_once(':').ok_or_else(invalid_range)?;
+    let vmaddr = parse_number(vmaddr).ok_or_else(invalid_range)?;
+    let size = parse_number(size).ok_or_else(invalid_range)?;
+    Ok((vmaddr, size))
+}
+
+fn parse_number(number: &str) -> Option<u64> {
+    match number.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => number.parse().ok(),
+    }
 }
@@ -4,7 +4,84 @@ pub fn get_file_offset<R: Read + Seek>(file: &mut R) -> io::Result<u64> {
     file.stream_position()
 }
 
+/// Size of the whole file; the position is left where it was.
+pub fn get_file_size<R: Read + Seek>(file: &mut R) -> io::Result<u64> {
+    let position = file.stream_position()?;
+    let size = file.seek(SeekFrom::End(0))?;
+    file.seek(SeekFrom::Start(position))?;
+    Ok(size)
+}
+
 pub fn advance_to_next_load_command<R: Read + Seek>(file: &mut R, offset: u64, cmdsize: u64) -> io::Result<u64> {
     file.seek(SeekFrom::Start(offset + cmdsize))
 }
 
+pub fn read_bytes_at<R: Read + Seek>(file: &mut R, offset: u64, size: u64) -> io::Result<Vec<u8>> {
+    let bytes = read_bytes_up_to(file, offset, size)?;
+    if bytes.len() as u64 != size {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "data extends past the end of the file"));
+    }
+    Ok(bytes)
+}
+
+/// Like `read_bytes_at`, but returns whatever is left of the file if it ends early.
+pub fn read_bytes_up_to<R: Read + Seek>(file: &mut R, offset: u64, size: u64) -> io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.by_ref().take(size).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+pub fn read_c_string<R: Read>(file: &mut R) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    loop {
+        let mut byte = [0u8; 1];
+        if file.read(&mut byte)? == 0 || byte[0] == 0 {
+            break;
+        }
+        bytes.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+pub fn read_uleb128<R: Read>(file: &mut R) -> io::Result<u64> {
+    let mut result = 0u64;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        file.read_exact(&mut byte)?;
+        if shift >= 64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "uleb128 value is too large"));
+        }
+        result |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+    }
+}
+
+pub fn fixed_bytes_to_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+pub fn read_sleb128<R: Read>(file: &mut R) -> io::Result<i64> {
+    let mut result = 0i64;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8; 1];
+        file.read_exact(&mut byte)?;
+        if shift >= 64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "sleb128 value is too large"));
+        }
+        result |= ((byte[0] & 0x7f) as i64) << shift;
+        shift += 7;
+        if byte[0] & 0x80 == 0 {
+            if shift < 64 && byte[0] & 0x40 != 0 {
+                result |= -1i64 << shift;
+            }
+            return Ok(result);
+        }
+    }
+}
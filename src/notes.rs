@@ -0,0 +1,306 @@
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+use byteorder::ReadBytesExt;
+
+use crate::constants::*;
+use crate::error::AppError;
+use crate::load_commands::NoteCommand;
+use crate::memory_utils::*;
+
+#[derive(Debug)]
+pub struct Note {
+    pub data_owner: String,
+    pub offset: u64,
+    pub size: u64,
+    pub data: NoteData,
+}
+
+#[derive(Debug)]
+pub enum NoteData {
+    AddrableBits(AddrableBitsNote),
+    MainBinSpec(MainBinSpecNote),
+    LoadBinary(LoadBinaryNote),
+    AllImageInfos(AllImageInfosNote),
+    ProcessMetadata(String),
+    KernVerStr(KernVerStrNote),
+    Unknown(Vec<u8>),
+}
+
+impl Note {
+    pub fn from_file<R: Read + Seek, E: byteorder::ByteOrder>(file: &mut R, command: &NoteCommand) -> Result<Note, AppError> {
+        let data_owner = fixed_bytes_to_string(&command.data_owner);
+        let payload = read_bytes_at(file, command.offset, command.size).map_err(AppError::out_of_bounds("LC_NOTE payload", command.offset, command.size))?;
+        let data = decode_payload::<R, E>(file, &data_owner, payload).map_err(|error| match error {
+            AppError::Io(error) if error.kind() == io::ErrorKind::UnexpectedEof => AppError::from(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("\"{}\" note payload at 0x{:x} is too short (0x{:x} bytes)", data_owner, command.offset, command.size),
+            )),
+            error => error,
+        })?;
+
+        Ok(Note {
+            data_owner,
+            offset: command.offset,
+            size: command.size,
+            data,
+        })
+    }
+}
+
+fn decode_payload<R: Read + Seek, E: byteorder::ByteOrder>(file: &mut R, data_owner: &str, payload: Vec<u8>) -> Result<NoteData, AppError> {
+    let mut cursor = Cursor::new(payload.as_slice());
+    Ok(match data_owner {
+        NOTE_OWNER_ADDRABLE_BITS => NoteData::AddrableBits(AddrableBitsNote::from_file::<_, E>(&mut cursor)?),
+        NOTE_OWNER_MAIN_BIN_SPEC => NoteData::MainBinSpec(MainBinSpecNote::from_file::<_, E>(&mut cursor)?),
+        NOTE_OWNER_LOAD_BINARY => NoteData::LoadBinary(LoadBinaryNote::from_file::<_, E>(&mut cursor)?),
+        NOTE_OWNER_ALL_IMAGE_INFOS => NoteData::AllImageInfos(AllImageInfosNote::from_file::<_, R, E>(&mut cursor, file)?),
+        NOTE_OWNER_PROCESS_METADATA => NoteData::ProcessMetadata(String::from_utf8_lossy(&payload).trim_end_matches('\0').to_string()),
+        NOTE_OWNER_KERN_VER_STR => NoteData::KernVerStr(KernVerStrNote::from_file::<_, E>(&mut cursor)?),
+        _ => NoteData::Unknown(payload),
+    })
+}
+
+fn read_uuid<R: Read>(file: &mut R) -> Result<[u8; 16], AppError> {
+    let mut uuid = [0u8; 16];
+    file.read_exact(&mut uuid)?;
+    Ok(uuid)
+}
+
+#[derive(Debug)]
+pub struct AddrableBitsNote {
+    pub version: u32,
+    pub addressing_bits: u32,
+    pub lo_addressing_bits: Option<u32>,
+    pub hi_addressing_bits: Option<u32>,
+}
+
+impl AddrableBitsNote {
+    pub fn from_file<R: Read, E: byteorder::ByteOrder>(file: &mut R) -> Result<AddrableBitsNote, AppError> {
+        let version = file.read_u32::<E>()?;
+        let addressing_bits = file.read_u32::<E>()?;
+        // Version 3 is {version, addressing_bits, unused}; version 4 reuses the second and third
+        // words for the low and high memory addressing bits.
+        let (lo_addressing_bits, hi_addressing_bits) = if version >= 4 {
+            (Some(addressing_bits), Some(file.read_u32::<E>()?))
+        } else {
+            (None, None)
+        };
+        Ok(AddrableBitsNote {
+            version,
+            addressing_bits,
+            lo_addressing_bits,
+            hi_addressing_bits,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct MainBinSpecNote {
+    pub version: u32,
+    pub bin_type: u32,
+    pub address: u64,
+    pub slide: Option<u64>,
+    pub uuid: [u8; 16],
+    pub log2_pagesize: u32,
+    pub platform: Option<u32>,
+}
+
+impl MainBinSpecNote {
+    pub fn from_file<R: Read, E: byteorder::ByteOrder>(file: &mut R) -> Result<MainBinSpecNote, AppError> {
+        let version = file.read_u32::<E>()?;
+        let bin_type = file.read_u32::<E>()?;
+        let address = file.read_u64::<E>()?;
+        let slide = if version >= 2 { Some(file.read_u64::<E>()?) } else { None };
+        let uuid = read_uuid(file)?;
+        let log2_pagesize = file.read_u32::<E>()?;
+        let platform = if version >= 2 { Some(file.read_u32::<E>()?) } else { None };
+        Ok(MainBinSpecNote {
+            version,
+            bin_type,
+            address,
+            slide,
+            uuid,
+            log2_pagesize,
+            platform,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct LoadBinaryNote {
+    pub version: u32,
+    pub uuid: [u8; 16],
+    pub load_address: u64,
+    pub slide: u64,
+    pub name: String,
+}
+
+impl LoadBinaryNote {
+    pub fn from_file<R: Read, E: byteorder::ByteOrder>(file: &mut R) -> Result<LoadBinaryNote, AppError> {
+        let load_binary = LoadBinaryNote {
+            version: file.read_u32::<E>()?,
+            uuid: read_uuid(file)?,
+            load_address: file.read_u64::<E>()?,
+            slide: file.read_u64::<E>()?,
+            name: read_c_string(file)?,
+        };
+        Ok(load_binary)
+    }
+}
+
+#[derive(Debug)]
+pub struct AllImageInfosNote {
+    pub version: u32,
+    pub imgcount: u32,
+    pub entries_fileoff: u64,
+    pub entries_size: u32,
+    pub images: Vec<ImageEntry>,
+}
+
+impl AllImageInfosNote {
+    pub fn from_file<R: Read, F: Read + Seek, E: byteorder::ByteOrder>(payload: &mut R, file: &mut F) -> Result<AllImageInfosNote, AppError> {
+        let mut all_image_infos = AllImageInfosNote {
+            version: payload.read_u32::<E>()?,
+            imgcount: payload.read_u32::<E>()?,
+            entries_fileoff: payload.read_u64::<E>()?,
+            entries_size: payload.read_u32::<E>()?,
+            images: Vec::new(),
+        };
+
+        // The image entries, their paths and segment lists live elsewhere in the file.
+        if all_image_infos.imgcount != 0 {
+            if all_image_infos.entries_size < IMAGE_ENTRY_SIZE {
+                return Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("image entries of {} bytes are too small", all_image_infos.entries_size))));
+            }
+            let (offset, size) = (all_image_infos.entries_fileoff, all_image_infos.imgcount as u64 * all_image_infos.entries_size as u64);
+            if offset.saturating_add(size) > get_file_size(file)? {
+                return Err(AppError::OutOfBounds { what: "image entries", offset, size });
+            }
+        }
+        for index in 0..all_image_infos.imgcount as u64 {
+            file.seek(SeekFrom::Start(all_image_infos.entries_fileoff + index * all_image_infos.entries_size as u64))?;
+            let image = ImageEntry::from_file::<F, E>(file)?;
+            all_image_infos.images.push(image);
+        }
+
+        Ok(all_image_infos)
+    }
+}
+
+/// Size of the fields of an image entry.
+const IMAGE_ENTRY_SIZE: u32 = 48;
+
+#[derive(Debug)]
+pub struct ImageEntry {
+    pub filepath_offset: u64,
+    pub uuid: [u8; 16],
+    pub load_address: u64,
+    pub seg_addrs_offset: u64,
+    pub segment_count: u32,
+    pub filepath: String,
+    pub segments: Vec<SegmentVmaddr>,
+}
+
+impl ImageEntry {
+    pub fn from_file<R: Read + Seek, E: byteorder::ByteOrder>(file: &mut R) -> Result<ImageEntry, AppError> {
+        let mut image = ImageEntry {
+            filepath_offset: file.read_u64::<E>()?,
+            uuid: read_uuid(file)?,
+            load_address: file.read_u64::<E>()?,
+            seg_addrs_offset: file.read_u64::<E>()?,
+            segment_count: file.read_u32::<E>()?,
+            filepath: String::new(),
+            segments: Vec::new(),
+        };
+        file.read_u32::<E>()?; // unused
+
+        if image.filepath_offset != u64::MAX {
+            file.seek(SeekFrom::Start(image.filepath_offset))?;
+            image.filepath = read_c_string(file)?;
+        }
+
+        file.seek(SeekFrom::Start(image.seg_addrs_offset))?;
+        for _ in 0..image.segment_count {
+            let segment = SegmentVmaddr::from_file::<R, E>(file)?;
+            image.segments.push(segment);
+        }
+
+        Ok(image)
+    }
+}
+
+#[derive(Debug)]
+pub struct SegmentVmaddr {
+    pub segname: [u8; 16],
+    pub vmaddr: u64,
+}
+
+impl SegmentVmaddr {
+    pub fn from_file<R: Read, E: byteorder::ByteOrder>(file: &mut R) -> Result<SegmentVmaddr, AppError> {
+        let mut segname = [0u8; 16];
+        file.read_exact(&mut segname)?;
+        let segment = SegmentVmaddr {
+            segname,
+            vmaddr: file.read_u64::<E>()?,
+        };
+        file.read_u64::<E>()?; // unused
+        Ok(segment)
+    }
+}
+
+#[derive(Debug)]
+pub struct KernVerStrNote {
+    pub version: u32,
+    pub version_string: String,
+}
+
+impl KernVerStrNote {
+    pub fn from_file<R: Read, E: byteorder::ByteOrder>(file: &mut R) -> Result<KernVerStrNote, AppError> {
+        let kern_ver_str = KernVerStrNote {
+            version: file.read_u32::<E>()?,
+            version_string: read_c_string(file)?,
+        };
+        Ok(kern_ver_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::LittleEndian;
+
+    use super::*;
+    use crate::mach_o::MachO;
+    use crate::test_utils::*;
+
+    #[test]
+    fn addrable_bits_v3_has_a_single_field() {
+        let note = AddrableBitsNote::from_file::<_, LittleEndian>(&mut Cursor::new(words(&[3, 47, 0]))).unwrap();
+        assert_eq!(note.addressing_bits, 47);
+        assert_eq!(note.lo_addressing_bits, None);
+        assert_eq!(note.hi_addressing_bits, None);
+    }
+
+    #[test]
+    fn addrable_bits_v4_splits_low_and_high() {
+        let note = AddrableBitsNote::from_file::<_, LittleEndian>(&mut Cursor::new(words(&[4, 47, 55]))).unwrap();
+        assert_eq!(note.lo_addressing_bits, Some(47));
+        assert_eq!(note.hi_addressing_bits, Some(55));
+    }
+
+    fn note_command(data_owner: &str, offset: u64, size: u64) -> Vec<u8> {
+        let mut owner = [0u8; 16];
+        owner[..data_owner.len()].copy_from_slice(data_owner.as_bytes());
+        let owner: Vec<u32> = owner.chunks(4).map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap())).collect();
+        command(LC_NOTE, &[&owner[..], &[offset as u32, (offset >> 32) as u32, size as u32, (size >> 32) as u32]].concat())
+    }
+
+    #[test]
+    fn malformed_note_does_not_fail_a_strict_parse() {
+        let notes = [note_command(NOTE_OWNER_ADDRABLE_BITS, 0x100, 4), note_command("custom", 0x104, 4)];
+        let mach_o = MachO::from_bytes(&image(&notes, &[(0x100, &words(&[4, 0xdead_beef]))])).unwrap();
+        assert_eq!(mach_o.notes.len(), 1);
+        assert!(matches!(&mach_o.notes[0].data, NoteData::Unknown(payload) if payload == &0xdead_beefu32.to_le_bytes()));
+        assert_eq!(mach_o.diagnostics.len(), 1);
+        assert_eq!(mach_o.diagnostics[0].to_string(), "error: \"addrable bits\" note payload at 0x100 is too short (0x4 bytes)");
+    }
+}
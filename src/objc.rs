@@ -0,0 +1,594 @@
+use std::io::{self, Read, Seek};
+
+use byteorder::{BigEndian, LittleEndian};
+
+use crate::address_space::{AddressSpace, Pointer};
+use crate::constants::*;
+use crate::error::AppError;
+use crate::header::Header;
+use crate::load_commands::SectionLike;
+use crate::mach_o::MachO;
+use crate::memory_utils::fixed_bytes_to_string;
+
+#[derive(Debug)]
+pub struct ObjcMetadata {
+    pub image_info: Option<ObjcImageInfo>,
+    pub classes: Vec<ObjcClass>,
+    pub categories: Vec<ObjcCategory>,
+    pub protocols: Vec<ObjcProtocol>,
+    pub selector_refs: Vec<ObjcReference>,
+    pub class_refs: Vec<ObjcReference>,
+    pub super_refs: Vec<ObjcReference>,
+}
+
+#[derive(Debug)]
+pub struct ObjcImageInfo {
+    pub version: u32,
+    pub flags: u32,
+}
+
+impl ObjcImageInfo {
+    pub fn swift_version(&self) -> u32 {
+        (self.flags >> 8) & 0xff
+    }
+}
+
+#[derive(Debug)]
+pub struct ObjcClass {
+    pub address: u64,
+    pub name: String,
+    /// Name of the superclass, resolved through bind records when it lives in another image.
+    pub superclass: Option<String>,
+    pub flags: u32,
+    pub instance_start: u32,
+    pub instance_size: u32,
+    pub instance_methods: Vec<ObjcMethod>,
+    pub class_methods: Vec<ObjcMethod>,
+    pub ivars: Vec<ObjcIvar>,
+    pub properties: Vec<ObjcProperty>,
+    pub protocols: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ObjcCategory {
+    pub address: u64,
+    pub name: String,
+    pub class_name: Option<String>,
+    pub instance_methods: Vec<ObjcMethod>,
+    pub class_methods: Vec<ObjcMethod>,
+    pub properties: Vec<ObjcProperty>,
+    pub protocols: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ObjcProtocol {
+    pub address: u64,
+    pub name: String,
+    pub protocols: Vec<String>,
+    pub instance_methods: Vec<ObjcMethod>,
+    pub class_methods: Vec<ObjcMethod>,
+    pub optional_instance_methods: Vec<ObjcMethod>,
+    pub optional_class_methods: Vec<ObjcMethod>,
+    pub properties: Vec<ObjcProperty>,
+}
+
+#[derive(Debug)]
+pub struct ObjcMethod {
+    pub name: String,
+    pub types: String,
+    pub implementation: u64,
+    /// Size of the method_t entry, which is smaller for relative method lists.
+    pub entsize: u32,
+}
+
+#[derive(Debug)]
+pub struct ObjcIvar {
+    pub name: String,
+    pub type_encoding: String,
+    /// Value of the ivar offset variable, if the image defines one.
+    pub offset: Option<u32>,
+    pub size: u32,
+    pub alignment: u32,
+}
+
+#[derive(Debug)]
+pub struct ObjcProperty {
+    pub name: String,
+    pub attributes: String,
+}
+
+#[derive(Debug)]
+pub struct ObjcReference {
+    pub address: u64,
+    pub name: Option<String>,
+}
+
+// class_ro_t fields we care about
+struct ClassRo {
+    flags: u32,
+    instance_start: u32,
+    instance_size: u32,
+    name: String,
+    base_methods: u64,
+    base_protocols: u64,
+    ivars: u64,
+    base_properties: u64,
+}
+
+impl ObjcMetadata {
+    pub fn from_file<R: Read + Seek>(file: &mut R, mach_o: &MachO) -> Result<ObjcMetadata, AppError> {
+        match mach_o.header.magic() {
+            MH_MAGIC | MH_MAGIC_64 => ObjcReader::<R, BigEndian>::new(file, mach_o).parse(),
+            _ => ObjcReader::<R, LittleEndian>::new(file, mach_o).parse(),
+        }
+    }
+}
+
+struct ObjcReader<'a, R: Read + Seek, E: byteorder::ByteOrder> {
+    space: AddressSpace<'a, R, E>,
+    // (sectname, addr, size) of every section in the image
+    sections: Vec<(String, u64, u64)>,
+}
+
+impl<'a, R: Read + Seek, E: byteorder::ByteOrder> ObjcReader<'a, R, E> {
+    fn new(file: &'a mut R, mach_o: &'a MachO) -> ObjcReader<'a, R, E> {
+        let sections = mach_o.sections()
+            .map(|section| (fixed_bytes_to_string(section.sectname()), section.addr(), section.size()))
+            .collect();
+        ObjcReader {
+            space: AddressSpace::new(file, mach_o),
+            sections,
+        }
+    }
+
+    fn parse(&mut self) -> Result<ObjcMetadata, AppError> {
+        let image_info = match self.section_addresses("__objc_imageinfo").first() {
+            Some(&address) => Some(ObjcImageInfo {
+                version: self.space.read_u32(address)?,
+                flags: self.space.read_u32(address.wrapping_add(4))?,
+            }),
+            None => None,
+        };
+
+        let mut metadata = ObjcMetadata {
+            image_info,
+            classes: Vec::new(),
+            categories: Vec::new(),
+            protocols: Vec::new(),
+            selector_refs: Vec::new(),
+            class_refs: Vec::new(),
+            super_refs: Vec::new(),
+        };
+
+        for slot in self.pointer_slots("__objc_classlist")? {
+            let class = self.space.read_address(slot)?;
+            metadata.classes.push(self.read_class(class)?);
+        }
+        for slot in self.pointer_slots("__objc_catlist")? {
+            let category = self.space.read_address(slot)?;
+            metadata.categories.push(self.read_category(category)?);
+        }
+        for slot in self.pointer_slots("__objc_protolist")? {
+            let protocol = self.space.read_address(slot)?;
+            metadata.protocols.push(self.read_protocol(protocol)?);
+        }
+        for slot in self.pointer_slots("__objc_selrefs")? {
+            let selector = self.space.read_address(slot)?;
+            let name = if self.space.is_mapped(selector) { Some(self.space.read_c_string(selector)?) } else { None };
+            metadata.selector_refs.push(ObjcReference { address: slot, name });
+        }
+        for slot in self.pointer_slots("__objc_classrefs")? {
+            let class = self.space.read_pointer(slot)?;
+            let name = self.class_name(class)?;
+            metadata.class_refs.push(ObjcReference { address: slot, name });
+        }
+        for slot in self.pointer_slots("__objc_superrefs")? {
+            let class = self.space.read_pointer(slot)?;
+            let name = self.class_name(class)?;
+            metadata.super_refs.push(ObjcReference { address: slot, name });
+        }
+
+        Ok(metadata)
+    }
+
+    fn section_addresses(&self, sectname: &str) -> Vec<u64> {
+        self.sections.iter().filter(|(name, _, _)| name == sectname).map(|(_, addr, _)| *addr).collect()
+    }
+
+    /// Addresses of the pointer-sized slots of every section named `sectname`. The sections have
+    /// to be backed by the file, which also keeps a corrupt section size from running away.
+    fn pointer_slots(&self, sectname: &str) -> Result<Vec<u64>, AppError> {
+        let pointer_size = self.space.pointer_size();
+        let mut slots = Vec::new();
+        for (_, addr, size) in self.sections.iter().filter(|(name, _, _)| name == sectname) {
+            self.space.check_range(*addr, *size)?;
+            slots.extend((0..size / pointer_size).map(|index| addr + index * pointer_size));
+        }
+        Ok(slots)
+    }
+
+    fn read_c_string_at_pointer(&mut self, address: u64) -> Result<String, AppError> {
+        let string = self.space.read_address(address)?;
+        if string == 0 {
+            return Ok(String::new());
+        }
+        self.space.read_c_string(string)
+    }
+
+    // class_t :== isa, superclass, cache, vtable, data
+    fn read_class(&mut self, address: u64) -> Result<ObjcClass, AppError> {
+        let pointer_size = self.space.pointer_size();
+        let metaclass = self.space.read_address(address)?;
+        let superclass = self.space.read_pointer(address.wrapping_add(pointer_size))?;
+        let superclass = self.class_name(superclass)?;
+        let ro = self.read_class_ro(address)?;
+
+        let class_methods = if metaclass != 0 && self.space.is_mapped(metaclass) {
+            let meta_ro = self.read_class_ro(metaclass)?;
+            self.read_method_list(meta_ro.base_methods)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(ObjcClass {
+            address,
+            name: ro.name,
+            superclass,
+            flags: ro.flags,
+            instance_start: ro.instance_start,
+            instance_size: ro.instance_size,
+            instance_methods: self.read_method_list(ro.base_methods)?,
+            class_methods,
+            ivars: self.read_ivar_list(ro.ivars)?,
+            properties: self.read_property_list(ro.base_properties)?,
+            protocols: self.read_protocol_names(ro.base_protocols)?,
+        })
+    }
+
+    fn read_class_ro(&mut self, class: u64) -> Result<ClassRo, AppError> {
+        let pointer_size = self.space.pointer_size();
+        // The low bits of the data pointer carry Swift and runtime flags.
+        let data_mask = if pointer_size == 8 { OBJC_CLASS_DATA_MASK_64 } else { OBJC_CLASS_DATA_MASK_32 };
+        let ro = self.space.read_address(class.wrapping_add(4 * pointer_size))? & data_mask;
+
+        let flags = self.space.read_u32(ro)?;
+        let instance_start = self.space.read_u32(ro.wrapping_add(4))?;
+        let instance_size = self.space.read_u32(ro.wrapping_add(8))?;
+        // 64-bit images pad instanceSize with a reserved field before the pointers.
+        let pointers = if pointer_size == 8 { ro.wrapping_add(16) } else { ro.wrapping_add(12) };
+        // ivarLayout, name, baseMethods, baseProtocols, ivars, weakIvarLayout, baseProperties
+        Ok(ClassRo {
+            flags,
+            instance_start,
+            instance_size,
+            name: self.read_c_string_at_pointer(pointers.wrapping_add(pointer_size))?,
+            base_methods: self.space.read_address(pointers.wrapping_add(2 * pointer_size))?,
+            base_protocols: self.space.read_address(pointers.wrapping_add(3 * pointer_size))?,
+            ivars: self.space.read_address(pointers.wrapping_add(4 * pointer_size))?,
+            base_properties: self.space.read_address(pointers.wrapping_add(6 * pointer_size))?,
+        })
+    }
+
+    /// Resolves a class pointer to its name, either from an imported `_OBJC_CLASS_$_` symbol or
+    /// from the class_ro_t of a class defined in this image.
+    fn class_name(&mut self, class: Pointer) -> Result<Option<String>, AppError> {
+        match class {
+            Pointer::Import(symbol_name) => {
+                Ok(Some(symbol_name.strip_prefix(OBJC_CLASS_SYMBOL_PREFIX).unwrap_or(&symbol_name).to_string()))
+            }
+            Pointer::Address(address) if address != 0 && self.space.is_mapped(address) => {
+                Ok(Some(self.read_class_ro(address)?.name))
+            }
+            Pointer::Address(_) => Ok(None),
+        }
+    }
+
+    // category_t :== name, cls, instanceMethods, classMethods, protocols, instanceProperties
+    fn read_category(&mut self, address: u64) -> Result<ObjcCategory, AppError> {
+        let pointer_size = self.space.pointer_size();
+        let class = self.space.read_pointer(address.wrapping_add(pointer_size))?;
+        let instance_methods = self.space.read_address(address.wrapping_add(2 * pointer_size))?;
+        let class_methods = self.space.read_address(address.wrapping_add(3 * pointer_size))?;
+        let protocols = self.space.read_address(address.wrapping_add(4 * pointer_size))?;
+        let properties = self.space.read_address(address.wrapping_add(5 * pointer_size))?;
+
+        Ok(ObjcCategory {
+            address,
+            name: self.read_c_string_at_pointer(address)?,
+            class_name: self.class_name(class)?,
+            instance_methods: self.read_method_list(instance_methods)?,
+            class_methods: self.read_method_list(class_methods)?,
+            properties: self.read_property_list(properties)?,
+            protocols: self.read_protocol_names(protocols)?,
+        })
+    }
+
+    // protocol_t :== isa, name, protocols, instanceMethods, classMethods, optionalInstanceMethods,
+    //                optionalClassMethods, instanceProperties, size, flags, extendedMethodTypes, ...
+    fn read_protocol(&mut self, address: u64) -> Result<ObjcProtocol, AppError> {
+        let pointer_size = self.space.pointer_size();
+        let protocols = self.space.read_address(address.wrapping_add(2 * pointer_size))?;
+        let instance_methods = self.space.read_address(address.wrapping_add(3 * pointer_size))?;
+        let class_methods = self.space.read_address(address.wrapping_add(4 * pointer_size))?;
+        let optional_instance_methods = self.space.read_address(address.wrapping_add(5 * pointer_size))?;
+        let optional_class_methods = self.space.read_address(address.wrapping_add(6 * pointer_size))?;
+        let properties = self.space.read_address(address.wrapping_add(7 * pointer_size))?;
+
+        let mut protocol = ObjcProtocol {
+            address,
+            name: self.read_c_string_at_pointer(address.wrapping_add(pointer_size))?,
+            protocols: self.read_protocol_names(protocols)?,
+            instance_methods: self.read_method_list(instance_methods)?,
+            class_methods: self.read_method_list(class_methods)?,
+            optional_instance_methods: self.read_method_list(optional_instance_methods)?,
+            optional_class_methods: self.read_method_list(optional_class_methods)?,
+            properties: self.read_property_list(properties)?,
+        };
+
+        // Newer protocols carry extended method types that spell out the classes of object
+        // arguments and block signatures, one per method in list order.
+        let size = self.space.read_u32(address.wrapping_add(8 * pointer_size))? as u64;
+        if size >= 9 * pointer_size + 8 {
+            let extended_method_types = self.space.read_address(address.wrapping_add(8 * pointer_size + 8))?;
+            if extended_method_types != 0 {
+                let methods = protocol.instance_methods.iter_mut()
+                    .chain(protocol.class_methods.iter_mut())
+                    .chain(protocol.optional_instance_methods.iter_mut())
+                    .chain(protocol.optional_class_methods.iter_mut());
+                for (index, method) in methods.enumerate() {
+                    method.types = self.read_c_string_at_pointer(extended_method_types.wrapping_add(index as u64 * pointer_size))?;
+                }
+            }
+        }
+
+        Ok(protocol)
+    }
+
+    // method_list_t :== entsizeAndFlags, count, method_t{count}
+    fn read_method_list(&mut self, list: u64) -> Result<Vec<ObjcMethod>, AppError> {
+        let mut methods = Vec::new();
+        if list == 0 {
+            return Ok(methods);
+        }
+        let entsize_and_flags = self.space.read_u32(list)?;
+        let count = self.space.read_u32(list.wrapping_add(4))?;
+        let entsize = (entsize_and_flags & !OBJC_METHOD_LIST_FLAGS_MASK) as u64;
+        let pointer_size = self.space.pointer_size();
+        let method_size = if entsize_and_flags & OBJC_METHOD_LIST_IS_RELATIVE != 0 { 12 } else { 3 * pointer_size };
+        self.check_list(list, count as u64, entsize, method_size)?;
+
+        for index in 0..count as u64 {
+            let entry = list + 8 + index * entsize;
+            let method = if entsize_and_flags & OBJC_METHOD_LIST_IS_RELATIVE != 0 {
+                // Relative method lists store signed 32-bit offsets from each field to its target,
+                // and the name points at a selector reference rather than the string itself.
+                let name = entry.wrapping_add_signed(self.space.read_i32(entry)? as i64);
+                let name = if entsize_and_flags & OBJC_METHOD_LIST_USES_SELECTOR_OFFSETS != 0 {
+                    // Offsets into the shared cache selector table cannot be resolved from a lone image.
+                    format!("<selector offset 0x{:x}>", name.wrapping_sub(entry))
+                } else {
+                    self.read_c_string_at_pointer(name)?
+                };
+                let types = entry.wrapping_add(4).wrapping_add_signed(self.space.read_i32(entry.wrapping_add(4))? as i64);
+                let implementation_offset = self.space.read_i32(entry.wrapping_add(8))?;
+                ObjcMethod {
+                    name,
+                    types: self.space.read_c_string(types)?,
+                    implementation: if implementation_offset == 0 { 0 } else { entry.wrapping_add(8).wrapping_add_signed(implementation_offset as i64) },
+                    entsize: entsize as u32,
+                }
+            } else {
+                ObjcMethod {
+                    name: self.read_c_string_at_pointer(entry)?,
+                    types: self.read_c_string_at_pointer(entry.wrapping_add(pointer_size))?,
+                    implementation: self.space.read_address(entry.wrapping_add(2 * pointer_size))?,
+                    entsize: entsize as u32,
+                }
+            };
+            methods.push(method);
+        }
+        Ok(methods)
+    }
+
+    // ivar_list_t :== entsize, count, ivar_t{count}
+    // ivar_t :== offset, name, type, alignment_raw, size
+    fn read_ivar_list(&mut self, list: u64) -> Result<Vec<ObjcIvar>, AppError> {
+        let mut ivars = Vec::new();
+        if list == 0 {
+            return Ok(ivars);
+        }
+        let entsize = self.space.read_u32(list)? as u64;
+        let count = self.space.read_u32(list.wrapping_add(4))?;
+        let pointer_size = self.space.pointer_size();
+        self.check_list(list, count as u64, entsize, 3 * pointer_size + 8)?;
+
+        for index in 0..count as u64 {
+            let entry = list + 8 + index * entsize;
+            let offset = self.space.read_address(entry)?;
+            let offset = if offset != 0 && self.space.is_mapped(offset) { Some(self.space.read_u32(offset)?) } else { None };
+            let alignment_raw = self.space.read_u32(entry.wrapping_add(3 * pointer_size))?;
+            ivars.push(ObjcIvar {
+                name: self.read_c_string_at_pointer(entry.wrapping_add(pointer_size))?,
+                type_encoding: self.read_c_string_at_pointer(entry.wrapping_add(2 * pointer_size))?,
+                offset,
+                size: self.space.read_u32(entry.wrapping_add(3 * pointer_size + 4))?,
+                // alignment_raw holds log2 of the alignment, with all bits set meaning pointer alignment.
+                alignment: if alignment_raw == u32::MAX { pointer_size as u32 } else { 1u32.checked_shl(alignment_raw).unwrap_or(0) },
+            });
+        }
+        Ok(ivars)
+    }
+
+    // property_list_t :== entsize, count, property_t{count}
+    fn read_property_list(&mut self, list: u64) -> Result<Vec<ObjcProperty>, AppError> {
+        let mut properties = Vec::new();
+        if list == 0 {
+            return Ok(properties);
+        }
+        let entsize = self.space.read_u32(list)? as u64;
+        let count = self.space.read_u32(list.wrapping_add(4))?;
+        let pointer_size = self.space.pointer_size();
+        self.check_list(list, count as u64, entsize, 2 * pointer_size)?;
+
+        for index in 0..count as u64 {
+            let entry = list + 8 + index * entsize;
+            properties.push(ObjcProperty {
+                name: self.read_c_string_at_pointer(entry)?,
+                attributes: self.read_c_string_at_pointer(entry.wrapping_add(pointer_size))?,
+            });
+        }
+        Ok(properties)
+    }
+
+    /// Checks the entries of an entsize/count list header at `list` before they are read, so that
+    /// a corrupt count cannot keep the list readers going for billions of iterations.
+    fn check_list(&self, list: u64, count: u64, entsize: u64, min_entsize: u64) -> Result<(), AppError> {
+        if entsize < min_entsize {
+            return Err(invalid_list(list));
+        }
+        let size = count.checked_mul(entsize).and_then(|size| size.checked_add(8)).ok_or_else(|| invalid_list(list))?;
+        self.space.check_range(list, size)
+    }
+
+    // protocol_list_t :== count (pointer sized), protocol_t *{count}
+    fn read_protocol_names(&mut self, list: u64) -> Result<Vec<String>, AppError> {
+        let mut names = Vec::new();
+        if list == 0 {
+            return Ok(names);
+        }
+        let pointer_size = self.space.pointer_size();
+        let count = self.space.read_word(list)?;
+        let size = count.checked_add(1).and_then(|words| words.checked_mul(pointer_size)).ok_or_else(|| invalid_list(list))?;
+        self.space.check_range(list, size)?;
+
+        for index in 0..count {
+            let name = match self.space.read_pointer(list.wrapping_add((index + 1) * pointer_size))? {
+                Pointer::Import(symbol_name) => symbol_name,
+                Pointer::Address(protocol) => self.read_c_string_at_pointer(protocol.wrapping_add(pointer_size))?,
+            };
+            names.push(name);
+        }
+        Ok(names)
+    }
+}
+
+fn invalid_list(list: u64) -> AppError {
+    AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("list at 0x{:x} has an invalid entry size or count", list)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn method_list(data: &mut SegmentData, methods: &[(&str, &str, u64)]) -> u64 {
+        let mut entries = vec![24 | ((methods.len() as u64) << 32)];
+        for &(name, types, implementation) in methods {
+            entries.extend([data.string(name), data.string(types), implementation]);
+        }
+        data.pointers(&entries)
+    }
+
+    // class_ro_t followed by its class_t, whose isa and superclass are filled in by the caller
+    fn class(data: &mut SegmentData, name: &str, methods: u64, protocols: u64, ivars: u64, properties: u64) -> u64 {
+        let name = data.string(name);
+        let ro = data.pointers(&[8 << 32, 24, 0, name, methods, protocols, ivars, 0, properties]);
+        data.pointers(&[0, 0, 0, 0, ro])
+    }
+
+    fn parse(data: &SegmentData, sections: &[(&str, u64, u64)]) -> Result<ObjcMetadata, AppError> {
+        let image = data_image(data, sections);
+        let mach_o = MachO::from_bytes(&image).unwrap();
+        ObjcMetadata::from_file(&mut Cursor::new(&image), &mach_o)
+    }
+
+    #[test]
+    fn decodes_classes_and_categories() {
+        let mut data = SegmentData::new();
+        let protocol_name = data.string("NSCopying");
+        let protocol = data.pointers(&[0, protocol_name, 0, 0, 0, 0, 0, 0, 72]);
+        let protocols = data.pointers(&[1, protocol]);
+        let ivar_offset = data.push(&words(&[8]));
+        let (ivar_name, ivar_type) = (data.string("_value"), data.string("@\"NSString\""));
+        let ivars = data.pointers(&[32 | 1 << 32, ivar_offset, ivar_name, ivar_type, 3 | 8 << 32]);
+        let (property_name, attributes) = (data.string("value"), data.string("T@\"NSString\",C,N,V_value"));
+        let properties = data.pointers(&[16 | 1 << 32, property_name, attributes]);
+        let methods = method_list(&mut data, &[("value", "@16@0:8", 0x1_0000_1000), ("setValue:", "v24@0:8@16", 0x1_0000_1010)]);
+        let class_methods = method_list(&mut data, &[("sharedWidget", "@16@0:8", 0x1_0000_1020)]);
+
+        let metaclass = class(&mut data, "Widget", class_methods, 0, 0, 0);
+        let widget = class(&mut data, "Widget", methods, protocols, ivars, properties);
+        data.write(widget, &metaclass.to_le_bytes());
+        let category_name = data.string("Extras");
+        let category_methods = method_list(&mut data, &[("extra", "v16@0:8", 0x1_0000_1030)]);
+        let category = data.pointers(&[category_name, widget, category_methods, 0, 0, 0]);
+
+        let selector = data.string("value");
+        let lists = [
+            ("__objc_classlist", data.pointers(&[widget]), 8),
+            ("__objc_catlist", data.pointers(&[category]), 8),
+            ("__objc_protolist", data.pointers(&[protocol]), 8),
+            ("__objc_selrefs", data.pointers(&[selector]), 8),
+            ("__objc_classrefs", data.pointers(&[widget]), 8),
+        ];
+        let metadata = parse(&data, &lists).unwrap();
+
+        let class = &metadata.classes[0];
+        assert_eq!((class.name.as_str(), class.superclass.as_deref(), class.instance_size), ("Widget", None, 24));
+        let methods: Vec<(&str, &str, u64)> = class.instance_methods.iter().map(|method| (method.name.as_str(), method.types.as_str(), method.implementation)).collect();
+        assert_eq!(methods, [("value", "@16@0:8", 0x1_0000_1000), ("setValue:", "v24@0:8@16", 0x1_0000_1010)]);
+        assert_eq!(class.class_methods[0].name, "sharedWidget");
+        let ivar = &class.ivars[0];
+        assert_eq!((ivar.name.as_str(), ivar.type_encoding.as_str(), ivar.offset, ivar.size, ivar.alignment), ("_value", "@\"NSString\"", Some(8), 8, 8));
+        assert_eq!((class.properties[0].name.as_str(), class.properties[0].attributes.as_str()), ("value", "T@\"NSString\",C,N,V_value"));
+        assert_eq!(class.protocols, ["NSCopying"]);
+
+        let category = &metadata.categories[0];
+        assert_eq!((category.name.as_str(), category.class_name.as_deref()), ("Extras", Some("Widget")));
+        assert_eq!(category.instance_methods[0].name, "extra");
+        assert_eq!(metadata.protocols[0].name, "NSCopying");
+        assert_eq!(metadata.selector_refs[0].name.as_deref(), Some("value"));
+        assert_eq!(metadata.class_refs[0].name.as_deref(), Some("Widget"));
+    }
+
+    #[test]
+    fn class_that_is_its_own_superclass_and_metaclass() {
+        let mut data = SegmentData::new();
+        let methods = method_list(&mut data, &[("spin", "v16@0:8", 0)]);
+        let class = class(&mut data, "Loop", methods, 0, 0, 0);
+        data.write(class, &[class.to_le_bytes(), class.to_le_bytes()].concat());
+        let classlist = data.pointers(&[class]);
+
+        let metadata = parse(&data, &[("__objc_classlist", classlist, 8)]).unwrap();
+        assert_eq!(metadata.classes[0].superclass.as_deref(), Some("Loop"));
+        assert_eq!(metadata.classes[0].class_methods[0].name, "spin");
+    }
+
+    #[test]
+    fn truncated_pointers_are_errors() {
+        let mut data = SegmentData::new();
+        let unmapped = data.pointers(&[0xdead_0000]);
+        assert!(parse(&data, &[("__objc_classlist", unmapped, 8)]).is_err());
+
+        // A method count that runs far past the end of the segment.
+        let mut data = SegmentData::new();
+        let methods = data.pointers(&[24 | 0xffff_ffff << 32]);
+        let class = class(&mut data, "Huge", methods, 0, 0, 0);
+        let classlist = data.pointers(&[class]);
+        let error = parse(&data, &[("__objc_classlist", classlist, 8)]).unwrap_err();
+        assert!(error.to_string().contains("run past the end of the segment"));
+
+        // A class list section larger than the file.
+        let mut data = SegmentData::new();
+        let classlist = data.pointers(&[0]);
+        assert!(parse(&data, &[("__objc_classlist", classlist, u64::MAX)]).is_err());
+
+        // A data pointer at the top of the address space.
+        let mut data = SegmentData::new();
+        let class = data.pointers(&[0, 0, 0, 0, u64::MAX - 3]);
+        let classlist = data.pointers(&[class]);
+        assert!(parse(&data, &[("__objc_classlist", classlist, 8)]).is_err());
+    }
+}
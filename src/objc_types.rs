@@ -0,0 +1,351 @@
+/// Deepest nesting of pointers, qualifiers, arrays and aggregates decoded before giving up.
+const MAX_NESTING: usize = 256;
+
+/// A decoded Objective-C type encoding, as produced by `@encode` and stored in method, ivar and
+/// property metadata.
+#[derive(Debug)]
+pub enum ObjcType {
+    Primitive(&'static str),
+    /// `id`, optionally with a class name and adopted protocols.
+    Object(Option<String>, Vec<String>),
+    Class,
+    Selector,
+    CString,
+    Block,
+    FunctionPointer,
+    Pointer(Box<ObjcType>),
+    Array(u64, Box<ObjcType>),
+    Struct(Option<String>, Vec<ObjcField>),
+    Union(Option<String>, Vec<ObjcField>),
+    Bitfield(u64),
+    Complex(Box<ObjcType>),
+    Qualified(&'static str, Box<ObjcType>),
+}
+
+#[derive(Debug)]
+pub struct ObjcField {
+    pub name: Option<String>,
+    pub field_type: ObjcType,
+}
+
+struct EncodingParser<'a> {
+    encoding: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+/// Decodes a single type, e.g. an ivar type or the `T` attribute of a property.
+pub fn parse_type(encoding: &str) -> Option<ObjcType> {
+    let mut parser = EncodingParser { encoding: encoding.as_bytes(), position: 0, depth: 0 };
+    parser.parse_type()
+}
+
+/// Decodes a method signature into its return type followed by the argument types, skipping
+/// the stack offsets that follow each type.
+pub fn parse_method_types(encoding: &str) -> Option<Vec<ObjcType>> {
+    let mut parser = EncodingParser { encoding: encoding.as_bytes(), position: 0, depth: 0 };
+    let mut types = Vec::new();
+    while parser.peek().is_some() {
+        types.push(parser.parse_type()?);
+        parser.skip_number();
+    }
+    Some(types)
+}
+
+impl<'a> EncodingParser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.encoding.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn skip_number(&mut self) {
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.position += 1;
+        }
+    }
+
+    fn parse_number(&mut self) -> Option<u64> {
+        let start = self.position;
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.encoding[start..self.position]).ok()?.parse().ok()
+    }
+
+    fn read_until(&mut self, terminators: &[u8]) -> Option<String> {
+        let start = self.position;
+        while !terminators.contains(&self.peek()?) {
+            self.position += 1;
+        }
+        Some(String::from_utf8_lossy(&self.encoding[start..self.position]).into_owned())
+    }
+
+    fn parse_type(&mut self) -> Option<ObjcType> {
+        // The encodings come from the file, so their nesting has to be bounded to keep the
+        // recursion from overflowing the stack.
+        if self.depth >= MAX_NESTING {
+            return None;
+        }
+        self.depth += 1;
+        let objc_type = self.parse_nested_type();
+        self.depth -= 1;
+        objc_type
+    }
+
+    fn parse_nested_type(&mut self) -> Option<ObjcType> {
+        let objc_type = match self.next()? {
+            b'r' => ObjcType::Qualified("const", Box::new(self.parse_type()?)),
+            b'n' => ObjcType::Qualified("in", Box::new(self.parse_type()?)),
+            b'N' => ObjcType::Qualified("inout", Box::new(self.parse_type()?)),
+            b'o' => ObjcType::Qualified("out", Box::new(self.parse_type()?)),
+            b'O' => ObjcType::Qualified("bycopy", Box::new(self.parse_type()?)),
+            b'R' => ObjcType::Qualified("byref", Box::new(self.parse_type()?)),
+            b'V' => ObjcType::Qualified("oneway", Box::new(self.parse_type()?)),
+            b'A' => ObjcType::Qualified("_Atomic", Box::new(self.parse_type()?)),
+            b'c' => ObjcType::Primitive("char"),
+            b'i' => ObjcType::Primitive("int"),
+            b's' => ObjcType::Primitive("short"),
+            b'l' => ObjcType::Primitive("long"),
+            b'q' => ObjcType::Primitive("long long"),
+            b'C' => ObjcType::Primitive("unsigned char"),
+            b'I' => ObjcType::Primitive("unsigned int"),
+            b'S' => ObjcType::Primitive("unsigned short"),
+            b'L' => ObjcType::Primitive("unsigned long"),
+            b'Q' => ObjcType::Primitive("unsigned long long"),
+            b't' => ObjcType::Primitive("__int128"),
+            b'T' => ObjcType::Primitive("unsigned __int128"),
+            b'f' => ObjcType::Primitive("float"),
+            b'd' => ObjcType::Primitive("double"),
+            b'D' => ObjcType::Primitive("long double"),
+            b'B' => ObjcType::Primitive("_Bool"),
+            b'v' => ObjcType::Primitive("void"),
+            b'*' => ObjcType::CString,
+            b'#' => ObjcType::Class,
+            b':' => ObjcType::Selector,
+            b'?' => ObjcType::FunctionPointer,
+            b'@' => self.parse_object()?,
+            b'^' => {
+                if self.peek() == Some(b'?') {
+                    self.position += 1;
+                    ObjcType::FunctionPointer
+                } else {
+                    ObjcType::Pointer(Box::new(self.parse_type()?))
+                }
+            }
+            b'[' => {
+                let count = self.parse_number()?;
+                let element_type = self.parse_type()?;
+                if self.next()? != b']' {
+                    return None;
+                }
+                ObjcType::Array(count, Box::new(element_type))
+            }
+            b'{' => {
+                let (name, fields) = self.parse_aggregate(b'}')?;
+                ObjcType::Struct(name, fields)
+            }
+            b'(' => {
+                let (name, fields) = self.parse_aggregate(b')')?;
+                ObjcType::Union(name, fields)
+            }
+            b'b' => ObjcType::Bitfield(self.parse_number()?),
+            b'j' => ObjcType::Complex(Box::new(self.parse_type()?)),
+            _ => return None,
+        };
+        Some(objc_type)
+    }
+
+    // @ | @? | @?<signature> | @"ClassName<Protocol>..."
+    fn parse_object(&mut self) -> Option<ObjcType> {
+        match self.peek() {
+            Some(b'?') => {
+                self.position += 1;
+                // Extended block signatures carry the block's own method types in angle brackets.
+                if self.peek() == Some(b'<') {
+                    let mut depth = 0;
+                    loop {
+                        match self.next()? {
+                            b'<' => depth += 1,
+                            b'>' => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                Some(ObjcType::Block)
+            }
+            Some(b'"') => {
+                // Inside a struct a bare `@` may be followed by the quoted name of the next field
+                // instead of a class name; a class name is always followed by another field name,
+                // the end of the aggregate or a stack offset.
+                let start = self.position;
+                self.position += 1;
+                let name = self.read_until(b"\"")?;
+                self.position += 1;
+                match self.peek() {
+                    None | Some(b'"' | b'}' | b')' | b'>' | b'0'..=b'9') => {
+                        let (class_name, protocols) = split_class_and_protocols(&name);
+                        Some(ObjcType::Object(class_name, protocols))
+                    }
+                    _ => {
+                        self.position = start;
+                        Some(ObjcType::Object(None, Vec::new()))
+                    }
+                }
+            }
+            _ => Some(ObjcType::Object(None, Vec::new())),
+        }
+    }
+
+    // {name=fields} where fields may be prefixed with "field name"
+    fn parse_aggregate(&mut self, terminator: u8) -> Option<(Option<String>, Vec<ObjcField>)> {
+        let name = self.read_until(&[b'=', terminator])?;
+        let name = if name.is_empty() || name == "?" { None } else { Some(name) };
+        let mut fields = Vec::new();
+        if self.next()? == b'=' {
+            while self.peek()? != terminator {
+                let field_name = if self.peek() == Some(b'"') {
+                    self.position += 1;
+                    let field_name = self.read_until(b"\"")?;
+                    self.position += 1;
+                    Some(field_name)
+                } else {
+                    None
+                };
+                fields.push(ObjcField { name: field_name, field_type: self.parse_type()? });
+            }
+            self.position += 1;
+        }
+        Some((name, fields))
+    }
+}
+
+fn split_class_and_protocols(name: &str) -> (Option<String>, Vec<String>) {
+    let (class_name, protocols) = match name.find('<') {
+        Some(index) => (&name[..index], &name[index..]),
+        None => (name, ""),
+    };
+    let protocols = protocols.split(['<', '>']).filter(|protocol| !protocol.is_empty()).map(String::from).collect();
+    let class_name = if class_name.is_empty() { None } else { Some(class_name.to_string()) };
+    (class_name, protocols)
+}
+
+impl ObjcType {
+    /// Spells the type as a C declaration of `name`, e.g. `char _buffer[16]` or `NSString *_title`.
+    /// With an empty name this is the bare type as used in a method signature.
+    pub fn declaration(&self, name: &str) -> String {
+        match self {
+            ObjcType::Primitive(primitive) => join_declaration(primitive, name),
+            ObjcType::Object(None, protocols) => join_declaration(&format!("id{}", format_protocols(protocols)), name),
+            ObjcType::Object(Some(class_name), protocols) => join_declaration(&format!("{}{}", class_name, format_protocols(protocols)), &format!("*{}", name)),
+            ObjcType::Class => join_declaration("Class", name),
+            ObjcType::Selector => join_declaration("SEL", name),
+            ObjcType::CString => join_declaration("char", &format!("*{}", name)),
+            ObjcType::Block => join_declaration("CDUnknownBlockType", name),
+            ObjcType::FunctionPointer => join_declaration("CDUnknownFunctionPointerType", name),
+            ObjcType::Pointer(pointee) => match pointee.as_ref() {
+                ObjcType::Array(..) => pointee.declaration(&format!("(*{})", name)),
+                _ => pointee.declaration(&format!("*{}", name)),
+            },
+            ObjcType::Array(count, element_type) => element_type.declaration(&format!("{}[{}]", name, count)),
+            ObjcType::Struct(aggregate_name, fields) => join_declaration(&format_aggregate("struct", aggregate_name, fields), name),
+            ObjcType::Union(aggregate_name, fields) => join_declaration(&format_aggregate("union", aggregate_name, fields), name),
+            ObjcType::Bitfield(width) => format!("{}:{}", join_declaration("unsigned int", name), width),
+            ObjcType::Complex(inner) => format!("_Complex {}", inner.declaration(name)),
+            ObjcType::Qualified(qualifier, inner) => format!("{} {}", qualifier, inner.declaration(name)),
+        }
+    }
+}
+
+fn join_declaration(base: &str, name: &str) -> String {
+    if name.is_empty() {
+        base.to_string()
+    } else {
+        format!("{} {}", base, name)
+    }
+}
+
+fn format_protocols(protocols: &[String]) -> String {
+    if protocols.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", protocols.join(", "))
+    }
+}
+
+fn format_aggregate(keyword: &str, name: &Option<String>, fields: &[ObjcField]) -> String {
+    match name {
+        Some(name) => format!("{} {}", keyword, name),
+        None => {
+            let fields: Vec<String> = fields.iter().enumerate().map(|(index, field)| {
+                let field_name = field.name.clone().unwrap_or_else(|| format!("_field{}", index + 1));
+                format!("{}; ", field.field_type.declaration(&field_name))
+            }).collect();
+            format!("{} {{ {}}}", keyword, fields.concat())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declaration(encoding: &str, name: &str) -> Option<String> {
+        parse_type(encoding).map(|objc_type| objc_type.declaration(name))
+    }
+
+    #[test]
+    fn ivar_and_property_types() {
+        assert_eq!(declaration("i", "_count").as_deref(), Some("int _count"));
+        assert_eq!(declaration("@\"NSString\"", "_title").as_deref(), Some("NSString *_title"));
+        assert_eq!(declaration("@\"<NSCopying><NSCoding>\"", "_value").as_deref(), Some("id<NSCopying, NSCoding> _value"));
+        assert_eq!(declaration("[16c]", "_buffer").as_deref(), Some("char _buffer[16]"));
+        assert_eq!(declaration("^[4i]", "_rows").as_deref(), Some("int (*_rows)[4]"));
+        assert_eq!(declaration("r*", "_name").as_deref(), Some("const char *_name"));
+        assert_eq!(declaration("{CGPoint=dd}", "_origin").as_deref(), Some("struct CGPoint _origin"));
+        assert_eq!(declaration("{?=\"x\"i\"y\"i}", "_pair").as_deref(), Some("struct { int x; int y; } _pair"));
+        assert_eq!(declaration("(?=iq)", "_either").as_deref(), Some("union { int _field1; long long _field2; } _either"));
+        assert_eq!(declaration("b3", "_flags").as_deref(), Some("unsigned int _flags:3"));
+        assert_eq!(declaration("@?", "_handler").as_deref(), Some("CDUnknownBlockType _handler"));
+        assert_eq!(declaration("^?", "_callback").as_deref(), Some("CDUnknownFunctionPointerType _callback"));
+    }
+
+    #[test]
+    fn method_types() {
+        let types = parse_method_types("v24@0:8@\"NSString\"16").unwrap();
+        let declarations: Vec<String> = types.iter().map(|objc_type| objc_type.declaration("")).collect();
+        assert_eq!(declarations, ["void", "id", "SEL", "NSString *"]);
+
+        let types = parse_method_types("@?<v@?@>16@0:8").unwrap();
+        assert!(matches!(types[0], ObjcType::Block));
+        assert_eq!(types.len(), 3);
+    }
+
+    #[test]
+    fn rejects_truncated_and_garbage_input() {
+        for encoding in ["", "[4", "[4i", "{CGPoint=dd", "(?=i", "@\"NSString", "Z"] {
+            assert!(parse_type(encoding).is_none(), "{}", encoding);
+        }
+        assert!(parse_method_types("v24@0:8{").is_none());
+    }
+
+    #[test]
+    fn deep_nesting_is_rejected() {
+        assert!(parse_type(&format!("{}i", "^".repeat(MAX_NESTING - 1))).is_some());
+        assert!(parse_type(&format!("{}i", "^".repeat(MAX_NESTING))).is_none());
+        assert!(parse_type(&format!("{}i", "^".repeat(500_000))).is_none());
+        assert!(parse_type(&"{a=".repeat(500_000)).is_none());
+    }
+}
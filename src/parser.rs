@@ -4,74 +4,241 @@ use std::io::{Read, Seek, SeekFrom};
 use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt};
 
 use crate::constants::*;
+use crate::corefile::CoreFile;
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::dyld_info::{parse_binds, parse_exports, ChainedFixups};
+use crate::encryption::EncryptedRange;
 use crate::error::AppError;
+use crate::function_starts;
 use crate::header::*;
 use crate::load_commands::*;
 use crate::mach_o::MachO;
 use crate::memory_utils::*;
+use crate::notes::*;
 use crate::symbols::*;
 
+/// Parses a thin Mach-O image of either byte order. The image must start at offset 0 of `file`,
+/// since the load commands locate their data by file offset; use [`MachO::from_bytes`] for an
+/// image held in a larger buffer.
+///
+/// ```
+/// use std::fs::File;
+///
+/// let mut file = File::open("test_files/mach_o_ex1")?;
+/// let mach_o = mach_o_rust::parser::parse(&mut file)?;
+/// println!("{} load commands, {} sections", mach_o.load_commands.len(), mach_o.sections().count());
+/// # Ok::<(), mach_o_rust::AppError>(())
+/// ```
 pub fn parse<R: Read + Seek>(file: &mut R) -> Result<MachO, AppError> {
-    let magic = file.read_u32::<BigEndian>()?;
+    parse_with_options(file, ParseOptions::default())
+}
+
+/// How [`parse_with_options`] treats malformed input.
+#[derive(Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// Skip or truncate broken structures and record them in [`MachO::diagnostics`] instead of
+    /// failing. The header still has to be readable.
+    pub lenient: bool,
+    /// Caps on counts and sizes taken from the file; see [`ParseLimits`].
+    pub limits: ParseLimits,
+}
+
+/// Upper bounds on the counts and sizes a file can make the parser allocate for. The load
+/// command count is checked against sizeofcmds and the file size as well, and tables are only
+/// read as far as the file goes, so these only come into play for large files. Going
+/// over a limit fails the parse, or truncates the structure in lenient mode. Bind, export and
+/// chained fixup tables that go over one are left out with a diagnostic either way.
+#[derive(Clone, Copy, Debug)]
+pub struct ParseLimits {
+    pub max_load_commands: u32,
+    /// Entries of the symbol table, the indirect symbol table and the chained fixup imports.
+    pub max_symbols: u64,
+    /// Bind and export records.
+    pub max_records: u64,
+    /// Bytes of a table or opcode stream read in one piece, and of the symbol names copied from
+    /// one into its records.
+    pub max_table_size: u64,
+}
+
+impl Default for ParseLimits {
+    fn default() -> ParseLimits {
+        ParseLimits {
+            max_load_commands: 1 << 16,
+            max_symbols: 1 << 22,
+            max_records: 1 << 22,
+            max_table_size: 1 << 28,
+        }
+    }
+}
+
+pub fn parse_with_options<R: Read + Seek>(file: &mut R, options: ParseOptions) -> Result<MachO, AppError> {
+    let magic = file.read_u32::<BigEndian>().map_err(AppError::truncated(0, 4))?;
     check_magic_number(magic)?;
 
+    let mut diagnostics = Diagnostics { lenient: options.lenient, entries: Vec::new() };
     match magic {
-        MH_MAGIC | MH_MAGIC_64 => parse_with_endian::<R, BigEndian>(file, magic),
-        MH_CIGAM | MH_CIGAM_64 => parse_with_endian::<R, LittleEndian>(file, magic),
+        MH_MAGIC | MH_MAGIC_64 => parse_with_endian::<R, BigEndian>(file, magic, &options.limits, &mut diagnostics),
+        MH_CIGAM | MH_CIGAM_64 => parse_with_endian::<R, LittleEndian>(file, magic, &options.limits, &mut diagnostics),
         _ => unreachable!(),
     }
 }
 
+/// Errors a lenient parse has recovered from. When parsing strictly, every error is handed back
+/// to abort the parse, except for the optional tables passed through [`Diagnostics::tolerate`].
+struct Diagnostics {
+    lenient: bool,
+    entries: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    fn report(&mut self, severity: Severity, error: AppError) -> Result<(), AppError> {
+        if !self.lenient {
+            return Err(error);
+        }
+        self.entries.push(Diagnostic::from_error(severity, &error));
+        Ok(())
+    }
+
+    /// Returns `count`, or `limit` after recording that `count` went over it.
+    fn cap(&mut self, what: &'static str, count: u64, limit: u64) -> Result<u64, AppError> {
+        if count <= limit {
+            return Ok(count);
+        }
+        self.report(Severity::Warning, AppError::LimitExceeded { what, count, limit })?;
+        Ok(limit)
+    }
+
+    /// Unwraps the result of decoding an optional `__LINKEDIT` table or LC_NOTE payload, or records
+    /// the error and falls back to `default`. The rest of the image is usable without these, so they
+    /// never fail the parse, even a strict one.
+    fn tolerate<T>(&mut self, result: Result<T, AppError>, default: T) -> T {
+        result.unwrap_or_else(|error| {
+            self.entries.push(Diagnostic::from_error(Severity::Error, &error));
+            default
+        })
+    }
+
+    /// Unwraps `result`, or records the error and falls back to `default`.
+    fn recover<T>(&mut self, result: Result<T, AppError>, default: T) -> Result<T, AppError> {
+        match result {
+            Ok(value) => Ok(value),
+            Err(error) => self.report(Severity::Error, error).map(|_| default),
+        }
+    }
+}
+
 fn check_magic_number(magic: u32) -> Result<(), AppError> {
     match magic {
         MH_MAGIC | MH_MAGIC_64 | MH_CIGAM | MH_CIGAM_64 => Ok(()),
-        _ => Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, "Invalid Mach-O magic number")))
+        _ => Err(AppError::BadMagic { magic })
     }
 }
 
-fn parse_with_endian<R: Read + Seek, E: ByteOrder>(file: &mut R, magic: u32) -> Result<MachO, AppError> {
-    let mut mach_o = MachO::new();
+fn parse_with_endian<R: Read + Seek, E: ByteOrder>(file: &mut R, magic: u32, limits: &ParseLimits, diagnostics: &mut Diagnostics) -> Result<MachO, AppError> {
+    let header_size = if matches!(magic, MH_MAGIC_64 | MH_CIGAM_64) { 32 } else { 28 };
+    let header = MachHeader::from_file::<R, E>(file, magic).map_err(AppError::truncated(4, header_size - 4))?;
+    let load_commands = parse_load_commands::<R, E>(file, &header, limits, diagnostics)?;
+    let symtab = parse_symtab::<R, E>(file, &load_commands, magic, limits, diagnostics)?;
+    let strtab = parse_strtab(file, &load_commands, limits, diagnostics)?;
+    let indirect_symbols = parse_indirect_symbols::<R, E>(file, &load_commands, limits, diagnostics)?;
+    let notes = parse_notes::<R, E>(file, &load_commands, diagnostics);
 
-    let header = parse_header::<R, E>(file, magic)?;
-    mach_o.header = Some(header);
+    let core = if header.filetype() == MH_CORE {
+        diagnostics.recover(CoreFile::from_load_commands::<E>(&load_commands, header.cputype()).map(Some), None)?
+    } else {
+        None
+    };
 
-    let load_commands = parse_load_commands::<R, E>(file, mach_o.header.as_ref().unwrap())?;
-    mach_o.load_commands = Some(load_commands);
+    let encrypted_ranges = EncryptedRange::from_load_commands(&load_commands);
 
-    let symtab = parse_symtab::<R, E>(file, &mach_o.load_commands.as_ref().unwrap().0, magic)?;
-    mach_o.symtab = Some(symtab);
+    let pointer_size = if header.is_64() { 8 } else { 4 };
+    let binds = diagnostics.tolerate(parse_binds(file, &load_commands, pointer_size, limits), Vec::new());
+    let exports = diagnostics.tolerate(parse_exports(file, &load_commands, limits), Vec::new());
+    let chained_fixups = diagnostics.tolerate(parse_chained_fixups::<R, E>(file, &load_commands, limits), None);
+    let function_starts = diagnostics.tolerate(parse_function_starts(file, &load_commands), None);
 
-    Ok(mach_o)
-}
-
-// TODO: remove boilerplate same as i did in symbols.rs
-fn parse_header<R: Read + Seek, E: ByteOrder>(file: &mut R, magic: u32) -> Result<MachHeader, AppError> {
-    match magic {
-        MH_MAGIC | MH_CIGAM => MachHeader32::from_file::<R, E>(file, magic),
-        MH_MAGIC_64 | MH_CIGAM_64 => MachHeader64::from_file::<R, E>(file, magic),
-        _ => unreachable!(),
-    }
+    Ok(MachO {
+        header,
+        load_commands,
+        symtab,
+        strtab,
+        indirect_symbols,
+        notes,
+        core,
+        encrypted_ranges,
+        binds,
+        exports,
+        chained_fixups,
+        function_starts,
+        diagnostics: std::mem::take(&mut diagnostics.entries),
+    })
 }
 
-fn parse_load_commands<R: Read + Seek, E: ByteOrder>(file: &mut R, header: &MachHeader) -> Result<(Vec<LoadCommand>, Vec<Vec<Section>>, Vec<LcStr>), AppError> {
+fn parse_load_commands<R: Read + Seek, E: ByteOrder>(file: &mut R, header: &MachHeader, limits: &ParseLimits, diagnostics: &mut Diagnostics) -> Result<Vec<LoadCommandEntry>, AppError> {
     let mut load_commands = Vec::new();
-    let mut sections = Vec::new();
-    let mut load_commands_strings = Vec::new();
 
-    for _ in 0..header.ncmds() {
+    let commands_offset = get_file_offset(file)?;
+    let sizeofcmds = header.sizeofcmds() as u64;
+    let file_size = get_file_size(file)?;
+    if commands_offset + sizeofcmds > file_size {
+        diagnostics.report(Severity::Warning, AppError::OutOfBounds { what: "load commands", offset: commands_offset, size: sizeofcmds })?;
+    }
+    // Every command takes at least 8 bytes of sizeofcmds, and of the file.
+    let fitting = sizeofcmds.min(file_size.saturating_sub(commands_offset)) / 8;
+    let mut ncmds = header.ncmds() as u64;
+    if ncmds > fitting {
+        diagnostics.report(Severity::Warning, invalid_data(format!("{} load commands do not fit in sizeofcmds 0x{:x}", ncmds, sizeofcmds)))?;
+        ncmds = fitting;
+    }
+    let ncmds = diagnostics.cap("load command", ncmds, limits.max_load_commands as u64)? as u32;
+
+    for index in 0..ncmds {
         let offset = get_file_offset(file)?;
-        let load_command_prefix = LoadCommandPrefix::from_file::<R, E>(file)?;
-        let load_command = parse_command::<R, E>(file, &load_command_prefix)?;
-        let load_command_sections = parse_sections_for_segment::<R, E>(file, &load_command)?;
-        let load_command_string = parse_load_command_string::<R, E>(file, &load_command, offset, load_command_prefix.cmdsize)?;
+        match parse_load_command::<R, E>(file, index) {
+            Ok(entry) => load_commands.push(entry),
+            Err(error) => {
+                diagnostics.report(Severity::Error, error)?;
+                // Skip the broken command; without a usable cmdsize the next one cannot be found.
+                file.seek(SeekFrom::Start(offset))?;
+                match LoadCommandPrefix::from_file::<R, E>(file) {
+                    Ok(prefix) if prefix.cmdsize >= 8 => advance_to_next_load_command(file, offset, prefix.cmdsize as u64)?,
+                    _ => break,
+                };
+            }
+        }
+    }
+    Ok(load_commands)
+}
 
-        load_commands.push(load_command);
-        sections.push(load_command_sections);
-        load_commands_strings.push(load_command_string);
+/// Decodes the load command at the current position and leaves `file` at the next one. Errors are
+/// reported as [`AppError::InvalidLoadCommand`] with the command's `index` and file offset.
+pub(crate) fn parse_load_command<R: Read + Seek, E: ByteOrder>(file: &mut R, index: u32) -> Result<LoadCommandEntry, AppError> {
+    let offset = get_file_offset(file)?;
+    let load_command_prefix = LoadCommandPrefix::from_file::<R, E>(file).map_err(AppError::truncated(offset, 8))?;
+    parse_load_command_fields::<R, E>(file, &load_command_prefix, offset)
+        .map_err(AppError::truncated(offset, load_command_prefix.cmdsize as u64))
+        .map_err(|source| AppError::InvalidLoadCommand { index, cmd: load_command_prefix.cmd, offset, source: Box::new(source) })
+}
 
-        advance_to_next_load_command(file, offset, load_command_prefix.cmdsize as u64)?;
+fn parse_load_command_fields<R: Read + Seek, E: ByteOrder>(file: &mut R, load_command_prefix: &LoadCommandPrefix, offset: u64) -> Result<LoadCommandEntry, AppError> {
+    let cmdsize = load_command_prefix.cmdsize as u64;
+    if cmdsize < 8 {
+        return Err(invalid_data(format!("cmdsize 0x{:x} is smaller than the cmd and cmdsize fields", cmdsize)));
     }
-    Ok((load_commands, sections, load_commands_strings))
+    // Checked up front so that no structure sized by the command is read past the end of the file.
+    if offset + cmdsize > get_file_size(file)? {
+        return Err(AppError::Truncated { offset, needed: cmdsize });
+    }
+
+    let command = parse_command::<R, E>(file, load_command_prefix)?;
+    let sections = parse_sections_for_segment::<R, E>(file, &command, offset + cmdsize)?;
+    let lc_str = parse_load_command_string(file, &command, offset, load_command_prefix.cmdsize)?;
+    if get_file_offset(file)? > offset + cmdsize {
+        return Err(invalid_data(format!("cmdsize 0x{:x} is too small for its fields", cmdsize)));
+    }
+
+    advance_to_next_load_command(file, offset, cmdsize)?;
+    Ok(LoadCommandEntry { offset, cmd: load_command_prefix.cmd, cmdsize: load_command_prefix.cmdsize, command, sections, lc_str })
 }
 
 // TODO: Sub-parse remaining commands as seen on warning after build...
@@ -80,9 +247,12 @@ fn parse_command<R: Read, E: ByteOrder>(file: &mut R, load_command_prefix: &Load
         LC_SYMTAB => SymtabCommand::from_file::<R, E>(file, load_command_prefix),
         LC_SYMSEG => SymsegCommand::from_file::<R, E>(file, load_command_prefix),
         LC_THREAD | LC_UNIXTHREAD => ThreadCommand::from_file::<R, E>(file, load_command_prefix),
+        LC_LOADFVMLIB | LC_IDFVMLIB => FvmlibCommand::from_file::<R, E>(file, load_command_prefix),
         LC_IDENT => IdentCommand::from_file::<E>(load_command_prefix),
+        LC_FVMFILE => FvmfileCommand::from_file::<R, E>(file, load_command_prefix),
+        LC_PREPAGE => PrepageCommand::from_file(load_command_prefix),
         LC_DYSYMTAB => DynSymtabCommand::from_file::<R, E>(file, load_command_prefix),
-        LC_LOAD_DYLIB | LC_ID_DYLIB | LC_LOAD_WEAK_DYLIB | LC_REEXPORT_DYLIB => DylibCommand::from_file::<R, E>(file, load_command_prefix),
+        LC_LOAD_DYLIB | LC_ID_DYLIB | LC_LOAD_WEAK_DYLIB | LC_REEXPORT_DYLIB | LC_LAZY_LOAD_DYLIB | LC_LOAD_UPWARD_DYLIB => DylibCommand::from_file::<R, E>(file, load_command_prefix),
         LC_LOAD_DYLINKER | LC_ID_DYLINKER | LC_DYLD_ENVIRONMENT => DylinkerCommand::from_file::<R, E>(file, load_command_prefix),
         LC_PREBOUND_DYLIB => PreboundDylibCommand::from_file::<R, E>(file, load_command_prefix),
         LC_ROUTINES => RoutinesCommand32::from_file::<R, E>(file, load_command_prefix),
@@ -97,46 +267,36 @@ fn parse_command<R: Read, E: ByteOrder>(file: &mut R, load_command_prefix: &Load
         LC_ROUTINES_64 => RoutinesCommand64::from_file::<R, E>(file, load_command_prefix),
         LC_UUID => UuidCommand::from_file::<R, E>(file, load_command_prefix),
         LC_RPATH => RpathCommand::from_file::<R, E>(file, load_command_prefix),
-        LC_CODE_SIGNATURE | LC_SEGMENT_SPLIT_INFO | LC_FUNCTION_STARTS | LC_DATA_IN_CODE | LC_DYLIB_CODE_SIGN_DRS | LC_LINKER_OPTIMIZATION_HINT => LinkeditDataCommand::from_file::<R, E>(file, load_command_prefix),
+        LC_CODE_SIGNATURE | LC_SEGMENT_SPLIT_INFO | LC_FUNCTION_STARTS | LC_DATA_IN_CODE | LC_DYLIB_CODE_SIGN_DRS | LC_LINKER_OPTIMIZATION_HINT | LC_DYLD_EXPORTS_TRIE | LC_DYLD_CHAINED_FIXUPS => LinkeditDataCommand::from_file::<R, E>(file, load_command_prefix),
         LC_ENCRYPTION_INFO => EncryptionInfoCommand32::from_file::<R, E>(file, load_command_prefix),
         LC_DYLD_INFO | LC_DYLD_INFO_ONLY => DyldInfoCommand::from_file::<R, E>(file, load_command_prefix),
-        LC_VERSION_MIN_MACOSX | LC_VERSION_MIN_IPHONEOS | LC_VERSION_MIN_TVOS | LC_VERSION_MIN_WATCHOS => SegmentCommand32::from_file::<R, E>(file, load_command_prefix),
+        LC_VERSION_MIN_MACOSX | LC_VERSION_MIN_IPHONEOS | LC_VERSION_MIN_TVOS | LC_VERSION_MIN_WATCHOS => VersionMinCommand::from_file::<R, E>(file, load_command_prefix),
         LC_MAIN => EntryPointCommand::from_file::<R, E>(file, load_command_prefix),
         LC_SOURCE_VERSION => SourceVersionCommand::from_file::<R, E>(file, load_command_prefix),
         LC_ENCRYPTION_INFO_64 => EncryptionInfoCommand64::from_file::<R, E>(file, load_command_prefix),
         LC_LINKER_OPTION => LinkerOptionCommand::from_file::<R, E>(file, load_command_prefix),
         LC_NOTE => NoteCommand::from_file::<R, E>(file, load_command_prefix),
         LC_BUILD_VERSION => BuildVersionCommand::from_file::<R, E>(file, load_command_prefix),
-        _ => Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, "unknown load command type!")))
+        _ => Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, "unknown load command type")))
     }
 }
 
-// TODO: remove boilerplate same as i did in symbols.rs
-fn parse_sections_for_segment<R: Read + Seek, E: ByteOrder>(file: &mut R, load_command: &LoadCommand) -> Result<Vec<Section>, AppError> {
+fn parse_sections_for_segment<R: Read + Seek, E: ByteOrder>(file: &mut R, load_command: &LoadCommand, end: u64) -> Result<Vec<Section>, AppError> {
     let mut load_command_sections = Vec::new();
-    match load_command {
-        LoadCommand::SegmentCommand(command) => {
-            match command {
-                SegmentCommand::SEG32(command) => {
-                    for _ in 0..command.nsects {
-                        let section = Section32::from_file::<R, E>(file)?;
-                        load_command_sections.push(section);
-                    }
-                }
-                SegmentCommand::SEG64(command) => {
-                    for _ in 0..command.nsects {
-                        let section = Section64::from_file::<R, E>(file)?;
-                        load_command_sections.push(section);
-                    }
-                }
-            }
+    if let LoadCommand::SegmentCommand(command) = load_command {
+        // section_64 is 80 bytes, section 68
+        let section_size = if command.cmd() == LC_SEGMENT_64 { 80 } else { 68 };
+        if command.nsects() as u64 * section_size > end.saturating_sub(get_file_offset(file)?) {
+            return Err(invalid_data(format!("cmdsize 0x{:x} is too small for {} sections", command.cmdsize(), command.nsects())));
+        }
+        for _ in 0..command.nsects() {
+            load_command_sections.push(Section::from_file::<R, E>(file, command.cmd())?);
         }
-        _ => {}
     }
     Ok(load_command_sections)
 }
 
-fn parse_load_command_string<R: Read + Seek, E: ByteOrder>(file: &mut R, load_command: &LoadCommand, lc_offset: u64, cmdsize: u32) -> Result<LcStr, AppError> {
+fn parse_load_command_string<R: Read + Seek>(file: &mut R, load_command: &LoadCommand, lc_offset: u64, cmdsize: u32) -> Result<LcStr, AppError> {
     let mut load_command_string = Vec::new();
     match load_command {
         LoadCommand::DylibCommand(_) |
@@ -146,39 +306,233 @@ fn parse_load_command_string<R: Read + Seek, E: ByteOrder>(file: &mut R, load_co
         LoadCommand::SubLibraryCommand(_) |
         LoadCommand::PreboundDylibCommand(_) |
         LoadCommand::DylinkerCommand(_) |
-        LoadCommand::RpathCommand(_) => {
-            let remaining_size = get_load_command_remaining_size(lc_offset, cmdsize as u64, get_file_offset(file)?)?;
+        LoadCommand::RpathCommand(_) |
+        LoadCommand::FvmlibCommand(_) |
+        LoadCommand::FvmfileCommand(_) |
+        LoadCommand::IdentCommand(_) => {
+            let string_offset = get_file_offset(file)?;
+            let remaining_size = get_load_command_remaining_size(lc_offset, cmdsize as u64, string_offset)?;
             if remaining_size > 0 {
                 for _ in 0..remaining_size {
                     load_command_string.push(file.read_u8()?);
                 }
             }
+            if !matches!(load_command, LoadCommand::IdentCommand(_)) {
+                check_lc_str_utf8(&load_command_string, string_offset)?;
+            }
         },
         _ => {}
     }
     Ok(load_command_string)
 }
 
+/// The first string of an `lc_str` is a path or a name, which must be valid UTF-8.
+fn check_lc_str_utf8(lc_str: &[u8], offset: u64) -> Result<(), AppError> {
+    let end = lc_str.iter().position(|&byte| byte == 0).unwrap_or(lc_str.len());
+    match std::str::from_utf8(&lc_str[..end]) {
+        Ok(_) => Ok(()),
+        Err(error) => Err(AppError::Utf8 { offset: offset + error.valid_up_to() as u64 }),
+    }
+}
+
 fn get_load_command_remaining_size(lc_offset: u64, lc_size: u64, file_offset: u64) -> Result<u64, AppError> {
-    Ok((lc_offset + lc_size) - file_offset)
+    (lc_offset + lc_size).checked_sub(file_offset)
+        .ok_or_else(|| invalid_data(format!("cmdsize 0x{:x} is too small for its fields", lc_size)))
+}
+
+fn invalid_data(message: String) -> AppError {
+    AppError::from(io::Error::new(io::ErrorKind::InvalidData, message))
 }
 
 // TODO: print symtab to make sure parsing works as intended
-fn parse_symtab<R: Read + Seek, E: ByteOrder>(file: &mut R, load_commands: &Vec<LoadCommand>, magic: u32) -> Result<Symtab, AppError> {
+fn parse_symtab<R: Read + Seek, E: ByteOrder>(file: &mut R, load_commands: &[LoadCommandEntry], magic: u32, limits: &ParseLimits, diagnostics: &mut Diagnostics) -> Result<Symtab, AppError> {
     let mut symtab = Vec::new();
     for load_command in load_commands {
-        match load_command {
-            LoadCommand::SymtabCommand(command) => {
-                file.seek(SeekFrom::Start(command.stroff as u64))?;
-                for _ in 0..command.nsyms {
-                    let entry = Nlist::from_file::<R, E>(file, magic)?;
-                    symtab.push(entry);
+        if let LoadCommand::SymtabCommand(command) = &load_command.command {
+            let entry_size = if matches!(magic, MH_MAGIC_64 | MH_CIGAM_64) { 16 } else { 12 };
+            let out_of_bounds = AppError::out_of_bounds("symbol table", command.symoff as u64, command.nsyms as u64 * entry_size);
+            let nsyms = diagnostics.cap("symbol", command.nsyms as u64, limits.max_symbols)?;
+            file.seek(SeekFrom::Start(command.symoff as u64))?;
+            for _ in 0..nsyms {
+                match Nlist::from_file::<R, E>(file, magic) {
+                    Ok(entry) => symtab.push(entry),
+                    Err(error) => {
+                        diagnostics.report(Severity::Warning, out_of_bounds(error))?;
+                        break;
+                    }
                 }
             }
-            _ => {}
         }
     }
     Ok(symtab)
 }
 
+fn parse_strtab<R: Read + Seek>(file: &mut R, load_commands: &[LoadCommandEntry], limits: &ParseLimits, diagnostics: &mut Diagnostics) -> Result<Strtab, AppError> {
+    for load_command in load_commands {
+        if let LoadCommand::SymtabCommand(command) = &load_command.command {
+            let offset = command.stroff as u64;
+            let size = diagnostics.cap("string table byte", command.strsize as u64, limits.max_table_size)?;
+            return match read_bytes_at(file, offset, size) {
+                Ok(strtab) => Ok(strtab),
+                Err(error) => {
+                    diagnostics.report(Severity::Warning, AppError::out_of_bounds("string table", offset, size)(error))?;
+                    Ok(read_bytes_up_to(file, offset, size)?)
+                }
+            };
+        }
+    }
+    Ok(Strtab::new())
+}
+
+fn parse_indirect_symbols<R: Read + Seek, E: ByteOrder>(file: &mut R, load_commands: &[LoadCommandEntry], limits: &ParseLimits, diagnostics: &mut Diagnostics) -> Result<Vec<u32>, AppError> {
+    let mut indirect_symbols = Vec::new();
+    for load_command in load_commands {
+        if let LoadCommand::DynSymtabCommand(command) = &load_command.command {
+            let out_of_bounds = AppError::out_of_bounds("indirect symbol table", command.indirectsymoff as u64, command.nindirectsyms as u64 * 4);
+            let nindirectsyms = diagnostics.cap("indirect symbol", command.nindirectsyms as u64, limits.max_symbols)?;
+            file.seek(SeekFrom::Start(command.indirectsymoff as u64))?;
+            for _ in 0..nindirectsyms {
+                match file.read_u32::<E>() {
+                    Ok(entry) => indirect_symbols.push(entry),
+                    Err(error) => {
+                        diagnostics.report(Severity::Warning, out_of_bounds(error))?;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    Ok(indirect_symbols)
+}
+
+fn parse_notes<R: Read + Seek, E: ByteOrder>(file: &mut R, load_commands: &[LoadCommandEntry], diagnostics: &mut Diagnostics) -> Vec<Note> {
+    let mut notes = Vec::new();
+    for load_command in load_commands {
+        if let LoadCommand::NoteCommand(command) = &load_command.command {
+            if let Some(note) = diagnostics.tolerate(Note::from_file::<R, E>(file, command).map(Some), None) {
+                notes.push(note);
+            }
+        }
+    }
+    notes
+}
+
+fn parse_chained_fixups<R: Read + Seek, E: ByteOrder>(file: &mut R, load_commands: &[LoadCommandEntry], limits: &ParseLimits) -> Result<Option<ChainedFixups>, AppError> {
+    for load_command in load_commands {
+        if let LoadCommand::LinkeditDataCommand(command) = &load_command.command {
+            if command.cmd == LC_DYLD_CHAINED_FIXUPS {
+                return Ok(Some(ChainedFixups::from_file::<R, E>(file, command, limits)?));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn parse_function_starts<R: Read + Seek>(file: &mut R, load_commands: &[LoadCommandEntry]) -> Result<Option<Vec<u64>>, AppError> {
+    let text_vmaddr = load_commands.iter().find_map(|load_command| match &load_command.command {
+        LoadCommand::SegmentCommand(segment) if segment.fileoff() == 0 && segment.filesize() != 0 => Some(segment.vmaddr()),
+        _ => None,
+    });
+    for load_command in load_commands {
+        if let LoadCommand::LinkeditDataCommand(command) = &load_command.command {
+            if command.cmd == LC_FUNCTION_STARTS {
+                return Ok(Some(function_starts::from_file(file, command, text_vmaddr.unwrap_or(0))?));
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn symtab_is_read_from_symoff() {
+        let symtab = command(LC_SYMTAB, &[0x100, 2, 0x140, 16]);
+        let symbols = [nlist_64(1, N_SECT | N_EXT, 1, 0x1000), nlist_64(7, N_SECT, 1, 0x2000)].concat();
+        let data = image(&[symtab], &[(0x100, &symbols), (0x140, b"\0_main\0_helper\0\0")]);
+
+        let mach_o = parse(&mut Cursor::new(data)).unwrap();
+        let names: Vec<String> = mach_o.symtab.iter().map(|nlist| symbol_name(&mach_o.strtab, nlist.n_strx())).collect();
+        let values: Vec<u64> = mach_o.symtab.iter().map(|nlist| nlist.n_value()).collect();
+        assert_eq!(names, ["_main", "_helper"]);
+        assert_eq!(values, [0x1000, 0x2000]);
+    }
+
+    fn lenient(limits: ParseLimits) -> ParseOptions {
+        ParseOptions { lenient: true, limits }
+    }
+
+    #[test]
+    fn load_commands_over_the_limit() {
+        let uuid = command(LC_UUID, &[0; 4]);
+        let data = image(&[uuid.clone(), uuid], &[]);
+        let limits = ParseLimits { max_load_commands: 1, ..ParseLimits::default() };
+
+        let error = parse_with_options(&mut Cursor::new(&data), ParseOptions { lenient: false, limits }).err().unwrap();
+        assert!(matches!(error, AppError::LimitExceeded { count: 2, limit: 1, .. }));
+        let mach_o = parse_with_options(&mut Cursor::new(&data), lenient(limits)).unwrap();
+        assert_eq!(mach_o.load_commands.len(), 1);
+        assert_eq!(mach_o.diagnostics[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn cmdsize_smaller_than_the_prefix() {
+        let data = image(&[LC_UUID.to_le_bytes().into_iter().chain(4u32.to_le_bytes()).collect()], &[]);
 
+        let error = parse(&mut Cursor::new(&data)).err().unwrap();
+        assert!(matches!(error, AppError::InvalidLoadCommand { index: 0, offset: 32, .. }));
+        let mach_o = parse_with_options(&mut Cursor::new(&data), lenient(ParseLimits::default())).unwrap();
+        assert!(mach_o.load_commands.is_empty());
+        assert!(mach_o.diagnostics[0].message.contains("smaller than the cmd and cmdsize fields"));
+    }
+
+    #[test]
+    fn sizeofcmds_past_the_end_of_the_file() {
+        let mut data = image(&[command(LC_UUID, &[0; 4])], &[]);
+        data[20..24].copy_from_slice(&0xffff_ffffu32.to_le_bytes());
+
+        let error = parse(&mut Cursor::new(&data)).err().unwrap();
+        assert!(matches!(error, AppError::OutOfBounds { what: "load commands", offset: 32, size: 0xffff_ffff }));
+        let mach_o = parse_with_options(&mut Cursor::new(&data), lenient(ParseLimits::default())).unwrap();
+        assert_eq!(mach_o.load_commands.len(), 1);
+    }
+
+    #[test]
+    fn ncmds_beyond_sizeofcmds() {
+        let mut data = image(&[command(LC_UUID, &[0; 4])], &[]);
+        data[16..20].copy_from_slice(&0xffff_ffffu32.to_le_bytes());
+
+        assert!(parse(&mut Cursor::new(&data)).is_err());
+        let mach_o = parse_with_options(&mut Cursor::new(&data), lenient(ParseLimits::default())).unwrap();
+        assert!(mach_o.diagnostics[0].message.contains("4294967295 load commands do not fit in sizeofcmds 0x18"));
+        assert_eq!(mach_o.load_commands.len(), 1);
+    }
+
+    #[test]
+    fn nsects_beyond_cmdsize() {
+        let mut segment = segment_64("__TEXT", 0x1000, 0x1000, 0, 0, &[]);
+        segment[64..68].copy_from_slice(&0x0400_0000u32.to_le_bytes());
+        let data = image(&[segment], &[]);
+
+        let error = parse(&mut Cursor::new(&data)).err().unwrap();
+        assert!(Diagnostic::from_error(Severity::Error, &error).message.contains("cmdsize 0x48 is too small for 67108864 sections"));
+    }
+
+    #[test]
+    fn symbols_over_the_limit_are_truncated() {
+        let symtab = command(LC_SYMTAB, &[0x100, 3, 0x130, 4]);
+        let symbols = [nlist_64(0, N_SECT, 1, 0x1000), nlist_64(0, N_SECT, 1, 0x1010), nlist_64(0, N_SECT, 1, 0x1020)].concat();
+        let data = image(&[symtab], &[(0x100, &symbols), (0x130, &[0; 4])]);
+        let limits = ParseLimits { max_symbols: 2, ..ParseLimits::default() };
+
+        assert!(parse_with_options(&mut Cursor::new(&data), ParseOptions { lenient: false, limits }).is_err());
+        let mach_o = parse_with_options(&mut Cursor::new(&data), lenient(limits)).unwrap();
+        assert_eq!(mach_o.symtab.len(), 2);
+        assert!(mach_o.diagnostics[0].message.contains("symbol"));
+    }
+}
@@ -1,37 +1,70 @@
+//! Renders parsed Mach-O data as the tables printed by the command-line tool.
+//!
+//! ```
+//! use std::fs::File;
+//! use mach_o_rust::demangle::{DemangleOptions, DemangleStyle};
+//! use mach_o_rust::printer;
+//!
+//! let mut file = File::open("test_files/mach_o_ex1")?;
+//! let mach_o = mach_o_rust::parse(&mut file)?;
+//! printer::print_header(&mach_o.header);
+//! printer::print_symbols(&mach_o, Some(DemangleOptions { style: DemangleStyle::Full, hide_hash: false }));
+//! # Ok::<(), mach_o_rust::AppError>(())
+//! ```
+
 use prettytable::{row, Table};
 
 use crate::constants::*;
+use crate::corefile::*;
+use crate::coverage::*;
+use crate::demangle::{demangle_symbol, DemangleOptions};
+use crate::diagnostics::Diagnostic;
+use crate::encryption::*;
+use crate::go::*;
 use crate::header::*;
+use crate::linkedit::*;
 use crate::load_commands::*;
+use crate::loh::*;
+use crate::mach_o::MachO;
+use crate::memory_utils::fixed_bytes_to_string;
+use crate::notes::*;
+use crate::objc::*;
+use crate::objc_usage::*;
+use crate::split_info::*;
+use crate::swift::*;
+use crate::swift_demangle::demangle_type;
+use crate::symbols::*;
 
-pub fn print_header(header: &MachHeader) {
+pub fn print_header<H: Header + ?Sized>(header: &H) {
     let mut table = Table::new();
     print_common_title("Header", &mut table);
-    match header {
-        MachHeader::MH32(header) => print_header_32(header, &mut table),
-        MachHeader::MH64(header) => print_header_64(header, &mut table),
+    print_header_magic(header.magic(), &mut table);
+    print_header_cputype(header.cputype(), &mut table);
+    print_header_cpusubtype(header.cpusubtype(), &mut table);
+    print_header_filetype(header.filetype(), &mut table);
+    print_ncmds_and_sizeofcmds(header.ncmds(), header.sizeofcmds(), &mut table);
+    print_header_flags(header.flags(), &mut table);
+    if let Some(reserved) = header.reserved() {
+        table.add_row(row![ Fcc->"reserved", Fyc->format!("0x{:x}", reserved), c->"-"]);
     }
     table.printstd();
 }
 
-fn print_header_32(header: &MachHeader32, table: &mut Table) {
-    print_common_header_fields(header.magic, header.cputype, header.cpusubtype, header.filetype, header.ncmds, header.sizeofcmds, header.flags, None, table);
-}
-
-fn print_header_64(header: &MachHeader64, table: &mut Table) {
-    print_common_header_fields(header.magic, header.cputype, header.cpusubtype, header.filetype, header.ncmds, header.sizeofcmds, header.flags, Some(header.reserved), table);
-}
-
-fn print_common_header_fields(magic: u32, cputype: i32, cpusubtype: i32, filetype: u32, ncmds: u32, sizeofcmds: u32, flags: u32, reserved: Option<u32>, table: &mut Table) {
-    print_header_magic(magic, table);
-    print_header_cputype(cputype, table);
-    print_header_cpusubtype(cpusubtype, table);
-    print_header_filetype(filetype, table);
-    print_ncmds_and_sizeofcmds(ncmds, sizeofcmds, table);
-    print_header_flags(flags, table);
-    if let Some(r) = reserved {
-        table.add_row(row![ Fcc->"reserved", Fyc->format!("0x{:x}",r), c->"-"]);
+pub fn print_encryption_summary(ranges: &[EncryptedRange]) {
+    let mut table = Table::new();
+    print_common_title("Encryption", &mut table);
+    if ranges.is_empty() {
+        table.add_row(row![ Fcc->"encryption", Fyc->"none", c->"No LC_ENCRYPTION_INFO load command"]);
+    }
+    for range in ranges {
+        let status = if range.is_encrypted() { "Encrypted, section contents in this range are unreadable" } else { "Not encrypted (cryptid 0)" };
+        table.add_row(row![ Fcc->"cryptoff", Fyc->format!("0x{:x}", range.cryptoff), c->"-"]);
+        table.add_row(row![ Fcc->"cryptsize", Fyc->format!("0x{:x}", range.cryptsize), c->"-"]);
+        table.add_row(row![ Fcc->"cryptid", Fyc->format!("0x{:x}", range.cryptid), c->status]);
+        let sections: Vec<String> = range.sections.iter().map(|(segname, sectname)| format!("{},{}", segname, sectname)).collect();
+        table.add_row(row![ Fcc->"sections", Fyc->"-", c->sections.join("\n")]);
     }
+    table.printstd();
 }
 
 fn print_header_magic(magic: u32, table: &mut Table) {
@@ -146,81 +179,64 @@ fn print_header_flags(flags_combined: u32, table: &mut Table) {
     table.add_row(row![Fcc->"flags", Fyc->format!("0x{:x}\n({})", flags_combined, format!("{}", decomposed_flags.join(" | "))), c->flags_table]);
 }
 
-pub fn print_load_commands(load_commands: &(Vec<LoadCommand>, Vec<Vec<Section>>, Vec<LcStr>)) {
+pub fn print_load_commands(load_commands: &[LoadCommandEntry]) {
     let mut table = Table::new();
 
     print_common_title("Load Commands", &mut table);
-    for (index, load_command) in load_commands.0.iter().enumerate() {
+    for (index, load_command) in load_commands.iter().enumerate() {
         table.add_row(row![Fmbc->format!("Load Command #{}", index), c->"-", c->"-"]);
-        match load_command {
+        match &load_command.command {
             LoadCommand::SegmentCommand(command) => {
-                match command {
-                    SegmentCommand::SEG32(command) => print_segment_command32(command, &mut table),
-                    SegmentCommand::SEG64(command) => print_segment_command64(command, &mut table),
-                }
-                print_sections_for_segment(&load_commands.1[index], &mut table);
+                print_segment_command(command, &mut table);
+                print_sections_for_segment(&load_command.sections, &mut table);
             }
-            LoadCommand::DylibCommand(command) => unsafe { print_dylib_command(command, String::from_utf8(load_commands.2[index].clone()).unwrap() ,&mut table) },
-            LoadCommand::SubFrameWorkCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "umbrella", String::from_utf8(load_commands.2[index].clone()).unwrap(), &mut table),
-            LoadCommand::SubClientCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "client", String::from_utf8(load_commands.2[index].clone()).unwrap(), &mut table),
-            LoadCommand::SubUmbrellaCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "sub_umbrella", String::from_utf8(load_commands.2[index].clone()).unwrap(), &mut table),
-            LoadCommand::SubLibraryCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "sub_library", String::from_utf8(load_commands.2[index].clone()).unwrap(), &mut table),
-            LoadCommand::PreboundDylibCommand(command) => print_prebound_dylib_command(command, &load_commands.2[index], &mut table),
-            LoadCommand::DylinkerCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "name", String::from_utf8(load_commands.2[index].clone()).unwrap(), &mut table),
+            LoadCommand::DylibCommand(command) => unsafe { print_dylib_command(command, String::from_utf8_lossy(&load_command.lc_str).into_owned() ,&mut table) },
+            LoadCommand::SubFrameWorkCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "umbrella", String::from_utf8_lossy(&load_command.lc_str).into_owned(), &mut table),
+            LoadCommand::SubClientCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "client", String::from_utf8_lossy(&load_command.lc_str).into_owned(), &mut table),
+            LoadCommand::SubUmbrellaCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "sub_umbrella", String::from_utf8_lossy(&load_command.lc_str).into_owned(), &mut table),
+            LoadCommand::SubLibraryCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "sub_library", String::from_utf8_lossy(&load_command.lc_str).into_owned(), &mut table),
+            LoadCommand::PreboundDylibCommand(command) => print_prebound_dylib_command(command, &load_command.lc_str, &mut table),
+            LoadCommand::DylinkerCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "name", String::from_utf8_lossy(&load_command.lc_str).into_owned(), &mut table),
             LoadCommand::ThreadCommand(command) => print_thread_command(command, &mut table),
-            LoadCommand::RoutinesCommand(command) => {
-                match command {
-                    RoutinesCommand::RTN32(command) => print_routines_command_32(command, &mut table),
-                    RoutinesCommand::RTN64(command) => print_routines_command_64(command, &mut table),
-                }
-            }
+            LoadCommand::RoutinesCommand(command) => print_routines_command(command, &mut table),
             LoadCommand::SymtabCommand(command) => print_symtab_command(command, &mut table),
             LoadCommand::DynSymtabCommand(command) => print_dynsymtab_command(command, &mut table),
             LoadCommand::TwoLevelHintsCommand(command) => print_two_level_hints_command(command, &mut table),
             LoadCommand::PrebindCksumCommand(command) => print_prebind_cksum_command(command, &mut table),
             LoadCommand::UuidCommand(command) => print_uuid_command(command, &mut table),
-            LoadCommand::RpathCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "path", String::from_utf8(load_commands.2[index].clone()).unwrap(), &mut table),
+            LoadCommand::RpathCommand(command) => print_common_lcstr(command.cmd, command.cmdsize, "path", String::from_utf8_lossy(&load_command.lc_str).into_owned(), &mut table),
             LoadCommand::LinkeditDataCommand(command) => print_linkedit_data_command(command, &mut table),
-            LoadCommand::EncryptionInfoCommand(command) => {
-                match command {
-                    EncryptionInfoCommand::ENI32(command) => print_encryption_info_command_32(command, &mut table),
-                    EncryptionInfoCommand::ENI64(command) => print_encryption_info_command_64(command, &mut table),
-                }
-            }
+            LoadCommand::EncryptionInfoCommand(command) => print_encryption_info_command(command, &mut table),
             LoadCommand::VersionMinCommand(command) => print_version_min_command(command, &mut table),
             LoadCommand::BuildVersionCommand(command) => print_build_version_command(command, &mut table),
             LoadCommand::DyldInfoCommand(command) => print_dyld_info_command(command, &mut table),
             LoadCommand::LinkerOptionCommand(command) => print_linker_options_command(command, &mut table),
             LoadCommand::SymsegCommand(command) => print_symseg_command(command, &mut table),
-            LoadCommand::IdentCommand(command) => print_lc_cmd_and_cmdsize(command.cmd, command.cmdsize, &mut table),
+            LoadCommand::IdentCommand(command) => print_ident_command(command, &load_command.lc_str, &mut table),
             LoadCommand::EntryPointCommand(command) => print_entry_point_command(command, &mut table),
             LoadCommand::SourceVersionCommand(command) => print_source_version_command(command, &mut table),
             LoadCommand::NoteCommand(command) => print_note_command(command, &mut table),
+            // The parser only ever stores the offset variant of the lc_str union.
+            LoadCommand::FvmlibCommand(command) => print_fvmlib_command(command, unsafe { command.fvmlib.name.offset }, String::from_utf8_lossy(&load_command.lc_str).into_owned(), &mut table),
+            LoadCommand::FvmfileCommand(command) => print_fvmfile_command(command, unsafe { command.name.offset }, String::from_utf8_lossy(&load_command.lc_str).into_owned(), &mut table),
+            LoadCommand::PrepageCommand(command) => print_lc_cmd_and_cmdsize(command.cmd, command.cmdsize, &mut table),
         }
         table.add_row(row![c=>"-", "-", "-"]);
     }
     table.printstd();
 }
 
-fn print_segment_command32(command: &SegmentCommand32, table: &mut Table) {
-    print_common_segment_fields(command.cmd, command.cmdsize, &command.segname, command.vmaddr as u64, command.vmsize as u64, command.fileoff as u64, command.filesize as u64, command.nsects, command.maxprot, command.initprot, command.flags, table);
-}
-
-fn print_segment_command64(command: &SegmentCommand64, table: &mut Table) {
-    print_common_segment_fields(command.cmd, command.cmdsize, &command.segname, command.vmaddr, command.vmsize, command.fileoff, command.filesize, command.nsects, command.maxprot, command.initprot, command.flags, table);
-}
-
-fn print_common_segment_fields(cmd: u32, cmdsize: u32, segname: &[u8], vmaddr: u64, vmsize: u64, fileoff: u64, filesize: u64, nsects: u32, maxprot: i32, initprot: i32,flags: u32, table: &mut Table) {
-    print_lc_cmd_and_cmdsize(cmd, cmdsize, table);
-    print_bytes_array("segname", segname, table);
-    table.add_row(row![Fcc->"vmaddr", Fyc->format!("0x{:x}", vmaddr), c->"-"]);
-    table.add_row(row![Fcc->"vmsize", Fyc->format!("0x{:x}", vmsize), c->"-"]);
-    table.add_row(row![Fcc->"fileoff", Fyc->format!("0x{:x}", fileoff), c->"-"]);
-    table.add_row(row![Fcc->"filesize", Fyc->format!("0x{:x}", filesize), c->"-"]);
-    print_segment_maxprot_or_initprot(maxprot, table);
-    print_segment_maxprot_or_initprot(initprot, table);
-    table.add_row(row![Fcc->"nsects", Fyc->format!("0x{:x}", nsects), c->"-"]);
-    print_segment_flags(flags, table);
+fn print_segment_command<S: Segment + ?Sized>(command: &S, table: &mut Table) {
+    print_lc_cmd_and_cmdsize(command.cmd(), command.cmdsize(), table);
+    print_bytes_array("segname", command.segname(), table);
+    table.add_row(row![Fcc->"vmaddr", Fyc->format!("0x{:x}", command.vmaddr()), c->"-"]);
+    table.add_row(row![Fcc->"vmsize", Fyc->format!("0x{:x}", command.vmsize()), c->"-"]);
+    table.add_row(row![Fcc->"fileoff", Fyc->format!("0x{:x}", command.fileoff()), c->"-"]);
+    table.add_row(row![Fcc->"filesize", Fyc->format!("0x{:x}", command.filesize()), c->"-"]);
+    print_segment_maxprot_or_initprot(command.maxprot(), table);
+    print_segment_maxprot_or_initprot(command.initprot(), table);
+    table.add_row(row![Fcc->"nsects", Fyc->format!("0x{:x}", command.nsects()), c->"-"]);
+    print_segment_flags(command.flags(), table);
 }
 
 fn print_segment_maxprot_or_initprot(prot: i32, table: &mut Table) {
@@ -267,43 +283,32 @@ fn print_segment_flags(flags_combined: u32, table: &mut Table) {
     }
 }
 
-fn print_sections_for_segment(sections: &Vec<Section>, table: &mut Table) {
+fn print_sections_for_segment(sections: &[Section], table: &mut Table) {
     for (index, section) in sections.iter().enumerate() {
         table.add_row(row![Fgbc->format!("Section #{}", index), c->"-", c->"-"]);
-        match section {
-            Section::SEC32(section32) => print_section32(section32, table),
-            Section::SEC64(section64) => print_section64(section64, table),
-        }
+        print_section(section, table);
     }
 }
 
-fn print_section32(section: &Section32, table: &mut Table) {
-    print_common_section_fields(&section.sectname, &section.segname, section.addr as u64, section.size as u64, section.offset, section.align, section.reloff, section.nreloc, section.flags, section.reserved1, section.reserved2, None, table);
-}
-
-fn print_section64(section: &Section64, table: &mut Table) {
-    print_common_section_fields(&section.sectname, &section.segname, section.addr, section.size, section.offset, section.align, section.reloff, section.nreloc, section.flags, section.reserved1, section.reserved2, None, table);
-}
-
-fn print_common_section_fields(sectname: &[u8], segname: &[u8], addr: u64, size: u64, offset: u32, align: u32, reloff: u32, nreloc: u32, flags: u32, reserved1: u32, reserved2: u32, reserved3: Option<u32>, table: &mut Table) {
-    print_bytes_array("sectname", sectname, table);
-    print_bytes_array("segname", segname, table);
-    table.add_row(row![Fcc->"addr", Fyc->format!("0x{:x}", addr), c->"-"]);
-    table.add_row(row![Fcc->"size", Fyc->format!("0x{:x}", size), c->"-"]);
-    table.add_row(row![Fcc->"offset", Fyc->format!("0x{:x}", offset), c->"-"]);
-    table.add_row(row![Fcc->"align", Fyc->format!("0x{:x}", align), c->"-"]);
-    table.add_row(row![Fcc->"reloff", Fyc->format!("0x{:x}", reloff), c->"-"]);
-    table.add_row(row![Fcc->"nreloc", Fyc->format!("0x{:x}", nreloc), c->"-"]);
-    table.add_row(row![Fcc->"flags", Fyc->format!("0x{:x}", flags), c->"-"]);
-    table.add_row(row![Fcc->"reserved1", Fyc->format!("0x{:x}", reserved1), c->"-"]);
-    table.add_row(row![Fcc->"reserved2", Fyc->format!("0x{:x}", reserved2), c->"-"]);
-    if let Some(r3) = reserved3 {
-        table.add_row(row![Fcc->"reserved3", Fyc->format!("0x{:x}", r3), c->"-"]);
+fn print_section<S: SectionLike + ?Sized>(section: &S, table: &mut Table) {
+    print_bytes_array("sectname", section.sectname(), table);
+    print_bytes_array("segname", section.segname(), table);
+    table.add_row(row![Fcc->"addr", Fyc->format!("0x{:x}", section.addr()), c->"-"]);
+    table.add_row(row![Fcc->"size", Fyc->format!("0x{:x}", section.size()), c->"-"]);
+    table.add_row(row![Fcc->"offset", Fyc->format!("0x{:x}", section.offset()), c->"-"]);
+    table.add_row(row![Fcc->"align", Fyc->format!("0x{:x}", section.align()), c->"-"]);
+    table.add_row(row![Fcc->"reloff", Fyc->format!("0x{:x}", section.reloff()), c->"-"]);
+    table.add_row(row![Fcc->"nreloc", Fyc->format!("0x{:x}", section.nreloc()), c->"-"]);
+    table.add_row(row![Fcc->"flags", Fyc->format!("0x{:x}", section.flags()), c->"-"]);
+    table.add_row(row![Fcc->"reserved1", Fyc->format!("0x{:x}", section.reserved1()), c->"-"]);
+    table.add_row(row![Fcc->"reserved2", Fyc->format!("0x{:x}", section.reserved2()), c->"-"]);
+    if let Some(reserved3) = section.reserved3() {
+        table.add_row(row![Fcc->"reserved3", Fyc->format!("0x{:x}", reserved3), c->"-"]);
     }
 }
 
-unsafe fn print_dylib_command(command: &DylibCommand, lc_str: String, mut table: &mut Table) {
-    print_lc_cmd_and_cmdsize(command.cmd, command.cmdsize, &mut table);
+unsafe fn print_dylib_command(command: &DylibCommand, lc_str: String, table: &mut Table) {
+    print_lc_cmd_and_cmdsize(command.cmd, command.cmdsize, table);
     table.add_row(row![Frbc->"struct dylib = {", c->"-", c->"-"]);
     table.add_row(row![ Fcc->"name.offset", Fyc->format!("0x{:x}", command.dylib.name.offset),  c->"-"]);
     table.add_row(row![ Fcc->"timestamp", Fyc->format!("0x{:x}", command.dylib.timestamp),  c->"-"]);
@@ -313,12 +318,37 @@ unsafe fn print_dylib_command(command: &DylibCommand, lc_str: String, mut table:
     table.add_row(row![ Fcc->"name (lc_str)", Fyc->"-",  c->lc_str]);
 }
 
+fn print_fvmlib_command(command: &FvmlibCommand, name_offset: u32, lc_str: String, table: &mut Table) {
+    print_lc_cmd_and_cmdsize(command.cmd, command.cmdsize, table);
+    table.add_row(row![Frbc->"struct fvmlib = {", c->"-", c->"-"]);
+    table.add_row(row![ Fcc->"name.offset", Fyc->format!("0x{:x}", name_offset),  c->"-"]);
+    table.add_row(row![ Fcc->"minor_version", Fyc->format!("0x{:x}", command.fvmlib.minor_version),  c->"-"]);
+    table.add_row(row![ Fcc->"header_addr", Fyc->format!("0x{:x}", command.fvmlib.header_addr),  c->"-"]);
+    table.add_row(row![Frbc->"}", c->"-", c->"-"]);
+    table.add_row(row![ Fcc->"name (lc_str)", Fyc->"-",  c->lc_str]);
+}
+
+fn print_fvmfile_command(command: &FvmfileCommand, name_offset: u32, lc_str: String, table: &mut Table) {
+    print_lc_cmd_and_cmdsize(command.cmd, command.cmdsize, table);
+    table.add_row(row![ Fcc->"name.offset", Fyc->format!("0x{:x}", name_offset),  c->"-"]);
+    table.add_row(row![ Fcc->"header_addr", Fyc->format!("0x{:x}", command.header_addr),  c->"-"]);
+    table.add_row(row![ Fcc->"name (lc_str)", Fyc->"-",  c->lc_str]);
+}
+
+fn print_ident_command(command: &IdentCommand, lc_str: &LcStr, table: &mut Table) {
+    print_lc_cmd_and_cmdsize(command.cmd, command.cmdsize, table);
+    let strings = lc_str.split(|&byte| byte == 0).filter(|string| !string.is_empty());
+    for (index, string) in strings.enumerate() {
+        table.add_row(row![ Fcc->format!("ident #{} (lc_str)", index), Fyc->"-",  c->String::from_utf8_lossy(string)]);
+    }
+}
+
 fn print_prebound_dylib_command(command: &PreboundDylibCommand, lc_str: &LcStr, table: &mut Table) {
     print_lc_cmd_and_cmdsize(command.cmd, command.cmdsize, table);
     table.add_row(row![ Fcc->"nmodules", Fyc->format!("0x{:x}", command.nmodules),  c->"-"]);
-    let split_index = lc_str.len() - command.nmodules as usize;
+    let split_index = lc_str.len().saturating_sub(command.nmodules as usize);
     let (name, linked_modules) = split_lc_str_at(lc_str, split_index);
-    table.add_row(row![ Fcc->"name (lc_str)", Fyc->"-",  c->String::from_utf8(name).unwrap()]);
+    table.add_row(row![ Fcc->"name (lc_str)", Fyc->"-",  c->String::from_utf8_lossy(&name)]);
     print_bytes_array("linked_modules (lc_str)", &linked_modules, table);
 }
 
@@ -341,24 +371,13 @@ fn print_thread_state(thread_state: &ThreadState, table: &mut Table) {
     print_bytes_array("state", &thread_state.state, table);
 }
 
-fn print_routines_command_32(command: &RoutinesCommand32, table: &mut Table) {
-    print_common_routines_fields(command.cmd, command.cmdsize, command.init_address as u64, command.init_module as u64, command.reserved1 as u64, command.reserved2 as u64, command.reserved3 as u64, command.reserved4 as u64, command.reserved5 as u64, command.reserved6 as u64, table);
-}
-
-fn print_routines_command_64(command: &RoutinesCommand64, table: &mut Table) {
-    print_common_routines_fields(command.cmd, command.cmdsize, command.init_address, command.init_module, command.reserved1, command.reserved2, command.reserved3, command.reserved4, command.reserved5, command.reserved6, table);
-}
-
-fn print_common_routines_fields(cmd: u32, cmdsize: u32, init_address: u64, init_module: u64, reserved1: u64, reserved2: u64, reserved3: u64, reserved4: u64, reserved5: u64, reserved6: u64, table: &mut Table) {
-    print_lc_cmd_and_cmdsize(cmd, cmdsize, table);
-    table.add_row(row![Fcc->"init_address", Fyc->format!("0x{:x}", init_address), c->"-"]);
-    table.add_row(row![Fcc->"init_module", Fyc->format!("0x{:x}", init_module), c->"-"]);
-    table.add_row(row![Fcc->"reserved1", Fyc->format!("0x{:x}", reserved1), c->"-"]);
-    table.add_row(row![Fcc->"reserved2", Fyc->format!("0x{:x}", reserved2), c->"-"]);
-    table.add_row(row![Fcc->"reserved3", Fyc->format!("0x{:x}", reserved3), c->"-"]);
-    table.add_row(row![Fcc->"reserved4", Fyc->format!("0x{:x}", reserved4), c->"-"]);
-    table.add_row(row![Fcc->"reserved5", Fyc->format!("0x{:x}", reserved5), c->"-"]);
-    table.add_row(row![Fcc->"reserved6", Fyc->format!("0x{:x}", reserved6), c->"-"]);
+fn print_routines_command<R: Routines + ?Sized>(command: &R, table: &mut Table) {
+    print_lc_cmd_and_cmdsize(command.cmd(), command.cmdsize(), table);
+    table.add_row(row![Fcc->"init_address", Fyc->format!("0x{:x}", command.init_address()), c->"-"]);
+    table.add_row(row![Fcc->"init_module", Fyc->format!("0x{:x}", command.init_module()), c->"-"]);
+    for (index, reserved) in command.reserved().iter().enumerate() {
+        table.add_row(row![Fcc->format!("reserved{}", index + 1), Fyc->format!("0x{:x}", reserved), c->"-"]);
+    }
 }
 
 fn print_symtab_command(command: &SymtabCommand, table: &mut Table) {
@@ -413,14 +432,6 @@ fn print_linkedit_data_command(command: &LinkeditDataCommand, table: &mut Table)
     table.add_row(row![ Fcc->"datasize", Fyc->format!("0x{:x}", command.datasize),  c->"-"]);
 }
 
-fn print_encryption_info_command_32(command: &EncryptionInfoCommand32, table: &mut Table) {
-    print_common_encryption_info_fields(command.cmd, command.cmdsize, command.cryptoff, command.cryptsize, command.cryptid, None, table);
-}
-
-fn print_encryption_info_command_64(command: &EncryptionInfoCommand64, table: &mut Table) {
-    print_common_encryption_info_fields(command.cmd, command.cmdsize, command.cryptoff, command.cryptsize, command.cryptid, Some(command.pad), table);
-}
-
 fn print_version_min_command(command: &VersionMinCommand, table: &mut Table) {
     print_lc_cmd_and_cmdsize(command.cmd, command.cmdsize, table);
     table.add_row(row![ Fcc->"version", Fyc->format!("0x{:x}", command.version),  c->"-"]);
@@ -478,13 +489,687 @@ fn print_note_command(command: &NoteCommand, table: &mut Table) {
     table.add_row(row![ Fcc->"size", Fyc->format!("0x{:x}", command.size),  c->"-"]);
 }
 
-fn print_common_encryption_info_fields(cmd: u32, cmdsize: u32, cryptoff: u32, cryptsize: u32, cryptid: u32, pad: Option<u32>, table: &mut Table) {
-    print_lc_cmd_and_cmdsize(cmd, cmdsize, table);
-    table.add_row(row![Fcc->"cryptoff", Fyc->format!("0x{:x}", cryptoff), c->"-"]);
-    table.add_row(row![Fcc->"cryptsize", Fyc->format!("0x{:x}", cryptsize), c->"-"]);
-    table.add_row(row![Fcc->"cryptid", Fyc->format!("0x{:x}", cryptid), c->"-"]);
-    if let Some(p) = pad {
-        table.add_row(row![Fcc->"pad", Fyc->format!("0x{:x}", p), c->"-"]);
+pub fn print_coverage(coverage: &FileCoverage) {
+    let mut table = Table::new();
+    table.add_row(row![FBbc->"File Coverage", c->format!("0x{:x} of 0x{:x} bytes accounted for", coverage.covered_bytes(), coverage.file_size), c->"-"]);
+    table.add_row(row![Bbbc=>"Range", "Size", "Contents"]);
+    let mut rows = Vec::new();
+    for range in &coverage.structures {
+        rows.push((range.offset, row![Fyc->format!("0x{:x}-0x{:x}", range.offset, range.offset.saturating_add(range.size)), c->format!("0x{:x}", range.size), c->&range.name]));
+    }
+    let gaps = coverage.gaps.iter().map(|gap| (gap, "gap")).chain(coverage.trailing.iter().map(|gap| (gap, "trailing data")));
+    for (gap, kind) in gaps {
+        let contents = match gap.nonzero_bytes {
+            0 => format!("{} after {}, all zero", kind, gap.after),
+            count => format!("{} after {}, {} non-zero bytes", kind, gap.after, count),
+        };
+        let range = format!("0x{:x}-0x{:x}", gap.offset, gap.offset + gap.size);
+        if gap.nonzero_bytes == 0 {
+            rows.push((gap.offset, row![Fgc->range, Fgc->format!("0x{:x}", gap.size), Fgc->contents]));
+        } else {
+            rows.push((gap.offset, row![Frc->range, Frc->format!("0x{:x}", gap.size), Frc->contents]));
+        }
+    }
+    rows.sort_by_key(|(offset, _)| *offset);
+    for (_, row) in rows {
+        table.add_row(row);
+    }
+    table.printstd();
+}
+
+pub fn print_linkedit_layout(layout: &LinkeditLayout) {
+    let mut table = Table::new();
+    table.add_row(row![FBbc->"__LINKEDIT", c->format!("fileoff 0x{:x}", layout.fileoff), c->format!("filesize 0x{:x}", layout.filesize), c->"-", c->"-", c->"-", c->"-"]);
+    table.add_row(row![Bbbc=>"#", "Blob", "Offset", "Size", "Alignment", "Share", "Padding Before"]);
+    for (index, entry) in layout.entries.iter().enumerate() {
+        let blob = &entry.blob;
+        let padding = if entry.padding_before == 0 { String::from("-") } else { format!("0x{:x}", entry.padding_before) };
+        table.add_row(row![c->index, Fcc->blob.name, Fyc->format!("0x{:x}", blob.offset), c->format!("0x{:x}", blob.size), c->entry.alignment, c->format!("{:.1}%", layout.percentage(blob.size)), c->padding]);
+    }
+    let padding = layout.padding();
+    table.add_row(row![c->"-", Fcc->"unused padding", c->"-", c->format!("0x{:x}", padding), c->"-", c->format!("{:.1}%", layout.percentage(padding)), c->format!("0x{:x} at the end", layout.trailing_padding)]);
+    table.printstd();
+}
+
+pub fn print_lint(findings: &[Diagnostic]) {
+    let mut table = Table::new();
+    table.add_row(row![FBbc->"Lint", c->format!("{} findings", findings.len()), c->"-"]);
+    table.add_row(row![Bbbc=>"Severity", "Offset", "Finding"]);
+    for finding in findings {
+        let offset = finding.offset.map_or_else(|| String::from("-"), |offset| format!("0x{:x}", offset));
+        table.add_row(row![Fcc->finding.severity, Fyc->offset, c->&finding.message]);
+    }
+    table.printstd();
+}
+
+pub fn print_notes(notes: &[Note]) {
+    let mut table = Table::new();
+
+    print_common_title("Notes", &mut table);
+    for (index, note) in notes.iter().enumerate() {
+        table.add_row(row![Fmbc->format!("Note #{}", index), c->"-", c->"-"]);
+        table.add_row(row![ Fcc->"data_owner", Fyc->note.data_owner, c->"-"]);
+        table.add_row(row![ Fcc->"offset", Fyc->format!("0x{:x}", note.offset), c->"-"]);
+        table.add_row(row![ Fcc->"size", Fyc->format!("0x{:x}", note.size), c->"-"]);
+        match &note.data {
+            NoteData::AddrableBits(data) => print_addrable_bits_note(data, &mut table),
+            NoteData::MainBinSpec(data) => print_main_bin_spec_note(data, &mut table),
+            NoteData::LoadBinary(data) => print_load_binary_note(data, &mut table),
+            NoteData::AllImageInfos(data) => print_all_image_infos_note(data, &mut table),
+            NoteData::ProcessMetadata(json) => { table.add_row(row![ Fcc->"process metadata", Fyc->"-", json]); },
+            NoteData::KernVerStr(data) => print_kern_ver_str_note(data, &mut table),
+            NoteData::Unknown(bytes) => { table.add_row(row![ Fcc->"data", Fyc->"-", format_hex_dump(bytes)]); },
+        }
+        table.add_row(row![c=>"-", "-", "-"]);
+    }
+    table.printstd();
+}
+
+fn print_addrable_bits_note(note: &AddrableBitsNote, table: &mut Table) {
+    table.add_row(row![ Fcc->"version", Fyc->format!("0x{:x}", note.version), c->"-"]);
+    match (note.lo_addressing_bits, note.hi_addressing_bits) {
+        (Some(lo), Some(hi)) => {
+            table.add_row(row![ Fcc->"lo_addressing_bits", Fyc->lo, c->"Addressing bits for low (user) memory"]);
+            table.add_row(row![ Fcc->"hi_addressing_bits", Fyc->hi, c->"Addressing bits for high (kernel) memory"]);
+        }
+        _ => {
+            table.add_row(row![ Fcc->"addressing_bits", Fyc->note.addressing_bits, c->"Number of bits used for addressing"]);
+        }
+    }
+}
+
+fn print_main_bin_spec_note(note: &MainBinSpecNote, table: &mut Table) {
+    let bin_type_string = match note.bin_type {
+        MAIN_BIN_SPEC_TYPE_UNKNOWN => "Unknown",
+        MAIN_BIN_SPEC_TYPE_KERNEL => "Kernel",
+        MAIN_BIN_SPEC_TYPE_USER_PROCESS => "User process",
+        MAIN_BIN_SPEC_TYPE_STANDALONE => "Standalone binary",
+        _ => "Unrecognized binary type!",
+    };
+    table.add_row(row![ Fcc->"version", Fyc->format!("0x{:x}", note.version), c->"-"]);
+    table.add_row(row![ Fcc->"type", Fyc->format!("0x{:x}", note.bin_type), c->bin_type_string]);
+    table.add_row(row![ Fcc->"address", Fyc->format!("0x{:x}", note.address), c->"-"]);
+    if let Some(slide) = note.slide {
+        table.add_row(row![ Fcc->"slide", Fyc->format!("0x{:x}", slide), c->"-"]);
+    }
+    print_bytes_array("uuid", &note.uuid, table);
+    table.add_row(row![ Fcc->"log2_pagesize", Fyc->format!("0x{:x}", note.log2_pagesize), c->"-"]);
+    if let Some(platform) = note.platform {
+        table.add_row(row![ Fcc->"platform", Fyc->format!("0x{:x}", platform), c->"-"]);
+    }
+}
+
+fn print_load_binary_note(note: &LoadBinaryNote, table: &mut Table) {
+    table.add_row(row![ Fcc->"version", Fyc->format!("0x{:x}", note.version), c->"-"]);
+    print_bytes_array("uuid", &note.uuid, table);
+    table.add_row(row![ Fcc->"load_address", Fyc->format!("0x{:x}", note.load_address), c->"-"]);
+    table.add_row(row![ Fcc->"slide", Fyc->format!("0x{:x}", note.slide), c->"-"]);
+    table.add_row(row![ Fcc->"name", Fyc->"-", c->note.name]);
+}
+
+fn print_all_image_infos_note(note: &AllImageInfosNote, table: &mut Table) {
+    table.add_row(row![ Fcc->"version", Fyc->format!("0x{:x}", note.version), c->"-"]);
+    table.add_row(row![ Fcc->"imgcount", Fyc->format!("0x{:x}", note.imgcount), c->"-"]);
+    table.add_row(row![ Fcc->"entries_fileoff", Fyc->format!("0x{:x}", note.entries_fileoff), c->"-"]);
+    table.add_row(row![ Fcc->"entries_size", Fyc->format!("0x{:x}", note.entries_size), c->"-"]);
+    for (index, image) in note.images.iter().enumerate() {
+        table.add_row(row![Fgbc->format!("Image #{}", index), c->"-", c->"-"]);
+        table.add_row(row![ Fcc->"filepath", Fyc->"-", c->image.filepath]);
+        print_bytes_array("uuid", &image.uuid, table);
+        table.add_row(row![ Fcc->"load_address", Fyc->format!("0x{:x}", image.load_address), c->"-"]);
+        for segment in &image.segments {
+            print_bytes_array("segname", &segment.segname, table);
+            table.add_row(row![ Fcc->"vmaddr", Fyc->format!("0x{:x}", segment.vmaddr), c->"-"]);
+        }
+    }
+}
+
+fn print_kern_ver_str_note(note: &KernVerStrNote, table: &mut Table) {
+    table.add_row(row![ Fcc->"version", Fyc->format!("0x{:x}", note.version), c->"-"]);
+    table.add_row(row![ Fcc->"version_string", Fyc->"-", c->note.version_string]);
+}
+
+pub fn print_core_file(core_file: &CoreFile) {
+    let mut table = Table::new();
+
+    print_common_title("Core File Threads", &mut table);
+    for (index, thread) in core_file.threads.iter().enumerate() {
+        table.add_row(row![Fmbc->format!("Thread #{}", index), c->"-", c->"-"]);
+        for state in &thread.states {
+            table.add_row(row![Fgbc->state.flavor_name, Fyc->format!("0x{:x}", state.flavor), c->"-"]);
+            match &state.registers {
+                Some(registers) => {
+                    for register in registers {
+                        let mapping = match core_file.vm_to_file_offset(register.value) {
+                            Some(offset) => format!("points into core (file offset 0x{:x})", offset),
+                            None => String::from("-"),
+                        };
+                        table.add_row(row![ Fcc->register.name, Fyc->format!("0x{:016x}", register.value), c->mapping]);
+                    }
+                }
+                None => print_bytes_array("state", &state.raw, &mut table),
+            }
+        }
+        table.add_row(row![c=>"-", "-", "-"]);
+    }
+    table.printstd();
+
+    let mut table = Table::new();
+    table.add_row(row![FBbc->"Core File Memory Regions", c->"-", c->"-", c->"-", c->"-"]);
+    table.add_row(row![Bbbc=>"Segment", "VM Range", "File Range", "Zero Filled", "Protection"]);
+    for region in &core_file.memory_regions {
+        table.add_row(row![
+            c->String::from_utf8_lossy(&region.segname).trim_end_matches('\0'),
+            Fyc->format!("0x{:x}-0x{:x}", region.vmaddr, region.vmaddr.saturating_add(region.vmsize)),
+            Fyc->format!("0x{:x}-0x{:x}", region.fileoff, region.fileoff.saturating_add(region.filesize)),
+            c->format!("0x{:x}", region.vmsize.saturating_sub(region.filesize)),
+            c->format_protection(region.initprot),
+        ]);
+    }
+    table.printstd();
+}
+
+pub fn print_memory(vmaddr: u64, memory: &[u8]) {
+    let mut table = Table::new();
+    table.add_row(row![FBbc->format!("Memory at 0x{:x} (0x{:x} bytes)", vmaddr, memory.len())]);
+    table.add_row(row![format_hex_dump(memory)]);
+    table.printstd();
+}
+
+fn format_protection(prot: i32) -> String {
+    let read = if prot & VM_PROT_READ != 0 { 'r' } else { '-' };
+    let write = if prot & VM_PROT_WRITE != 0 { 'w' } else { '-' };
+    let execute = if prot & VM_PROT_EXECUTE != 0 { 'x' } else { '-' };
+    format!("{}{}{}", read, write, execute)
+}
+
+pub fn print_split_info(split_info: &SplitSegInfo) {
+    let mut table = Table::new();
+    let cross_segment_count = split_info.references.iter().filter(|reference| reference.is_cross_segment()).count();
+
+    table.add_row(row![FBbc->"Segment Split Info", c->format!("v{}", split_info.version), c->format!("{} references", split_info.references.len()), c->format!("{} cross-segment", cross_segment_count)]);
+    table.add_row(row![Bbbc=>"#", "Kind", "From", "To"]);
+    for (index, reference) in split_info.references.iter().enumerate() {
+        let to = match &reference.to {
+            Some(to) => format_split_seg_location(to),
+            None => String::from("-"),
+        };
+        table.add_row(row![c->index, Fyc->split_seg_kind_name(split_info.version, reference.kind), c->format_split_seg_location(&reference.from), c->to]);
+    }
+    table.printstd();
+}
+
+fn format_split_seg_location(location: &SplitSegLocation) -> String {
+    format!("0x{:x}\n({},{})", location.address, location.segname, location.sectname)
+}
+
+fn split_seg_kind_name(version: u32, kind: u64) -> String {
+    if version == 1 {
+        let name = match kind {
+            1 => "32-bit pointer",
+            2 => "64-bit pointer",
+            3 => "ppc hi16",
+            4 => "32-bit offset to IMPORT",
+            5 => "thumb2 movw",
+            6 => "arm movw",
+            0x10..=0x1F => "thumb2 movt",
+            0x20..=0x2F => "arm movt",
+            _ => "Unrecognized kind!",
+        };
+        return format!("0x{:x}\n({})", kind, name);
+    }
+    let name = match kind {
+        DYLD_CACHE_ADJ_V2_POINTER_32 => "DYLD_CACHE_ADJ_V2_POINTER_32",
+        DYLD_CACHE_ADJ_V2_POINTER_64 => "DYLD_CACHE_ADJ_V2_POINTER_64",
+        DYLD_CACHE_ADJ_V2_DELTA_32 => "DYLD_CACHE_ADJ_V2_DELTA_32",
+        DYLD_CACHE_ADJ_V2_DELTA_64 => "DYLD_CACHE_ADJ_V2_DELTA_64",
+        DYLD_CACHE_ADJ_V2_ARM64_ADRP => "DYLD_CACHE_ADJ_V2_ARM64_ADRP",
+        DYLD_CACHE_ADJ_V2_ARM64_OFF12 => "DYLD_CACHE_ADJ_V2_ARM64_OFF12",
+        DYLD_CACHE_ADJ_V2_ARM64_BR26 => "DYLD_CACHE_ADJ_V2_ARM64_BR26",
+        DYLD_CACHE_ADJ_V2_ARM_MOVW_MOVT => "DYLD_CACHE_ADJ_V2_ARM_MOVW_MOVT",
+        DYLD_CACHE_ADJ_V2_ARM_BR24 => "DYLD_CACHE_ADJ_V2_ARM_BR24",
+        DYLD_CACHE_ADJ_V2_THUMB_MOVW_MOVT => "DYLD_CACHE_ADJ_V2_THUMB_MOVW_MOVT",
+        DYLD_CACHE_ADJ_V2_THUMB_BR22 => "DYLD_CACHE_ADJ_V2_THUMB_BR22",
+        DYLD_CACHE_ADJ_V2_IMAGE_OFF_32 => "DYLD_CACHE_ADJ_V2_IMAGE_OFF_32",
+        DYLD_CACHE_ADJ_V2_THREADED_POINTER_64 => "DYLD_CACHE_ADJ_V2_THREADED_POINTER_64",
+        _ => "Unrecognized kind!",
+    };
+    format!("0x{:x}\n({})", kind, name)
+}
+
+pub fn print_linker_optimization_hints(hints: &[LinkerOptimizationHint], demangle: Option<DemangleOptions>) {
+    let mut table = Table::new();
+
+    table.add_row(row![FBbc->"Linker Optimization Hints", c->format!("{} hints", hints.len()), c->"-"]);
+    table.add_row(row![Bbbc=>"#", "Kind", "Addresses"]);
+    for (index, hint) in hints.iter().enumerate() {
+        let mut addresses: Vec<String> = hint.addresses.iter().map(|address| format_loh_address(address, demangle)).collect();
+        if let Some(expected) = hint.expected_address_count() {
+            if expected != hint.addresses.len() {
+                addresses.push(format!("(expected {} addresses, found {})", expected, hint.addresses.len()));
+            }
+        }
+        table.add_row(row![c->index, Fyc->loh_kind_name(hint.kind), addresses.join("\n")]);
+    }
+    table.printstd();
+}
+
+fn format_loh_address(address: &LohAddress, demangle: Option<DemangleOptions>) -> String {
+    let section = match &address.section {
+        Some((segname, sectname)) => format!(" ({},{})", segname, sectname),
+        None => String::from(" (no section)"),
+    };
+    let symbol = match &address.symbol {
+        Some((name, 0)) => format!(" {}", format_symbol_name(name, demangle)),
+        Some((name, offset)) => format!(" {}+0x{:x}", format_symbol_name(name, demangle), offset),
+        None => String::new(),
+    };
+    format!("0x{:x}{}{}", address.address, section, symbol)
+}
+
+fn loh_kind_name(kind: u64) -> String {
+    let name = match kind {
+        LOH_ARM64_ADRP_ADRP => "AdrpAdrp",
+        LOH_ARM64_ADRP_LDR => "AdrpLdr",
+        LOH_ARM64_ADRP_ADD_LDR => "AdrpAddLdr",
+        LOH_ARM64_ADRP_LDR_GOT_LDR => "AdrpLdrGotLdr",
+        LOH_ARM64_ADRP_ADD_STR => "AdrpAddStr",
+        LOH_ARM64_ADRP_LDR_GOT_STR => "AdrpLdrGotStr",
+        LOH_ARM64_ADRP_ADD => "AdrpAdd",
+        LOH_ARM64_ADRP_LDR_GOT => "AdrpLdrGot",
+        _ => "Unrecognized kind!",
+    };
+    format!("0x{:x}\n({})", kind, name)
+}
+
+pub fn print_objc(metadata: &ObjcMetadata) {
+    let mut table = Table::new();
+    table.add_row(row![FBbc->"Objective-C Metadata", c->format!("{} classes, {} categories, {} protocols", metadata.classes.len(), metadata.categories.len(), metadata.protocols.len()), c->"-"]);
+    match &metadata.image_info {
+        Some(image_info) => {
+            table.add_row(row![Fcc->"version", Fyc->image_info.version, c->"-"]);
+            table.add_row(row![Fcc->"flags", Fyc->format!("0x{:x}", image_info.flags), c->format!("swift version {}", image_info.swift_version())]);
+        }
+        None => {
+            table.add_row(row![Fcc->"image info", c->"no __objc_imageinfo section", c->"-"]);
+        }
+    }
+    table.printstd();
+
+    for class in &metadata.classes {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Class", Fyc->&class.name, c->format!("0x{:x}", class.address)]);
+        table.add_row(row![Fcc->"superclass", Fyc->class.superclass.as_deref().unwrap_or("-"), c->"-"]);
+        table.add_row(row![Fcc->"flags", Fyc->format!("0x{:x}", class.flags), c->"-"]);
+        table.add_row(row![Fcc->"instance start", Fyc->format!("0x{:x}", class.instance_start), c->"-"]);
+        table.add_row(row![Fcc->"instance size", Fyc->format!("0x{:x}", class.instance_size), c->"-"]);
+        print_objc_protocol_names(&class.protocols, &mut table);
+        print_objc_methods("Instance Methods", &class.instance_methods, &mut table);
+        print_objc_methods("Class Methods", &class.class_methods, &mut table);
+        if !class.ivars.is_empty() {
+            table.add_row(row![Fmbc->"Ivars", c->"-", c->"-"]);
+            table.add_row(row![Bbbc=>"Name", "Type", "Offset / Size / Alignment"]);
+            for ivar in &class.ivars {
+                let offset = ivar.offset.map_or(String::from("-"), |offset| format!("0x{:x}", offset));
+                table.add_row(row![Fcc->&ivar.name, Fyc->&ivar.type_encoding, c->format!("{} / 0x{:x} / {}", offset, ivar.size, ivar.alignment)]);
+            }
+        }
+        print_objc_properties(&class.properties, &mut table);
+        table.printstd();
+    }
+
+    for category in &metadata.categories {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Category", Fyc->&category.name, c->format!("0x{:x}", category.address)]);
+        table.add_row(row![Fcc->"class", Fyc->category.class_name.as_deref().unwrap_or("-"), c->"-"]);
+        print_objc_protocol_names(&category.protocols, &mut table);
+        print_objc_methods("Instance Methods", &category.instance_methods, &mut table);
+        print_objc_methods("Class Methods", &category.class_methods, &mut table);
+        print_objc_properties(&category.properties, &mut table);
+        table.printstd();
+    }
+
+    for protocol in &metadata.protocols {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Protocol", Fyc->&protocol.name, c->format!("0x{:x}", protocol.address)]);
+        print_objc_protocol_names(&protocol.protocols, &mut table);
+        print_objc_methods("Instance Methods", &protocol.instance_methods, &mut table);
+        print_objc_methods("Class Methods", &protocol.class_methods, &mut table);
+        print_objc_methods("Optional Instance Methods", &protocol.optional_instance_methods, &mut table);
+        print_objc_methods("Optional Class Methods", &protocol.optional_class_methods, &mut table);
+        print_objc_properties(&protocol.properties, &mut table);
+        table.printstd();
+    }
+
+    print_objc_references("Selector References", &metadata.selector_refs);
+    print_objc_references("Class References", &metadata.class_refs);
+    print_objc_references("Super References", &metadata.super_refs);
+}
+
+pub fn print_objc_usage(report: &ObjcUsageReport) {
+    let mut table = Table::new();
+    let total_cost: u64 = report.unused_classes.iter().fold(0, |total, class| total.saturating_add(class.cost));
+    table.add_row(row![FBbc->"Unused Classes", c->format!("{} candidates", report.unused_classes.len()), c->format!("~{} bytes", total_cost)]);
+    table.add_row(row![Bbbc=>"Name", "Address", "Cost (bytes)"]);
+    for class in &report.unused_classes {
+        table.add_row(row![Fcc->&class.name, Fyc->format!("0x{:x}", class.address), c->class.cost]);
+    }
+    table.printstd();
+
+    let mut table = Table::new();
+    let total_cost: u64 = report.unused_methods.iter().fold(0, |total, method| total.saturating_add(method.cost));
+    table.add_row(row![FBbc->"Unused Methods", c->format!("{} candidates", report.unused_methods.len()), c->format!("~{} bytes", total_cost)]);
+    table.add_row(row![Bbbc=>"Method", "Implementation", "Cost (bytes)"]);
+    for method in &report.unused_methods {
+        let kind = if method.is_class_method { '+' } else { '-' };
+        table.add_row(row![Fcc->format!("{}[{} {}]", kind, method.owner, method.name), Fyc->format!("0x{:x}", method.implementation), c->method.cost]);
+    }
+    table.printstd();
+}
+
+pub fn print_swift(metadata: &SwiftMetadata, demangle: Option<DemangleOptions>) {
+    let mut table = Table::new();
+    table.add_row(row![FBbc->"Swift Metadata", c->format!("{} types, {} protocols, {} conformances", metadata.types.len(), metadata.protocols.len(), metadata.conformances.len()), c->"-"]);
+    table.printstd();
+
+    for swift_type in &metadata.types {
+        let (kind, field_title) = match swift_type.kind {
+            SwiftTypeKind::Class => ("Class", "Fields"),
+            SwiftTypeKind::Struct => ("Struct", "Fields"),
+            SwiftTypeKind::Enum => ("Enum", "Cases"),
+        };
+        let mut table = Table::new();
+        table.add_row(row![FBbc->kind, Fyc->&swift_type.name, c->format!("0x{:x}", swift_type.address)]);
+        if let Some(superclass) = &swift_type.superclass {
+            table.add_row(row![Fcc->"superclass", Fyc->format_swift_name(superclass, demangle), c->"-"]);
+        }
+        if !swift_type.conformances.is_empty() {
+            let conformances: Vec<String> = swift_type.conformances.iter().map(|protocol| format_swift_name(protocol, demangle)).collect();
+            table.add_row(row![Fcc->"conformances", Fyc->conformances.join("\n"), c->"-"]);
+        }
+        if !swift_type.fields.is_empty() {
+            table.add_row(row![Fmbc->field_title, c->"-", c->"-"]);
+            table.add_row(row![Bbbc=>"Name", "Type", "Flags"]);
+            for field in &swift_type.fields {
+                let flags = match (swift_type.kind, field.is_var, field.is_indirect) {
+                    (SwiftTypeKind::Enum, _, true) => "indirect",
+                    (SwiftTypeKind::Enum, _, false) => "-",
+                    (_, true, _) => "var",
+                    (_, false, _) => "let",
+                };
+                table.add_row(row![Fcc->&field.name, Fyc->format_swift_name(&field.type_name, demangle), c->flags]);
+            }
+        }
+        table.printstd();
+    }
+
+    let mut table = Table::new();
+    table.add_row(row![FBbc->"Protocols", c->format!("{} protocols", metadata.protocols.len()), c->"-"]);
+    table.add_row(row![Bbbc=>"Name", "Requirements", "Associated Types"]);
+    for protocol in &metadata.protocols {
+        table.add_row(row![Fcc->&protocol.name, Fyc->protocol.num_requirements, c->protocol.associated_type_names.join("\n")]);
+    }
+    table.printstd();
+
+    let mut table = Table::new();
+    table.add_row(row![FBbc->"Conformances", c->format!("{} conformances", metadata.conformances.len()), c->"-"]);
+    table.add_row(row![Bbbc=>"Type", "Protocol", "Descriptor"]);
+    for conformance in &metadata.conformances {
+        table.add_row(row![Fcc->format_swift_name(&conformance.type_name, demangle), Fyc->format_swift_name(&conformance.protocol, demangle), c->format!("0x{:x}", conformance.address)]);
+    }
+    table.printstd();
+
+    if !metadata.associated_types.is_empty() {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Associated Types", c->"-", c->"-"]);
+        table.add_row(row![Bbbc=>"Conforming Type", "Protocol", "Types"]);
+        for associated_types in &metadata.associated_types {
+            let types: Vec<String> = associated_types.types.iter().map(|(name, type_name)| format!("{} = {}", name, format_swift_name(type_name, demangle))).collect();
+            table.add_row(row![Fcc->format_swift_name(&associated_types.conforming_type, demangle), Fyc->format_swift_name(&associated_types.protocol, demangle), c->types.join("\n")]);
+        }
+        table.printstd();
+    }
+
+    if !metadata.builtin_types.is_empty() {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Builtin Types", c->"-", c->"-", c->"-", c->"-"]);
+        table.add_row(row![Bbbc=>"Type", "Size", "Alignment", "Stride", "Extra Inhabitants"]);
+        for builtin_type in &metadata.builtin_types {
+            table.add_row(row![Fcc->format_swift_name(&builtin_type.type_name, demangle), Fyc->builtin_type.size, c->builtin_type.alignment, c->builtin_type.stride, c->builtin_type.num_extra_inhabitants]);
+        }
+        table.printstd();
+    }
+}
+
+pub fn print_go(metadata: &GoMetadata) {
+    if let Some(build_info) = &metadata.build_info {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Go Build Info", c->&build_info.go_version, c->"-"]);
+        if let Some(path) = &build_info.path {
+            table.add_row(row![Fcc->"path", Fyc->path, c->"-"]);
+        }
+        if let Some(module) = &build_info.main_module {
+            table.add_row(row![Fcc->"main module", Fyc->format_go_module(module), c->"-"]);
+        }
+        if !build_info.settings.is_empty() {
+            table.add_row(row![Fmbc->"Build Settings", c->"-", c->"-"]);
+            for (key, value) in &build_info.settings {
+                table.add_row(row![Fcc->key, Fyc->value, c->"-"]);
+            }
+        }
+        table.printstd();
+
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Go Dependencies", c->format!("{} modules", build_info.dependencies.len()), c->"-"]);
+        table.add_row(row![Bbbc=>"Module", "Version", "Checksum"]);
+        for dependency in &build_info.dependencies {
+            // A replaced module is verified against the checksum of its replacement.
+            let sum = dependency.replacement.as_ref().map_or(&dependency.sum, |replacement| &replacement.sum);
+            table.add_row(row![Fcc->&dependency.path, Fyc->format_go_module_version(dependency), c->if sum.is_empty() { "-" } else { sum }]);
+        }
+        table.printstd();
+    }
+
+    if let Some(line_table) = &metadata.line_table {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Go Functions", c->format!("{} functions, pclntab {}", line_table.functions.len(), line_table.version), c->"-"]);
+        table.add_row(row![Bbbc=>"Entry", "Name", "Lines"]);
+        for function in &line_table.functions {
+            let lines: Vec<String> = function.lines.iter().map(|line| format!("0x{:x} {}:{}", line.address, line.file, line.line)).collect();
+            table.add_row(row![Fcc->format!("0x{:x}", function.entry), Fyc->&function.name, c->lines.join("\n")]);
+        }
+        table.printstd();
+    }
+}
+
+fn format_go_module(module: &GoModule) -> String {
+    if module.version.is_empty() {
+        return module.path.clone();
+    }
+    format!("{} {}", module.path, module.version)
+}
+
+fn format_go_module_version(module: &GoModule) -> String {
+    let version = if module.version.is_empty() { "-" } else { &module.version };
+    match &module.replacement {
+        Some(replacement) => format!("{}\n=> {}", version, format_go_module(replacement)),
+        None => version.to_string(),
+    }
+}
+
+pub fn print_symbols(mach_o: &MachO, demangle: Option<DemangleOptions>) {
+    let symtab = &mach_o.symtab;
+    let strtab = &mach_o.strtab;
+    let sections: Vec<&Section> = mach_o.sections().collect();
+
+    let mut table = Table::new();
+    table.add_row(row![FBbc->"Symbol Table", c->format!("{} symbols", symtab.len()), c->"-", c->"-"]);
+    table.add_row(row![Bbbc=>"Value", "Type", "Section", "Name"]);
+    for nlist in symtab {
+        let section = match nlist.n_sect() {
+            0 => String::from("-"),
+            n_sect => match sections.get(n_sect as usize - 1) {
+                Some(section) => format!("{},{}", fixed_bytes_to_string(section.segname()), fixed_bytes_to_string(section.sectname())),
+                None => format!("{} (invalid)", n_sect),
+            },
+        };
+        let name = format_symbol_name(&symbol_name(strtab, nlist.n_strx()), demangle);
+        table.add_row(row![c->format!("0x{:x}", nlist.n_value()), Fyc->format_symbol_type(nlist.n_type()), c->section, Fcc->name]);
+    }
+    table.printstd();
+
+    {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Exports", c->format!("{} symbols", mach_o.exports.len()), c->"-"]);
+        table.add_row(row![Bbbc=>"Offset", "Flags", "Name"]);
+        for export in &mach_o.exports {
+            let target = match (&export.reexport, export.resolver) {
+                (Some((ordinal, imported_name)), _) if imported_name.is_empty() => format!("-\n(re-export from dylib #{})", ordinal),
+                (Some((ordinal, imported_name)), _) => format!("-\n(re-export of {} from dylib #{})", format_symbol_name(imported_name, demangle), ordinal),
+                (None, Some(resolver)) => format!("0x{:x}\n(resolver 0x{:x})", export.address, resolver),
+                (None, None) => format!("0x{:x}", export.address),
+            };
+            table.add_row(row![c->target, Fyc->format_export_flags(export.flags), Fcc->format_symbol_name(&export.name, demangle)]);
+        }
+        table.printstd();
+    }
+
+    if !mach_o.binds.is_empty() {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Binds", c->format!("{} binds", mach_o.binds.len())]);
+        table.add_row(row![Bbbc=>"Address", "Symbol"]);
+        for bind in &mach_o.binds {
+            table.add_row(row![c->format!("0x{:x}", bind.address), Fcc->format_symbol_name(&bind.symbol_name, demangle)]);
+        }
+        table.printstd();
+    }
+
+    let stubs = symbol_stubs(&sections, &mach_o.indirect_symbols, symtab, strtab);
+    if !stubs.is_empty() {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Stubs", c->format!("{} stubs", stubs.len())]);
+        table.add_row(row![Bbbc=>"Address", "Symbol"]);
+        for stub in &stubs {
+            table.add_row(row![c->format!("0x{:x}", stub.address), Fcc->format_symbol_name(&stub.symbol_name, demangle)]);
+        }
+        table.printstd();
+    }
+
+    if let Some(chained_fixups) = &mach_o.chained_fixups {
+        let mut table = Table::new();
+        table.add_row(row![FBbc->"Chained Fixup Imports", c->format!("{} imports", chained_fixups.imports.len())]);
+        table.add_row(row![Bbbc=>"Ordinal", "Symbol"]);
+        for (ordinal, name) in chained_fixups.imports.iter().enumerate() {
+            table.add_row(row![c->ordinal, Fcc->format_symbol_name(name, demangle)]);
+        }
+        table.printstd();
+    }
+}
+
+fn format_symbol_type(n_type: u8) -> String {
+    if n_type & N_STAB != 0 {
+        return format!("0x{:02x}\n(N_STAB)", n_type);
+    }
+    let kind = match n_type & N_TYPE {
+        N_UNDF => "N_UNDF",
+        N_ABS => "N_ABS",
+        N_SECT => "N_SECT",
+        N_PBUD => "N_PBUD",
+        N_INDR => "N_INDR",
+        _ => "Unrecognized type!",
+    };
+    let mut names = vec![kind];
+    if n_type & N_PEXT != 0 {
+        names.push("N_PEXT");
+    }
+    if n_type & N_EXT != 0 {
+        names.push("N_EXT");
+    }
+    format!("0x{:02x}\n({})", n_type, names.join(" | "))
+}
+
+fn format_export_flags(flags: u64) -> String {
+    let kind = match flags & EXPORT_SYMBOL_FLAGS_KIND_MASK {
+        EXPORT_SYMBOL_FLAGS_KIND_REGULAR => "REGULAR",
+        EXPORT_SYMBOL_FLAGS_KIND_THREAD_LOCAL => "THREAD_LOCAL",
+        EXPORT_SYMBOL_FLAGS_KIND_ABSOLUTE => "ABSOLUTE",
+        _ => "Unrecognized kind!",
+    };
+    let mut names = vec![kind];
+    let options = [
+        (EXPORT_SYMBOL_FLAGS_WEAK_DEFINITION, "WEAK_DEFINITION"),
+        (EXPORT_SYMBOL_FLAGS_REEXPORT, "REEXPORT"),
+        (EXPORT_SYMBOL_FLAGS_STUB_AND_RESOLVER, "STUB_AND_RESOLVER"),
+    ];
+    names.extend(options.iter().filter(|(flag, _)| flags & flag != 0).map(|(_, name)| *name));
+    format!("0x{:x}\n({})", flags, names.join(" | "))
+}
+
+fn format_symbol_name(name: &str, demangle: Option<DemangleOptions>) -> String {
+    let Some(options) = demangle else {
+        return name.to_string();
+    };
+    demangle_symbol(name, options).unwrap_or_else(|| name.to_string())
+}
+
+// Swift metadata refers to types by mangled name and to imported protocols and types by the
+// symbol of their descriptor.
+fn format_swift_name(name: &str, demangle: Option<DemangleOptions>) -> String {
+    let Some(options) = demangle else {
+        return name.to_string();
+    };
+    if let Some(demangled) = demangle_symbol(name, options) {
+        let descriptors = ["protocol descriptor for ", "nominal type descriptor for ", "type metadata for "];
+        return descriptors.iter().find_map(|prefix| demangled.strip_prefix(prefix)).map_or(demangled.clone(), str::to_string);
+    }
+    demangle_type(name).unwrap_or_else(|| name.to_string())
+}
+
+fn print_objc_protocol_names(protocols: &[String], table: &mut Table) {
+    if !protocols.is_empty() {
+        table.add_row(row![Fcc->"protocols", Fyc->protocols.join("\n"), c->"-"]);
+    }
+}
+
+fn print_objc_methods(title: &str, methods: &[ObjcMethod], table: &mut Table) {
+    if methods.is_empty() {
+        return;
+    }
+    table.add_row(row![Fmbc->title, c->"-", c->"-"]);
+    table.add_row(row![Bbbc=>"Name", "Types", "Implementation"]);
+    for method in methods {
+        table.add_row(row![Fcc->&method.name, Fyc->&method.types, c->format!("0x{:x}", method.implementation)]);
+    }
+}
+
+fn print_objc_properties(properties: &[ObjcProperty], table: &mut Table) {
+    if properties.is_empty() {
+        return;
+    }
+    table.add_row(row![Fmbc->"Properties", c->"-", c->"-"]);
+    table.add_row(row![Bbbc=>"Name", "Attributes", "-"]);
+    for property in properties {
+        table.add_row(row![Fcc->&property.name, Fyc->&property.attributes, c->"-"]);
+    }
+}
+
+fn print_objc_references(title: &str, references: &[ObjcReference]) {
+    let mut table = Table::new();
+    table.add_row(row![FBbc->title, c->format!("{} references", references.len())]);
+    table.add_row(row![Bbbc=>"Address", "Target"]);
+    for reference in references {
+        table.add_row(row![c->format!("0x{:x}", reference.address), Fyc->reference.name.as_deref().unwrap_or("-")]);
+    }
+    table.printstd();
+}
+
+fn print_encryption_info_command<E: EncryptionInfo + ?Sized>(command: &E, table: &mut Table) {
+    print_lc_cmd_and_cmdsize(command.cmd(), command.cmdsize(), table);
+    table.add_row(row![Fcc->"cryptoff", Fyc->format!("0x{:x}", command.cryptoff()), c->"-"]);
+    table.add_row(row![Fcc->"cryptsize", Fyc->format!("0x{:x}", command.cryptsize()), c->"-"]);
+    table.add_row(row![Fcc->"cryptid", Fyc->format!("0x{:x}", command.cryptid()), c->"-"]);
+    if let Some(pad) = command.pad() {
+        table.add_row(row![Fcc->"pad", Fyc->format!("0x{:x}", pad), c->"-"]);
     }
 }
 
@@ -494,60 +1179,7 @@ fn print_common_title(title: &str, table: &mut Table) {
 }
 
 fn print_lc_cmd_and_cmdsize(cmd: u32, cmdsize: u32, table: &mut Table) {
-    let cmd_string = match cmd {
-        LC_SEGMENT => "LC_SEGMENT",
-        LC_SYMTAB => "LC_SYMTAB",
-        LC_SYMSEG => "LC_SYMSEG",
-        LC_THREAD => "LC_THREAD",
-        LC_UNIXTHREAD => "LC_UNIXTHREAD",
-        LC_LOADFVMLIB => "LC_LOADFVMLIB",
-        LC_IDFVMLIB =>"LC_IDFVMLIB",
-        LC_IDENT => "LC_IDENT",
-        LC_FVMFILE => "LC_FVMFILE",
-        LC_PREPAGE => "LC_PREPAGE",
-        LC_DYSYMTAB => "LC_DYSYMTAB",
-        LC_LOAD_DYLIB => "LC_LOAD_DYLIB",
-        LC_ID_DYLIB => "LC_ID_DYLIB",
-        LC_LOAD_DYLINKER => "LC_LOAD_DYLINKER",
-        LC_ID_DYLINKER => "LC_ID_DYLINKER",
-        LC_PREBOUND_DYLIB => "LC_PREBOUND_DYLIB",
-        LC_ROUTINES => "LC_ROUTINES",
-        LC_SUB_FRAMEWORK => "LC_SUB_FRAMEWORK",
-        LC_SUB_UMBRELLA => "LC_SUB_UMBRELLA",
-        LC_SUB_CLIENT => "LC_SUB_CLIENT",
-        LC_SUB_LIBRARY => "LC_SUB_LIBRARY",
-        LC_TWOLEVEL_HINTS => "LC_TWOLEVEL_HINTS",
-        LC_PREBIND_CKSUM => "LC_PREBIND_CKSUM",
-        LC_LOAD_WEAK_DYLIB => "LC_LOAD_WEAK_DYLIB",
-        LC_SEGMENT_64 => "LC_SEGMENT_64",
-        LC_ROUTINES_64 => "LC_ROUTINES_64",
-        LC_UUID => "LC_UUID",
-        LC_RPATH => "LC_RPATH",
-        LC_CODE_SIGNATURE => "LC_CODE_SIGNATURE",
-        LC_SEGMENT_SPLIT_INFO => "LC_SEGMENT_SPLIT_INFO",
-        LC_REEXPORT_DYLIB => "LC_REEXPORT_DYLIB",
-        LC_LAZY_LOAD_DYLIB => "LC_LAZY_LOAD_DYLIB",
-        LC_ENCRYPTION_INFO => "LC_ENCRYPTION_INFO",
-        LC_DYLD_INFO => "LC_DYLD_INFO",
-        LC_DYLD_INFO_ONLY => "LC_DYLD_INFO_ONLY",
-        LC_LOAD_UPWARD_DYLIB => "LC_LOAD_UPWARD_DYLIB",
-        LC_VERSION_MIN_MACOSX => "LC_VERSION_MIN_MACOSX",
-        LC_VERSION_MIN_IPHONEOS => "LC_VERSION_MIN_IPHONEOS",
-        LC_FUNCTION_STARTS => "LC_FUNCTION_STARTS",
-        LC_DYLD_ENVIRONMENT => "LC_DYLD_ENVIRONMENT",
-        LC_MAIN => "LC_MAIN",
-        LC_DATA_IN_CODE => "LC_DATA_IN_CODE",
-        LC_SOURCE_VERSION => "LC_SOURCE_VERSION",
-        LC_DYLIB_CODE_SIGN_DRS => "LC_DYLIB_CODE_SIGN_DRS",
-        LC_ENCRYPTION_INFO_64 => "LC_ENCRYPTION_INFO_64",
-        LC_LINKER_OPTION => "LC_LINKER_OPTION",
-        LC_LINKER_OPTIMIZATION_HINT => "LC_LINKER_OPTIMIZATION_HINT",
-        LC_VERSION_MIN_TVOS => "LC_VERSION_MIN_TVOS",
-        LC_VERSION_MIN_WATCHOS => "LC_VERSION_MIN_WATCHOS",
-        LC_NOTE => "LC_NOTE",
-        LC_BUILD_VERSION => "LC_BUILD_VERSION",
-        _ => "",
-    };
+    let cmd_string = load_command_name(cmd).unwrap_or("");
     table.add_row(row![ Fcc->"cmd", Fyc->format!("0x{:x}\n({})", cmd, cmd_string),  c->"-"]);
     table.add_row(row![ Fcc->"cmdsize", Fyc->format!("0x{:x}", cmdsize),  c->"-"]);
 }
@@ -572,3 +1204,30 @@ fn print_bytes_array(field: &str, bytes: &[u8], table: &mut Table) {
     let as_string = String::from_utf8(bytes.to_vec()).unwrap_or_else(|_| String::from("-"));
     table.add_row(row![ Fcc->format!("{}", field), Fyc->format!("{}", result),  c->as_string]);
 }
+
+fn format_hex_dump(bytes: &[u8]) -> String {
+    let mut lines = Vec::new();
+    for (index, chunk) in bytes.chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let ascii: String = chunk.iter().map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' }).collect();
+        lines.push(format!("{:08x}  {:<47}  |{}|", index * 16, hex.join(" "), ascii));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mach_o::MachO;
+    use crate::test_utils::*;
+
+    #[test]
+    fn core_file_regions_at_the_top_of_the_address_space() {
+        let segment = segment_64("__DATA", u64::MAX - 0xf, 0x1000, u64::MAX - 0xf, 0x1000, &[]);
+        let mut data = image(&[segment], &[]);
+        data[12..16].copy_from_slice(&MH_CORE.to_le_bytes());
+
+        let mach_o = MachO::from_bytes(&data).unwrap();
+        print_core_file(mach_o.core.as_ref().unwrap());
+    }
+}
@@ -0,0 +1,761 @@
+use std::fmt::Write;
+
+// Rust symbols come in two manglings. The legacy one reuses the Itanium nested name syntax with
+// escaped identifiers and a trailing hash component, the v0 one is described in RFC 2603 and is
+// printed while it is parsed, much like rustc-demangle does.
+
+const MAX_SYMBOL_LENGTH: usize = 16 * 1024;
+const MAX_NESTING: usize = 256;
+const MAX_OUTPUT_LENGTH: usize = 64 * 1024;
+
+/// Demangles a legacy (`__ZN3foo3bar17h0123456789abcdefE`) or v0 (`__RNvCs1234_3foo3bar`) Rust
+/// symbol. With `hide_hash` the hash of legacy symbols and the crate disambiguators of v0 symbols
+/// are left out. Returns `None` for names that are not Rust mangled or could not be decoded.
+pub fn demangle_symbol(name: &str, hide_hash: bool) -> Option<String> {
+    // Mach-O symbol names carry an extra leading underscore.
+    let name = match name.strip_prefix('_') {
+        Some(stripped) if stripped.starts_with("_ZN") || stripped.starts_with("_R") => stripped,
+        _ => name,
+    };
+    if name.len() > MAX_SYMBOL_LENGTH {
+        return None;
+    }
+    // LLVM appends a suffix to symbols it makes local to a module.
+    let name = name.split_once(".llvm.").map_or(name, |(name, _)| name);
+    if let Some(mangled) = name.strip_prefix("_ZN") {
+        return demangle_legacy(mangled, hide_hash);
+    }
+    let mangled = name.strip_prefix("_R")?;
+    if !mangled.starts_with(|character: char| character.is_ascii_uppercase() || character.is_ascii_digit()) {
+        return None;
+    }
+    demangle_v0(mangled, hide_hash)
+}
+
+// Legacy symbols are only told apart from C++ ones by their last component, `17h` followed by
+// 16 hex digits.
+fn demangle_legacy(mangled: &str, hide_hash: bool) -> Option<String> {
+    let bytes = mangled.as_bytes();
+    let mut components = Vec::new();
+    let mut position = 0;
+    while bytes.get(position)? != &b'E' {
+        let start = position;
+        while bytes.get(position)?.is_ascii_digit() {
+            position += 1;
+        }
+        let length: usize = mangled[start..position].parse().ok()?;
+        let component = mangled.get(position..position.checked_add(length)?)?;
+        position += length;
+        components.push(component);
+    }
+    // Anything after the terminating `E` is a vendor suffix such as `.cold`.
+    let hash = components.pop()?;
+    if components.is_empty() || !is_legacy_hash(hash) {
+        return None;
+    }
+    let mut output = String::new();
+    for (index, component) in components.iter().enumerate() {
+        if index > 0 {
+            output.push_str("::");
+        }
+        unescape_legacy_component(component, &mut output)?;
+    }
+    if !hide_hash {
+        output.push_str("::");
+        output.push_str(hash);
+    }
+    Some(output)
+}
+
+fn is_legacy_hash(component: &str) -> bool {
+    component.len() == 17 && component.strip_prefix('h').is_some_and(|digits| digits.bytes().all(|byte| byte.is_ascii_hexdigit()))
+}
+
+fn unescape_legacy_component(component: &str, output: &mut String) -> Option<()> {
+    // Components starting with `$` get an underscore so they are valid identifiers.
+    let mut rest = if component.starts_with("_$") { &component[1..] } else { component };
+    while !rest.is_empty() {
+        if let Some(escaped) = rest.strip_prefix('$') {
+            let (escape, remainder) = escaped.split_once('$')?;
+            let unescaped = match escape {
+                "SP" => '@',
+                "BP" => '*',
+                "RF" => '&',
+                "LT" => '<',
+                "GT" => '>',
+                "LP" => '(',
+                "RP" => ')',
+                "C" => ',',
+                _ => {
+                    let code = u32::from_str_radix(escape.strip_prefix('u')?, 16).ok()?;
+                    char::from_u32(code).filter(|character| !character.is_control())?
+                }
+            };
+            output.push(unescaped);
+            rest = remainder;
+        } else if let Some(remainder) = rest.strip_prefix("..") {
+            output.push_str("::");
+            rest = remainder;
+        } else {
+            let end = rest.char_indices().skip(1).find(|(_, character)| matches!(character, '$' | '.')).map_or(rest.len(), |(end, _)| end);
+            output.push_str(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+    Some(())
+}
+
+fn demangle_v0(mangled: &str, hide_hash: bool) -> Option<String> {
+    // The symbol may start with an encoding version, of which only 0 exists so far.
+    let mut printer = V0Printer { text: mangled.as_bytes(), position: 0, output: String::new(), depth: 0, bound_lifetimes: 0, hide_hash };
+    if printer.peek()?.is_ascii_digit() {
+        return None;
+    }
+    printer.print_path(true)?;
+    // An instantiating crate may follow, and a vendor suffix after it.
+    if printer.peek().is_some_and(|byte| byte.is_ascii_uppercase()) {
+        let mut skipped = V0Printer { output: String::new(), ..printer };
+        skipped.print_path(false)?;
+        printer.position = skipped.position;
+    }
+    match printer.peek() {
+        None | Some(b'.' | b'$') => Some(printer.output),
+        Some(_) => None,
+    }
+}
+
+struct V0Printer<'a> {
+    text: &'a [u8],
+    position: usize,
+    output: String,
+    depth: usize,
+    // Number of lifetimes bound by the enclosing `for<...>` binders.
+    bound_lifetimes: u64,
+    hide_hash: bool,
+}
+
+impl V0Printer<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn next_if(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn push(&mut self, text: &str) -> Option<()> {
+        if self.output.len() > MAX_OUTPUT_LENGTH {
+            return None;
+        }
+        self.output.push_str(text);
+        Some(())
+    }
+
+    fn nested(&mut self, print: impl FnOnce(&mut Self) -> Option<()>) -> Option<()> {
+        if self.depth >= MAX_NESTING {
+            return None;
+        }
+        self.depth += 1;
+        let result = print(self);
+        self.depth -= 1;
+        result
+    }
+
+    // base-62-number ::= {<0-9a-zA-Z>} _, where `_` alone is 0 and anything else is one more.
+    fn base62(&mut self) -> Option<u64> {
+        if self.next_if(b'_') {
+            return Some(0);
+        }
+        let mut value: u64 = 0;
+        loop {
+            let digit = match self.next()? {
+                byte @ b'0'..=b'9' => byte - b'0',
+                byte @ b'a'..=b'z' => byte - b'a' + 10,
+                byte @ b'A'..=b'Z' => byte - b'A' + 36,
+                b'_' => return value.checked_add(1),
+                _ => return None,
+            };
+            value = value.checked_mul(62)?.checked_add(digit as u64)?;
+        }
+    }
+
+    fn optional_base62(&mut self, tag: u8) -> Option<u64> {
+        if self.next_if(tag) {
+            self.base62()?.checked_add(1)
+        } else {
+            Some(0)
+        }
+    }
+
+    // decimal-number ::= 0 | <1-9> {<0-9>}
+    fn decimal(&mut self) -> Option<usize> {
+        if self.next_if(b'0') {
+            return Some(0);
+        }
+        let start = self.position;
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.text[start..self.position]).ok()?.parse().ok()
+    }
+
+    // undisambiguated-identifier ::= [u] <decimal-number> [_] <bytes>
+    fn identifier(&mut self) -> Option<String> {
+        let is_punycode = self.next_if(b'u');
+        let length = self.decimal()?;
+        self.next_if(b'_');
+        let bytes = self.text.get(self.position..self.position.checked_add(length)?)?;
+        self.position += length;
+        let text = std::str::from_utf8(bytes).ok()?;
+        if is_punycode {
+            decode_punycode(text)
+        } else {
+            Some(text.to_string())
+        }
+    }
+
+    // Follows a back reference to an earlier position and prints what is found there.
+    fn print_backref(&mut self, print: impl FnOnce(&mut Self) -> Option<()>) -> Option<()> {
+        let start = self.position - 1;
+        let target = usize::try_from(self.base62()?).ok()?;
+        if target >= start {
+            return None;
+        }
+        let resume = std::mem::replace(&mut self.position, target);
+        let result = self.nested(print);
+        self.position = resume;
+        result
+    }
+
+    fn print_path(&mut self, in_value: bool) -> Option<()> {
+        self.nested(|printer| printer.print_path_inner(in_value))
+    }
+
+    fn print_path_inner(&mut self, in_value: bool) -> Option<()> {
+        match self.next()? {
+            b'C' => {
+                let disambiguator = self.optional_base62(b's')?;
+                let name = self.identifier()?;
+                self.push(&name)?;
+                if !self.hide_hash && disambiguator != 0 {
+                    write!(self.output, "[{:x}]", disambiguator).ok()?;
+                }
+            }
+            b'N' => {
+                let namespace = self.next()?;
+                if !namespace.is_ascii_alphabetic() {
+                    return None;
+                }
+                self.print_path(in_value)?;
+                let disambiguator = self.optional_base62(b's')?;
+                let name = self.identifier()?;
+                if namespace.is_ascii_uppercase() {
+                    self.push("::{")?;
+                    match namespace {
+                        b'C' => self.push("closure")?,
+                        b'S' => self.push("shim")?,
+                        other => self.push(&(other as char).to_string())?,
+                    }
+                    if !name.is_empty() {
+                        self.push(":")?;
+                        self.push(&name)?;
+                    }
+                    write!(self.output, "#{}}}", disambiguator).ok()?;
+                } else if !name.is_empty() {
+                    self.push("::")?;
+                    self.push(&name)?;
+                }
+            }
+            tag @ (b'M' | b'X' | b'Y') => {
+                if tag != b'Y' {
+                    // The path of the impl itself is only there to tell impls apart.
+                    self.optional_base62(b's')?;
+                    let mut skipped = V0Printer { text: self.text, position: self.position, output: String::new(), depth: self.depth, bound_lifetimes: self.bound_lifetimes, hide_hash: true };
+                    skipped.print_path(false)?;
+                    self.position = skipped.position;
+                }
+                self.push("<")?;
+                self.print_type()?;
+                if tag != b'M' {
+                    self.push(" as ")?;
+                    self.print_path(false)?;
+                }
+                self.push(">")?;
+            }
+            b'I' => {
+                self.print_path(in_value)?;
+                if in_value {
+                    self.push("::")?;
+                }
+                self.push("<")?;
+                self.print_generic_args()?;
+                self.push(">")?;
+            }
+            b'B' => self.print_backref(|printer| printer.print_path_inner(in_value))?,
+            _ => return None,
+        }
+        Some(())
+    }
+
+    // {<generic-arg>} E
+    fn print_generic_args(&mut self) -> Option<()> {
+        let mut first = true;
+        while !self.next_if(b'E') {
+            if !first {
+                self.push(", ")?;
+            }
+            first = false;
+            self.print_generic_arg()?;
+        }
+        Some(())
+    }
+
+    fn print_generic_arg(&mut self) -> Option<()> {
+        if self.next_if(b'L') {
+            let lifetime = self.base62()?;
+            self.print_lifetime(lifetime)
+        } else if self.next_if(b'K') {
+            self.print_const()
+        } else {
+            self.print_type()
+        }
+    }
+
+    fn print_lifetime(&mut self, lifetime: u64) -> Option<()> {
+        if lifetime == 0 {
+            return self.push("'_");
+        }
+        let depth = self.bound_lifetimes.checked_sub(lifetime)?;
+        if depth < 26 {
+            self.push("'")?;
+            self.push(&((b'a' + depth as u8) as char).to_string())
+        } else {
+            write!(self.output, "'_{}", depth).ok()
+        }
+    }
+
+    // binder ::= G <base-62-number>, introducing `for<'a, ...>` around what follows.
+    fn print_binder(&mut self, print: impl FnOnce(&mut Self) -> Option<()>) -> Option<()> {
+        let count = self.optional_base62(b'G')?;
+        if count == 0 {
+            return print(self);
+        }
+        if count > 64 {
+            return None;
+        }
+        self.push("for<")?;
+        for index in 0..count {
+            if index > 0 {
+                self.push(", ")?;
+            }
+            self.bound_lifetimes += 1;
+            self.print_lifetime(1)?;
+        }
+        self.push("> ")?;
+        let result = print(self);
+        self.bound_lifetimes -= count;
+        result
+    }
+
+    fn print_type(&mut self) -> Option<()> {
+        self.nested(|printer| printer.print_type_inner())
+    }
+
+    fn print_type_inner(&mut self) -> Option<()> {
+        let tag = self.next()?;
+        if let Some(basic) = basic_type(tag) {
+            return self.push(basic);
+        }
+        match tag {
+            b'R' | b'Q' => {
+                self.push("&")?;
+                if self.next_if(b'L') {
+                    let lifetime = self.base62()?;
+                    if lifetime != 0 {
+                        self.print_lifetime(lifetime)?;
+                        self.push(" ")?;
+                    }
+                }
+                if tag == b'Q' {
+                    self.push("mut ")?;
+                }
+                self.print_type()?;
+            }
+            b'P' => {
+                self.push("*const ")?;
+                self.print_type()?;
+            }
+            b'O' => {
+                self.push("*mut ")?;
+                self.print_type()?;
+            }
+            b'A' => {
+                self.push("[")?;
+                self.print_type()?;
+                self.push("; ")?;
+                self.print_const()?;
+                self.push("]")?;
+            }
+            b'S' => {
+                self.push("[")?;
+                self.print_type()?;
+                self.push("]")?;
+            }
+            b'T' => {
+                self.push("(")?;
+                let mut count = 0;
+                while !self.next_if(b'E') {
+                    if count > 0 {
+                        self.push(", ")?;
+                    }
+                    self.print_type()?;
+                    count += 1;
+                }
+                if count == 1 {
+                    self.push(",")?;
+                }
+                self.push(")")?;
+            }
+            b'F' => self.print_binder(|printer| printer.print_fn_sig())?,
+            b'D' => {
+                self.push("dyn ")?;
+                self.print_binder(|printer| printer.print_dyn_bounds())?;
+                self.expect_lifetime_bound()?;
+            }
+            b'B' => self.print_backref(|printer| printer.print_type_inner())?,
+            _ => {
+                self.position -= 1;
+                self.print_path(false)?;
+            }
+        }
+        Some(())
+    }
+
+    // fn-sig ::= [U] [K <abi>] {<type>} E <type>
+    fn print_fn_sig(&mut self) -> Option<()> {
+        if self.next_if(b'U') {
+            self.push("unsafe ")?;
+        }
+        if self.next_if(b'K') {
+            let abi = if self.next_if(b'C') { String::from("C") } else { self.identifier()?.replace('_', "-") };
+            self.push("extern \"")?;
+            self.push(&abi)?;
+            self.push("\" ")?;
+        }
+        self.push("fn(")?;
+        let mut first = true;
+        while !self.next_if(b'E') {
+            if !first {
+                self.push(", ")?;
+            }
+            first = false;
+            self.print_type()?;
+        }
+        self.push(")")?;
+        if self.next_if(b'u') {
+            return Some(());
+        }
+        self.push(" -> ")?;
+        self.print_type()
+    }
+
+    // dyn-bounds ::= {<path> {p <undisambiguated-identifier> <type>}} E
+    fn print_dyn_bounds(&mut self) -> Option<()> {
+        let mut first = true;
+        while !self.next_if(b'E') {
+            if !first {
+                self.push(" + ")?;
+            }
+            first = false;
+            self.print_dyn_trait()?;
+        }
+        Some(())
+    }
+
+    fn print_dyn_trait(&mut self) -> Option<()> {
+        // Associated type bindings join the generic arguments of the trait, if there are any.
+        let is_generic = self.print_path_maybe_open_generics()?;
+        let mut open = is_generic;
+        while self.next_if(b'p') {
+            self.push(if open { ", " } else { "<" })?;
+            open = true;
+            let name = self.identifier()?;
+            self.push(&name)?;
+            self.push(" = ")?;
+            self.print_type()?;
+        }
+        if open {
+            self.push(">")?;
+        }
+        Some(())
+    }
+
+    // Prints a path, leaving the generic argument list open if it has one.
+    fn print_path_maybe_open_generics(&mut self) -> Option<bool> {
+        if self.next_if(b'B') {
+            let start = self.position - 1;
+            let target = usize::try_from(self.base62()?).ok()?;
+            if target >= start || self.depth >= MAX_NESTING {
+                return None;
+            }
+            let resume = std::mem::replace(&mut self.position, target);
+            self.depth += 1;
+            let result = self.print_path_maybe_open_generics();
+            self.depth -= 1;
+            self.position = resume;
+            result
+        } else if self.next_if(b'I') {
+            self.print_path(false)?;
+            self.push("<")?;
+            let mut first = true;
+            while !self.next_if(b'E') {
+                if !first {
+                    self.push(", ")?;
+                }
+                first = false;
+                self.print_generic_arg()?;
+            }
+            Some(true)
+        } else {
+            self.print_path(false)?;
+            Some(false)
+        }
+    }
+
+    fn expect_lifetime_bound(&mut self) -> Option<()> {
+        if self.next() != Some(b'L') {
+            return None;
+        }
+        let lifetime = self.base62()?;
+        if lifetime != 0 {
+            self.push(" + ")?;
+            self.print_lifetime(lifetime)?;
+        }
+        Some(())
+    }
+
+    // const ::= <type> <const-data> | p | <backref>
+    fn print_const(&mut self) -> Option<()> {
+        self.nested(|printer| printer.print_const_inner())
+    }
+
+    fn print_const_inner(&mut self) -> Option<()> {
+        let tag = self.next()?;
+        match tag {
+            b'p' => self.push("_"),
+            b'B' => self.print_backref(|printer| printer.print_const_inner()),
+            b'h' | b't' | b'm' | b'y' | b'o' | b'j' | b'a' | b's' | b'l' | b'x' | b'n' | b'i' => {
+                let negative = self.next_if(b'n');
+                let value = self.const_hex()?;
+                if negative {
+                    self.push("-")?;
+                }
+                self.push(&value)?;
+                if !self.hide_hash {
+                    self.push(basic_type(tag)?)?;
+                }
+                Some(())
+            }
+            b'b' => match self.const_hex()?.as_str() {
+                "0" => self.push("false"),
+                "1" => self.push("true"),
+                _ => None,
+            },
+            b'c' => {
+                let code: u32 = self.const_hex()?.parse().ok()?;
+                let character = char::from_u32(code)?;
+                self.push(&format!("{:?}", character))
+            }
+            _ => None,
+        }
+    }
+
+    // const-data ::= {<hex-digit>} _, printed in decimal when it fits in 64 bits.
+    fn const_hex(&mut self) -> Option<String> {
+        let start = self.position;
+        while self.peek().is_some_and(|byte| byte.is_ascii_hexdigit() && !byte.is_ascii_uppercase()) {
+            self.position += 1;
+        }
+        let digits = std::str::from_utf8(&self.text[start..self.position]).ok()?;
+        if !self.next_if(b'_') {
+            return None;
+        }
+        if digits.is_empty() {
+            return Some(String::from("0"));
+        }
+        match u64::from_str_radix(digits, 16) {
+            Ok(value) => Some(value.to_string()),
+            Err(_) => Some(format!("0x{}", digits)),
+        }
+    }
+}
+
+fn basic_type(tag: u8) -> Option<&'static str> {
+    let name = match tag {
+        b'a' => "i8",
+        b'b' => "bool",
+        b'c' => "char",
+        b'd' => "f64",
+        b'e' => "str",
+        b'f' => "f32",
+        b'h' => "u8",
+        b'i' => "isize",
+        b'j' => "usize",
+        b'l' => "i32",
+        b'm' => "u32",
+        b'n' => "i128",
+        b'o' => "u128",
+        b's' => "i16",
+        b't' => "u16",
+        b'u' => "()",
+        b'v' => "...",
+        b'x' => "i64",
+        b'y' => "u64",
+        b'z' => "!",
+        b'p' => "_",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// Non-ASCII identifiers are Punycode encoded (RFC 3492), with `_` as the delimiter.
+fn decode_punycode(text: &str) -> Option<String> {
+    const BASE: u32 = 36;
+    const T_MIN: u32 = 1;
+    const T_MAX: u32 = 26;
+    const SKEW: u32 = 38;
+    const DAMP: u32 = 700;
+
+    let (basic, encoded) = match text.rfind('_') {
+        Some(delimiter) => (&text[..delimiter], &text[delimiter + 1..]),
+        None => ("", text),
+    };
+    let mut output: Vec<char> = basic.chars().collect();
+    let mut code = 0x80u32;
+    let mut bias = 72u32;
+    let mut index = 0u32;
+    let mut digits = encoded.bytes().peekable();
+    while digits.peek().is_some() {
+        let old_index = index;
+        let mut weight = 1u32;
+        let mut k = BASE;
+        loop {
+            let digit = match digits.next()? {
+                byte @ b'a'..=b'z' => (byte - b'a') as u32,
+                byte @ b'0'..=b'9' => (byte - b'0') as u32 + 26,
+                _ => return None,
+            };
+            index = index.checked_add(digit.checked_mul(weight)?)?;
+            let threshold = if k <= bias { T_MIN } else if k >= bias + T_MAX { T_MAX } else { k - bias };
+            if digit < threshold {
+                break;
+            }
+            weight = weight.checked_mul(BASE - threshold)?;
+            k += BASE;
+        }
+        let length = output.len() as u32 + 1;
+        let mut delta = (index - old_index) / if old_index == 0 { DAMP } else { 2 };
+        delta += delta / length;
+        let mut k = 0;
+        while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+            delta /= BASE - T_MIN;
+            k += BASE;
+        }
+        bias = k + ((BASE - T_MIN + 1) * delta) / (delta + SKEW);
+        code = code.checked_add(index / length)?;
+        index %= length;
+        if output.len() > MAX_SYMBOL_LENGTH {
+            return None;
+        }
+        output.insert(index as usize, char::from_u32(code)?);
+        index += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demangle(name: &str) -> Option<String> {
+        demangle_symbol(name, false)
+    }
+
+    fn demangle_without_hash(name: &str) -> Option<String> {
+        demangle_symbol(name, true)
+    }
+
+    #[test]
+    fn legacy() {
+        let cases = [
+            ("_ZN3foo17h05af221e174051e9E", "foo::h05af221e174051e9"),
+            ("__ZN4core3fmt5write17h0123456789abcdefE", "core::fmt::write::h0123456789abcdef"),
+            ("_ZN4test1a2bc17h05af221e174051e9E", "test::a::bc::h05af221e174051e9"),
+            ("_ZN71_$LT$Test$u20$$u2b$$u20$$u27$static$u20$as$u20$foo..Bar$LT$Test$GT$$GT$3bar17h930b740aa94f1d3aE", "<Test + 'static as foo::Bar<Test>>::bar::h930b740aa94f1d3a"),
+            ("_ZN35Bar$LT$$u5b$u32$u3b$$u20$4$u5d$$GT$17h05af221e174051e9E", "Bar<[u32; 4]>::h05af221e174051e9"),
+            ("_ZN12test$BP$test4foob17h05af221e174051e9E", "test*test::foob::h05af221e174051e9"),
+            ("_ZN12test$RF$test4foob17h05af221e174051e9E", "test&test::foob::h05af221e174051e9"),
+            ("_ZN8caf$ue9$17h05af221e174051e9E", "café::h05af221e174051e9"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle(mangled).as_deref(), Some(expected), "{}", mangled);
+        }
+    }
+
+    #[test]
+    fn legacy_without_hash() {
+        assert_eq!(demangle_without_hash("_ZN3foo17h05af221e174051e9E").as_deref(), Some("foo"));
+        assert_eq!(demangle_without_hash("_ZN9backtrace3foo17hbb467fcdaea5d79bE.llvm.A5310EB9").as_deref(), Some("backtrace::foo"));
+    }
+
+    #[test]
+    fn v0() {
+        let cases = [
+            ("_RNvC6_123foo3bar", "123foo::bar"),
+            ("_RNvNtCs1234_7mycrate3foo3bar", "mycrate[3c1c0]::foo::bar"),
+            ("__RNvNtCs1234_7mycrate3foo3bar", "mycrate[3c1c0]::foo::bar"),
+            ("_RNvXCs1234_7mycrateNtCs1234_7mycrate3FooNtNtC4core3fmt5Debug3fmt", "<mycrate[3c1c0]::Foo as core::fmt::Debug>::fmt"),
+            ("_RNvC7mycrateu7caf_dma", "mycrate::café"),
+            ("_RNqCs4fqI2P2rA04_11utf8_identsu30____7hkackfecea1cbdathfdh9hlq6y", "utf8_idents[317d481089b8c8fe]::საჭმელად_გემრიელი_სადილი"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle(mangled).as_deref(), Some(expected), "{}", mangled);
+        }
+    }
+
+    #[test]
+    fn v0_without_hash() {
+        let cases = [
+            ("_RNvNtCs1234_7mycrate3foo3bar", "mycrate::foo::bar"),
+            ("_RNCNCNgCs6DXkGYLi8lr_2cc5spawn00B5_", "cc::spawn::{closure#0}::{closure#0}"),
+            ("_RNqCs4fqI2P2rA04_11utf8_identsu30____7hkackfecea1cbdathfdh9hlq6y", "utf8_idents::საჭმელად_გემრიელი_სადილი"),
+            ("_RINbNbCskIICzLVDPPb_5alloc5alloc8box_freeDINbNiB4_5boxed5FnBoxuEp6OutputuEL_ECs1iopQbuBiw2_3std", "alloc::alloc::box_free::<dyn alloc::boxed::FnBox<(), Output = ()>>"),
+            ("_RMC0INtC8arrayvec8ArrayVechKj7b_E", "<arrayvec::ArrayVec<u8, 123>>"),
+            ("_RNCINkXs25_NgCsbmNqQUJIY6D_4core5sliceINyB9_4IterhENuNgNoBb_4iter8iterator8Iterator9rpositionNCNgNpB9_6memchr7memrchrs_0E0Bb_", "<core::slice::Iter<u8> as core::iter::iterator::Iterator>::rposition::<core::slice::memchr::memrchr::{closure#1}>::{closure#0}"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle_without_hash(mangled).as_deref(), Some(expected), "{}", mangled);
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_and_garbage_input() {
+        let long = format!("_RNvC7mycrate{}", "A".repeat(MAX_SYMBOL_LENGTH));
+        let deep = format!("_R{}C7mycrate", "NvNv".repeat(MAX_NESTING));
+        let cases = ["", "main", "_ZN", "_ZN3foo", "_ZN3fooE", "_ZN3foo17h05af221e174051e9", "_R", "_RNvC", "_RNvC7mycrate3fo", "_RB_", "_RNvB_3foo", &long, &deep];
+        for mangled in cases {
+            assert_eq!(demangle(mangled), None, "{}", mangled);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use std::io::{self, Cursor, Read, Seek};
+
+use byteorder::ReadBytesExt;
+
+use crate::constants::*;
+use crate::error::AppError;
+use crate::load_commands::*;
+use crate::mach_o::MachO;
+use crate::memory_utils::*;
+
+#[derive(Debug)]
+pub struct SplitSegInfo {
+    pub version: u32,
+    pub references: Vec<SplitSegReference>,
+}
+
+#[derive(Debug)]
+pub struct SplitSegReference {
+    pub kind: u64,
+    pub from: SplitSegLocation,
+    /// Only the v2 format records the target of a reference.
+    pub to: Option<SplitSegLocation>,
+}
+
+#[derive(Debug)]
+pub struct SplitSegLocation {
+    pub address: u64,
+    pub segname: String,
+    pub sectname: String,
+}
+
+impl SplitSegReference {
+    pub fn is_cross_segment(&self) -> bool {
+        match &self.to {
+            Some(to) => to.segname != self.from.segname,
+            None => false,
+        }
+    }
+}
+
+impl SplitSegInfo {
+    /// Decodes the LC_SEGMENT_SPLIT_INFO payload, or returns `None` if the image has none.
+    pub fn from_file<R: Read + Seek>(file: &mut R, mach_o: &MachO) -> Result<Option<SplitSegInfo>, AppError> {
+        let command = mach_o.load_commands.iter().find_map(|entry| match &entry.command {
+            LoadCommand::LinkeditDataCommand(command) if command.cmd == LC_SEGMENT_SPLIT_INFO => Some(command),
+            _ => None,
+        });
+        let Some(command) = command else { return Ok(None) };
+        let data = command.read_data(file)?;
+        let image = ImageLayout::new(&mach_o.load_commands);
+        let mut cursor = Cursor::new(data.as_slice());
+
+        if data.first() == Some(&DYLD_CACHE_ADJ_V2_FORMAT) {
+            cursor.read_u8()?;
+            Ok(Some(SplitSegInfo { version: 2, references: Self::parse_v2(&mut cursor, &image)? }))
+        } else {
+            Ok(Some(SplitSegInfo { version: 1, references: Self::parse_v1(&mut cursor, &image)? }))
+        }
+    }
+
+    // v1 :== (<kind> <uleb128 address delta>* 0)* 0, deltas starting at the image base
+    fn parse_v1(cursor: &mut Cursor<&[u8]>, image: &ImageLayout) -> Result<Vec<SplitSegReference>, AppError> {
+        let mut references = Vec::new();
+        let size = cursor.get_ref().len() as u64;
+
+        while cursor.position() < size {
+            let kind = cursor.read_u8()?;
+            if kind == 0 {
+                break;
+            }
+            let mut address = image.base_address;
+            loop {
+                let delta = read_uleb128(cursor)?;
+                if delta == 0 {
+                    break;
+                }
+                address = address.wrapping_add(delta);
+                references.push(SplitSegReference {
+                    kind: kind as u64,
+                    from: image.location_for_address(address),
+                    to: None,
+                });
+            }
+        }
+        Ok(references)
+    }
+
+    // v2 :== <count> (<from-sect> <to-sect> <count> (<to-offset-delta> <count> (<kind> <count> <from-offset-delta>*)*)*)*
+    fn parse_v2(cursor: &mut Cursor<&[u8]>, image: &ImageLayout) -> Result<Vec<SplitSegReference>, AppError> {
+        let mut references = Vec::new();
+
+        let section_count = read_uleb128(cursor)?;
+        for _ in 0..section_count {
+            let from_section_index = read_uleb128(cursor)?;
+            let to_section_index = read_uleb128(cursor)?;
+            let to_offset_count = read_uleb128(cursor)?;
+            let mut to_section_offset = 0u64;
+            for _ in 0..to_offset_count {
+                to_section_offset = to_section_offset.wrapping_add(read_uleb128(cursor)?);
+                let from_offset_count = read_uleb128(cursor)?;
+                for _ in 0..from_offset_count {
+                    let kind = read_uleb128(cursor)?;
+                    let from_offset_delta_count = read_uleb128(cursor)?;
+                    let mut from_section_offset = 0u64;
+                    for _ in 0..from_offset_delta_count {
+                        from_section_offset = from_section_offset.wrapping_add(read_uleb128(cursor)?);
+                        references.push(SplitSegReference {
+                            kind,
+                            from: image.location_for_section(from_section_index, from_section_offset)?,
+                            to: Some(image.location_for_section(to_section_index, to_section_offset)?),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(references)
+    }
+}
+
+struct ImageLayout {
+    base_address: u64,
+    // (segname, sectname, addr, size) in load command order; v2 section index n refers to entry n - 1
+    sections: Vec<(String, String, u64, u64)>,
+}
+
+impl ImageLayout {
+    fn new(load_commands: &[LoadCommandEntry]) -> ImageLayout {
+        let mut base_address = None;
+        let mut flat_sections = Vec::new();
+        for load_command in load_commands {
+            if let LoadCommand::SegmentCommand(segment) = &load_command.command {
+                if base_address.is_none() && segment.fileoff() == 0 && segment.filesize() != 0 {
+                    base_address = Some(segment.vmaddr());
+                }
+            }
+            for section in &load_command.sections {
+                flat_sections.push((fixed_bytes_to_string(section.segname()), fixed_bytes_to_string(section.sectname()), section.addr(), section.size()));
+            }
+        }
+        ImageLayout {
+            base_address: base_address.unwrap_or(0),
+            sections: flat_sections,
+        }
+    }
+
+    fn location_for_address(&self, address: u64) -> SplitSegLocation {
+        let section = self.sections.iter().find(|(_, _, addr, size)| address >= *addr && address - *addr < *size);
+        match section {
+            Some((segname, sectname, _, _)) => SplitSegLocation { address, segname: segname.clone(), sectname: sectname.clone() },
+            None => SplitSegLocation { address, segname: String::new(), sectname: String::new() },
+        }
+    }
+
+    fn location_for_section(&self, section_index: u64, offset: u64) -> Result<SplitSegLocation, AppError> {
+        // Section index 0 stands for the mach header itself.
+        if section_index == 0 {
+            return Ok(SplitSegLocation { address: self.base_address.wrapping_add(offset), segname: String::from("__TEXT"), sectname: String::from("mach_header") });
+        }
+        let (segname, sectname, addr, _) = self.sections.get(section_index as usize - 1).ok_or_else(|| {
+            AppError::from(io::Error::new(io::ErrorKind::InvalidData, "split seg info references an unknown section"))
+        })?;
+        Ok(SplitSegLocation { address: addr.wrapping_add(offset), segname: segname.clone(), sectname: sectname.clone() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn image_with_split_info(split_info: &[u8]) -> Vec<u8> {
+        let text = segment_64("__TEXT", 0x1_0000_0000, 0x1000, 0, 0x1000, &[("__text", 0x1_0000_0400, 0x100, 0x400)]);
+        let command = command(LC_SEGMENT_SPLIT_INFO, &[0x800, split_info.len() as u32]);
+        image(&[text, command], &[(0x800, split_info), (0xfff, &[0])])
+    }
+
+    fn v2(from_section: u64, to_section: u64, to_offset: u64) -> Vec<u8> {
+        let mut data = vec![DYLD_CACHE_ADJ_V2_FORMAT];
+        for value in [1, from_section, to_section, 1, to_offset, 1, 1, 1, 4] {
+            data.extend(uleb128(value));
+        }
+        data
+    }
+
+    #[test]
+    fn decodes_v2_references() {
+        let data = image_with_split_info(&v2(1, 0, 0x10));
+        let mach_o = MachO::from_bytes(&data).unwrap();
+        let split_info = SplitSegInfo::from_file(&mut Cursor::new(&data), &mach_o).unwrap().unwrap();
+        assert_eq!(split_info.version, 2);
+        assert_eq!(split_info.references[0].from.address, 0x1_0000_0404);
+        assert_eq!(split_info.references[0].to.as_ref().unwrap().address, 0x1_0000_0010);
+    }
+
+    #[test]
+    fn unknown_section_is_reported_on_decode() {
+        let data = image_with_split_info(&v2(1, 5, 0));
+        // The table is only decoded on demand, so the parse itself succeeds.
+        let mach_o = MachO::from_bytes(&data).unwrap();
+        assert!(mach_o.diagnostics.is_empty());
+        assert!(SplitSegInfo::from_file(&mut Cursor::new(&data), &mach_o).is_err());
+    }
+
+    #[test]
+    fn offsets_wrap_instead_of_overflowing() {
+        let data = image_with_split_info(&v2(1, 0, u64::MAX));
+        let mach_o = MachO::from_bytes(&data).unwrap();
+        let split_info = SplitSegInfo::from_file(&mut Cursor::new(&data), &mach_o).unwrap().unwrap();
+        assert_eq!(split_info.references[0].to.as_ref().unwrap().address, 0xffff_ffff);
+    }
+}
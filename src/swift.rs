@@ -0,0 +1,604 @@
+use std::io::{self, Read, Seek};
+
+use byteorder::{BigEndian, LittleEndian};
+
+use crate::address_space::{AddressSpace, Pointer};
+use crate::constants::*;
+use crate::error::AppError;
+use crate::header::Header;
+use crate::load_commands::SectionLike;
+use crate::mach_o::MachO;
+use crate::memory_utils::fixed_bytes_to_string;
+
+/// Deepest chain of parent contexts followed before the descriptor is assumed to be cyclic.
+const MAX_CONTEXT_DEPTH: usize = 256;
+
+#[derive(Debug)]
+pub struct SwiftMetadata {
+    pub types: Vec<SwiftType>,
+    pub protocols: Vec<SwiftProtocol>,
+    pub conformances: Vec<SwiftConformance>,
+    pub associated_types: Vec<SwiftAssociatedTypes>,
+    pub builtin_types: Vec<SwiftBuiltinType>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwiftTypeKind {
+    Class,
+    Struct,
+    Enum,
+}
+
+#[derive(Debug)]
+pub struct SwiftType {
+    pub address: u64,
+    pub kind: SwiftTypeKind,
+    /// Fully qualified name, e.g. `MyModule.Outer.Inner`.
+    pub name: String,
+    /// Mangled superclass type of a class.
+    pub superclass: Option<String>,
+    /// Stored properties of a struct or class, or the cases of an enum.
+    pub fields: Vec<SwiftField>,
+    /// Protocols this type conforms to according to the image's conformance records.
+    pub conformances: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SwiftField {
+    pub name: String,
+    /// Mangled field type; empty for enum cases without a payload.
+    pub type_name: String,
+    pub is_var: bool,
+    pub is_indirect: bool,
+}
+
+#[derive(Debug)]
+pub struct SwiftProtocol {
+    pub name: String,
+    pub num_requirements: u32,
+    pub associated_type_names: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct SwiftConformance {
+    pub address: u64,
+    pub protocol: String,
+    pub type_name: String,
+    /// Descriptor of the conforming type when it is defined in this image.
+    pub type_descriptor: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct SwiftAssociatedTypes {
+    pub conforming_type: String,
+    pub protocol: String,
+    /// (associated type name, mangled substituted type)
+    pub types: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+pub struct SwiftBuiltinType {
+    pub type_name: String,
+    pub size: u32,
+    pub alignment: u32,
+    pub stride: u32,
+    pub num_extra_inhabitants: u32,
+}
+
+/// A context descriptor referenced from metadata, which may live in this image or be imported.
+enum SwiftContext {
+    Descriptor(u64),
+    Import(String),
+}
+
+impl SwiftMetadata {
+    pub fn from_file<R: Read + Seek>(file: &mut R, mach_o: &MachO) -> Result<SwiftMetadata, AppError> {
+        match mach_o.header.magic() {
+            MH_MAGIC | MH_MAGIC_64 => SwiftReader::<R, BigEndian>::new(file, mach_o).parse(),
+            _ => SwiftReader::<R, LittleEndian>::new(file, mach_o).parse(),
+        }
+    }
+}
+
+struct SwiftReader<'a, R: Read + Seek, E: byteorder::ByteOrder> {
+    space: AddressSpace<'a, R, E>,
+    // (sectname, addr, size) of every section in the image
+    sections: Vec<(String, u64, u64)>,
+}
+
+impl<'a, R: Read + Seek, E: byteorder::ByteOrder> SwiftReader<'a, R, E> {
+    fn new(file: &'a mut R, mach_o: &'a MachO) -> SwiftReader<'a, R, E> {
+        let sections = mach_o.sections()
+            .map(|section| (fixed_bytes_to_string(section.sectname()), section.addr(), section.size()))
+            .collect();
+        SwiftReader {
+            space: AddressSpace::new(file, mach_o),
+            sections,
+        }
+    }
+
+    fn parse(&mut self) -> Result<SwiftMetadata, AppError> {
+        let mut metadata = SwiftMetadata {
+            types: Vec::new(),
+            protocols: Vec::new(),
+            conformances: Vec::new(),
+            associated_types: Vec::new(),
+            builtin_types: Vec::new(),
+        };
+
+        // __swift5_types and __swift5_protos hold relative pointers that may be indirect.
+        for slot in self.section_range("__swift5_types", 4)? {
+            if let SwiftContext::Descriptor(descriptor) = self.read_relative_context(slot)? {
+                metadata.types.push(self.read_type(descriptor)?);
+            }
+        }
+        for slot in self.section_range("__swift5_protos", 4)? {
+            if let SwiftContext::Descriptor(descriptor) = self.read_relative_context(slot)? {
+                metadata.protocols.push(self.read_protocol(descriptor)?);
+            }
+        }
+        for slot in self.section_range("__swift5_proto", 4)? {
+            let descriptor = self.read_relative_direct(slot)?;
+            metadata.conformances.push(self.read_conformance(descriptor)?);
+        }
+        for (addr, size) in self.section_bounds("__swift5_assocty")? {
+            let mut descriptor = addr;
+            while descriptor < addr.saturating_add(size) {
+                let (associated_types, descriptor_size) = self.read_associated_types(descriptor)?;
+                metadata.associated_types.push(associated_types);
+                descriptor += descriptor_size;
+            }
+        }
+        // BuiltinTypeDescriptor :== typeName, size, alignmentAndFlags, stride, numExtraInhabitants
+        for descriptor in self.section_range("__swift5_builtin", 20)? {
+            let type_name = self.read_relative_direct(descriptor)?;
+            metadata.builtin_types.push(SwiftBuiltinType {
+                type_name: self.read_mangled_name(type_name)?,
+                size: self.space.read_u32(descriptor.wrapping_add(4))?,
+                alignment: self.space.read_u32(descriptor.wrapping_add(8))? & 0xffff,
+                stride: self.space.read_u32(descriptor.wrapping_add(12))?,
+                num_extra_inhabitants: self.space.read_u32(descriptor.wrapping_add(16))?,
+            });
+        }
+
+        for swift_type in &mut metadata.types {
+            swift_type.conformances = metadata.conformances.iter()
+                .filter(|conformance| conformance.type_descriptor == Some(swift_type.address))
+                .map(|conformance| conformance.protocol.clone())
+                .collect();
+        }
+
+        Ok(metadata)
+    }
+
+    /// Address and size of every section named `sectname`, which have to be backed by the file.
+    fn section_bounds(&self, sectname: &str) -> Result<Vec<(u64, u64)>, AppError> {
+        let mut bounds = Vec::new();
+        for (_, addr, size) in self.sections.iter().filter(|(name, _, _)| name == sectname) {
+            self.space.check_range(*addr, *size)?;
+            bounds.push((*addr, *size));
+        }
+        Ok(bounds)
+    }
+
+    /// Addresses of the `entry_size` sized entries of every section named `sectname`.
+    fn section_range(&self, sectname: &str, entry_size: u64) -> Result<Vec<u64>, AppError> {
+        Ok(self.section_bounds(sectname)?.into_iter()
+            .flat_map(|(addr, size)| (0..size / entry_size).map(move |index| addr + index * entry_size))
+            .collect())
+    }
+
+    /// Checks that `count` records of `record_size` bytes at `records` are backed by the file
+    /// before they are read one by one.
+    fn check_records(&self, records: u64, count: u64, record_size: u64, min_record_size: u64) -> Result<(), AppError> {
+        if record_size < min_record_size {
+            return Err(AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("records at 0x{:x} are {} bytes, too small for their fields", records, record_size))));
+        }
+        self.space.check_range(records, count * record_size)
+    }
+
+    // Swift metadata refers to other data with 32-bit offsets relative to the field itself.
+    fn read_relative_direct(&mut self, address: u64) -> Result<u64, AppError> {
+        let offset = self.space.read_i32(address)?;
+        Ok(address.wrapping_add_signed(offset as i64))
+    }
+
+    fn read_relative_optional(&mut self, address: u64) -> Result<Option<u64>, AppError> {
+        let offset = self.space.read_i32(address)?;
+        Ok(if offset == 0 { None } else { Some(address.wrapping_add_signed(offset as i64)) })
+    }
+
+    /// Follows a relative pointer whose low bit marks an indirection through a pointer slot,
+    /// which is bound to a symbol when the target lives in another image.
+    fn read_relative_context(&mut self, address: u64) -> Result<SwiftContext, AppError> {
+        let offset = self.space.read_i32(address)?;
+        let target = address.wrapping_add_signed((offset & !1) as i64);
+        if offset & 1 == 0 {
+            return Ok(SwiftContext::Descriptor(target));
+        }
+        match self.space.read_pointer(target)? {
+            Pointer::Address(descriptor) => Ok(SwiftContext::Descriptor(descriptor)),
+            Pointer::Import(symbol_name) => Ok(SwiftContext::Import(symbol_name)),
+        }
+    }
+
+    fn read_relative_string(&mut self, address: u64) -> Result<String, AppError> {
+        match self.read_relative_optional(address)? {
+            Some(string) => self.space.read_c_string(string),
+            None => Ok(String::new()),
+        }
+    }
+
+    // TargetContextDescriptor :== flags, parent, ...
+    // TargetTypeContextDescriptor :== ..., name, accessFunction, fields
+    fn read_type(&mut self, descriptor: u64) -> Result<SwiftType, AppError> {
+        let kind = match self.space.read_u32(descriptor)? & SWIFT_CONTEXT_DESCRIPTOR_KIND_MASK {
+            SWIFT_CONTEXT_DESCRIPTOR_KIND_CLASS => SwiftTypeKind::Class,
+            SWIFT_CONTEXT_DESCRIPTOR_KIND_STRUCT => SwiftTypeKind::Struct,
+            _ => SwiftTypeKind::Enum,
+        };
+        // Classes record their superclass right after the common type descriptor fields.
+        let superclass = match kind {
+            SwiftTypeKind::Class => match self.read_relative_optional(descriptor.wrapping_add(20))? {
+                Some(superclass) => Some(self.read_mangled_name(superclass)?),
+                None => None,
+            },
+            _ => None,
+        };
+        let fields = match self.read_relative_optional(descriptor.wrapping_add(16))? {
+            Some(field_descriptor) => self.read_fields(field_descriptor)?,
+            None => Vec::new(),
+        };
+
+        Ok(SwiftType {
+            address: descriptor,
+            kind,
+            name: self.qualified_name(descriptor)?,
+            superclass,
+            fields,
+            conformances: Vec::new(),
+        })
+    }
+
+    // FieldDescriptor :== mangledTypeName, superclass, kind (u16), fieldRecordSize (u16), numFields
+    // FieldRecord :== flags, mangledTypeName, fieldName
+    fn read_fields(&mut self, field_descriptor: u64) -> Result<Vec<SwiftField>, AppError> {
+        let record_size = self.space.read_u16(field_descriptor.wrapping_add(10))? as u64;
+        let num_fields = self.space.read_u32(field_descriptor.wrapping_add(12))? as u64;
+        if num_fields != 0 {
+            self.check_records(field_descriptor.wrapping_add(16), num_fields, record_size, 12)?;
+        }
+        let mut fields = Vec::new();
+        for index in 0..num_fields {
+            let record = field_descriptor + 16 + index * record_size;
+            let flags = self.space.read_u32(record)?;
+            let type_name = match self.read_relative_optional(record.wrapping_add(4))? {
+                Some(type_name) => self.read_mangled_name(type_name)?,
+                None => String::new(),
+            };
+            fields.push(SwiftField {
+                name: self.read_relative_string(record.wrapping_add(8))?,
+                type_name,
+                is_var: flags & SWIFT_FIELD_RECORD_IS_VAR != 0,
+                is_indirect: flags & SWIFT_FIELD_RECORD_IS_INDIRECT_CASE != 0,
+            });
+        }
+        Ok(fields)
+    }
+
+    // TargetProtocolDescriptor :== flags, parent, name, numRequirementsInSignature, numRequirements,
+    //                              associatedTypeNames
+    fn read_protocol(&mut self, descriptor: u64) -> Result<SwiftProtocol, AppError> {
+        let associated_type_names = self.read_relative_string(descriptor.wrapping_add(20))?;
+        Ok(SwiftProtocol {
+            name: self.qualified_name(descriptor)?,
+            num_requirements: self.space.read_u32(descriptor.wrapping_add(16))?,
+            associated_type_names: associated_type_names.split_whitespace().map(String::from).collect(),
+        })
+    }
+
+    // ProtocolConformanceDescriptor :== protocol, typeRef, witnessTablePattern, flags
+    fn read_conformance(&mut self, descriptor: u64) -> Result<SwiftConformance, AppError> {
+        let protocol = match self.read_relative_context(descriptor)? {
+            SwiftContext::Descriptor(protocol) => self.qualified_name(protocol)?,
+            SwiftContext::Import(symbol_name) => symbol_name,
+        };
+        let flags = self.space.read_u32(descriptor.wrapping_add(12))?;
+        let type_reference = descriptor.wrapping_add(4);
+
+        let (type_name, type_descriptor) = match (flags >> 3) & 0x7 {
+            SWIFT_TYPE_REFERENCE_DIRECT_TYPE_DESCRIPTOR => {
+                let type_descriptor = self.read_relative_direct(type_reference)?;
+                (self.qualified_name(type_descriptor)?, Some(type_descriptor))
+            }
+            SWIFT_TYPE_REFERENCE_INDIRECT_TYPE_DESCRIPTOR => {
+                let slot = self.read_relative_direct(type_reference)?;
+                match self.space.read_pointer(slot)? {
+                    Pointer::Address(type_descriptor) => (self.qualified_name(type_descriptor)?, Some(type_descriptor)),
+                    Pointer::Import(symbol_name) => (symbol_name, None),
+                }
+            }
+            SWIFT_TYPE_REFERENCE_DIRECT_OBJC_CLASS_NAME => (self.read_relative_string(type_reference)?, None),
+            SWIFT_TYPE_REFERENCE_INDIRECT_OBJC_CLASS => {
+                let slot = self.read_relative_direct(type_reference)?;
+                match self.space.read_pointer(slot)? {
+                    Pointer::Import(symbol_name) => (symbol_name.strip_prefix(OBJC_CLASS_SYMBOL_PREFIX).unwrap_or(&symbol_name).to_string(), None),
+                    Pointer::Address(class) => (format!("objc class 0x{:x}", class), None),
+                }
+            }
+            _ => (String::from("?"), None),
+        };
+
+        Ok(SwiftConformance { address: descriptor, protocol, type_name, type_descriptor })
+    }
+
+    // AssociatedTypeDescriptor :== conformingTypeName, protocolTypeName, numAssociatedTypes,
+    //                              associatedTypeRecordSize, (name, substitutedTypeName){numAssociatedTypes}
+    fn read_associated_types(&mut self, descriptor: u64) -> Result<(SwiftAssociatedTypes, u64), AppError> {
+        let conforming_type = self.read_relative_direct(descriptor)?;
+        let protocol = self.read_relative_direct(descriptor.wrapping_add(4))?;
+        let num_associated_types = self.space.read_u32(descriptor.wrapping_add(8))? as u64;
+        let record_size = self.space.read_u32(descriptor.wrapping_add(12))? as u64;
+        if num_associated_types != 0 {
+            self.check_records(descriptor.wrapping_add(16), num_associated_types, record_size, 8)?;
+        }
+
+        let mut types = Vec::new();
+        for index in 0..num_associated_types {
+            let record = descriptor + 16 + index * record_size;
+            let substituted_type = self.read_relative_direct(record.wrapping_add(4))?;
+            types.push((self.read_relative_string(record)?, self.read_mangled_name(substituted_type)?));
+        }
+
+        let associated_types = SwiftAssociatedTypes {
+            conforming_type: self.read_mangled_name(conforming_type)?,
+            protocol: self.read_mangled_name(protocol)?,
+            types,
+        };
+        Ok((associated_types, 16 + num_associated_types * record_size))
+    }
+
+    fn context_kind(&mut self, descriptor: u64) -> Result<u32, AppError> {
+        Ok(self.space.read_u32(descriptor)? & SWIFT_CONTEXT_DESCRIPTOR_KIND_MASK)
+    }
+
+    fn context_parent(&mut self, descriptor: u64) -> Result<Option<SwiftContext>, AppError> {
+        match self.space.read_i32(descriptor.wrapping_add(4))? {
+            0 => Ok(None),
+            _ => Ok(Some(self.read_relative_context(descriptor.wrapping_add(4))?)),
+        }
+    }
+
+    fn context_name(&mut self, descriptor: u64) -> Result<String, AppError> {
+        self.read_relative_string(descriptor.wrapping_add(8))
+    }
+
+    /// Joins the names of the module and enclosing types, e.g. `MyModule.Outer.Inner`.
+    fn qualified_name(&mut self, descriptor: u64) -> Result<String, AppError> {
+        let mut components = Vec::new();
+        let mut context = Some(SwiftContext::Descriptor(descriptor));
+        let mut depth = 0;
+        while let Some(current) = context {
+            // Parent offsets come from the file and may point back at a descriptor already visited.
+            if depth >= MAX_CONTEXT_DEPTH {
+                return Err(context_too_deep(descriptor));
+            }
+            depth += 1;
+            match current {
+                SwiftContext::Descriptor(descriptor) => {
+                    match self.context_kind(descriptor)? {
+                        // Extensions and anonymous contexts have no name of their own.
+                        SWIFT_CONTEXT_DESCRIPTOR_KIND_EXTENSION | SWIFT_CONTEXT_DESCRIPTOR_KIND_ANONYMOUS => {}
+                        _ => components.push(self.context_name(descriptor)?),
+                    }
+                    context = self.context_parent(descriptor)?;
+                }
+                SwiftContext::Import(symbol_name) => {
+                    components.push(symbol_name);
+                    context = None;
+                }
+            }
+        }
+        components.reverse();
+        Ok(components.join("."))
+    }
+
+    /// Mangles a nominal type context the way the Swift mangler spells it inside a type name,
+    /// e.g. `4main5OuterV5InnerC`, so symbolic references can be replaced by plain mangling.
+    fn mangled_context(&mut self, context: SwiftContext, depth: usize) -> Result<Option<String>, AppError> {
+        let descriptor = match context {
+            // Imported descriptors are referenced through their symbol, e.g. _$s4main3FooVMn.
+            SwiftContext::Import(symbol_name) => {
+                let mangled = symbol_name.trim_start_matches('_').trim_start_matches("$s");
+                return Ok(mangled.strip_suffix("Mn").or_else(|| mangled.strip_suffix("Mp")).map(String::from));
+            }
+            SwiftContext::Descriptor(descriptor) => descriptor,
+        };
+        if depth >= MAX_CONTEXT_DEPTH {
+            return Err(context_too_deep(descriptor));
+        }
+
+        let kind = self.context_kind(descriptor)?;
+        let suffix = match kind {
+            SWIFT_CONTEXT_DESCRIPTOR_KIND_MODULE => {
+                let name = self.context_name(descriptor)?;
+                return Ok(Some(if name == "Swift" { String::from("s") } else { format!("{}{}", name.len(), name) }));
+            }
+            SWIFT_CONTEXT_DESCRIPTOR_KIND_CLASS => 'C',
+            SWIFT_CONTEXT_DESCRIPTOR_KIND_STRUCT => 'V',
+            SWIFT_CONTEXT_DESCRIPTOR_KIND_ENUM => 'O',
+            SWIFT_CONTEXT_DESCRIPTOR_KIND_PROTOCOL => 'P',
+            _ => return Ok(None),
+        };
+        let parent = match self.context_parent(descriptor)? {
+            Some(parent) => self.mangled_context(parent, depth + 1)?,
+            None => None,
+        };
+        let name = self.context_name(descriptor)?;
+        Ok(parent.map(|parent| format!("{}{}{}{}", parent, name.len(), name, suffix)))
+    }
+
+    /// Reads a mangled type name, replacing the symbolic references the compiler embeds in
+    /// reflection metadata with the mangling of the context they point to.
+    fn read_mangled_name(&mut self, address: u64) -> Result<String, AppError> {
+        let mut mangled = String::new();
+        let mut position = address;
+        loop {
+            let byte = self.space.read_bytes(position, 1)?[0];
+            match byte {
+                0 => return Ok(mangled),
+                SWIFT_SYMBOLIC_REFERENCE_DIRECT_CONTEXT | SWIFT_SYMBOLIC_REFERENCE_INDIRECT_CONTEXT => {
+                    let target = self.read_relative_direct(position.wrapping_add(1))?;
+                    let context = if byte == SWIFT_SYMBOLIC_REFERENCE_DIRECT_CONTEXT {
+                        SwiftContext::Descriptor(target)
+                    } else {
+                        match self.space.read_pointer(target)? {
+                            Pointer::Address(descriptor) => SwiftContext::Descriptor(descriptor),
+                            Pointer::Import(symbol_name) => SwiftContext::Import(symbol_name),
+                        }
+                    };
+                    match self.mangled_context(context, 0)? {
+                        Some(context) => mangled.push_str(&context),
+                        None => mangled.push_str(&format!("<symbolic 0x{:x}>", target)),
+                    }
+                    position = position.wrapping_add(5);
+                }
+                // Other relative symbolic references (accessor functions, existential shapes, ...)
+                0x03..=0x17 => {
+                    mangled.push_str(&format!("<symbolic 0x{:02x}>", byte));
+                    position = position.wrapping_add(5);
+                }
+                // Absolute symbolic references are pointer sized.
+                0x18..=0x1f => {
+                    mangled.push_str(&format!("<symbolic 0x{:02x}>", byte));
+                    position = position.wrapping_add(1 + self.space.pointer_size());
+                }
+                _ => {
+                    mangled.push(byte as char);
+                    position = position.wrapping_add(1);
+                }
+            }
+        }
+    }
+}
+
+fn context_too_deep(descriptor: u64) -> AppError {
+    AppError::from(io::Error::new(io::ErrorKind::InvalidData, format!("context descriptor 0x{:x} has more than {} parents", descriptor, MAX_CONTEXT_DEPTH)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Points the 32-bit relative offset at `field` to `target`.
+    fn relative(data: &mut SegmentData, field: u64, target: u64) {
+        data.write(field, &(target.wrapping_sub(field) as i32).to_le_bytes());
+    }
+
+    // flags, parent, name; the parent is left for the caller to fill in
+    fn context(data: &mut SegmentData, kind: u32, name: &str, fields: u64) -> u64 {
+        let name = data.string(name);
+        let descriptor = data.push(&words(&[kind, 0, 0, 0, 0]));
+        relative(data, descriptor + 8, name);
+        if fields != 0 {
+            relative(data, descriptor + 16, fields);
+        }
+        descriptor
+    }
+
+    // A mangled type name made of a single direct symbolic reference to `descriptor`.
+    fn symbolic_reference(data: &mut SegmentData, descriptor: u64) -> u64 {
+        let name = data.push(&[SWIFT_SYMBOLIC_REFERENCE_DIRECT_CONTEXT, 0, 0, 0, 0, 0]);
+        relative(data, name + 1, descriptor);
+        name
+    }
+
+    // (flags, mangled type name, field name) records after a FieldDescriptor header
+    fn field_descriptor(data: &mut SegmentData, record_size: u32, records: &[(u32, u64, &str)]) -> u64 {
+        let names: Vec<u64> = records.iter().map(|&(_, _, name)| data.string(name)).collect();
+        let mut fields = vec![0, 0, record_size << 16, records.len() as u32];
+        for &(flags, _, _) in records {
+            fields.extend([flags, 0, 0]);
+        }
+        let descriptor = data.push(&words(&fields));
+        for (index, (&(_, type_name, _), name)) in records.iter().zip(names).enumerate() {
+            let record = descriptor + 16 + 12 * index as u64;
+            relative(data, record + 4, type_name);
+            relative(data, record + 8, name);
+        }
+        descriptor
+    }
+
+    fn parse(mut data: SegmentData, descriptor: u64) -> Result<SwiftMetadata, AppError> {
+        let types = data.push(&[0; 4]);
+        relative(&mut data, types, descriptor);
+        let image = data_image(&data, &[("__swift5_types", types, 4)]);
+        let mach_o = MachO::from_bytes(&image).unwrap();
+        SwiftMetadata::from_file(&mut Cursor::new(&image), &mach_o)
+    }
+
+    #[test]
+    fn decodes_struct_field_records() {
+        let mut data = SegmentData::new();
+        let module = context(&mut data, SWIFT_CONTEXT_DESCRIPTOR_KIND_MODULE, "main", 0);
+        let int = data.string("Si");
+        let counter = data.push(&[0; 20]);
+        let own_type = symbolic_reference(&mut data, counter);
+        let fields = field_descriptor(&mut data, 12, &[(SWIFT_FIELD_RECORD_IS_VAR, int, "count"), (0, own_type, "next")]);
+        let name = data.string("Counter");
+        data.write(counter, &words(&[SWIFT_CONTEXT_DESCRIPTOR_KIND_STRUCT]));
+        relative(&mut data, counter + 4, module);
+        relative(&mut data, counter + 8, name);
+        relative(&mut data, counter + 16, fields);
+
+        let metadata = parse(data, counter).unwrap();
+        let swift_type = &metadata.types[0];
+        assert_eq!((swift_type.kind, swift_type.name.as_str()), (SwiftTypeKind::Struct, "main.Counter"));
+        let fields: Vec<(&str, &str, bool)> = swift_type.fields.iter().map(|field| (field.name.as_str(), field.type_name.as_str(), field.is_var)).collect();
+        assert_eq!(fields, [("count", "Si", true), ("next", "4main7CounterV", false)]);
+    }
+
+    #[test]
+    fn cyclic_parents_are_rejected() {
+        // A type nested in an anonymous context that is its own parent.
+        let mut data = SegmentData::new();
+        let anonymous = context(&mut data, SWIFT_CONTEXT_DESCRIPTOR_KIND_ANONYMOUS, "", 0);
+        relative(&mut data, anonymous + 4, anonymous);
+        let nested = context(&mut data, SWIFT_CONTEXT_DESCRIPTOR_KIND_STRUCT, "Nested", 0);
+        relative(&mut data, nested + 4, anonymous);
+        let error = parse(data, nested).err().unwrap();
+        assert!(error.to_string().contains("has more than 256 parents"), "{}", error);
+
+        // A field whose type is a struct that is its own parent, which the mangler recurses on.
+        let mut data = SegmentData::new();
+        let module = context(&mut data, SWIFT_CONTEXT_DESCRIPTOR_KIND_MODULE, "main", 0);
+        let looped = context(&mut data, SWIFT_CONTEXT_DESCRIPTOR_KIND_STRUCT, "Loop", 0);
+        relative(&mut data, looped + 4, looped);
+        let type_name = symbolic_reference(&mut data, looped);
+        let fields = field_descriptor(&mut data, 12, &[(0, type_name, "value")]);
+        let holder = context(&mut data, SWIFT_CONTEXT_DESCRIPTOR_KIND_STRUCT, "Holder", fields);
+        relative(&mut data, holder + 4, module);
+        let error = parse(data, holder).err().unwrap();
+        assert!(error.to_string().contains("has more than 256 parents"), "{}", error);
+    }
+
+    #[test]
+    fn truncated_field_records_are_rejected() {
+        let mut data = SegmentData::new();
+        let int = data.string("Si");
+        let fields = field_descriptor(&mut data, 12, &[(0, int, "value")]);
+        data.write(fields + 12, &words(&[0x1000_0000]));
+        let holder = context(&mut data, SWIFT_CONTEXT_DESCRIPTOR_KIND_STRUCT, "Holder", fields);
+        assert!(parse(data, holder).is_err());
+
+        let mut data = SegmentData::new();
+        let int = data.string("Si");
+        let fields = field_descriptor(&mut data, 4, &[(0, int, "value")]);
+        let holder = context(&mut data, SWIFT_CONTEXT_DESCRIPTOR_KIND_STRUCT, "Holder", fields);
+        let error = parse(data, holder).err().unwrap();
+        assert!(error.to_string().contains("too small for their fields"), "{}", error);
+    }
+}
@@ -0,0 +1,2766 @@
+use std::fmt::Write;
+use std::rc::Rc;
+
+use crate::demangle::DemangleStyle;
+
+// Swift symbols are mangled as a postfix program: every operator either pushes a node or pops
+// the nodes it applies to. The demangler replays that program into a node tree, which is then
+// printed the way `swift demangle` spells it.
+
+// Guards against malformed or hostile names blowing up the tree, the stack or the output.
+const MAX_SYMBOL_LENGTH: usize = 16 * 1024;
+const MAX_REPEAT_COUNT: u64 = 2048;
+const MAX_NESTING: usize = 256;
+const MAX_OUTPUT_LENGTH: usize = 64 * 1024;
+const MAX_WORDS: usize = 26;
+const MAX_SPECIALIZATION_PASS: u8 = 10;
+
+// Function signature specialization parameter kinds, the first group is an enumeration and the
+// second an option set, see FunctionSigSpecializationParamKind in the Swift demangler.
+const SPEC_CONSTANT_PROP_FUNCTION: u64 = 0;
+const SPEC_CONSTANT_PROP_GLOBAL: u64 = 1;
+const SPEC_CONSTANT_PROP_INTEGER: u64 = 2;
+const SPEC_CONSTANT_PROP_FLOAT: u64 = 3;
+const SPEC_CONSTANT_PROP_STRING: u64 = 4;
+const SPEC_CLOSURE_PROP: u64 = 5;
+const SPEC_BOX_TO_VALUE: u64 = 6;
+const SPEC_BOX_TO_STACK: u64 = 7;
+const SPEC_IN_OUT_TO_OUT: u64 = 8;
+const SPEC_DEAD: u64 = 1 << 6;
+const SPEC_OWNED_TO_GUARANTEED: u64 = 1 << 7;
+const SPEC_SROA: u64 = 1 << 8;
+const SPEC_GUARANTEED_TO_OWNED: u64 = 1 << 9;
+const SPEC_EXISTENTIAL_TO_GENERIC: u64 = 1 << 10;
+
+/// Demangles a Swift symbol such as `_$s4main3FooV3baryyF` or the Swift 4 form
+/// `__T04main3FooV3baryyF`, as well as Objective-C runtime names like `_TtC4main3Foo`.
+/// Returns `None` for names that are not Swift mangled or use mangling this demangler
+/// does not understand. The name-only style leaves out the types of functions and variables.
+pub fn demangle_symbol(name: &str, style: DemangleStyle) -> Option<String> {
+    // Mach-O symbol names carry an extra leading underscore.
+    let name = match name.strip_prefix('_') {
+        Some(stripped) if is_mangled_prefix(stripped) => stripped,
+        _ => name,
+    };
+    if let Some(runtime_name) = name.strip_prefix("_Tt") {
+        return demangle_objc_runtime_name(runtime_name);
+    }
+    let (mangled, old_function_types) = if let Some(mangled) = name.strip_prefix("_T0") {
+        (mangled, true)
+    } else {
+        (["$s", "$S", "$e"].iter().find_map(|prefix| name.strip_prefix(prefix))?, false)
+    };
+    demangle_mangled_symbol(mangled, old_function_types, style == DemangleStyle::NameOnly)
+}
+
+/// Demangles a bare mangled type as stored in Swift reflection metadata, e.g. `SaySSG`.
+pub fn demangle_type(mangled: &str) -> Option<String> {
+    if mangled.is_empty() || mangled.len() > MAX_SYMBOL_LENGTH {
+        return None;
+    }
+    let mut demangler = Demangler::new(mangled, false);
+    while demangler.position < demangler.text.len() {
+        let node = demangler.demangle_operator()?;
+        demangler.stack.push(node);
+    }
+    if demangler.stack.len() != 1 || demangler.stack[0].kind != Kind::Type {
+        return None;
+    }
+    let node = demangler.stack.pop()?;
+    NodePrinter::print_tree(&node, false)
+}
+
+fn is_mangled_prefix(name: &str) -> bool {
+    ["$s", "$S", "$e", "_T0", "_Tt"].iter().any(|prefix| name.starts_with(prefix))
+}
+
+fn demangle_mangled_symbol(mangled: &str, old_function_types: bool, name_only: bool) -> Option<String> {
+    if mangled.is_empty() || mangled.len() > MAX_SYMBOL_LENGTH {
+        return None;
+    }
+    let node = Demangler::new(mangled, old_function_types).demangle_symbol()?;
+    NodePrinter::print_tree(&node, name_only)
+}
+
+// The pre-Swift 4 mangling survives in the names classes are registered with in the Objective-C
+// runtime: nominal kinds outermost first, then the module and one identifier per kind.
+fn demangle_objc_runtime_name(name: &str) -> Option<String> {
+    let bytes = name.as_bytes();
+    let kinds = bytes.iter().take_while(|byte| matches!(byte, b'C' | b'V' | b'O' | b'P')).count();
+    if kinds == 0 {
+        return None;
+    }
+    let mut position = kinds;
+    let mut components = Vec::new();
+    // The standard library is abbreviated to `s` as well as the older `Ss`.
+    let module = match (bytes.get(position..position + 2), bytes.get(position)) {
+        (Some(b"Ss"), _) => Some(("Swift", 2)),
+        (Some(b"So"), _) => Some(("__C", 2)),
+        (_, Some(b's')) => Some(("Swift", 1)),
+        _ => None,
+    };
+    if let Some((module, length)) = module {
+        components.push(module.to_string());
+        position += length;
+    }
+    while position < bytes.len() {
+        let digits = bytes[position..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+        if digits == 0 {
+            break;
+        }
+        let length: usize = name[position..position + digits].parse().ok()?;
+        position += digits;
+        let end = position.checked_add(length)?;
+        components.push(name.get(position..end)?.to_string());
+        position = end;
+    }
+    // Protocols are terminated by an underscore.
+    if bytes[kinds - 1] == b'P' && bytes.get(position) == Some(&b'_') {
+        position += 1;
+    }
+    if position != bytes.len() || components.len() != kinds + 1 {
+        return None;
+    }
+    Some(components.join("."))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Global,
+    Suffix,
+    Identifier,
+    Module,
+    LocalDeclName,
+    PrivateDeclName,
+    InfixOperator,
+    PrefixOperator,
+    PostfixOperator,
+    Number,
+    Type,
+    TypeMangling,
+    Class,
+    Structure,
+    Enum,
+    Protocol,
+    TypeAlias,
+    OtherNominalType,
+    Extension,
+    BoundGeneric,
+    BoundGenericFunction,
+    TypeList,
+    BuiltinTypeName,
+    Tuple,
+    TupleElement,
+    TupleElementName,
+    VariadicMarker,
+    EmptyList,
+    FirstElementMarker,
+    LabelList,
+    /// A function type; the text holds attributes like `@convention(c) `.
+    FunctionType,
+    ArgumentTuple,
+    ReturnType,
+    ThrowsAnnotation,
+    TypedThrowsAnnotation,
+    AsyncAnnotation,
+    ConcurrentFunctionType,
+    GlobalActorFunctionType,
+    IsolatedAnyFunctionType,
+    /// A type with a prefix such as `inout ` or `weak `, held in the text.
+    TypeAttribute,
+    Metatype,
+    ExistentialMetatype,
+    DynamicSelf,
+    ErrorType,
+    ProtocolList,
+    ProtocolListWithAnyObject,
+    ProtocolListWithClass,
+    DependentGenericParamType,
+    DependentGenericSignature,
+    DependentGenericParamCount,
+    DependentGenericType,
+    DependentMemberType,
+    DependentAssociatedTypeRef,
+    ConformanceRequirement,
+    SameTypeRequirement,
+    LayoutRequirement,
+    OpaqueReturnType,
+    OpaqueReturnTypeOf,
+    OpaqueType,
+    ImplFunctionType,
+    ImplAttribute,
+    ImplParameter,
+    ImplResult,
+    ImplErrorResult,
+    Function,
+    Variable,
+    Subscript,
+    Static,
+    Allocator,
+    Constructor,
+    Destructor,
+    Deallocator,
+    IVarInitializer,
+    IVarDestroyer,
+    Initializer,
+    ExplicitClosure,
+    ImplicitClosure,
+    DefaultArgumentInitializer,
+    PropertyWrapperBackingInitializer,
+    /// An accessor of a variable or subscript; the text holds its name, e.g. `getter`.
+    Accessor,
+    /// A description followed by its children, e.g. `type metadata for ` and a type.
+    Labeled,
+    /// A prefix like `@objc ` or `merged ` applied to the entity that follows.
+    FunctionAttribute,
+    PartialApply,
+    Specialization,
+    SpecializationParam,
+    IsSerialized,
+    FunctionSignatureSpecializationParam,
+    FunctionSignatureSpecializationReturn,
+    SpecializationParamFlags,
+    SpecializationPayload,
+    ProtocolConformance,
+    ProtocolWitness,
+    VTableThunk,
+    ReabstractionThunk,
+    KeyPathThunk,
+    LazyWitnessTable,
+    AssociatedTypeMetadataAccessor,
+    AssociatedTypeWitnessTableAccessor,
+    BaseWitnessTableAccessor,
+    AssociatedConformanceDescriptor,
+    BaseConformanceDescriptor,
+}
+
+type NodeRef = Rc<Node>;
+
+#[derive(Debug, Clone)]
+struct Node {
+    kind: Kind,
+    text: String,
+    index: u64,
+    children: Vec<NodeRef>,
+}
+
+impl Node {
+    fn new(kind: Kind) -> Node {
+        Node { kind, text: String::new(), index: 0, children: Vec::new() }
+    }
+
+    fn with_text(kind: Kind, text: impl Into<String>) -> Node {
+        Node { text: text.into(), ..Node::new(kind) }
+    }
+
+    fn with_index(kind: Kind, index: u64) -> Node {
+        Node { index, ..Node::new(kind) }
+    }
+
+    fn with_children(kind: Kind, children: Vec<NodeRef>) -> Node {
+        Node { children, ..Node::new(kind) }
+    }
+
+    fn rc(self) -> NodeRef {
+        Rc::new(self)
+    }
+
+    fn child(&self, index: usize) -> Result<&NodeRef, ()> {
+        self.children.get(index).ok_or(())
+    }
+
+    fn find(&self, kind: Kind) -> Option<&NodeRef> {
+        self.children.iter().find(|child| child.kind == kind)
+    }
+}
+
+fn type_node(child: NodeRef) -> NodeRef {
+    Node::with_children(Kind::Type, vec![child]).rc()
+}
+
+fn labeled(description: &str, children: Vec<NodeRef>) -> NodeRef {
+    Node { children, ..Node::with_text(Kind::Labeled, description) }.rc()
+}
+
+fn is_decl_name(kind: Kind) -> bool {
+    matches!(kind, Kind::Identifier | Kind::LocalDeclName | Kind::PrivateDeclName | Kind::InfixOperator | Kind::PrefixOperator | Kind::PostfixOperator)
+}
+
+fn is_any_generic(kind: Kind) -> bool {
+    matches!(kind, Kind::Class | Kind::Structure | Kind::Enum | Kind::Protocol | Kind::TypeAlias | Kind::OtherNominalType)
+}
+
+fn is_context(kind: Kind) -> bool {
+    is_any_generic(kind) || matches!(kind,
+        Kind::Module | Kind::Extension | Kind::Function | Kind::Variable | Kind::Subscript | Kind::Static
+        | Kind::Allocator | Kind::Constructor | Kind::Destructor | Kind::Deallocator | Kind::IVarInitializer
+        | Kind::IVarDestroyer | Kind::Initializer | Kind::ExplicitClosure | Kind::ImplicitClosure
+        | Kind::DefaultArgumentInitializer | Kind::PropertyWrapperBackingInitializer | Kind::Accessor
+        | Kind::OpaqueReturnTypeOf)
+}
+
+fn is_entity(kind: Kind) -> bool {
+    kind == Kind::Type || is_context(kind)
+}
+
+fn is_requirement(kind: Kind) -> bool {
+    matches!(kind, Kind::ConformanceRequirement | Kind::SameTypeRequirement | Kind::LayoutRequirement)
+}
+
+fn is_function_attribute(kind: Kind) -> bool {
+    matches!(kind, Kind::FunctionAttribute | Kind::PartialApply | Kind::Specialization)
+}
+
+fn generic_parameter_name(depth: u64, index: u64) -> String {
+    let mut name = String::new();
+    let mut index = index;
+    loop {
+        name.push((b'A' + (index % 26) as u8) as char);
+        index /= 26;
+        if index == 0 {
+            break;
+        }
+    }
+    if depth != 0 {
+        write!(name, "{}", depth).unwrap();
+    }
+    name
+}
+
+fn generic_param_type(depth: u64, index: u64) -> NodeRef {
+    Node::with_text(Kind::DependentGenericParamType, generic_parameter_name(depth, index)).rc()
+}
+
+fn swift_type(kind: Kind, name: &str) -> NodeRef {
+    let module = Node::with_text(Kind::Module, "Swift").rc();
+    type_node(Node::with_children(kind, vec![module, Node::with_text(Kind::Identifier, name).rc()]).rc())
+}
+
+// `S` followed by a letter names a well-known type of the standard library, `Sc` followed by a
+// letter one from the concurrency library.
+fn standard_type(code: u8, concurrency: bool) -> Option<(Kind, &'static str)> {
+    let standard_type = if concurrency {
+        match code {
+            b'A' => (Kind::Protocol, "Actor"),
+            b'C' => (Kind::Structure, "CheckedContinuation"),
+            b'c' => (Kind::Structure, "UnsafeContinuation"),
+            b'E' => (Kind::Structure, "CancellationError"),
+            b'e' => (Kind::Structure, "UnownedSerialExecutor"),
+            b'F' => (Kind::Protocol, "Executor"),
+            b'f' => (Kind::Protocol, "SerialExecutor"),
+            b'G' => (Kind::Structure, "TaskGroup"),
+            b'g' => (Kind::Structure, "ThrowingTaskGroup"),
+            b'I' => (Kind::Protocol, "AsyncIteratorProtocol"),
+            b'i' => (Kind::Protocol, "AsyncSequence"),
+            b'J' => (Kind::Structure, "UnownedJob"),
+            b'M' => (Kind::Class, "MainActor"),
+            b'P' => (Kind::Structure, "TaskPriority"),
+            b'S' => (Kind::Structure, "AsyncStream"),
+            b's' => (Kind::Structure, "AsyncThrowingStream"),
+            b'T' => (Kind::Structure, "Task"),
+            b't' => (Kind::Structure, "UnsafeCurrentTask"),
+            _ => return None,
+        }
+    } else {
+        match code {
+            b'A' => (Kind::Structure, "AutoreleasingUnsafeMutablePointer"),
+            b'a' => (Kind::Structure, "Array"),
+            b'b' => (Kind::Structure, "Bool"),
+            b'D' => (Kind::Structure, "Dictionary"),
+            b'd' => (Kind::Structure, "Double"),
+            b'f' => (Kind::Structure, "Float"),
+            b'h' => (Kind::Structure, "Set"),
+            b'I' => (Kind::Structure, "DefaultIndices"),
+            b'i' => (Kind::Structure, "Int"),
+            b'J' => (Kind::Structure, "Character"),
+            b'N' => (Kind::Structure, "ClosedRange"),
+            b'n' => (Kind::Structure, "Range"),
+            b'O' => (Kind::Structure, "ObjectIdentifier"),
+            b'P' => (Kind::Structure, "UnsafePointer"),
+            b'p' => (Kind::Structure, "UnsafeMutablePointer"),
+            b'R' => (Kind::Structure, "UnsafeBufferPointer"),
+            b'r' => (Kind::Structure, "UnsafeMutableBufferPointer"),
+            b'S' => (Kind::Structure, "String"),
+            b's' => (Kind::Structure, "Substring"),
+            b'u' => (Kind::Structure, "UInt"),
+            b'V' => (Kind::Structure, "UnsafeRawPointer"),
+            b'v' => (Kind::Structure, "UnsafeMutableRawPointer"),
+            b'W' => (Kind::Structure, "UnsafeRawBufferPointer"),
+            b'w' => (Kind::Structure, "UnsafeMutableRawBufferPointer"),
+            b'q' => (Kind::Enum, "Optional"),
+            b'B' => (Kind::Protocol, "BinaryFloatingPoint"),
+            b'E' => (Kind::Protocol, "Encodable"),
+            b'e' => (Kind::Protocol, "Decodable"),
+            b'F' => (Kind::Protocol, "FloatingPoint"),
+            b'G' => (Kind::Protocol, "RandomNumberGenerator"),
+            b'H' => (Kind::Protocol, "Hashable"),
+            b'j' => (Kind::Protocol, "Numeric"),
+            b'K' => (Kind::Protocol, "BidirectionalCollection"),
+            b'k' => (Kind::Protocol, "RandomAccessCollection"),
+            b'L' => (Kind::Protocol, "Comparable"),
+            b'l' => (Kind::Protocol, "Collection"),
+            b'M' => (Kind::Protocol, "MutableCollection"),
+            b'm' => (Kind::Protocol, "RangeReplaceableCollection"),
+            b'Q' => (Kind::Protocol, "Equatable"),
+            b'T' => (Kind::Protocol, "Sequence"),
+            b't' => (Kind::Protocol, "IteratorProtocol"),
+            b'U' => (Kind::Protocol, "UnsignedInteger"),
+            b'X' => (Kind::Protocol, "RangeExpression"),
+            b'x' => (Kind::Protocol, "Strideable"),
+            b'Y' => (Kind::Protocol, "RawRepresentable"),
+            b'y' => (Kind::Protocol, "StringProtocol"),
+            b'Z' => (Kind::Protocol, "SignedInteger"),
+            b'z' => (Kind::Protocol, "BinaryInteger"),
+            _ => return None,
+        }
+    };
+    Some(standard_type)
+}
+
+fn value_witness_name(code: &[u8]) -> Option<&'static str> {
+    let name = match code {
+        b"al" => "allocateBuffer",
+        b"ca" => "assignWithCopy",
+        b"ta" => "assignWithTake",
+        b"de" => "deallocateBuffer",
+        b"xx" => "destroy",
+        b"XX" => "destroyBuffer",
+        b"Xx" => "destroyArray",
+        b"CP" => "initializeBufferWithCopyOfBuffer",
+        b"Cp" => "initializeBufferWithCopy",
+        b"cp" => "initializeWithCopy",
+        b"Tk" => "initializeBufferWithTake",
+        b"tk" => "initializeWithTake",
+        b"pr" => "projectBuffer",
+        b"TK" => "initializeBufferWithTakeOfBuffer",
+        b"Cc" => "initializeArrayWithCopy",
+        b"Tt" => "initializeArrayWithTakeFrontToBack",
+        b"tT" => "initializeArrayWithTakeBackToFront",
+        b"xs" => "storeExtraInhabitant",
+        b"xg" => "getExtraInhabitantIndex",
+        b"ug" => "getEnumTag",
+        b"up" => "destructiveProjectEnumData",
+        b"ui" => "destructiveInjectEnumTag",
+        b"et" => "getEnumTagSinglePayload",
+        b"st" => "storeEnumTagSinglePayload",
+        _ => return None,
+    };
+    Some(name)
+}
+
+struct Demangler<'a> {
+    text: &'a [u8],
+    position: usize,
+    stack: Vec<NodeRef>,
+    substitutions: Vec<NodeRef>,
+    words: Vec<String>,
+    // Swift 4.0 (`_T0`) kept argument labels inside the parameter tuple.
+    old_function_types: bool,
+}
+
+impl<'a> Demangler<'a> {
+    fn new(text: &'a str, old_function_types: bool) -> Demangler<'a> {
+        Demangler {
+            text: text.as_bytes(),
+            position: 0,
+            stack: Vec::new(),
+            substitutions: Vec::new(),
+            words: Vec::new(),
+            old_function_types,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn next_if(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn push_back(&mut self) {
+        self.position -= 1;
+    }
+
+    fn pop(&mut self) -> Option<NodeRef> {
+        self.stack.pop()
+    }
+
+    fn pop_kind(&mut self, kind: Kind) -> Option<NodeRef> {
+        self.pop_if(|node_kind| node_kind == kind)
+    }
+
+    fn pop_if(&mut self, predicate: impl Fn(Kind) -> bool) -> Option<NodeRef> {
+        if predicate(self.stack.last()?.kind) {
+            self.stack.pop()
+        } else {
+            None
+        }
+    }
+
+    fn pop_type_child(&mut self) -> Option<NodeRef> {
+        Some(self.pop_kind(Kind::Type)?.children.first()?.clone())
+    }
+
+    fn pop_module(&mut self) -> Option<NodeRef> {
+        if let Some(identifier) = self.pop_kind(Kind::Identifier) {
+            return Some(Node::with_text(Kind::Module, identifier.text.clone()).rc());
+        }
+        self.pop_kind(Kind::Module)
+    }
+
+    fn pop_context(&mut self) -> Option<NodeRef> {
+        if let Some(module) = self.pop_module() {
+            return Some(module);
+        }
+        if let Some(context_type) = self.pop_kind(Kind::Type) {
+            let child = context_type.children.first()?;
+            return if context_type.children.len() == 1 && is_context(child.kind) { Some(child.clone()) } else { None };
+        }
+        self.pop_if(is_context)
+    }
+
+    fn pop_protocol(&mut self) -> Option<NodeRef> {
+        if let Some(protocol) = self.pop_kind(Kind::Type) {
+            return if protocol.children.first()?.kind == Kind::Protocol { Some(protocol) } else { None };
+        }
+        let name = self.pop_if(is_decl_name)?;
+        let context = self.pop_context()?;
+        Some(type_node(Node::with_children(Kind::Protocol, vec![context, name]).rc()))
+    }
+
+    fn demangle_natural(&mut self) -> Option<u64> {
+        let start = self.position;
+        while self.peek().is_some_and(|byte| byte.is_ascii_digit()) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.text[start..self.position]).ok()?.parse().ok()
+    }
+
+    // index ::= '_' | NATURAL '_', encoding zero and NATURAL + 1
+    fn demangle_index(&mut self) -> Option<u64> {
+        if self.next_if(b'_') {
+            return Some(0);
+        }
+        let natural = self.demangle_natural()?;
+        if !self.next_if(b'_') {
+            return None;
+        }
+        natural.checked_add(1)
+    }
+
+    fn demangle_index_node(&mut self) -> Option<NodeRef> {
+        Some(Node::with_index(Kind::Number, self.demangle_index()?).rc())
+    }
+
+    fn demangle_symbol(&mut self) -> Option<NodeRef> {
+        while self.position < self.text.len() {
+            if self.peek() == Some(b'.') {
+                let suffix = String::from_utf8_lossy(&self.text[self.position..]).into_owned();
+                self.stack.push(Node::with_text(Kind::Suffix, suffix).rc());
+                break;
+            }
+            let node = self.demangle_operator()?;
+            self.stack.push(node);
+        }
+
+        // Attributes such as @objc or a specialization are mangled after the entity they
+        // apply to. A partial apply forwarder wraps everything that follows it.
+        let mut attributes = Vec::new();
+        while let Some(attribute) = self.pop_if(is_function_attribute) {
+            attributes.push(attribute);
+        }
+        let mut children: Vec<NodeRef> = self.stack.drain(..)
+            .map(|node| if node.kind == Kind::Type { node.children[0].clone() } else { node })
+            .collect();
+        for attribute in attributes.into_iter().rev() {
+            if attribute.kind == Kind::PartialApply {
+                children = vec![Node { children, ..(*attribute).clone() }.rc()];
+            } else {
+                children.insert(0, attribute);
+            }
+        }
+        if children.is_empty() {
+            return None;
+        }
+        Some(Node::with_children(Kind::Global, children).rc())
+    }
+
+    fn demangle_operator(&mut self) -> Option<NodeRef> {
+        let node = match self.next()? {
+            b'A' => return self.demangle_multi_substitutions(),
+            b'B' => self.demangle_builtin_type()?,
+            b'C' => self.demangle_nominal_type(Kind::Class)?,
+            b'D' => Node::with_children(Kind::TypeMangling, vec![self.pop_kind(Kind::Type)?]).rc(),
+            b'E' => self.demangle_extension_context()?,
+            b'F' => self.demangle_plain_function()?,
+            b'G' => self.demangle_bound_generic_type()?,
+            b'I' => self.demangle_impl_function_type()?,
+            b'K' => Node::new(Kind::ThrowsAnnotation).rc(),
+            b'L' => self.demangle_local_identifier()?,
+            b'M' => self.demangle_metatype()?,
+            b'N' => labeled("type metadata for ", vec![self.pop_kind(Kind::Type)?]),
+            b'O' => self.demangle_nominal_type(Kind::Enum)?,
+            b'P' => self.demangle_nominal_type(Kind::Protocol)?,
+            b'Q' => self.demangle_archetype()?,
+            b'R' => self.demangle_generic_requirement()?,
+            b'S' => return self.demangle_standard_substitution(),
+            b'T' => self.demangle_thunk_or_specialization()?,
+            b'V' => self.demangle_nominal_type(Kind::Structure)?,
+            b'W' => self.demangle_witness()?,
+            b'X' => self.demangle_special_type()?,
+            b'Y' => self.demangle_type_annotation()?,
+            b'Z' => Node::with_children(Kind::Static, vec![self.pop_if(is_entity)?]).rc(),
+            b'a' => self.demangle_nominal_type(Kind::TypeAlias)?,
+            b'c' => self.pop_function_type("")?,
+            b'd' => Node::new(Kind::VariadicMarker).rc(),
+            b'f' => self.demangle_function_entity()?,
+            b'h' => self.type_attribute("__shared ")?,
+            b'i' => self.demangle_subscript()?,
+            b'l' => self.demangle_generic_signature(false)?,
+            b'm' => type_node(Node::with_children(Kind::Metatype, vec![self.pop_kind(Kind::Type)?]).rc()),
+            b'n' => self.type_attribute("__owned ")?,
+            b'o' => self.demangle_operator_identifier()?,
+            b'p' => type_node(self.demangle_protocol_list()?),
+            b'q' => type_node(self.demangle_generic_param_index()?),
+            b'r' => self.demangle_generic_signature(true)?,
+            b's' => Node::with_text(Kind::Module, "Swift").rc(),
+            b't' => self.pop_tuple()?,
+            b'u' => {
+                let signature = self.pop_kind(Kind::DependentGenericSignature)?;
+                let generic_type = self.pop_kind(Kind::Type)?;
+                type_node(Node::with_children(Kind::DependentGenericType, vec![signature, generic_type]).rc())
+            }
+            b'v' => {
+                let variable = self.demangle_entity(Kind::Variable)?;
+                self.demangle_accessor(variable)?
+            }
+            b'w' => {
+                let code = [self.next()?, self.next()?];
+                let description = format!("{} value witness for ", value_witness_name(&code)?);
+                labeled(&description, vec![self.pop_kind(Kind::Type)?])
+            }
+            b'x' => type_node(generic_param_type(0, 0)),
+            b'y' => Node::new(Kind::EmptyList).rc(),
+            b'z' => self.type_attribute("inout ")?,
+            b'_' => Node::new(Kind::FirstElementMarker).rc(),
+            b'0'..=b'9' => {
+                self.push_back();
+                self.demangle_identifier()?
+            }
+            _ => return None,
+        };
+        Some(node)
+    }
+
+    fn type_attribute(&mut self, attribute: &str) -> Option<NodeRef> {
+        let child = self.pop_type_child()?;
+        Some(type_node(Node { children: vec![child], ..Node::with_text(Kind::TypeAttribute, attribute) }.rc()))
+    }
+
+    // identifier ::= NATURAL IDENTIFIER-STRING
+    // identifier ::= '0' (NATURAL IDENTIFIER-STRING | [a-z])* [A-Z]   -- with word substitutions
+    // identifier ::= '00' NATURAL '_'? PUNYCODE-STRING
+    fn demangle_identifier(&mut self) -> Option<NodeRef> {
+        let mut has_word_substitutions = false;
+        let mut is_punycoded = false;
+        if self.next_if(b'0') {
+            if self.next_if(b'0') {
+                is_punycoded = true;
+            } else {
+                has_word_substitutions = true;
+            }
+        }
+
+        let mut identifier = String::new();
+        loop {
+            while has_word_substitutions && self.peek().is_some_and(|byte| byte.is_ascii_alphabetic()) {
+                let byte = self.next()?;
+                let word_index = if byte.is_ascii_lowercase() {
+                    byte - b'a'
+                } else {
+                    has_word_substitutions = false;
+                    byte - b'A'
+                };
+                identifier.push_str(self.words.get(word_index as usize)?);
+            }
+            if self.next_if(b'0') {
+                break;
+            }
+            let length = self.demangle_natural()? as usize;
+            if length == 0 {
+                return None;
+            }
+            if is_punycoded {
+                self.next_if(b'_');
+            }
+            let slice = self.text.get(self.position..self.position.checked_add(length)?)?;
+            self.position += length;
+            if is_punycoded {
+                identifier.push_str(&decode_punycode(slice)?);
+            } else {
+                identifier.push_str(std::str::from_utf8(slice).ok()?);
+                self.collect_words(slice);
+            }
+            if !has_word_substitutions {
+                break;
+            }
+        }
+        if identifier.is_empty() {
+            return None;
+        }
+        let node = Node::with_text(Kind::Identifier, identifier).rc();
+        self.substitutions.push(node.clone());
+        Some(node)
+    }
+
+    // Words start at a letter and end before an underscore or a lowercase to uppercase
+    // transition, and may be referenced by later identifiers.
+    fn collect_words(&mut self, slice: &[u8]) {
+        let mut word_start = None;
+        for index in 0..=slice.len() {
+            let byte = slice.get(index).copied().unwrap_or(0);
+            if let Some(start) = word_start {
+                let previous = slice[index - 1];
+                let is_word_end = byte == b'_' || byte == 0 || (!previous.is_ascii_uppercase() && byte.is_ascii_uppercase());
+                if is_word_end {
+                    if index - start >= 2 && self.words.len() < MAX_WORDS {
+                        self.words.push(String::from_utf8_lossy(&slice[start..index]).into_owned());
+                    }
+                    word_start = None;
+                }
+            }
+            if word_start.is_none() && !byte.is_ascii_digit() && byte != b'_' && byte != 0 {
+                word_start = Some(index);
+            }
+        }
+    }
+
+    fn demangle_operator_identifier(&mut self) -> Option<NodeRef> {
+        const OPERATOR_CHARACTERS: &[u8; 26] = b"& @/= >    <*!|+?%-~   ^ .";
+        let identifier = self.pop_kind(Kind::Identifier)?;
+        let mut operator = String::new();
+        for character in identifier.text.chars() {
+            if !character.is_ascii() {
+                operator.push(character);
+                continue;
+            }
+            if !character.is_ascii_lowercase() {
+                return None;
+            }
+            match OPERATOR_CHARACTERS[(character as u8 - b'a') as usize] {
+                b' ' => return None,
+                operator_character => operator.push(operator_character as char),
+            }
+        }
+        let kind = match self.next()? {
+            b'i' => Kind::InfixOperator,
+            b'p' => Kind::PrefixOperator,
+            b'P' => Kind::PostfixOperator,
+            _ => return None,
+        };
+        Some(Node::with_text(kind, operator).rc())
+    }
+
+    fn demangle_local_identifier(&mut self) -> Option<NodeRef> {
+        if self.next_if(b'L') {
+            let discriminator = self.pop_kind(Kind::Identifier)?;
+            let name = self.pop_if(is_decl_name)?;
+            return Some(Node::with_children(Kind::PrivateDeclName, vec![discriminator, name]).rc());
+        }
+        if self.next_if(b'l') {
+            let discriminator = self.pop_kind(Kind::Identifier)?;
+            return Some(Node::with_children(Kind::PrivateDeclName, vec![discriminator]).rc());
+        }
+        let discriminator = self.demangle_index_node()?;
+        let name = self.pop_if(is_decl_name)?;
+        Some(Node::with_children(Kind::LocalDeclName, vec![discriminator, name]).rc())
+    }
+
+    // substitution ::= 'A' INDEX | 'A' (NATURAL? [a-z])* NATURAL? [A-Z]
+    fn demangle_multi_substitutions(&mut self) -> Option<NodeRef> {
+        let mut repeat_count = None;
+        loop {
+            let byte = self.next()?;
+            if byte.is_ascii_lowercase() {
+                let node = self.push_multi_substitutions(repeat_count, (byte - b'a') as usize)?;
+                self.stack.push(node);
+                repeat_count = None;
+            } else if byte.is_ascii_uppercase() {
+                return self.push_multi_substitutions(repeat_count, (byte - b'A') as usize);
+            } else if byte == b'_' {
+                let index = repeat_count.unwrap_or(0) as usize + 27;
+                return self.substitutions.get(index).cloned();
+            } else {
+                self.push_back();
+                repeat_count = Some(self.demangle_natural()?);
+            }
+        }
+    }
+
+    fn push_multi_substitutions(&mut self, repeat_count: Option<u64>, index: usize) -> Option<NodeRef> {
+        let node = self.substitutions.get(index)?.clone();
+        let repeat_count = repeat_count.unwrap_or(1);
+        if repeat_count > MAX_REPEAT_COUNT {
+            return None;
+        }
+        for _ in 1..repeat_count {
+            self.stack.push(node.clone());
+        }
+        Some(node)
+    }
+
+    fn demangle_standard_substitution(&mut self) -> Option<NodeRef> {
+        match self.next()? {
+            b'o' => return Some(Node::with_text(Kind::Module, "__C").rc()),
+            b'C' => return Some(Node::with_text(Kind::Module, "__C_Synthesized").rc()),
+            b'g' => {
+                let wrapped = self.pop_kind(Kind::Type)?;
+                let optional = swift_type(Kind::Enum, "Optional");
+                let arguments = Node::with_children(Kind::TypeList, vec![wrapped]).rc();
+                let node = type_node(Node::with_children(Kind::BoundGeneric, vec![optional, arguments]).rc());
+                self.substitutions.push(node.clone());
+                return Some(node);
+            }
+            _ => self.push_back(),
+        }
+        let repeat_count = self.demangle_natural().unwrap_or(1);
+        if repeat_count > MAX_REPEAT_COUNT {
+            return None;
+        }
+        let concurrency = self.next_if(b'c');
+        let (kind, name) = standard_type(self.next()?, concurrency)?;
+        let node = swift_type(kind, name);
+        for _ in 1..repeat_count {
+            self.stack.push(node.clone());
+        }
+        Some(node)
+    }
+
+    fn demangle_builtin_type(&mut self) -> Option<NodeRef> {
+        let name = match self.next()? {
+            b'b' => String::from("Builtin.BridgeObject"),
+            b'B' => String::from("Builtin.UnsafeValueBuffer"),
+            b'e' => String::from("Builtin.Executor"),
+            b'f' => format!("Builtin.FPIEEE{}", self.demangle_index()?.checked_sub(1)?),
+            b'i' => format!("Builtin.Int{}", self.demangle_index()?.checked_sub(1)?),
+            b'I' => String::from("Builtin.IntLiteral"),
+            b'v' => {
+                let count = self.demangle_index()?.checked_sub(1)?;
+                let element = self.pop_type_child()?;
+                if element.kind != Kind::BuiltinTypeName {
+                    return None;
+                }
+                format!("Builtin.Vec{}x{}", count, element.text.strip_prefix("Builtin.").unwrap_or(&element.text))
+            }
+            b'O' => String::from("Builtin.UnknownObject"),
+            b'o' => String::from("Builtin.NativeObject"),
+            b'p' => String::from("Builtin.RawPointer"),
+            b't' => String::from("Builtin.SILToken"),
+            b'w' => String::from("Builtin.Word"),
+            b'c' => String::from("Builtin.RawUnsafeContinuation"),
+            b'D' => String::from("Builtin.DefaultActorStorage"),
+            b'd' => String::from("Builtin.NonDefaultDistributedActorStorage"),
+            b'j' => String::from("Builtin.Job"),
+            _ => return None,
+        };
+        Some(type_node(Node::with_text(Kind::BuiltinTypeName, name).rc()))
+    }
+
+    fn demangle_nominal_type(&mut self, kind: Kind) -> Option<NodeRef> {
+        let name = self.pop_if(is_decl_name)?;
+        let context = self.pop_context()?;
+        let node = type_node(Node::with_children(kind, vec![context, name]).rc());
+        self.substitutions.push(node.clone());
+        Some(node)
+    }
+
+    fn demangle_extension_context(&mut self) -> Option<NodeRef> {
+        let signature = self.pop_kind(Kind::DependentGenericSignature);
+        let module = self.pop_module()?;
+        let extended_type = self.pop_type_child()?;
+        if !is_any_generic(extended_type.kind) {
+            return None;
+        }
+        let mut children = vec![module, extended_type];
+        children.extend(signature);
+        Some(Node::with_children(Kind::Extension, children).rc())
+    }
+
+    fn demangle_bound_generic_type(&mut self) -> Option<NodeRef> {
+        let type_lists = self.pop_type_lists()?;
+        let nominal = self.pop_type_child()?;
+        let bound = self.demangle_bound_generic_args(nominal, &type_lists, 0, 0)?;
+        let node = type_node(bound);
+        self.substitutions.push(node.clone());
+        Some(node)
+    }
+
+    // Generic arguments are mangled outermost type first, with the lists separated by '_'.
+    // The returned lists start with the innermost one.
+    fn pop_type_lists(&mut self) -> Option<Vec<NodeRef>> {
+        let mut type_lists = Vec::new();
+        loop {
+            let mut types = Vec::new();
+            while let Some(argument) = self.pop_kind(Kind::Type) {
+                types.push(argument);
+            }
+            types.reverse();
+            type_lists.push(Node::with_children(Kind::TypeList, types).rc());
+            if self.pop_kind(Kind::EmptyList).is_some() {
+                return Some(type_lists);
+            }
+            self.pop_kind(Kind::FirstElementMarker)?;
+        }
+    }
+
+    fn demangle_bound_generic_args(&mut self, nominal: NodeRef, type_lists: &[NodeRef], index: usize, depth: usize) -> Option<NodeRef> {
+        if index >= type_lists.len() || depth > MAX_NESTING {
+            return None;
+        }
+        let context = nominal.children.first()?.clone();
+        let consumes_arguments = !matches!(nominal.kind,
+            Kind::Variable | Kind::Subscript | Kind::ImplicitClosure | Kind::ExplicitClosure
+            | Kind::DefaultArgumentInitializer | Kind::Initializer | Kind::PropertyWrapperBackingInitializer | Kind::Static);
+        let arguments = type_lists[index].clone();
+        let next_index = if consumes_arguments { index + 1 } else { index };
+
+        let mut nominal = nominal;
+        if next_index < type_lists.len() {
+            let bound_parent = if context.kind == Kind::Extension {
+                let extended_type = self.demangle_bound_generic_args(context.children.get(1)?.clone(), type_lists, next_index, depth + 1)?;
+                let mut children = vec![context.children[0].clone(), extended_type];
+                children.extend(context.children.get(2).cloned());
+                Node::with_children(Kind::Extension, children).rc()
+            } else {
+                self.demangle_bound_generic_args(context, type_lists, next_index, depth + 1)?
+            };
+            let mut children = vec![bound_parent];
+            children.extend(nominal.children[1..].iter().cloned());
+            nominal = Node { children, ..(*nominal).clone() }.rc();
+        }
+        if !consumes_arguments || arguments.children.is_empty() {
+            return Some(nominal);
+        }
+        match nominal.kind {
+            kind if is_any_generic(kind) => Some(Node::with_children(Kind::BoundGeneric, vec![type_node(nominal), arguments]).rc()),
+            Kind::Function | Kind::Constructor => Some(Node::with_children(Kind::BoundGenericFunction, vec![nominal, arguments]).rc()),
+            _ => None,
+        }
+    }
+
+    fn pop_tuple(&mut self) -> Option<NodeRef> {
+        let mut elements = Vec::new();
+        if self.pop_kind(Kind::EmptyList).is_none() {
+            loop {
+                let is_first = self.pop_kind(Kind::FirstElementMarker).is_some();
+                let mut element = Vec::new();
+                element.extend(self.pop_kind(Kind::VariadicMarker));
+                if let Some(label) = self.pop_kind(Kind::Identifier) {
+                    element.push(Node::with_text(Kind::TupleElementName, label.text.clone()).rc());
+                }
+                element.push(self.pop_kind(Kind::Type)?);
+                elements.push(Node::with_children(Kind::TupleElement, element).rc());
+                if is_first {
+                    break;
+                }
+            }
+            elements.reverse();
+        }
+        Some(type_node(Node::with_children(Kind::Tuple, elements).rc()))
+    }
+
+    // function-signature ::= result-type params-type async? sendable? throws? global-actor?
+    fn pop_function_type(&mut self, attributes: &str) -> Option<NodeRef> {
+        let mut function = Node::with_text(Kind::FunctionType, attributes);
+        let annotations: [&[Kind]; 5] = [
+            &[Kind::GlobalActorFunctionType],
+            &[Kind::IsolatedAnyFunctionType],
+            &[Kind::ThrowsAnnotation, Kind::TypedThrowsAnnotation],
+            &[Kind::ConcurrentFunctionType],
+            &[Kind::AsyncAnnotation],
+        ];
+        for kinds in annotations {
+            function.children.extend(self.pop_if(|kind| kinds.contains(&kind)));
+        }
+        function.children.push(self.pop_function_params(Kind::ArgumentTuple)?);
+        function.children.push(self.pop_function_params(Kind::ReturnType)?);
+        Some(type_node(function.rc()))
+    }
+
+    fn pop_function_params(&mut self, kind: Kind) -> Option<NodeRef> {
+        let params = match self.pop_kind(Kind::EmptyList) {
+            Some(_) => type_node(Node::new(Kind::Tuple).rc()),
+            None => self.pop_kind(Kind::Type)?,
+        };
+        Some(Node::with_children(kind, vec![params]).rc())
+    }
+
+    // label-list ::= 'y'                    -- no argument labels at all
+    // label-list ::= ('_' | identifier)*    -- one per parameter
+    fn pop_function_param_labels(&mut self, function_type: &NodeRef) -> Option<NodeRef> {
+        if !self.old_function_types && self.pop_kind(Kind::EmptyList).is_some() {
+            return Some(Node::new(Kind::LabelList).rc());
+        }
+        // Swift 4.0 mangling keeps the labels in the parameter tuple, where they get printed.
+        if self.old_function_types || function_type.kind != Kind::Type {
+            return None;
+        }
+        let mut function = function_type.children.first()?;
+        if function.kind == Kind::DependentGenericType {
+            function = function.children.get(1)?.children.first()?;
+        }
+        if function.kind != Kind::FunctionType {
+            return None;
+        }
+        let params = function.find(Kind::ArgumentTuple)?.children.first()?.children.first()?;
+        let param_count = if params.kind == Kind::Tuple { params.children.len() } else { 1 };
+        if param_count == 0 {
+            return None;
+        }
+        let mut labels = Vec::new();
+        for _ in 0..param_count {
+            let label = self.pop_if(|kind| kind == Kind::Identifier || kind == Kind::FirstElementMarker)?;
+            labels.push(label);
+        }
+        labels.reverse();
+        Some(Node::with_children(Kind::LabelList, labels).rc())
+    }
+
+    fn demangle_plain_function(&mut self) -> Option<NodeRef> {
+        let signature = self.pop_kind(Kind::DependentGenericSignature);
+        let mut function_type = self.pop_function_type("")?;
+        let labels = self.pop_function_param_labels(&function_type);
+        if let Some(signature) = signature {
+            function_type = type_node(Node::with_children(Kind::DependentGenericType, vec![signature, function_type]).rc());
+        }
+        let name = self.pop_if(is_decl_name)?;
+        let context = self.pop_context()?;
+        let mut children = vec![context, name];
+        children.extend(labels);
+        children.push(function_type);
+        Some(Node::with_children(Kind::Function, children).rc())
+    }
+
+    fn demangle_entity(&mut self, kind: Kind) -> Option<NodeRef> {
+        let entity_type = self.pop_kind(Kind::Type)?;
+        let labels = self.pop_function_param_labels(&entity_type);
+        let name = self.pop_if(is_decl_name)?;
+        let context = self.pop_context()?;
+        let mut children = vec![context, name];
+        children.extend(labels);
+        children.push(entity_type);
+        Some(Node::with_children(kind, children).rc())
+    }
+
+    fn demangle_subscript(&mut self) -> Option<NodeRef> {
+        let private_name = self.pop_kind(Kind::PrivateDeclName);
+        let subscript_type = self.pop_kind(Kind::Type)?;
+        let labels = self.pop_function_param_labels(&subscript_type);
+        let context = self.pop_context()?;
+        let mut children = vec![context];
+        children.extend(labels);
+        children.push(subscript_type);
+        children.extend(private_name);
+        let subscript = Node::with_children(Kind::Subscript, children).rc();
+        self.demangle_accessor(subscript)
+    }
+
+    fn demangle_accessor(&mut self, storage: NodeRef) -> Option<NodeRef> {
+        let name = match self.next()? {
+            b'm' => "materializeForSet",
+            b's' => "setter",
+            b'g' => "getter",
+            b'G' => "globalGetter",
+            b'w' => "willset",
+            b'W' => "didset",
+            b'r' => "read",
+            b'M' => "modify",
+            b'i' => "init",
+            b'y' => "read2",
+            b'x' => "modify2",
+            b'a' => match self.next()? {
+                b'O' => "owningMutableAddressor",
+                b'o' => "nativeOwningMutableAddressor",
+                b'P' => "nativePinningMutableAddressor",
+                b'u' => "unsafeMutableAddressor",
+                _ => return None,
+            },
+            b'l' => match self.next()? {
+                b'O' => "owningAddressor",
+                b'o' => "nativeOwningAddressor",
+                b'p' => "nativePinningAddressor",
+                b'u' => "unsafeAddressor",
+                _ => return None,
+            },
+            // A pseudo accessor refers to the storage itself.
+            b'p' => return Some(storage),
+            _ => return None,
+        };
+        Some(Node { children: vec![storage], ..Node::with_text(Kind::Accessor, name) }.rc())
+    }
+
+    fn demangle_function_entity(&mut self) -> Option<NodeRef> {
+        enum Arguments {
+            None,
+            TypeAndMaybePrivateName,
+            TypeAndIndex,
+            Index,
+        }
+        let (arguments, kind) = match self.next()? {
+            b'D' => (Arguments::None, Kind::Deallocator),
+            b'd' => (Arguments::None, Kind::Destructor),
+            b'E' => (Arguments::None, Kind::IVarDestroyer),
+            b'e' => (Arguments::None, Kind::IVarInitializer),
+            b'i' => (Arguments::None, Kind::Initializer),
+            b'C' => (Arguments::TypeAndMaybePrivateName, Kind::Allocator),
+            b'c' => (Arguments::TypeAndMaybePrivateName, Kind::Constructor),
+            b'U' => (Arguments::TypeAndIndex, Kind::ExplicitClosure),
+            b'u' => (Arguments::TypeAndIndex, Kind::ImplicitClosure),
+            b'A' => (Arguments::Index, Kind::DefaultArgumentInitializer),
+            b'P' => (Arguments::None, Kind::PropertyWrapperBackingInitializer),
+            _ => return None,
+        };
+        let mut extra = Vec::new();
+        match arguments {
+            Arguments::None => {}
+            Arguments::TypeAndMaybePrivateName => {
+                let private_name = self.pop_kind(Kind::PrivateDeclName);
+                let entity_type = self.pop_kind(Kind::Type)?;
+                extra.extend(self.pop_function_param_labels(&entity_type));
+                extra.push(entity_type);
+                extra.extend(private_name);
+            }
+            Arguments::TypeAndIndex => {
+                let index = self.demangle_index_node()?;
+                let entity_type = self.pop_kind(Kind::Type)?;
+                extra.push(index);
+                extra.push(entity_type);
+            }
+            Arguments::Index => extra.push(self.demangle_index_node()?),
+        }
+        let mut children = vec![self.pop_context()?];
+        children.extend(extra);
+        Some(Node::with_children(kind, children).rc())
+    }
+
+    fn demangle_generic_param_index(&mut self) -> Option<NodeRef> {
+        if self.next_if(b'd') {
+            let depth = self.demangle_index()?.checked_add(1)?;
+            let index = self.demangle_index()?;
+            return Some(generic_param_type(depth, index));
+        }
+        if self.next_if(b'z') {
+            return Some(generic_param_type(0, 0));
+        }
+        Some(generic_param_type(0, self.demangle_index()?.checked_add(1)?))
+    }
+
+    fn demangle_generic_signature(&mut self, has_param_counts: bool) -> Option<NodeRef> {
+        let mut children = Vec::new();
+        if has_param_counts {
+            while !self.next_if(b'l') {
+                let count = if self.next_if(b'z') { 0 } else { self.demangle_index()?.checked_add(1)? };
+                children.push(Node::with_index(Kind::DependentGenericParamCount, count).rc());
+            }
+        } else {
+            children.push(Node::with_index(Kind::DependentGenericParamCount, 1).rc());
+        }
+        let mut requirements = Vec::new();
+        while let Some(requirement) = self.pop_if(is_requirement) {
+            requirements.push(requirement);
+        }
+        requirements.reverse();
+        children.extend(requirements);
+        Some(Node::with_children(Kind::DependentGenericSignature, children).rc())
+    }
+
+    fn demangle_generic_requirement(&mut self) -> Option<NodeRef> {
+        enum Subject {
+            Generic,
+            Assoc,
+            CompoundAssoc,
+            Substitution,
+        }
+        enum Constraint {
+            Protocol,
+            BaseClass,
+            SameType,
+            Layout,
+        }
+        let (constraint, subject) = match self.next()? {
+            b'c' => (Constraint::BaseClass, Subject::Assoc),
+            b'C' => (Constraint::BaseClass, Subject::CompoundAssoc),
+            b'b' => (Constraint::BaseClass, Subject::Generic),
+            b'B' => (Constraint::BaseClass, Subject::Substitution),
+            b't' => (Constraint::SameType, Subject::Assoc),
+            b'T' => (Constraint::SameType, Subject::CompoundAssoc),
+            b's' => (Constraint::SameType, Subject::Generic),
+            b'S' => (Constraint::SameType, Subject::Substitution),
+            b'm' => (Constraint::Layout, Subject::Assoc),
+            b'M' => (Constraint::Layout, Subject::CompoundAssoc),
+            b'l' => (Constraint::Layout, Subject::Generic),
+            b'L' => (Constraint::Layout, Subject::Substitution),
+            b'p' => (Constraint::Protocol, Subject::Assoc),
+            b'P' => (Constraint::Protocol, Subject::CompoundAssoc),
+            b'Q' => (Constraint::Protocol, Subject::Substitution),
+            _ => {
+                self.push_back();
+                (Constraint::Protocol, Subject::Generic)
+            }
+        };
+        let subject = match subject {
+            Subject::Generic => type_node(self.demangle_generic_param_index()?),
+            Subject::Assoc => {
+                let base = self.demangle_generic_param_index()?;
+                let associated_type = self.demangle_associated_type_simple(Some(base))?;
+                self.substitutions.push(associated_type.clone());
+                associated_type
+            }
+            Subject::CompoundAssoc => {
+                let base = self.demangle_generic_param_index()?;
+                let associated_type = self.demangle_associated_type_compound(Some(base))?;
+                self.substitutions.push(associated_type.clone());
+                associated_type
+            }
+            Subject::Substitution => self.pop_kind(Kind::Type)?,
+        };
+        let requirement = match constraint {
+            Constraint::Protocol => Node::with_children(Kind::ConformanceRequirement, vec![subject, self.pop_protocol()?]),
+            Constraint::BaseClass => Node::with_children(Kind::ConformanceRequirement, vec![subject, self.pop_kind(Kind::Type)?]),
+            Constraint::SameType => Node::with_children(Kind::SameTypeRequirement, vec![subject, self.pop_kind(Kind::Type)?]),
+            Constraint::Layout => {
+                let (name, parameter_count) = match self.next()? {
+                    b'U' => ("_UnknownLayout", 0),
+                    b'R' => ("_RefCountedObject", 0),
+                    b'N' => ("_NativeRefCountedObject", 0),
+                    b'C' => ("AnyObject", 0),
+                    b'D' => ("_NativeClass", 0),
+                    b'T' => ("_Trivial", 0),
+                    b'E' => ("_Trivial", 2),
+                    b'e' => ("_Trivial", 1),
+                    b'M' => ("_TrivialAtMost", 2),
+                    b'm' => ("_TrivialAtMost", 1),
+                    _ => return None,
+                };
+                let mut children = vec![subject];
+                for _ in 0..parameter_count {
+                    children.push(self.demangle_index_node()?);
+                }
+                Node { children, ..Node::with_text(Kind::LayoutRequirement, name) }
+            }
+        };
+        Some(requirement.rc())
+    }
+
+    fn pop_associated_type_name(&mut self) -> Option<NodeRef> {
+        let protocol = self.pop_kind(Kind::Type);
+        if let Some(protocol) = &protocol {
+            if protocol.children.first()?.kind != Kind::Protocol {
+                return None;
+            }
+        }
+        let name = self.pop_kind(Kind::Identifier)?;
+        Some(Node { children: protocol.into_iter().collect(), ..Node::with_text(Kind::DependentAssociatedTypeRef, name.text.clone()) }.rc())
+    }
+
+    fn demangle_associated_type_simple(&mut self, base: Option<NodeRef>) -> Option<NodeRef> {
+        let name = self.pop_associated_type_name()?;
+        let base = match base {
+            Some(base) => type_node(base),
+            None => self.pop_kind(Kind::Type)?,
+        };
+        Some(type_node(Node::with_children(Kind::DependentMemberType, vec![base, name]).rc()))
+    }
+
+    fn demangle_associated_type_compound(&mut self, base: Option<NodeRef>) -> Option<NodeRef> {
+        let mut names = Vec::new();
+        loop {
+            let is_first = self.pop_kind(Kind::FirstElementMarker).is_some();
+            names.push(self.pop_associated_type_name()?);
+            if is_first {
+                break;
+            }
+        }
+        let mut base = match base {
+            Some(base) => type_node(base),
+            None => self.pop_kind(Kind::Type)?,
+        };
+        while let Some(name) = names.pop() {
+            base = type_node(Node::with_children(Kind::DependentMemberType, vec![base, name]).rc());
+        }
+        Some(base)
+    }
+
+    fn demangle_archetype(&mut self) -> Option<NodeRef> {
+        let node = match self.next()? {
+            b'y' => {
+                let base = self.demangle_generic_param_index()?;
+                self.demangle_associated_type_simple(Some(base))?
+            }
+            b'z' => self.demangle_associated_type_simple(Some(generic_param_type(0, 0)))?,
+            b'Y' => {
+                let base = self.demangle_generic_param_index()?;
+                self.demangle_associated_type_compound(Some(base))?
+            }
+            b'Z' => self.demangle_associated_type_compound(Some(generic_param_type(0, 0)))?,
+            b'x' => self.demangle_associated_type_simple(None)?,
+            b'X' => self.demangle_associated_type_compound(None)?,
+            b'r' => return Some(type_node(Node::new(Kind::OpaqueReturnType).rc())),
+            b'O' => return Some(Node::with_children(Kind::OpaqueReturnTypeOf, vec![self.pop_context()?]).rc()),
+            b'o' => {
+                let index = self.demangle_index_node()?;
+                let type_lists = self.pop_type_lists()?;
+                let declaration = self.pop()?;
+                let mut arguments: Vec<NodeRef> = type_lists.into_iter().rev().collect();
+                arguments.retain(|list| !list.children.is_empty());
+                let mut children = vec![declaration, index];
+                children.extend(arguments);
+                type_node(Node::with_children(Kind::OpaqueType, children).rc())
+            }
+            _ => return None,
+        };
+        self.substitutions.push(node.clone());
+        Some(node)
+    }
+
+    fn demangle_protocol_list(&mut self) -> Option<NodeRef> {
+        let mut protocols = Vec::new();
+        if self.pop_kind(Kind::EmptyList).is_none() {
+            loop {
+                let is_first = self.pop_kind(Kind::FirstElementMarker).is_some();
+                protocols.push(self.pop_protocol()?);
+                if is_first {
+                    break;
+                }
+            }
+            protocols.reverse();
+        }
+        let type_list = Node::with_children(Kind::TypeList, protocols).rc();
+        Some(Node::with_children(Kind::ProtocolList, vec![type_list]).rc())
+    }
+
+    fn demangle_special_type(&mut self) -> Option<NodeRef> {
+        let node = match self.next()? {
+            b'E' => return self.pop_function_type(""),
+            b'A' => return self.pop_function_type("@escaping @autoclosure "),
+            b'K' => return self.pop_function_type("@autoclosure "),
+            b'f' => return self.pop_function_type("@convention(thin) "),
+            b'U' => return self.pop_function_type(""),
+            b'B' => return self.pop_function_type("@convention(block) "),
+            b'L' => return self.pop_function_type("@escaping @convention(block) "),
+            b'C' => return self.pop_function_type("@convention(c) "),
+            b'o' => return self.type_attribute("unowned "),
+            b'u' => return self.type_attribute("unowned(unsafe) "),
+            b'w' => return self.type_attribute("weak "),
+            b'D' => {
+                self.pop_kind(Kind::Type)?;
+                Node::new(Kind::DynamicSelf)
+            }
+            b'p' => Node::with_children(Kind::ExistentialMetatype, vec![self.pop_kind(Kind::Type)?]),
+            b'c' => {
+                let superclass = self.pop_kind(Kind::Type)?;
+                let protocols = self.demangle_protocol_list()?;
+                Node::with_children(Kind::ProtocolListWithClass, vec![protocols, superclass])
+            }
+            b'l' => Node::with_children(Kind::ProtocolListWithAnyObject, vec![self.demangle_protocol_list()?]),
+            b'Y' => return self.demangle_nominal_type(Kind::OtherNominalType),
+            b'e' => Node::new(Kind::ErrorType),
+            _ => return None,
+        };
+        Some(type_node(node.rc()))
+    }
+
+    fn demangle_type_annotation(&mut self) -> Option<NodeRef> {
+        let node = match self.next()? {
+            b'a' => Node::new(Kind::AsyncAnnotation),
+            b'A' => Node::new(Kind::IsolatedAnyFunctionType),
+            b'b' => Node::new(Kind::ConcurrentFunctionType),
+            b'c' => Node::with_children(Kind::GlobalActorFunctionType, vec![self.pop_type_child()?]),
+            b'K' => Node::with_children(Kind::TypedThrowsAnnotation, vec![self.pop_kind(Kind::Type)?]),
+            b'i' => return self.type_attribute("isolated "),
+            b'k' => return self.type_attribute("@noDerivative "),
+            b't' => return self.type_attribute("_const "),
+            b'T' => return self.type_attribute("sending "),
+            _ => return None,
+        };
+        Some(node.rc())
+    }
+
+    fn demangle_metatype(&mut self) -> Option<NodeRef> {
+        let node = match self.next()? {
+            b'a' => labeled("type metadata accessor for ", vec![self.pop_kind(Kind::Type)?]),
+            b'A' => labeled("reflection metadata associated type descriptor ", vec![self.pop_protocol_conformance()?]),
+            b'B' => labeled("reflection metadata builtin descriptor ", vec![self.pop_kind(Kind::Type)?]),
+            b'C' => labeled("reflection metadata superclass descriptor ", vec![self.pop_kind(Kind::Type)?]),
+            b'D' => labeled("demangling cache variable for type metadata for ", vec![self.pop_kind(Kind::Type)?]),
+            b'f' => labeled("full type metadata for ", vec![self.pop_kind(Kind::Type)?]),
+            b'F' => labeled("reflection metadata field descriptor ", vec![self.pop_kind(Kind::Type)?]),
+            b'g' => labeled("opaque type descriptor accessor for ", vec![self.pop()?]),
+            b'h' => labeled("opaque type descriptor accessor impl for ", vec![self.pop()?]),
+            b'i' => labeled("type metadata instantiation function for ", vec![self.pop_kind(Kind::Type)?]),
+            b'I' => labeled("type metadata instantiation cache for ", vec![self.pop_kind(Kind::Type)?]),
+            b'l' => labeled("lazy cache variable for type metadata for ", vec![self.pop_kind(Kind::Type)?]),
+            b'L' => labeled("type metadata singleton initialization cache for ", vec![self.pop_kind(Kind::Type)?]),
+            b'm' => labeled("metaclass for ", vec![self.pop_kind(Kind::Type)?]),
+            b'n' => labeled("nominal type descriptor for ", vec![self.pop_kind(Kind::Type)?]),
+            b'N' => labeled("nominal type descriptor runtime record for ", vec![self.pop_kind(Kind::Type)?]),
+            b'o' => labeled("class metadata base offset for ", vec![self.pop_kind(Kind::Type)?]),
+            b'p' => labeled("protocol descriptor for ", vec![self.pop_protocol()?]),
+            b'P' => labeled("generic type metadata pattern for ", vec![self.pop_kind(Kind::Type)?]),
+            b'Q' => labeled("opaque type descriptor for ", vec![self.pop()?]),
+            b'r' => labeled("type metadata completion function for ", vec![self.pop_kind(Kind::Type)?]),
+            b's' => labeled("ObjC resilient class stub for ", vec![self.pop_kind(Kind::Type)?]),
+            b'S' => labeled("protocol self-conformance descriptor for ", vec![self.pop_protocol()?]),
+            b't' => labeled("full ObjC resilient class stub for ", vec![self.pop_kind(Kind::Type)?]),
+            b'u' => labeled("method lookup function for ", vec![self.pop_kind(Kind::Type)?]),
+            b'U' => labeled("ObjC metadata update function for ", vec![self.pop_kind(Kind::Type)?]),
+            b'V' => labeled("property descriptor for ", vec![self.pop_if(is_entity)?]),
+            b'c' => labeled("protocol conformance descriptor for ", vec![self.pop_protocol_conformance()?]),
+            b'X' => match self.next()? {
+                b'E' => labeled("extension descriptor ", vec![self.pop_context()?]),
+                b'M' => labeled("module descriptor ", vec![self.pop_module()?]),
+                b'X' | b'Y' => labeled("anonymous descriptor ", vec![self.pop_context()?]),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(node)
+    }
+
+    fn pop_protocol_conformance(&mut self) -> Option<NodeRef> {
+        let signature = self.pop_kind(Kind::DependentGenericSignature);
+        let module = self.pop_module()?;
+        let protocol = self.pop_protocol()?;
+        let mut conforming_type = self.pop_kind(Kind::Type)?;
+        if let Some(signature) = signature {
+            conforming_type = type_node(Node::with_children(Kind::DependentGenericType, vec![signature, conforming_type]).rc());
+        }
+        Some(Node::with_children(Kind::ProtocolConformance, vec![conforming_type, protocol, module]).rc())
+    }
+
+    fn demangle_witness(&mut self) -> Option<NodeRef> {
+        let node = match self.next()? {
+            b'C' => labeled("enum case for ", vec![self.pop_if(is_entity)?]),
+            b'V' => labeled("value witness table for ", vec![self.pop_kind(Kind::Type)?]),
+            b'v' => {
+                let description = match self.next()? {
+                    b'd' => "direct field offset for ",
+                    b'i' => "indirect field offset for ",
+                    _ => return None,
+                };
+                labeled(description, vec![self.pop_if(is_entity)?])
+            }
+            b'S' => labeled("protocol self-conformance witness table for ", vec![self.pop_protocol()?]),
+            b'P' => labeled("protocol witness table for ", vec![self.pop_protocol_conformance()?]),
+            b'p' => labeled("protocol witness table pattern for ", vec![self.pop_protocol_conformance()?]),
+            b'G' => labeled("generic protocol witness table for ", vec![self.pop_protocol_conformance()?]),
+            b'I' => labeled("instantiation function for generic protocol witness table for ", vec![self.pop_protocol_conformance()?]),
+            b'r' => labeled("resilient protocol witness table for ", vec![self.pop_protocol_conformance()?]),
+            b'a' => labeled("protocol witness table accessor for ", vec![self.pop_protocol_conformance()?]),
+            b'l' | b'L' => {
+                let description = if self.text[self.position - 1] == b'l' {
+                    "lazy protocol witness table accessor for "
+                } else {
+                    "lazy protocol witness table cache variable for "
+                };
+                let conformance = self.pop_protocol_conformance()?;
+                let conforming_type = self.pop_kind(Kind::Type)?;
+                Node { children: vec![conforming_type, conformance], ..Node::with_text(Kind::LazyWitnessTable, description) }.rc()
+            }
+            b't' => {
+                let name = self.pop_if(is_decl_name)?;
+                let conformance = self.pop_protocol_conformance()?;
+                Node::with_children(Kind::AssociatedTypeMetadataAccessor, vec![conformance, name]).rc()
+            }
+            b'T' => {
+                let protocol = self.pop_kind(Kind::Type)?;
+                let path = self.pop_associated_type_path()?;
+                let conformance = self.pop_protocol_conformance()?;
+                Node::with_children(Kind::AssociatedTypeWitnessTableAccessor, vec![conformance, path, protocol]).rc()
+            }
+            b'b' => {
+                let protocol = self.pop_kind(Kind::Type)?;
+                let conformance = self.pop_protocol_conformance()?;
+                Node::with_children(Kind::BaseWitnessTableAccessor, vec![conformance, protocol]).rc()
+            }
+            b'O' => {
+                let description = match self.next()? {
+                    b'y' => "outlined copy of ",
+                    b'e' => "outlined consume of ",
+                    b'r' => "outlined retain of ",
+                    b's' => "outlined release of ",
+                    b'b' => "outlined init with take of ",
+                    b'c' | b'C' => "outlined init with copy of ",
+                    b'd' | b'D' => "outlined assign with take of ",
+                    b'f' | b'F' => "outlined assign with copy of ",
+                    b'h' | b'H' => "outlined destroy of ",
+                    _ => return None,
+                };
+                self.pop_kind(Kind::DependentGenericSignature);
+                labeled(description, vec![self.pop_kind(Kind::Type)?])
+            }
+            _ => return None,
+        };
+        Some(node)
+    }
+
+    fn pop_associated_type_path(&mut self) -> Option<NodeRef> {
+        let mut names = Vec::new();
+        loop {
+            let is_first = self.pop_kind(Kind::FirstElementMarker).is_some();
+            names.push(self.pop_if(is_decl_name)?);
+            if is_first {
+                break;
+            }
+        }
+        names.reverse();
+        let path = names.iter().map(|name| name.text.as_str()).collect::<Vec<_>>().join(".");
+        Some(Node::with_text(Kind::DependentAssociatedTypeRef, path).rc())
+    }
+
+    fn demangle_thunk_or_specialization(&mut self) -> Option<NodeRef> {
+        let byte = self.next()?;
+        let node = match byte {
+            b'c' => labeled("curry thunk of ", vec![self.pop_if(is_entity)?]),
+            b'j' => labeled("dispatch thunk of ", vec![self.pop_if(is_entity)?]),
+            b'q' => labeled("method descriptor for ", vec![self.pop_if(is_entity)?]),
+            b'S' => labeled("protocol self-conformance witness for ", vec![self.pop_if(is_entity)?]),
+            b'o' => Node::with_text(Kind::FunctionAttribute, "@objc ").rc(),
+            b'O' => Node::with_text(Kind::FunctionAttribute, "@nonobjc ").rc(),
+            b'D' => Node::with_text(Kind::FunctionAttribute, "dynamic ").rc(),
+            b'd' => Node::with_text(Kind::FunctionAttribute, "super ").rc(),
+            b'E' => Node::with_text(Kind::FunctionAttribute, "distributed thunk ").rc(),
+            b'F' => Node::with_text(Kind::FunctionAttribute, "distributed accessor for ").rc(),
+            b'm' => Node::with_text(Kind::FunctionAttribute, "merged ").rc(),
+            b'X' => Node::with_text(Kind::FunctionAttribute, "dynamically replaceable variable for ").rc(),
+            b'x' => Node::with_text(Kind::FunctionAttribute, "dynamically replaceable key for ").rc(),
+            b'I' => Node::with_text(Kind::FunctionAttribute, "dynamically replaceable thunk for ").rc(),
+            b'u' => Node::with_text(Kind::FunctionAttribute, "async function pointer to ").rc(),
+            b'Q' => {
+                let index = self.demangle_index()?;
+                Node::with_text(Kind::FunctionAttribute, format!("({}) await resume partial function for ", index)).rc()
+            }
+            b'Y' => {
+                let index = self.demangle_index()?;
+                Node::with_text(Kind::FunctionAttribute, format!("({}) suspend resume partial function for ", index)).rc()
+            }
+            b'v' => {
+                let index = self.demangle_index()?;
+                Node::with_text(Kind::FunctionAttribute, format!("outlined variable #{} of ", index)).rc()
+            }
+            b'a' => Node::with_text(Kind::PartialApply, "partial apply ObjC forwarder").rc(),
+            b'A' => Node::with_text(Kind::PartialApply, "partial apply forwarder").rc(),
+            b'V' => {
+                let base = self.pop_if(is_entity)?;
+                let derived = self.pop_if(is_entity)?;
+                Node::with_children(Kind::VTableThunk, vec![derived, base]).rc()
+            }
+            b'W' => {
+                let entity = self.pop_if(is_entity)?;
+                let conformance = self.pop_protocol_conformance()?;
+                Node::with_children(Kind::ProtocolWitness, vec![conformance, entity]).rc()
+            }
+            b'R' | b'r' => {
+                let description = if byte == b'R' { "reabstraction thunk helper " } else { "reabstraction thunk " };
+                let mut children = Vec::new();
+                children.extend(self.pop_kind(Kind::DependentGenericSignature));
+                children.push(self.pop_kind(Kind::Type)?);
+                children.push(self.pop_kind(Kind::Type)?);
+                Node { children, ..Node::with_text(Kind::ReabstractionThunk, description) }.rc()
+            }
+            b'g' => self.demangle_generic_specialization("generic specialization")?,
+            b'G' => self.demangle_generic_specialization("generic not re-abstracted specialization")?,
+            b'i' => self.demangle_generic_specialization("inlined generic function")?,
+            b'f' => self.demangle_function_specialization()?,
+            b'K' | b'k' => {
+                let description = if byte == b'K' { "key path getter for " } else { "key path setter for " };
+                self.next_if(b'q');
+                let mut types = Vec::new();
+                while let Some(argument) = self.pop_kind(Kind::Type) {
+                    types.push(argument);
+                }
+                if types.is_empty() {
+                    return None;
+                }
+                self.pop_kind(Kind::DependentGenericSignature);
+                let mut children = vec![self.pop()?];
+                children.extend(types.into_iter().rev());
+                Node { children, ..Node::with_text(Kind::KeyPathThunk, description) }.rc()
+            }
+            b'H' | b'h' => {
+                let description = if byte == b'H' { "key path equality operator for " } else { "key path hash function for " };
+                self.next_if(b'q');
+                let mut types = Vec::new();
+                while let Some(argument) = self.pop_kind(Kind::Type) {
+                    types.push(argument);
+                }
+                types.reverse();
+                self.pop_kind(Kind::DependentGenericSignature);
+                labeled(description, vec![Node::with_children(Kind::Tuple, types.into_iter().map(|argument| Node::with_children(Kind::TupleElement, vec![argument]).rc()).collect()).rc()])
+            }
+            b'l' => labeled("associated type descriptor for ", vec![self.pop_associated_type_name()?]),
+            b'L' => labeled("protocol requirements base descriptor for ", vec![self.pop_kind(Kind::Type)?]),
+            b'M' => labeled("default associated type metadata accessor for ", vec![self.pop_associated_type_name()?]),
+            b'n' => {
+                let requirement = self.pop_protocol()?;
+                let conforming_type = self.pop_associated_type_name()?;
+                let protocol = self.pop_kind(Kind::Type)?;
+                Node::with_children(Kind::AssociatedConformanceDescriptor, vec![protocol, conforming_type, requirement]).rc()
+            }
+            b'b' => {
+                let requirement = self.pop_protocol()?;
+                let protocol = self.pop_kind(Kind::Type)?;
+                Node::with_children(Kind::BaseConformanceDescriptor, vec![protocol, requirement]).rc()
+            }
+            _ => return None,
+        };
+        Some(node)
+    }
+
+    // spec-attributes ::= 'm'? 'q'? 'a'? PASS-ID
+    fn demangle_specialization_attributes(&mut self, description: &str) -> Option<Node> {
+        self.next_if(b'm');
+        let is_serialized = self.next_if(b'q');
+        self.next_if(b'a');
+        let pass = self.next()?.checked_sub(b'0')?;
+        if pass >= MAX_SPECIALIZATION_PASS {
+            return None;
+        }
+        let mut specialization = Node::with_text(Kind::Specialization, description);
+        if is_serialized {
+            specialization.children.push(Node::new(Kind::IsSerialized).rc());
+        }
+        Some(specialization)
+    }
+
+    fn demangle_generic_specialization(&mut self, description: &str) -> Option<NodeRef> {
+        let mut specialization = self.demangle_specialization_attributes(description)?;
+        let mut types = Vec::new();
+        if self.pop_kind(Kind::EmptyList).is_none() {
+            loop {
+                let is_first = self.pop_kind(Kind::FirstElementMarker).is_some();
+                types.push(self.pop_kind(Kind::Type)?);
+                if is_first {
+                    break;
+                }
+            }
+        }
+        for argument in types.into_iter().rev() {
+            specialization.children.push(Node::with_children(Kind::SpecializationParam, vec![argument]).rc());
+        }
+        Some(specialization.rc())
+    }
+
+    fn demangle_function_specialization(&mut self) -> Option<NodeRef> {
+        let mut specialization = self.demangle_specialization_attributes("function signature specialization")?;
+        let mut params = Vec::new();
+        while !self.next_if(b'_') {
+            params.push(self.demangle_function_specialization_param(Kind::FunctionSignatureSpecializationParam)?);
+        }
+        if !self.next_if(b'n') {
+            params.push(self.demangle_function_specialization_param(Kind::FunctionSignatureSpecializationReturn)?);
+        }
+
+        // Constant and closure propagated arguments refer to names mangled before the
+        // specialization, so they are popped starting with the last parameter.
+        for param in params.iter_mut().rev() {
+            if param.kind != Kind::FunctionSignatureSpecializationParam {
+                continue;
+            }
+            let Some(param_kind) = param.children.first().map(|kind| kind.index) else {
+                continue;
+            };
+            if !matches!(param_kind, SPEC_CONSTANT_PROP_FUNCTION | SPEC_CONSTANT_PROP_GLOBAL | SPEC_CONSTANT_PROP_STRING | SPEC_CLOSURE_PROP) {
+                continue;
+            }
+            let mut types = Vec::new();
+            while let Some(argument) = self.pop_kind(Kind::Type) {
+                if param_kind != SPEC_CLOSURE_PROP {
+                    return None;
+                }
+                types.push(argument);
+            }
+            let name = self.pop_kind(Kind::Identifier)?;
+            let mut text = name.text.as_str();
+            // A leading '_' escapes a string constant that starts with a digit or '_'.
+            if param_kind == SPEC_CONSTANT_PROP_STRING {
+                text = text.strip_prefix('_').unwrap_or(text);
+            }
+            param.children.push(Node::with_text(Kind::SpecializationPayload, text).rc());
+            param.children.extend(types.into_iter().rev());
+        }
+
+        specialization.children.extend(params.into_iter().map(Node::rc));
+        Some(specialization.rc())
+    }
+
+    fn demangle_function_specialization_param(&mut self, kind: Kind) -> Option<Node> {
+        let mut param = Node::new(kind);
+        let param_kind = |value: u64| Node::with_index(Kind::SpecializationParamFlags, value).rc();
+        match self.next()? {
+            b'n' => {}
+            b'c' => param.children.push(param_kind(SPEC_CLOSURE_PROP)),
+            b'p' => match self.next()? {
+                b'f' => param.children.push(param_kind(SPEC_CONSTANT_PROP_FUNCTION)),
+                b'g' => param.children.push(param_kind(SPEC_CONSTANT_PROP_GLOBAL)),
+                b'i' | b'd' => {
+                    let value_kind = if self.text[self.position - 1] == b'i' { SPEC_CONSTANT_PROP_INTEGER } else { SPEC_CONSTANT_PROP_FLOAT };
+                    let start = self.position;
+                    while self.peek().is_some_and(|byte| byte.is_ascii_digit() || byte == b'-' || byte == b'.') {
+                        self.position += 1;
+                    }
+                    if start == self.position {
+                        return None;
+                    }
+                    let value = String::from_utf8_lossy(&self.text[start..self.position]).into_owned();
+                    param.children.push(param_kind(value_kind));
+                    param.children.push(Node::with_text(Kind::SpecializationPayload, value).rc());
+                }
+                b's' => {
+                    let encoding = match self.next()? {
+                        b'b' => "u8",
+                        b'w' => "u16",
+                        b'c' => "objc",
+                        _ => return None,
+                    };
+                    param.children.push(param_kind(SPEC_CONSTANT_PROP_STRING));
+                    param.children.push(Node::with_text(Kind::SpecializationPayload, encoding).rc());
+                }
+                _ => return None,
+            },
+            first @ (b'e' | b'd' | b'g' | b'o' | b'x') => {
+                let mut value = match first {
+                    b'e' => SPEC_EXISTENTIAL_TO_GENERIC,
+                    b'd' => SPEC_DEAD,
+                    b'g' => SPEC_OWNED_TO_GUARANTEED,
+                    b'o' => SPEC_GUARANTEED_TO_OWNED,
+                    _ => SPEC_SROA,
+                };
+                if first == b'e' && self.next_if(b'D') {
+                    value |= SPEC_DEAD;
+                }
+                if matches!(first, b'e' | b'd') && self.next_if(b'G') {
+                    value |= SPEC_OWNED_TO_GUARANTEED;
+                }
+                if matches!(first, b'e' | b'd') && self.next_if(b'O') {
+                    value |= SPEC_GUARANTEED_TO_OWNED;
+                }
+                if first != b'x' && self.next_if(b'X') {
+                    value |= SPEC_SROA;
+                }
+                param.children.push(param_kind(value));
+            }
+            b'i' => param.children.push(param_kind(SPEC_BOX_TO_VALUE)),
+            b's' => param.children.push(param_kind(SPEC_BOX_TO_STACK)),
+            b'r' => param.children.push(param_kind(SPEC_IN_OUT_TO_OUT)),
+            _ => return None,
+        }
+        Some(param)
+    }
+
+    // impl-function-type ::= type* 'I' FUNC-ATTRIBUTES '_'
+    fn demangle_impl_function_type(&mut self) -> Option<NodeRef> {
+        let mut children = Vec::new();
+        let signature = self.pop_kind(Kind::DependentGenericSignature);
+        self.next_if(b'P');
+        if self.next_if(b'e') {
+            children.push(Node::with_text(Kind::ImplAttribute, "@escaping").rc());
+        }
+        let callee = match self.next()? {
+            b'y' => "@callee_unowned",
+            b'g' => "@callee_guaranteed",
+            b'x' => "@callee_owned",
+            b't' => "@convention(thin)",
+            _ => return None,
+        };
+        children.push(Node::with_text(Kind::ImplAttribute, callee).rc());
+        let convention = match self.next()? {
+            b'B' => Some("@convention(block)"),
+            b'C' => Some("@convention(c)"),
+            b'M' => Some("@convention(method)"),
+            b'O' => Some("@convention(objc_method)"),
+            b'K' => Some("@convention(closure)"),
+            b'W' => Some("@convention(witness_method)"),
+            _ => {
+                self.push_back();
+                None
+            }
+        };
+        children.extend(convention.map(|convention| Node::with_text(Kind::ImplAttribute, convention).rc()));
+        if self.next_if(b'A') {
+            children.push(Node::with_text(Kind::ImplAttribute, "@yield_once").rc());
+        } else if self.next_if(b'G') {
+            children.push(Node::with_text(Kind::ImplAttribute, "@yield_many").rc());
+        }
+        if self.next_if(b'h') {
+            children.push(Node::with_text(Kind::ImplAttribute, "@Sendable").rc());
+        }
+        if self.next_if(b'H') {
+            children.push(Node::with_text(Kind::ImplAttribute, "@async").rc());
+        }
+        children.extend(signature);
+
+        // Parameters and results list their conventions first; their types were pushed before.
+        let mut typed = Vec::new();
+        while let Some(convention) = self.demangle_impl_param_convention() {
+            typed.push(Node { children: vec![Node::with_text(Kind::ImplAttribute, convention).rc()], ..Node::new(Kind::ImplParameter) });
+        }
+        while let Some(convention) = self.demangle_impl_result_convention() {
+            typed.push(Node { children: vec![Node::with_text(Kind::ImplAttribute, convention).rc()], ..Node::new(Kind::ImplResult) });
+        }
+        if self.next_if(b'z') {
+            let convention = self.demangle_impl_result_convention()?;
+            typed.push(Node { children: vec![Node::with_text(Kind::ImplAttribute, convention).rc()], ..Node::new(Kind::ImplErrorResult) });
+        }
+        if !self.next_if(b'_') {
+            return None;
+        }
+        for node in typed.iter_mut().rev() {
+            node.children.push(self.pop_kind(Kind::Type)?);
+        }
+        children.extend(typed.into_iter().map(Node::rc));
+        Some(type_node(Node::with_children(Kind::ImplFunctionType, children).rc()))
+    }
+
+    fn demangle_impl_param_convention(&mut self) -> Option<&'static str> {
+        let convention = match self.next()? {
+            b'i' => "@in",
+            b'c' => "@in_constant",
+            b'l' => "@inout",
+            b'b' => "@inout_aliasable",
+            b'n' => "@in_guaranteed",
+            b'X' => "@in_cxx",
+            b'x' => "@owned",
+            b'g' => "@guaranteed",
+            b'e' => "@deallocating",
+            b'y' => "@unowned",
+            _ => {
+                self.push_back();
+                return None;
+            }
+        };
+        Some(convention)
+    }
+
+    fn demangle_impl_result_convention(&mut self) -> Option<&'static str> {
+        let convention = match self.next()? {
+            b'r' => "@out",
+            b'o' => "@owned",
+            b'd' => "@unowned",
+            b'u' => "@unowned_inner_pointer",
+            b'a' => "@autoreleased",
+            _ => {
+                self.push_back();
+                return None;
+            }
+        };
+        Some(convention)
+    }
+}
+
+// Punycode as used by Swift identifiers: '_' is the delimiter and the digits are a-z then A-J.
+fn decode_punycode(input: &[u8]) -> Option<String> {
+    const BASE: u32 = 36;
+    const T_MIN: u32 = 1;
+    const T_MAX: u32 = 26;
+    const SKEW: u32 = 38;
+    const DAMP: u32 = 700;
+
+    let mut output: Vec<char> = Vec::new();
+    let mut input = input;
+    if let Some(delimiter) = input.iter().rposition(|&byte| byte == b'_') {
+        for &byte in &input[..delimiter] {
+            if !byte.is_ascii() {
+                return None;
+            }
+            output.push(byte as char);
+        }
+        input = &input[delimiter + 1..];
+    }
+
+    let adapt = |delta: u32, num_points: u32, first_time: bool| {
+        let mut delta = if first_time { delta / DAMP } else { delta / 2 };
+        delta += delta / num_points;
+        let mut k = 0;
+        while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+            delta /= BASE - T_MIN;
+            k += BASE;
+        }
+        k + (((BASE - T_MIN + 1) * delta) / (delta + SKEW))
+    };
+
+    let mut code_point: u32 = 0x80;
+    let mut i: u32 = 0;
+    let mut bias = 72;
+    let mut bytes = input.iter();
+    while bytes.len() > 0 {
+        let old_i = i;
+        let mut weight: u32 = 1;
+        let mut k = BASE;
+        loop {
+            let digit = match *bytes.next()? {
+                byte @ b'a'..=b'z' => (byte - b'a') as u32,
+                byte @ b'A'..=b'J' => (byte - b'A') as u32 + 26,
+                _ => return None,
+            };
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let threshold = if k <= bias { T_MIN } else if k >= bias + T_MAX { T_MAX } else { k - bias };
+            if digit < threshold {
+                break;
+            }
+            weight = weight.checked_mul(BASE - threshold)?;
+            k += BASE;
+        }
+        let length = output.len() as u32 + 1;
+        bias = adapt(i - old_i, length, old_i == 0);
+        code_point = code_point.checked_add(i / length)?;
+        i %= length;
+        output.insert(i as usize, char::from_u32(code_point)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TypePrinting {
+    NoType,
+    WithColon,
+    FunctionStyle,
+}
+
+type PrintResult = Result<Option<NodeRef>, ()>;
+
+struct NodePrinter {
+    output: String,
+    depth: usize,
+    name_only: bool,
+}
+
+impl NodePrinter {
+    fn print_tree(node: &NodeRef, name_only: bool) -> Option<String> {
+        let mut printer = NodePrinter { output: String::new(), depth: 0, name_only };
+        printer.print(node, false).ok()?;
+        Some(printer.output)
+    }
+
+    fn push(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    /// Prints a node. When printed as the prefix context of another entity, an entity that can
+    /// only be spelled as `... in <context>` is returned instead of being printed.
+    fn print(&mut self, node: &NodeRef, as_prefix_context: bool) -> PrintResult {
+        if self.depth > MAX_NESTING || self.output.len() > MAX_OUTPUT_LENGTH {
+            return Err(());
+        }
+        self.depth += 1;
+        let result = self.print_node(node, as_prefix_context);
+        self.depth -= 1;
+        result
+    }
+
+    fn print_children(&mut self, node: &NodeRef, separator: &str) -> Result<(), ()> {
+        for (index, child) in node.children.iter().enumerate() {
+            if index > 0 {
+                self.push(separator);
+            }
+            self.print(child, false)?;
+        }
+        Ok(())
+    }
+
+    fn print_node(&mut self, node: &NodeRef, as_prefix_context: bool) -> PrintResult {
+        use TypePrinting::*;
+        match node.kind {
+            Kind::Global | Kind::TypeMangling => self.print_children(node, "")?,
+            Kind::Suffix => write!(self.output, " with unmangled suffix {:?}", node.text).unwrap(),
+            Kind::Identifier | Kind::Module | Kind::BuiltinTypeName | Kind::DependentGenericParamType
+            | Kind::DependentAssociatedTypeRef | Kind::ImplAttribute | Kind::FunctionAttribute
+            | Kind::SpecializationPayload => self.output.push_str(&node.text),
+            Kind::InfixOperator => write!(self.output, "{} infix", node.text).unwrap(),
+            Kind::PrefixOperator => write!(self.output, "{} prefix", node.text).unwrap(),
+            Kind::PostfixOperator => write!(self.output, "{} postfix", node.text).unwrap(),
+            Kind::Number => write!(self.output, "{}", node.index).unwrap(),
+            Kind::LocalDeclName => {
+                self.print(node.child(1)?, false)?;
+                write!(self.output, " #{}", node.child(0)?.index + 1).unwrap();
+            }
+            Kind::PrivateDeclName => {
+                if node.children.len() > 1 {
+                    self.push("(");
+                    self.print(node.child(1)?, false)?;
+                    self.push(" in ");
+                    self.print(node.child(0)?, false)?;
+                    self.push(")");
+                } else {
+                    self.push("(in ");
+                    self.print(node.child(0)?, false)?;
+                    self.push(")");
+                }
+            }
+            Kind::Type => return self.print(node.child(0)?, as_prefix_context),
+            Kind::Class | Kind::Structure | Kind::Enum | Kind::Protocol | Kind::TypeAlias | Kind::OtherNominalType => {
+                return self.print_entity(node, as_prefix_context, NoType, true, "", None, "");
+            }
+            Kind::Extension => {
+                self.push("(extension in ");
+                self.print(node.child(0)?, true)?;
+                self.push("):");
+                self.print(node.child(1)?, false)?;
+                if let Some(signature) = node.children.get(2) {
+                    self.print(signature, false)?;
+                }
+            }
+            Kind::BoundGeneric => {
+                self.print(node.child(0)?, false)?;
+                self.push("<");
+                self.print_children(node.child(1)?, ", ")?;
+                self.push(">");
+            }
+            Kind::BoundGenericFunction => {
+                self.print(node.child(0)?, false)?;
+                self.push("<");
+                self.print_children(node.child(1)?, ", ")?;
+                self.push(">");
+            }
+            Kind::TypeList => self.print_children(node, ", ")?,
+            Kind::Tuple => {
+                self.push("(");
+                self.print_children(node, ", ")?;
+                self.push(")");
+            }
+            Kind::TupleElement => {
+                if let Some(label) = node.find(Kind::TupleElementName) {
+                    write!(self.output, "{}: ", label.text).unwrap();
+                }
+                self.print(node.find(Kind::Type).ok_or(())?, false)?;
+                if node.find(Kind::VariadicMarker).is_some() {
+                    self.push("...");
+                }
+            }
+            Kind::FunctionType => self.print_function_type(None, node)?,
+            Kind::ArgumentTuple => self.print(node.child(0)?, false).map(|_| ())?,
+            Kind::ReturnType => {
+                self.push(" -> ");
+                self.print_children(node, "")?;
+            }
+            Kind::ThrowsAnnotation => self.push(" throws"),
+            Kind::TypedThrowsAnnotation => {
+                self.push(" throws(");
+                self.print(node.child(0)?, false)?;
+                self.push(")");
+            }
+            Kind::AsyncAnnotation => self.push(" async"),
+            Kind::ConcurrentFunctionType => self.push("@Sendable "),
+            Kind::IsolatedAnyFunctionType => self.push("@isolated(any) "),
+            Kind::GlobalActorFunctionType => {
+                self.push("@");
+                self.print(node.child(0)?, false)?;
+                self.push(" ");
+            }
+            Kind::TypeAttribute => {
+                self.push(&node.text);
+                self.print(node.child(0)?, false)?;
+            }
+            Kind::Metatype => {
+                let instance_type = node.child(0)?;
+                self.print_with_parens(instance_type)?;
+                let is_existential = matches!(instance_type.children.first().map(|child| child.kind),
+                    Some(Kind::ProtocolList | Kind::ProtocolListWithAnyObject | Kind::ProtocolListWithClass | Kind::ExistentialMetatype));
+                self.push(if is_existential { ".Protocol" } else { ".Type" });
+            }
+            Kind::ExistentialMetatype => {
+                self.print_with_parens(node.child(0)?)?;
+                self.push(".Type");
+            }
+            Kind::DynamicSelf => self.push("Self"),
+            Kind::ErrorType => self.push("<ERROR TYPE>"),
+            Kind::ProtocolList => {
+                let protocols = node.child(0)?;
+                if protocols.children.is_empty() {
+                    self.push("Any");
+                } else {
+                    self.print_children(protocols, " & ")?;
+                }
+            }
+            Kind::ProtocolListWithClass => {
+                self.print(node.child(1)?, false)?;
+                self.push(" & ");
+                self.print_children(node.child(0)?.child(0)?, " & ")?;
+            }
+            Kind::ProtocolListWithAnyObject => {
+                let protocols = node.child(0)?.child(0)?;
+                if !protocols.children.is_empty() {
+                    self.print_children(protocols, " & ")?;
+                    self.push(" & ");
+                }
+                self.push("Swift.AnyObject");
+            }
+            Kind::DependentGenericSignature => self.print_generic_signature(node)?,
+            Kind::DependentGenericType => {
+                self.print(node.child(0)?, false)?;
+                let dependent_type = node.child(1)?;
+                if need_space_before_type(dependent_type) {
+                    self.push(" ");
+                }
+                self.print(dependent_type, false)?;
+            }
+            Kind::DependentMemberType => {
+                self.print(node.child(0)?, false)?;
+                self.push(".");
+                self.print(node.child(1)?, false)?;
+            }
+            Kind::ConformanceRequirement => {
+                self.print(node.child(0)?, false)?;
+                self.push(": ");
+                self.print(node.child(1)?, false)?;
+            }
+            Kind::SameTypeRequirement => {
+                self.print(node.child(0)?, false)?;
+                self.push(" == ");
+                self.print(node.child(1)?, false)?;
+            }
+            Kind::LayoutRequirement => {
+                self.print(node.child(0)?, false)?;
+                write!(self.output, ": {}", node.text).unwrap();
+                if node.children.len() > 1 {
+                    let sizes: Vec<String> = node.children[1..].iter().map(|size| size.index.to_string()).collect();
+                    write!(self.output, "({})", sizes.join(", ")).unwrap();
+                }
+            }
+            Kind::OpaqueReturnType => self.push("some"),
+            Kind::OpaqueReturnTypeOf => {
+                self.push("<<opaque return type of ");
+                self.print(node.child(0)?, false)?;
+                self.push(">>");
+            }
+            Kind::OpaqueType => {
+                self.print(node.child(0)?, false)?;
+                self.push(".");
+                self.print(node.child(1)?, false)?;
+                for arguments in &node.children[2..] {
+                    self.push("<");
+                    self.print_children(arguments, ", ")?;
+                    self.push(">");
+                }
+            }
+            Kind::ImplFunctionType => self.print_impl_function_type(node)?,
+            Kind::ImplParameter | Kind::ImplResult => self.print_children(node, " ")?,
+            Kind::ImplErrorResult => {
+                self.push("@error ");
+                self.print_children(node, " ")?;
+            }
+            Kind::Function => return self.print_entity(node, as_prefix_context, FunctionStyle, true, "", None, ""),
+            Kind::Variable => return self.print_entity(node, as_prefix_context, WithColon, true, "", None, ""),
+            Kind::Subscript => return self.print_entity(node, as_prefix_context, FunctionStyle, false, "", None, "subscript"),
+            Kind::Static => {
+                self.push("static ");
+                self.print(node.child(0)?, false)?;
+            }
+            Kind::Allocator => {
+                let name = if is_class_type(node.child(0)?) { "__allocating_init" } else { "init" };
+                return self.print_entity(node, as_prefix_context, FunctionStyle, false, name, None, "");
+            }
+            Kind::Constructor => return self.print_entity(node, as_prefix_context, FunctionStyle, false, "init", None, ""),
+            Kind::Destructor => return self.print_entity(node, as_prefix_context, NoType, false, "deinit", None, ""),
+            Kind::Deallocator => {
+                let name = if is_class_type(node.child(0)?) { "__deallocating_deinit" } else { "deinit" };
+                return self.print_entity(node, as_prefix_context, NoType, false, name, None, "");
+            }
+            Kind::IVarInitializer => return self.print_entity(node, as_prefix_context, NoType, false, "__ivar_initializer", None, ""),
+            Kind::IVarDestroyer => return self.print_entity(node, as_prefix_context, NoType, false, "__ivar_destroyer", None, ""),
+            Kind::Initializer => return self.print_entity(node, as_prefix_context, NoType, false, "variable initialization expression", None, ""),
+            Kind::PropertyWrapperBackingInitializer => {
+                return self.print_entity(node, as_prefix_context, NoType, false, "property wrapper backing initializer", None, "");
+            }
+            Kind::ExplicitClosure | Kind::ImplicitClosure => {
+                let name = if node.kind == Kind::ExplicitClosure { "closure #" } else { "implicit closure #" };
+                let type_printing = if node.find(Kind::Type).is_some() { FunctionStyle } else { NoType };
+                let index = node.child(1)?.index + 1;
+                return self.print_entity(node, as_prefix_context, type_printing, false, name, Some(index), "");
+            }
+            Kind::DefaultArgumentInitializer => {
+                let index = node.child(1)?.index;
+                return self.print_entity(node, as_prefix_context, NoType, false, "default argument ", Some(index), "");
+            }
+            Kind::Accessor => {
+                let storage = node.child(0)?;
+                return match storage.kind {
+                    Kind::Variable => self.print_entity(storage, as_prefix_context, WithColon, true, &node.text, None, ""),
+                    Kind::Subscript => self.print_entity(storage, as_prefix_context, WithColon, false, &node.text, None, "subscript"),
+                    _ => Err(()),
+                };
+            }
+            Kind::Labeled => {
+                self.push(&node.text);
+                self.print_children(node, "")?;
+            }
+            Kind::PartialApply => {
+                self.push(&node.text);
+                if !node.children.is_empty() {
+                    self.push(" for ");
+                    self.print_children(node, "")?;
+                }
+            }
+            Kind::Specialization => self.print_specialization_prefix(node)?,
+            Kind::SpecializationParam => self.print(node.child(0)?, false).map(|_| ())?,
+            Kind::IsSerialized => self.push("serialized"),
+            Kind::ProtocolConformance => {
+                self.print(node.child(0)?, false)?;
+                self.push(" : ");
+                self.print(node.child(1)?, false)?;
+                if let Some(module) = node.children.get(2) {
+                    self.push(" in ");
+                    self.print(module, false)?;
+                }
+            }
+            Kind::ProtocolWitness => {
+                self.push("protocol witness for ");
+                self.print(node.child(1)?, false)?;
+                self.push(" in conformance ");
+                self.print(node.child(0)?, false)?;
+            }
+            Kind::VTableThunk => {
+                self.push("vtable thunk for ");
+                self.print(node.child(1)?, false)?;
+                self.push(" dispatching to ");
+                self.print(node.child(0)?, false)?;
+            }
+            Kind::ReabstractionThunk => {
+                self.push(&node.text);
+                let mut index = 0;
+                if node.children.len() == 3 {
+                    self.print(node.child(0)?, false)?;
+                    self.push(" ");
+                    index = 1;
+                }
+                self.push("from ");
+                self.print(node.child(index + 1)?, false)?;
+                self.push(" to ");
+                self.print(node.child(index)?, false)?;
+            }
+            Kind::KeyPathThunk => {
+                self.push(&node.text);
+                self.print(node.child(0)?, false)?;
+                self.push(" : ");
+                for (index, argument) in node.children[1..].iter().enumerate() {
+                    if index > 0 {
+                        self.push(", ");
+                    }
+                    self.print(argument, false)?;
+                }
+            }
+            Kind::LazyWitnessTable => {
+                self.push(&node.text);
+                self.push("type ");
+                self.print(node.child(0)?, false)?;
+                self.push(" and conformance ");
+                self.print(node.child(1)?, false)?;
+            }
+            Kind::AssociatedTypeMetadataAccessor => {
+                self.push("associated type metadata accessor for ");
+                self.print(node.child(1)?, false)?;
+                self.push(" in ");
+                self.print(node.child(0)?, false)?;
+            }
+            Kind::AssociatedTypeWitnessTableAccessor => {
+                self.push("associated type witness table accessor for ");
+                self.print(node.child(1)?, false)?;
+                self.push(" : ");
+                self.print(node.child(2)?, false)?;
+                self.push(" in ");
+                self.print(node.child(0)?, false)?;
+            }
+            Kind::BaseWitnessTableAccessor => {
+                self.push("base witness table accessor for ");
+                self.print(node.child(1)?, false)?;
+                self.push(" in ");
+                self.print(node.child(0)?, false)?;
+            }
+            Kind::AssociatedConformanceDescriptor => {
+                self.push("associated conformance descriptor for ");
+                self.print(node.child(0)?, false)?;
+                self.push(".");
+                self.print(node.child(1)?, false)?;
+                self.push(": ");
+                self.print(node.child(2)?, false)?;
+            }
+            Kind::BaseConformanceDescriptor => {
+                self.push("base conformance descriptor for ");
+                self.print(node.child(0)?, false)?;
+                self.push(": ");
+                self.print(node.child(1)?, false)?;
+            }
+            Kind::TupleElementName | Kind::VariadicMarker | Kind::EmptyList | Kind::FirstElementMarker | Kind::LabelList
+            | Kind::DependentGenericParamCount | Kind::FunctionSignatureSpecializationParam
+            | Kind::FunctionSignatureSpecializationReturn | Kind::SpecializationParamFlags => {}
+        }
+        Ok(None)
+    }
+
+    // An entity is printed as `<context>.<name><type>`, unless its name is made of several
+    // words like `closure #1`, in which case the context follows as `... in <context>`.
+    #[allow(clippy::too_many_arguments)]
+    fn print_entity(&mut self, entity: &NodeRef, as_prefix_context: bool, type_printing: TypePrinting, has_name: bool,
+                    extra_name: &str, extra_index: Option<u64>, overwrite_name: &str) -> PrintResult {
+        let type_printing = if self.name_only { TypePrinting::NoType } else { type_printing };
+        let mut extra_name = extra_name;
+        let mut extra_index = extra_index;
+        let is_local_name = has_name && entity.children.get(1).is_some_and(|name| name.kind == Kind::LocalDeclName);
+        let is_multi_word_name = extra_name.contains(' ') || is_local_name;
+        if as_prefix_context && (type_printing != TypePrinting::NoType || is_multi_word_name) {
+            return Ok(Some(entity.clone()));
+        }
+
+        let context = entity.child(0)?;
+        let postfix_context = if is_multi_word_name {
+            Some(context.clone())
+        } else {
+            let position = self.output.len();
+            let postfix_context = self.print(context, true)?;
+            if self.output.len() != position {
+                self.push(".");
+            }
+            postfix_context
+        };
+
+        if has_name || !overwrite_name.is_empty() {
+            if !extra_name.is_empty() && is_multi_word_name {
+                self.push(extra_name);
+                if let Some(index) = extra_index {
+                    write!(self.output, "{}", index).unwrap();
+                }
+                self.push(" of ");
+                extra_name = "";
+                extra_index = None;
+            }
+            let position = self.output.len();
+            if !overwrite_name.is_empty() {
+                self.push(overwrite_name);
+            } else {
+                let name = entity.child(1)?;
+                if name.kind != Kind::PrivateDeclName {
+                    self.print(name, false)?;
+                }
+                if let Some(private_name) = entity.find(Kind::PrivateDeclName) {
+                    self.print(private_name, false)?;
+                }
+            }
+            if self.output.len() != position && !extra_name.is_empty() {
+                self.push(".");
+            }
+        }
+        if !extra_name.is_empty() {
+            self.push(extra_name);
+            if let Some(index) = extra_index {
+                write!(self.output, "{}", index).unwrap();
+            }
+        }
+
+        if type_printing != TypePrinting::NoType {
+            let entity_type = entity.find(Kind::Type).ok_or(())?.child(0)?;
+            let mut type_printing = type_printing;
+            if type_printing == TypePrinting::FunctionStyle {
+                let mut function_type = entity_type;
+                while function_type.kind == Kind::DependentGenericType {
+                    function_type = function_type.child(1)?.child(0)?;
+                }
+                if function_type.kind != Kind::FunctionType {
+                    type_printing = TypePrinting::WithColon;
+                }
+            }
+            if type_printing == TypePrinting::WithColon {
+                self.push(" : ");
+            } else if is_multi_word_name || need_space_before_type(entity_type) {
+                self.push(" ");
+            }
+            self.print_entity_type(entity, entity_type)?;
+        }
+
+        if !as_prefix_context {
+            if let Some(postfix_context) = &postfix_context {
+                let is_initializer = matches!(entity.kind, Kind::DefaultArgumentInitializer | Kind::Initializer | Kind::PropertyWrapperBackingInitializer);
+                self.push(if is_initializer { " of " } else { " in " });
+                self.print(postfix_context, false)?;
+            }
+        }
+        Ok(postfix_context)
+    }
+
+    fn print_entity_type(&mut self, entity: &NodeRef, entity_type: &NodeRef) -> Result<(), ()> {
+        let labels = entity.find(Kind::LabelList);
+        if labels.is_none() && entity_type.kind != Kind::DependentGenericType {
+            return self.print(entity_type, false).map(|_| ());
+        }
+        let mut entity_type = entity_type;
+        if entity_type.kind == Kind::DependentGenericType {
+            self.print(entity_type.child(0)?, false)?;
+            let dependent_type = entity_type.child(1)?;
+            if need_space_before_type(dependent_type) {
+                self.push(" ");
+            }
+            entity_type = dependent_type.child(0)?;
+        }
+        if entity_type.kind == Kind::FunctionType {
+            self.print_function_type(labels, entity_type)
+        } else {
+            self.print(entity_type, false).map(|_| ())
+        }
+    }
+
+    fn print_with_parens(&mut self, node: &NodeRef) -> Result<(), ()> {
+        let needs_parens = !matches!(node.children.first().map(|child| child.kind),
+            Some(Kind::Class | Kind::Structure | Kind::Enum | Kind::Protocol | Kind::TypeAlias | Kind::BoundGeneric
+                | Kind::DependentGenericParamType | Kind::DependentMemberType | Kind::BuiltinTypeName | Kind::Tuple));
+        if needs_parens {
+            self.push("(");
+        }
+        self.print(node, false)?;
+        if needs_parens {
+            self.push(")");
+        }
+        Ok(())
+    }
+
+    fn print_function_type(&mut self, labels: Option<&NodeRef>, function: &NodeRef) -> Result<(), ()> {
+        self.push(&function.text);
+        let mut is_sendable = false;
+        let mut is_async = false;
+        let mut throws = None;
+        let mut index = 0;
+        while let Some(child) = function.children.get(index) {
+            match child.kind {
+                Kind::GlobalActorFunctionType | Kind::IsolatedAnyFunctionType => {
+                    self.print(child, false)?;
+                }
+                Kind::ThrowsAnnotation | Kind::TypedThrowsAnnotation => throws = Some(child),
+                Kind::ConcurrentFunctionType => is_sendable = true,
+                Kind::AsyncAnnotation => is_async = true,
+                _ => break,
+            }
+            index += 1;
+        }
+        if is_sendable {
+            self.push("@Sendable ");
+        }
+        self.print_function_parameters(labels, function.child(index)?)?;
+        if is_async {
+            self.push(" async");
+        }
+        if let Some(throws) = throws {
+            self.print(throws, false)?;
+        }
+        self.print(function.child(index + 1)?, false).map(|_| ())
+    }
+
+    fn print_function_parameters(&mut self, labels: Option<&NodeRef>, arguments: &NodeRef) -> Result<(), ()> {
+        let params = arguments.child(0)?.child(0)?;
+        if params.kind != Kind::Tuple {
+            self.push("(");
+            self.print(params, false)?;
+            self.push(")");
+            return Ok(());
+        }
+        let labels = labels.filter(|labels| !labels.children.is_empty());
+        self.push("(");
+        for (index, param) in params.children.iter().enumerate() {
+            if index > 0 {
+                self.push(", ");
+            }
+            if let Some(labels) = labels {
+                let label = labels.child(index)?;
+                let label = if label.kind == Kind::Identifier { label.text.as_str() } else { "_" };
+                write!(self.output, "{}: ", label).unwrap();
+            }
+            self.print(param, false)?;
+        }
+        self.push(")");
+        Ok(())
+    }
+
+    fn print_generic_signature(&mut self, signature: &NodeRef) -> Result<(), ()> {
+        self.push("<");
+        let counts: Vec<u64> = signature.children.iter()
+            .take_while(|child| child.kind == Kind::DependentGenericParamCount)
+            .map(|child| child.index)
+            .collect();
+        for (depth, &count) in counts.iter().enumerate() {
+            if depth > 0 {
+                self.push("><");
+            }
+            for index in 0..count {
+                if index > 0 {
+                    self.push(", ");
+                }
+                // Only a malformed symbol has this many parameters.
+                if index >= 128 {
+                    self.push("...");
+                    break;
+                }
+                self.push(&generic_parameter_name(depth as u64, index));
+            }
+        }
+        let requirements = &signature.children[counts.len()..];
+        if !requirements.is_empty() {
+            self.push(" where ");
+            for (index, requirement) in requirements.iter().enumerate() {
+                if index > 0 {
+                    self.push(", ");
+                }
+                self.print(requirement, false)?;
+            }
+        }
+        self.push(">");
+        Ok(())
+    }
+
+    fn print_impl_function_type(&mut self, function: &NodeRef) -> Result<(), ()> {
+        // 0: attributes, 1: parameters, 2: results
+        let mut state = 0;
+        let transition = |printer: &mut NodePrinter, state: &mut u8, new_state: u8| {
+            while *state < new_state {
+                printer.push(if *state == 0 { "(" } else { ") -> (" });
+                *state += 1;
+            }
+        };
+        for child in &function.children {
+            match child.kind {
+                Kind::ImplParameter => {
+                    if state == 1 {
+                        self.push(", ");
+                    }
+                    transition(self, &mut state, 1);
+                    self.print(child, false)?;
+                }
+                Kind::ImplResult | Kind::ImplErrorResult => {
+                    if state == 2 {
+                        self.push(", ");
+                    }
+                    transition(self, &mut state, 2);
+                    self.print(child, false)?;
+                }
+                _ => {
+                    self.print(child, false)?;
+                    self.push(" ");
+                }
+            }
+        }
+        transition(self, &mut state, 2);
+        self.push(")");
+        Ok(())
+    }
+
+    fn print_specialization_prefix(&mut self, specialization: &NodeRef) -> Result<(), ()> {
+        write!(self.output, "{} <", specialization.text).unwrap();
+        let mut separator = "";
+        let mut argument_index = 0;
+        for child in &specialization.children {
+            if child.kind == Kind::IsSerialized {
+                self.push(separator);
+                separator = ", ";
+                self.print(child, false)?;
+                continue;
+            }
+            if !child.children.is_empty() {
+                self.push(separator);
+                separator = ", ";
+                match child.kind {
+                    Kind::FunctionSignatureSpecializationParam => {
+                        write!(self.output, "Arg[{}] = ", argument_index).unwrap();
+                        self.print_function_specialization_param(child)?;
+                    }
+                    Kind::FunctionSignatureSpecializationReturn => {
+                        self.push("Return = ");
+                        self.print_function_specialization_param(child)?;
+                    }
+                    _ => {
+                        self.print(child, false)?;
+                    }
+                }
+            }
+            argument_index += 1;
+        }
+        self.push("> of ");
+        Ok(())
+    }
+
+    fn print_function_specialization_param(&mut self, param: &NodeRef) -> Result<(), ()> {
+        let param_kind = param.child(0)?.index;
+        match param_kind {
+            SPEC_CONSTANT_PROP_FUNCTION | SPEC_CONSTANT_PROP_GLOBAL => {
+                self.push("[");
+                self.push(&specialization_param_kind_name(param_kind));
+                self.push(" : ");
+                let payload = &param.child(1)?.text;
+                let demangled = demangle_mangled_symbol(payload, false, false);
+                self.push(demangled.as_deref().unwrap_or(payload));
+                self.push("]");
+            }
+            SPEC_CONSTANT_PROP_INTEGER | SPEC_CONSTANT_PROP_FLOAT => {
+                write!(self.output, "[{} : {}]", specialization_param_kind_name(param_kind), param.child(1)?.text).unwrap();
+            }
+            SPEC_CONSTANT_PROP_STRING => {
+                write!(self.output, "[{} : {} : '{}']", specialization_param_kind_name(param_kind), param.child(1)?.text, param.child(2)?.text).unwrap();
+            }
+            SPEC_CLOSURE_PROP => {
+                self.push("[");
+                self.push(&specialization_param_kind_name(param_kind));
+                self.push(" : ");
+                let payload = &param.child(1)?.text;
+                let demangled = demangle_mangled_symbol(payload, false, false);
+                self.push(demangled.as_deref().unwrap_or(payload));
+                self.push(", Argument Types : [");
+                for (index, argument) in param.children[2..].iter().enumerate() {
+                    if index > 0 {
+                        self.push(", ");
+                    }
+                    self.print(argument, false)?;
+                }
+                self.push("]]");
+            }
+            _ => self.push(&specialization_param_kind_name(param_kind)),
+        }
+        Ok(())
+    }
+}
+
+fn specialization_param_kind_name(value: u64) -> String {
+    let options = [
+        (SPEC_EXISTENTIAL_TO_GENERIC, "Existential To Protocol Constrained Generic"),
+        (SPEC_DEAD, "Dead"),
+        (SPEC_OWNED_TO_GUARANTEED, "Owned To Guaranteed"),
+        (SPEC_GUARANTEED_TO_OWNED, "Guaranteed To Owned"),
+        (SPEC_SROA, "Exploded"),
+    ];
+    let names: Vec<&str> = options.iter().filter(|(flag, _)| value & flag != 0).map(|(_, name)| *name).collect();
+    if !names.is_empty() {
+        return names.join(" and ");
+    }
+    let name = match value {
+        SPEC_CONSTANT_PROP_FUNCTION => "Constant Propagated Function",
+        SPEC_CONSTANT_PROP_GLOBAL => "Constant Propagated Global",
+        SPEC_CONSTANT_PROP_INTEGER => "Constant Propagated Integer",
+        SPEC_CONSTANT_PROP_FLOAT => "Constant Propagated Float",
+        SPEC_CONSTANT_PROP_STRING => "Constant Propagated String",
+        SPEC_CLOSURE_PROP => "Closure Propagated",
+        SPEC_BOX_TO_VALUE => "Value Promoted from Box",
+        SPEC_BOX_TO_STACK => "Stack Promoted from Box",
+        SPEC_IN_OUT_TO_OUT => "InOut Converted to Out",
+        _ => "Unknown",
+    };
+    name.to_string()
+}
+
+fn need_space_before_type(node: &NodeRef) -> bool {
+    match node.kind {
+        Kind::Type => node.children.first().is_some_and(need_space_before_type),
+        Kind::FunctionType => !node.text.is_empty(),
+        Kind::DependentGenericType => false,
+        _ => true,
+    }
+}
+
+fn is_class_type(context: &NodeRef) -> bool {
+    context.kind == Kind::Class
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demangle(name: &str) -> Option<String> {
+        demangle_symbol(name, DemangleStyle::Full)
+    }
+
+    // Expected output as printed by swift-demangle.
+    #[test]
+    fn functions() {
+        let cases = [
+            ("$s4main3FooV3baryyF", "main.Foo.bar() -> ()"),
+            ("_$s4main3FooV3baryyF", "main.Foo.bar() -> ()"),
+            ("_T04main3FooV3baryyF", "main.Foo.bar() -> ()"),
+            ("$s4main3fooyySiF", "main.foo(Swift.Int) -> ()"),
+            ("$s4main3addyS2i_SitF", "main.add(Swift.Int, Swift.Int) -> Swift.Int"),
+            ("$s4main3FooV3bar1xySi_tF", "main.Foo.bar(x: Swift.Int) -> ()"),
+            ("$s4main3fooyyxlF", "main.foo<A>(A) -> ()"),
+            ("$s4main3fooyyxSQRzlF", "main.foo<A where A: Swift.Equatable>(A) -> ()"),
+            ("$s4main3fooyyKF", "main.foo() throws -> ()"),
+            ("$s4main3fooyyYaF", "main.foo() async -> ()"),
+            ("$s4main3fooyyFyycfU_", "closure #1 () -> () in main.foo() -> ()"),
+            ("$s4main3FooC4nameSSvg", "main.Foo.name.getter : Swift.String"),
+            ("$s4main3FooCACycfC", "main.Foo.__allocating_init() -> main.Foo"),
+            ("$s4main3FooCfD", "main.Foo.__deallocating_deinit"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle(mangled).as_deref(), Some(expected), "{}", mangled);
+        }
+    }
+
+    #[test]
+    fn metadata() {
+        let cases = [
+            ("$s4main3FooVMn", "nominal type descriptor for main.Foo"),
+            ("$s4main3FooCMa", "type metadata accessor for main.Foo"),
+            ("$s4main3FooVN", "type metadata for main.Foo"),
+            ("$s4main1PMp", "protocol descriptor for main.P"),
+            ("$s4main3FooVAA1PAAMc", "protocol conformance descriptor for main.Foo : main.P in main"),
+            ("$s4main3FooV3baryyFTq", "method descriptor for main.Foo.bar() -> ()"),
+            ("$sSo8NSObjectCMn", "nominal type descriptor for __C.NSObject"),
+            ("_TtC4main3Foo", "main.Foo"),
+            ("_TtCs12_SwiftObject", "Swift._SwiftObject"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle(mangled).as_deref(), Some(expected), "{}", mangled);
+        }
+    }
+
+    #[test]
+    fn types() {
+        let cases = [
+            ("Si", "Swift.Int"),
+            ("SiSg", "Swift.Optional<Swift.Int>"),
+            ("SaySiG", "Swift.Array<Swift.Int>"),
+            ("SDySSSiG", "Swift.Dictionary<Swift.String, Swift.Int>"),
+            ("4main3FooV", "main.Foo"),
+            ("Si_SStSg", "Swift.Optional<(Swift.Int, Swift.String)>"),
+        ];
+        for (mangled, expected) in cases {
+            assert_eq!(demangle_type(mangled).as_deref(), Some(expected), "{}", mangled);
+        }
+    }
+
+    #[test]
+    fn name_only() {
+        assert_eq!(demangle_symbol("$s4main3addyS2i_SitF", DemangleStyle::NameOnly).as_deref(), Some("main.add"));
+        assert_eq!(demangle_symbol("$s4main3FooC4nameSSvg", DemangleStyle::NameOnly).as_deref(), Some("main.Foo.name.getter"));
+    }
+
+    #[test]
+    fn rejects_truncated_and_garbage_input() {
+        let long = format!("$s4main{}", "3FooV".repeat(MAX_SYMBOL_LENGTH));
+        let deep = format!("$s{}yyF", "SiSg".repeat(MAX_NESTING * 2));
+        let cases = ["", "main", "$s", "$s4main3Fo", "$s4main9Foo", "$s4main3FooVAZ", "$sXYZ", "_Tt", "_TtX4main3Foo", &long, &deep];
+        for mangled in cases {
+            assert_eq!(demangle(mangled), None, "{}", mangled);
+        }
+        assert_eq!(demangle_type("Say"), None);
+        assert_eq!(demangle_type("SiSi"), None);
+    }
+
+    #[test]
+    fn runtime_name_length_does_not_overflow() {
+        assert_eq!(demangle("_TtC18446744073709551615x"), None);
+    }
+}
@@ -1,9 +1,53 @@
 use std::io::Read;
-use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
-use crate::constants::{MH_CIGAM, MH_CIGAM_64, MH_MAGIC, MH_MAGIC_64};
+use byteorder::ReadBytesExt;
+use crate::constants::{INDIRECT_SYMBOL_ABS, INDIRECT_SYMBOL_LOCAL, MH_CIGAM, MH_CIGAM_64, MH_MAGIC, MH_MAGIC_64, SECTION_TYPE, S_SYMBOL_STUBS};
 use crate::error::AppError;
+use crate::load_commands::{Section, SectionLike};
 
 pub type Symtab = Vec<Nlist>;
+pub type Strtab = Vec<u8>;
+
+pub fn symbol_name(strtab: &Strtab, n_strx: u32) -> String {
+    match strtab.get(n_strx as usize..) {
+        Some(bytes) => {
+            let end = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+            String::from_utf8_lossy(&bytes[..end]).into_owned()
+        }
+        None => String::new(),
+    }
+}
+
+/// A stub in a `S_SYMBOL_STUBS` section and the symbol it jumps to.
+pub struct SymbolStub {
+    pub address: u64,
+    pub symbol_name: String,
+}
+
+/// Pairs each stub with its entry in the indirect symbol table. Entries for symbols that were
+/// made local or absolute are named `LOCAL` and `ABSOLUTE`, as otool does.
+pub fn symbol_stubs(sections: &[&Section], indirect_symbols: &[u32], symtab: &Symtab, strtab: &Strtab) -> Vec<SymbolStub> {
+    let mut stubs = Vec::new();
+    for section in sections {
+        let stub_size = section.reserved2() as u64;
+        if section.flags() & SECTION_TYPE != S_SYMBOL_STUBS || stub_size == 0 {
+            continue;
+        }
+        for index in 0..section.size() / stub_size {
+            let Some(&entry) = indirect_symbols.get(section.reserved1() as usize + index as usize) else {
+                break;
+            };
+            let symbol_name = if entry & INDIRECT_SYMBOL_LOCAL != 0 {
+                String::from("LOCAL")
+            } else if entry & INDIRECT_SYMBOL_ABS != 0 {
+                String::from("ABSOLUTE")
+            } else {
+                symtab.get(entry as usize).map_or_else(String::new, |nlist| symbol_name(strtab, nlist.n_strx()))
+            };
+            stubs.push(SymbolStub { address: section.addr().wrapping_add(index * stub_size), symbol_name });
+        }
+    }
+    stubs
+}
 
 #[derive(Debug)]
 pub enum Nlist {
@@ -19,6 +63,30 @@ impl Nlist {
             _ => unreachable!(),
         }
     }
+
+    fn as_dyn(&self) -> &dyn Symbol {
+        match self {
+            Nlist::NL32(nlist) => nlist,
+            Nlist::NL64(nlist) => nlist,
+        }
+    }
+}
+
+/// Fields of `nlist` and `nlist_64`, with the value widened to 64 bits.
+pub trait Symbol {
+    fn n_strx(&self) -> u32;
+    fn n_type(&self) -> u8;
+    fn n_sect(&self) -> u8;
+    fn n_desc(&self) -> u16;
+    fn n_value(&self) -> u64;
+}
+
+impl Symbol for Nlist {
+    fn n_strx(&self) -> u32 { self.as_dyn().n_strx() }
+    fn n_type(&self) -> u8 { self.as_dyn().n_type() }
+    fn n_sect(&self) -> u8 { self.as_dyn().n_sect() }
+    fn n_desc(&self) -> u16 { self.as_dyn().n_desc() }
+    fn n_value(&self) -> u64 { self.as_dyn().n_value() }
 }
 
 #[derive(Debug)]
@@ -44,6 +112,14 @@ impl Nlist32 {
     }
 }
 
+impl Symbol for Nlist32 {
+    fn n_strx(&self) -> u32 { self.n_strx }
+    fn n_type(&self) -> u8 { self.n_type }
+    fn n_sect(&self) -> u8 { self.n_sect }
+    fn n_desc(&self) -> u16 { self.n_desc as u16 }
+    fn n_value(&self) -> u64 { self.n_value as u64 }
+}
+
 #[derive(Debug)]
 #[repr(C)]
 pub struct Nlist64 {
@@ -65,4 +141,12 @@ impl Nlist64 {
         };
         Ok(Nlist::NL64(nlist64))
     }
-}
\ No newline at end of file
+}
+
+impl Symbol for Nlist64 {
+    fn n_strx(&self) -> u32 { self.n_strx }
+    fn n_type(&self) -> u8 { self.n_type }
+    fn n_sect(&self) -> u8 { self.n_sect }
+    fn n_desc(&self) -> u16 { self.n_desc }
+    fn n_value(&self) -> u64 { self.n_value }
+}
@@ -0,0 +1,146 @@
+//! Builders for the small crafted 64-bit little-endian images the unit tests parse.
+
+use crate::constants::*;
+
+/// An MH_EXECUTE image with `commands` after the header and each of `payloads` written at its
+/// file offset.
+pub(crate) fn image(commands: &[Vec<u8>], payloads: &[(u64, &[u8])]) -> Vec<u8> {
+    let sizeofcmds: usize = commands.iter().map(Vec::len).sum();
+    let mut data = Vec::new();
+    for word in [MH_MAGIC_64, CPU_TYPE_ARM64 as u32, 0, MH_EXECUTE, commands.len() as u32, sizeofcmds as u32, 0, 0] {
+        data.extend(word.to_le_bytes());
+    }
+    for command in commands {
+        data.extend(command);
+    }
+    for (offset, bytes) in payloads {
+        let offset = *offset as usize;
+        if data.len() < offset + bytes.len() {
+            data.resize(offset + bytes.len(), 0);
+        }
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+    data
+}
+
+/// A load command made of 32-bit fields, such as LC_SYMTAB or a linkedit_data_command.
+pub(crate) fn command(cmd: u32, fields: &[u32]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for word in [cmd, 8 + 4 * fields.len() as u32].iter().chain(fields) {
+        data.extend(word.to_le_bytes());
+    }
+    data
+}
+
+/// A section of [`segment_64`] given as (sectname, addr, size, offset).
+pub(crate) type SectionSpec<'a> = (&'a str, u64, u64, u32);
+
+pub(crate) fn segment_64(segname: &str, vmaddr: u64, vmsize: u64, fileoff: u64, filesize: u64, sections: &[SectionSpec]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend(LC_SEGMENT_64.to_le_bytes());
+    data.extend((72 + 80 * sections.len() as u32).to_le_bytes());
+    data.extend(fixed_name(segname));
+    for field in [vmaddr, vmsize, fileoff, filesize] {
+        data.extend(field.to_le_bytes());
+    }
+    for field in [7, 7, sections.len() as u32, 0] {
+        data.extend(field.to_le_bytes());
+    }
+    for &(sectname, addr, size, offset) in sections {
+        data.extend(fixed_name(sectname));
+        data.extend(fixed_name(segname));
+        data.extend(addr.to_le_bytes());
+        data.extend(size.to_le_bytes());
+        for field in [offset, 0, 0, 0, 0, 0, 0, 0] {
+            data.extend(field.to_le_bytes());
+        }
+    }
+    data
+}
+
+pub(crate) fn nlist_64(n_strx: u32, n_type: u8, n_sect: u8, n_value: u64) -> Vec<u8> {
+    let mut data = n_strx.to_le_bytes().to_vec();
+    data.extend([n_type, n_sect, 0, 0]);
+    data.extend(n_value.to_le_bytes());
+    data
+}
+
+pub(crate) fn uleb128(mut value: u64) -> Vec<u8> {
+    let mut data = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            data.push(byte);
+            return data;
+        }
+        data.push(byte | 0x80);
+    }
+}
+
+fn fixed_name(name: &str) -> [u8; 16] {
+    let mut bytes = [0u8; 16];
+    bytes[..name.len()].copy_from_slice(name.as_bytes());
+    bytes
+}
+
+/// Where [`data_image`] maps its `__DATA` segment, after a `__TEXT` segment holding the header.
+pub(crate) const DATA_VMADDR: u64 = 0x1_0000_4000;
+const DATA_FILEOFF: u64 = 0x4000;
+
+/// The contents of a `__DATA` segment laid out piece by piece, for metadata that points at
+/// other metadata.
+pub(crate) struct SegmentData {
+    data: Vec<u8>,
+}
+
+impl SegmentData {
+    pub(crate) fn new() -> SegmentData {
+        SegmentData { data: Vec::new() }
+    }
+
+    /// The address the next piece will be placed at.
+    pub(crate) fn next_address(&self) -> u64 {
+        DATA_VMADDR + self.data.len().next_multiple_of(8) as u64
+    }
+
+    /// Appends `bytes` at the next 8-byte aligned address and returns that address.
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> u64 {
+        let address = self.next_address();
+        self.data.resize((address - DATA_VMADDR) as usize, 0);
+        self.data.extend(bytes);
+        address
+    }
+
+    pub(crate) fn string(&mut self, string: &str) -> u64 {
+        self.push(format!("{}\0", string).as_bytes())
+    }
+
+    pub(crate) fn pointers(&mut self, pointers: &[u64]) -> u64 {
+        self.push(&pointers.iter().flat_map(|pointer| pointer.to_le_bytes()).collect::<Vec<u8>>())
+    }
+
+    /// Overwrites the bytes at `address`, e.g. to close a cycle.
+    pub(crate) fn write(&mut self, address: u64, bytes: &[u8]) {
+        let offset = (address - DATA_VMADDR) as usize;
+        self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+}
+
+pub(crate) fn words(words: &[u32]) -> Vec<u8> {
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+/// An image with `data` as its `__DATA` segment, holding the sections given as
+/// (sectname, addr, size).
+pub(crate) fn data_image(data: &SegmentData, sections: &[(&str, u64, u64)]) -> Vec<u8> {
+    let text = segment_64("__TEXT", 0x1_0000_0000, DATA_FILEOFF, 0, DATA_FILEOFF, &[]);
+    let sections: Vec<SectionSpec> = sections.iter()
+        .map(|&(sectname, addr, size)| (sectname, addr, size, (addr - DATA_VMADDR + DATA_FILEOFF) as u32))
+        .collect();
+    let size = (data.data.len() as u64).next_multiple_of(0x1000).max(0x1000);
+    let segment = segment_64("__DATA", DATA_VMADDR, size, DATA_FILEOFF, size, &sections);
+    let mut image = image(&[text, segment], &[(DATA_FILEOFF, &data.data)]);
+    image.resize((DATA_FILEOFF + size) as usize, 0);
+    image
+}
@@ -0,0 +1,487 @@
+//! Zero-copy parsing of a Mach-O image that is already in memory, such as a memory-mapped file.
+//!
+//! [`MachOView::parse`] only validates the header and the load command table. Segments, sections
+//! and symbols are decoded from the buffer when they are visited, and their names are borrowed
+//! from it instead of being copied. [`MachOView::to_mach_o`] falls back to the reader-based
+//! [`parser`](crate::parser) for everything this module does not cover.
+
+use std::borrow::Cow;
+use std::io::{self, Cursor};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::constants::*;
+use crate::error::AppError;
+use crate::header::{Header, MachHeader};
+use crate::load_commands::{LoadCommandEntry, SectionLike, Segment};
+use crate::mach_o::MachO;
+use crate::parser;
+use crate::symbols::Symbol;
+
+const MACH_HEADER_SIZE: usize = 28;
+const MACH_HEADER_64_SIZE: usize = 32;
+const LOAD_COMMAND_PREFIX_SIZE: usize = 8;
+const SYMTAB_COMMAND_SIZE: usize = 24;
+const SEGMENT_COMMAND_SIZE: usize = 56;
+const SEGMENT_COMMAND_64_SIZE: usize = 72;
+const SECTION_SIZE: usize = 68;
+const SECTION_64_SIZE: usize = 80;
+const NLIST_SIZE: usize = 12;
+const NLIST_64_SIZE: usize = 16;
+
+/// A Mach-O image borrowed from a byte slice.
+///
+/// ```
+/// use mach_o_rust::view::MachOView;
+/// use mach_o_rust::constants::N_EXT;
+/// use mach_o_rust::{Segment, Symbol};
+///
+/// // Any byte slice works; an mmap of the file avoids reading it up front.
+/// let data = std::fs::read("test_files/mach_o_ex1")?;
+/// let view = MachOView::parse(&data)?;
+///
+/// for segment in view.segments() {
+///     println!("{} at 0x{:x}", segment.name(), segment.vmaddr());
+/// }
+/// let external = view.symbols().filter(|symbol| symbol.n_type() & N_EXT != 0).count();
+/// println!("{} external symbols", external);
+///
+/// if let Some(cstrings) = view.section_by_name("__TEXT", "__cstring").and_then(|section| section.data()) {
+///     println!("{} bytes of C strings", cstrings.len());
+/// }
+/// # Ok::<(), mach_o_rust::AppError>(())
+/// ```
+pub struct MachOView<'a> {
+    image: Bytes<'a>,
+    header: MachHeader,
+    load_commands: Vec<LoadCommandView<'a>>,
+    symbol_table: &'a [u8],
+    strtab: &'a [u8],
+}
+
+impl<'a> MachOView<'a> {
+    /// Validates the header and load commands of the thin image at the start of `data`.
+    pub fn parse(data: &'a [u8]) -> Result<MachOView<'a>, AppError> {
+        let magic = data.get(..4).map(BigEndian::read_u32).ok_or(AppError::Truncated { offset: 0, needed: 4 })?;
+        let (big_endian, header_size) = match magic {
+            MH_MAGIC => (true, MACH_HEADER_SIZE),
+            MH_MAGIC_64 => (true, MACH_HEADER_64_SIZE),
+            MH_CIGAM => (false, MACH_HEADER_SIZE),
+            MH_CIGAM_64 => (false, MACH_HEADER_64_SIZE),
+            _ => return Err(AppError::BadMagic { magic }),
+        };
+        let mut cursor = Cursor::new(&data[4..]);
+        let header = if big_endian {
+            MachHeader::from_file::<_, BigEndian>(&mut cursor, magic)
+        } else {
+            MachHeader::from_file::<_, LittleEndian>(&mut cursor, magic)
+        }.map_err(AppError::truncated(4, header_size as u64 - 4))?;
+
+        let image = Bytes { data, big_endian };
+        let mut offset = header_size;
+        let mut load_commands = Vec::new();
+        for index in 0..header.ncmds() {
+            let command = LoadCommandView::parse(image, index, offset)?;
+            offset += command.bytes.data.len();
+            load_commands.push(command);
+        }
+
+        let mut view = MachOView { image, header, load_commands, symbol_table: &[], strtab: &[] };
+        if let Some(symtab) = view.load_commands.iter().find(|command| command.cmd() == LC_SYMTAB) {
+            let symtab = symtab.bytes;
+            let entry_size = if view.header.is_64() { NLIST_64_SIZE } else { NLIST_SIZE };
+            let (symoff, symbol_table_size) = (symtab.u32(8) as u64, symtab.u32(12) as u64 * entry_size as u64);
+            view.symbol_table = image.range(symoff, symbol_table_size)
+                .ok_or(AppError::OutOfBounds { what: "symbol table", offset: symoff, size: symbol_table_size })?;
+            let (stroff, strsize) = (symtab.u32(16) as u64, symtab.u32(20) as u64);
+            view.strtab = image.range(stroff, strsize)
+                .ok_or(AppError::OutOfBounds { what: "string table", offset: stroff, size: strsize })?;
+        }
+        Ok(view)
+    }
+
+    /// Decodes the whole image with the reader-based parser, for data the view does not expose.
+    pub fn to_mach_o(&self) -> Result<MachO, AppError> {
+        parser::parse(&mut Cursor::new(self.image.data))
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.image.data
+    }
+
+    pub fn header(&self) -> &MachHeader {
+        &self.header
+    }
+
+    pub fn load_commands(&self) -> &[LoadCommandView<'a>] {
+        &self.load_commands
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = SegmentView<'a>> + '_ {
+        self.load_commands.iter().filter_map(LoadCommandView::segment)
+    }
+
+    pub fn sections(&self) -> impl Iterator<Item = SectionView<'a>> + '_ {
+        self.segments().flat_map(|segment| segment.sections())
+    }
+
+    /// Looks up a section by segment and section name, e.g. `("__TEXT", "__text")`.
+    pub fn section_by_name(&self, segname: &str, sectname: &str) -> Option<SectionView<'a>> {
+        self.sections().find(|section| section.segment_name() == segname && section.name() == sectname)
+    }
+
+    /// Iterates over the symbol table, decoding each entry as it is visited.
+    pub fn symbols(&self) -> Symbols<'a> {
+        let entry_size = if self.header.is_64() { NLIST_64_SIZE } else { NLIST_SIZE };
+        Symbols {
+            table: Bytes { data: self.symbol_table, big_endian: self.image.big_endian },
+            strtab: self.strtab,
+            entry_size,
+            index: 0,
+        }
+    }
+
+    pub fn strtab(&self) -> &'a [u8] {
+        self.strtab
+    }
+}
+
+/// A load command borrowed from the image; the bytes cover the whole `cmdsize`.
+#[derive(Clone, Copy)]
+pub struct LoadCommandView<'a> {
+    index: u32,
+    offset: usize,
+    bytes: Bytes<'a>,
+    image: Bytes<'a>,
+}
+
+impl<'a> LoadCommandView<'a> {
+    fn parse(image: Bytes<'a>, index: u32, offset: usize) -> Result<LoadCommandView<'a>, AppError> {
+        let prefix = image.range(offset as u64, LOAD_COMMAND_PREFIX_SIZE as u64)
+            .ok_or(AppError::Truncated { offset: offset as u64, needed: LOAD_COMMAND_PREFIX_SIZE as u64 })?;
+        let prefix = Bytes { data: prefix, big_endian: image.big_endian };
+        let cmd = prefix.u32(0);
+        let cmdsize = prefix.u32(4) as usize;
+        let invalid = |source: AppError| AppError::InvalidLoadCommand { index, cmd, offset: offset as u64, source: Box::new(source) };
+        if cmdsize < LOAD_COMMAND_PREFIX_SIZE {
+            return Err(invalid(invalid_data(format!("cmdsize 0x{:x} is smaller than the cmd and cmdsize fields", cmdsize))));
+        }
+        let data = image.range(offset as u64, cmdsize as u64)
+            .ok_or_else(|| invalid(AppError::Truncated { offset: offset as u64, needed: cmdsize as u64 }))?;
+        let bytes = Bytes { data, big_endian: image.big_endian };
+
+        let minimum_size = match cmd {
+            LC_SEGMENT => SEGMENT_COMMAND_SIZE,
+            LC_SEGMENT_64 => SEGMENT_COMMAND_64_SIZE,
+            LC_SYMTAB => SYMTAB_COMMAND_SIZE,
+            _ => LOAD_COMMAND_PREFIX_SIZE,
+        };
+        if cmdsize < minimum_size {
+            return Err(invalid(invalid_data(format!("cmdsize 0x{:x} is too small for its fields", cmdsize))));
+        }
+        let view = LoadCommandView { index, offset, bytes, image };
+        if let Some(segment) = view.segment() {
+            let section_size = if segment.is_64() { SECTION_64_SIZE } else { SECTION_SIZE };
+            if (cmdsize - minimum_size) / section_size < segment.nsects() as usize {
+                return Err(invalid(invalid_data(format!("cmdsize 0x{:x} is too small for {} sections", cmdsize, segment.nsects()))));
+            }
+        }
+        Ok(view)
+    }
+
+    pub fn cmd(&self) -> u32 {
+        self.bytes.u32(0)
+    }
+
+    pub fn cmdsize(&self) -> u32 {
+        self.bytes.u32(4)
+    }
+
+    /// File offset of the command.
+    pub fn offset(&self) -> u64 {
+        self.offset as u64
+    }
+
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes.data
+    }
+
+    /// The `lc_str` of commands that start with one, such as a dylib's install name or an rpath.
+    pub fn string(&self) -> Option<Cow<'a, str>> {
+        match self.cmd() {
+            LC_LOAD_DYLIB | LC_ID_DYLIB | LC_LOAD_WEAK_DYLIB | LC_REEXPORT_DYLIB | LC_LAZY_LOAD_DYLIB | LC_LOAD_UPWARD_DYLIB |
+            LC_LOAD_DYLINKER | LC_ID_DYLINKER | LC_DYLD_ENVIRONMENT | LC_PREBOUND_DYLIB | LC_RPATH |
+            LC_SUB_FRAMEWORK | LC_SUB_UMBRELLA | LC_SUB_CLIENT | LC_SUB_LIBRARY |
+            LC_LOADFVMLIB | LC_IDFVMLIB | LC_FVMFILE => {
+                let offset = self.bytes.data.get(8..12).map(|_| self.bytes.u32(8) as usize)?;
+                Some(c_string(self.bytes.data.get(offset..)?))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn segment(&self) -> Option<SegmentView<'a>> {
+        matches!(self.cmd(), LC_SEGMENT | LC_SEGMENT_64).then_some(SegmentView { command: self.bytes, image: self.image })
+    }
+
+    /// Decodes the command into the owned form produced by the reader-based parser.
+    pub fn to_entry(&self) -> Result<LoadCommandEntry, AppError> {
+        let mut cursor = Cursor::new(self.image.data);
+        cursor.set_position(self.offset as u64);
+        if self.bytes.big_endian {
+            parser::parse_load_command::<_, BigEndian>(&mut cursor, self.index)
+        } else {
+            parser::parse_load_command::<_, LittleEndian>(&mut cursor, self.index)
+        }
+    }
+}
+
+/// An `LC_SEGMENT` or `LC_SEGMENT_64` command borrowed from the image.
+#[derive(Clone, Copy)]
+pub struct SegmentView<'a> {
+    command: Bytes<'a>,
+    image: Bytes<'a>,
+}
+
+impl<'a> SegmentView<'a> {
+    fn is_64(&self) -> bool {
+        self.command.u32(0) == LC_SEGMENT_64
+    }
+
+    pub fn name(&self) -> Cow<'a, str> {
+        c_string(&self.command.data[8..24])
+    }
+
+    pub fn sections(&self) -> impl Iterator<Item = SectionView<'a>> {
+        let (segment_size, section_size) = if self.is_64() { (SEGMENT_COMMAND_64_SIZE, SECTION_64_SIZE) } else { (SEGMENT_COMMAND_SIZE, SECTION_SIZE) };
+        let command = self.command;
+        let image = self.image;
+        (0..self.nsects() as usize).map(move |index| {
+            let offset = segment_size + index * section_size;
+            SectionView {
+                section: Bytes { data: &command.data[offset..offset + section_size], big_endian: command.big_endian },
+                image,
+            }
+        })
+    }
+
+    /// File contents of the segment, or `None` if they extend past the end of the image.
+    pub fn data(&self) -> Option<&'a [u8]> {
+        self.image.range(self.fileoff(), self.filesize())
+    }
+}
+
+impl Segment for SegmentView<'_> {
+    fn cmd(&self) -> u32 { self.command.u32(0) }
+    fn cmdsize(&self) -> u32 { self.command.u32(4) }
+    fn segname(&self) -> &[u8; 16] { self.command.data[8..24].try_into().unwrap() }
+    fn vmaddr(&self) -> u64 { if self.is_64() { self.command.u64(24) } else { self.command.u32(24) as u64 } }
+    fn vmsize(&self) -> u64 { if self.is_64() { self.command.u64(32) } else { self.command.u32(28) as u64 } }
+    fn fileoff(&self) -> u64 { if self.is_64() { self.command.u64(40) } else { self.command.u32(32) as u64 } }
+    fn filesize(&self) -> u64 { if self.is_64() { self.command.u64(48) } else { self.command.u32(36) as u64 } }
+    fn maxprot(&self) -> i32 { if self.is_64() { self.command.i32(56) } else { self.command.i32(40) } }
+    fn initprot(&self) -> i32 { if self.is_64() { self.command.i32(60) } else { self.command.i32(44) } }
+    fn nsects(&self) -> u32 { if self.is_64() { self.command.u32(64) } else { self.command.u32(48) } }
+    fn flags(&self) -> u32 { if self.is_64() { self.command.u32(68) } else { self.command.u32(52) } }
+}
+
+/// A `section` or `section_64` borrowed from its segment command.
+#[derive(Clone, Copy)]
+pub struct SectionView<'a> {
+    section: Bytes<'a>,
+    image: Bytes<'a>,
+}
+
+impl<'a> SectionView<'a> {
+    fn is_64(&self) -> bool {
+        self.section.data.len() == SECTION_64_SIZE
+    }
+
+    pub fn name(&self) -> Cow<'a, str> {
+        c_string(&self.section.data[..16])
+    }
+
+    pub fn segment_name(&self) -> Cow<'a, str> {
+        c_string(&self.section.data[16..32])
+    }
+
+    /// File contents of the section; `None` for zero-fill sections or contents past the end of the image.
+    pub fn data(&self) -> Option<&'a [u8]> {
+        if self.offset() == 0 {
+            return None;
+        }
+        self.image.range(self.offset() as u64, self.size())
+    }
+
+    /// Offset of the 32-bit fields that follow `addr` and `size`.
+    fn fields_offset(&self) -> usize {
+        if self.is_64() { 48 } else { 40 }
+    }
+}
+
+impl SectionLike for SectionView<'_> {
+    fn sectname(&self) -> &[u8; 16] { self.section.data[..16].try_into().unwrap() }
+    fn segname(&self) -> &[u8; 16] { self.section.data[16..32].try_into().unwrap() }
+    fn addr(&self) -> u64 { if self.is_64() { self.section.u64(32) } else { self.section.u32(32) as u64 } }
+    fn size(&self) -> u64 { if self.is_64() { self.section.u64(40) } else { self.section.u32(36) as u64 } }
+    fn offset(&self) -> u32 { self.section.u32(self.fields_offset()) }
+    fn align(&self) -> u32 { self.section.u32(self.fields_offset() + 4) }
+    fn reloff(&self) -> u32 { self.section.u32(self.fields_offset() + 8) }
+    fn nreloc(&self) -> u32 { self.section.u32(self.fields_offset() + 12) }
+    fn flags(&self) -> u32 { self.section.u32(self.fields_offset() + 16) }
+    fn reserved1(&self) -> u32 { self.section.u32(self.fields_offset() + 20) }
+    fn reserved2(&self) -> u32 { self.section.u32(self.fields_offset() + 24) }
+    fn reserved3(&self) -> Option<u32> { self.is_64().then(|| self.section.u32(76)) }
+}
+
+/// Lazily decodes the entries of the symbol table.
+pub struct Symbols<'a> {
+    table: Bytes<'a>,
+    strtab: &'a [u8],
+    entry_size: usize,
+    index: usize,
+}
+
+impl<'a> Iterator for Symbols<'a> {
+    type Item = SymbolView<'a>;
+
+    fn next(&mut self) -> Option<SymbolView<'a>> {
+        let offset = self.index * self.entry_size;
+        let data = self.table.data.get(offset..offset + self.entry_size)?;
+        self.index += 1;
+        Some(SymbolView { nlist: Bytes { data, big_endian: self.table.big_endian }, strtab: self.strtab })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.table.data.len() / self.entry_size - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Symbols<'_> {}
+
+/// An `nlist` or `nlist_64` borrowed from the symbol table.
+#[derive(Clone, Copy)]
+pub struct SymbolView<'a> {
+    nlist: Bytes<'a>,
+    strtab: &'a [u8],
+}
+
+impl<'a> SymbolView<'a> {
+    /// The symbol's name, borrowed from the string table when it is valid UTF-8.
+    pub fn name(&self) -> Cow<'a, str> {
+        c_string(self.strtab.get(self.n_strx() as usize..).unwrap_or_default())
+    }
+}
+
+impl Symbol for SymbolView<'_> {
+    fn n_strx(&self) -> u32 { self.nlist.u32(0) }
+    fn n_type(&self) -> u8 { self.nlist.data[4] }
+    fn n_sect(&self) -> u8 { self.nlist.data[5] }
+    fn n_desc(&self) -> u16 { self.nlist.u16(6) }
+    fn n_value(&self) -> u64 { if self.nlist.data.len() == NLIST_64_SIZE { self.nlist.u64(8) } else { self.nlist.u32(8) as u64 } }
+}
+
+/// A slice and the byte order of the image it belongs to. Callers only read offsets they have
+/// already checked against the slice length.
+#[derive(Clone, Copy)]
+struct Bytes<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+impl<'a> Bytes<'a> {
+    fn u16(&self, offset: usize) -> u16 {
+        let bytes = &self.data[offset..offset + 2];
+        if self.big_endian { BigEndian::read_u16(bytes) } else { LittleEndian::read_u16(bytes) }
+    }
+
+    fn u32(&self, offset: usize) -> u32 {
+        let bytes = &self.data[offset..offset + 4];
+        if self.big_endian { BigEndian::read_u32(bytes) } else { LittleEndian::read_u32(bytes) }
+    }
+
+    fn i32(&self, offset: usize) -> i32 {
+        self.u32(offset) as i32
+    }
+
+    fn u64(&self, offset: usize) -> u64 {
+        let bytes = &self.data[offset..offset + 8];
+        if self.big_endian { BigEndian::read_u64(bytes) } else { LittleEndian::read_u64(bytes) }
+    }
+
+    fn range(&self, offset: u64, size: u64) -> Option<&'a [u8]> {
+        let start = usize::try_from(offset).ok()?;
+        let end = start.checked_add(usize::try_from(size).ok()?)?;
+        self.data.get(start..end)
+    }
+}
+
+/// Decodes a NUL-terminated or NUL-padded string without copying it when it is valid UTF-8.
+fn c_string(bytes: &[u8]) -> Cow<'_, str> {
+    let end = bytes.iter().position(|&byte| byte == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end])
+}
+
+fn invalid_data(message: String) -> AppError {
+    AppError::from(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn image_with_symbol(name: &[u8]) -> Vec<u8> {
+        let text = segment_64("__TEXT", 0x1_0000_0000, 0x1000, 0, 0x1000, &[("__cstring", 0x1_0000_0400, 6, 0x400)]);
+        let mut rpath = [LC_RPATH, 32, 12].iter().flat_map(|word| word.to_le_bytes()).collect::<Vec<u8>>();
+        rpath.extend(b"@loader_path\0\0\0\0\0\0\0\0");
+        let symtab = command(LC_SYMTAB, &[0x500, 1, 0x510, 16]);
+        let mut strtab = vec![0];
+        strtab.extend(name);
+        strtab.resize(16, 0);
+        image(&[text, rpath, symtab], &[(0x400, b"hello\0"), (0x500, &nlist_64(1, N_SECT | N_EXT, 1, 0x1_0000_0400)), (0x510, &strtab), (0xfff, &[0])])
+    }
+
+    #[test]
+    fn names_are_borrowed_from_the_image() {
+        let data = image_with_symbol(b"_main");
+        let view = MachOView::parse(&data).unwrap();
+        let segment = view.segments().next().unwrap();
+        let section = view.section_by_name("__TEXT", "__cstring").unwrap();
+        let symbol = view.symbols().next().unwrap();
+        let rpath = view.load_commands()[1].string().unwrap();
+        for (name, expected) in [(segment.name(), "__TEXT"), (section.name(), "__cstring"), (section.segment_name(), "__TEXT"), (symbol.name(), "_main"), (rpath, "@loader_path")] {
+            assert!(matches!(name, Cow::Borrowed(_)), "{}", name);
+            assert_eq!(name, expected);
+        }
+        assert_eq!(section.data(), Some(&b"hello\0"[..]));
+        assert_eq!(symbol.n_value(), 0x1_0000_0400);
+    }
+
+    #[test]
+    fn invalid_utf8_names_are_copied() {
+        let data = image_with_symbol(b"_m\xffin");
+        let view = MachOView::parse(&data).unwrap();
+        let name = view.symbols().next().unwrap().name();
+        assert!(matches!(name, Cow::Owned(_)));
+        assert_eq!(name, "_m\u{fffd}in");
+    }
+
+    #[test]
+    fn sections_beyond_cmdsize_are_rejected() {
+        let mut data = image(&[segment_64("__TEXT", 0, 0x1000, 0, 0x1000, &[("__text", 0, 0, 0)])], &[(0xfff, &[0])]);
+        // nsects of the first segment
+        data[32 + 64..32 + 68].copy_from_slice(&2u32.to_le_bytes());
+        let Some(AppError::InvalidLoadCommand { index: 0, source, .. }) = MachOView::parse(&data).err() else { panic!("segment was accepted") };
+        assert_eq!(source.to_string(), "cmdsize 0x98 is too small for 2 sections");
+    }
+
+    #[test]
+    fn cmdsize_smaller_than_the_prefix_is_rejected() {
+        let mut data = image(&[command(LC_UUID, &[])], &[]);
+        data[36..40].copy_from_slice(&4u32.to_le_bytes());
+        let Some(AppError::InvalidLoadCommand { index: 0, source, .. }) = MachOView::parse(&data).err() else { panic!("command was accepted") };
+        assert_eq!(source.to_string(), "cmdsize 0x4 is smaller than the cmd and cmdsize fields");
+    }
+}